@@ -0,0 +1,44 @@
+//! Accelerations of random clusters of bodies, for each algorithm
+use bevy::{math::Vec3, tasks::TaskPool};
+use copernicus::forces::{Algorithm, BodyState, Forces};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Bodies spread uniformly in a cube, with masses of the order of the planets
+fn bodies(n: usize) -> Vec<BodyState> {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..n)
+        .map(|_| BodyState {
+            pos: Vec3::new(
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-100.0..100.0),
+            ),
+            mass: rng.gen_range(0.1..10.0),
+        })
+        .collect()
+}
+
+fn forces(c: &mut Criterion) {
+    let pool = TaskPool::new();
+    let mut group = c.benchmark_group("forces");
+    for &(name, algorithm) in &[
+        ("brute", Algorithm::BruteForce),
+        ("barnes-hut", Algorithm::BarnesHut),
+    ] {
+        let forces = Forces {
+            algorithm,
+            ..Forces::new(1.0, &pool)
+        };
+        for &n in &[10, 100, 1000] {
+            let bodies = bodies(n);
+            group.bench_with_input(BenchmarkId::new(name, n), &bodies, |b, bodies| {
+                b.iter(|| forces.compute(bodies))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, forces);
+criterion_main!(benches);
@@ -0,0 +1,333 @@
+//! Barnes-Hut approximation of the gravitational accelerations in `O(N log N)`.
+//!
+//! An octree is built over all the massive bodies, each node storing the total mass and the
+//! center of mass of the bodies it contains. A node of size `s` seen from a distance `d`
+//! is approximated by a single point mass when `s / d < θ`; otherwise its children are
+//! visited. `θ = 0` is equivalent to the brute-force computation.
+use bevy::{math::Vec3, tasks::TaskPool};
+
+use crate::forces::{attraction, chunk_size, potential};
+
+/// Past this depth, bodies falling in the same leaf are lumped together instead of
+/// splitting again (only happens for nearly coincident bodies).
+const MAX_DEPTH: usize = 32;
+
+struct Node {
+    center: Vec3,
+    half_size: f32,
+    mass: f32,
+    /// Sum of `m * x` over the bodies in the node, the center of mass is `moment / mass`
+    moment: Vec3,
+    /// Index of the first of the 8 contiguous children, `0` for leaves
+    children: usize,
+    /// Body stored in a leaf
+    body: Option<usize>,
+}
+
+impl Node {
+    fn new(center: Vec3, half_size: f32) -> Self {
+        Self {
+            center,
+            half_size,
+            mass: 0.0,
+            moment: Vec3::ZERO,
+            children: 0,
+            body: None,
+        }
+    }
+
+    fn octant(&self, pos: Vec3) -> usize {
+        (pos.x >= self.center.x) as usize
+            | ((pos.y >= self.center.y) as usize) << 1
+            | ((pos.z >= self.center.z) as usize) << 2
+    }
+}
+
+/// Octree rebuilt every evaluation, its buffers are reused between builds
+#[derive(Default)]
+pub struct Octree {
+    nodes: Vec<Node>,
+    /// Traversal stack of each task
+    stacks: Vec<Vec<usize>>,
+}
+
+impl Octree {
+    /// Tree of the bodies of index `bodies`, the others do not attract
+    pub fn build(&mut self, mass: &[f32], pos: &[Vec3], bodies: &[usize]) {
+        self.nodes.clear();
+        let first = match bodies.first() {
+            Some(&first) => pos[first],
+            None => return,
+        };
+
+        let (min, max) = bodies.iter().fold((first, first), |(min, max), &i| {
+            (min.min(pos[i]), max.max(pos[i]))
+        });
+        let half_size = (max - min).max_element() / 2.0;
+        // Grow the root slightly so that bodies on the boundary end up inside
+        self.nodes.push(Node::new(
+            (min + max) / 2.0,
+            half_size * 1.001 + f32::EPSILON,
+        ));
+
+        for &i in bodies {
+            self.insert(i, mass, pos);
+        }
+    }
+
+    fn insert(&mut self, i: usize, mass: &[f32], pos: &[Vec3]) {
+        let (m, x) = (mass[i], pos[i]);
+        let mut node = 0;
+        let mut depth = 0;
+        loop {
+            let n = &mut self.nodes[node];
+            let was_empty = n.children == 0 && n.body.is_none();
+            n.mass += m;
+            n.moment += x * m;
+
+            if n.children != 0 {
+                node = n.children + n.octant(x);
+            } else if was_empty {
+                n.body = Some(i);
+                return;
+            } else if depth >= MAX_DEPTH {
+                return;
+            } else {
+                // Split the leaf and move the body it held one level down
+                let other = n.body.take().unwrap();
+                self.subdivide(node);
+                let n = &self.nodes[node];
+                let child = n.children + n.octant(pos[other]);
+                let c = &mut self.nodes[child];
+                c.mass = mass[other];
+                c.moment = pos[other] * mass[other];
+                c.body = Some(other);
+
+                let n = &self.nodes[node];
+                node = n.children + n.octant(x);
+            }
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, node: usize) {
+        let first = self.nodes.len();
+        let center = self.nodes[node].center;
+        let half_size = self.nodes[node].half_size / 2.0;
+        for octant in 0..8 {
+            let side = |bit: usize| {
+                if octant & bit != 0 {
+                    half_size
+                } else {
+                    -half_size
+                }
+            };
+            let offset = Vec3::new(side(1), side(2), side(4));
+            self.nodes.push(Node::new(center + offset, half_size));
+        }
+        self.nodes[node].children = first;
+    }
+
+    /// Accelerations of all the bodies at `pos`, including the ones left out of the tree,
+    /// which is traversed for each body in parallel
+    #[allow(clippy::too_many_arguments)]
+    pub fn accelerations(
+        &mut self,
+        pool: &TaskPool,
+        g: f32,
+        eps: f32,
+        theta: f32,
+        mass: &[f32],
+        pos: &[Vec3],
+        acc: &mut [Vec3],
+    ) {
+        let chunk_size = chunk_size(pool, pos.len());
+        let chunks = acc.len().div_ceil(chunk_size);
+        if self.stacks.len() < chunks {
+            self.stacks.resize_with(chunks, Vec::new);
+        }
+        let (nodes, stacks) = (&self.nodes, &mut self.stacks);
+        pool.scope(|scope| {
+            let tasks = acc.chunks_mut(chunk_size).zip(stacks.iter_mut());
+            for (chunk, (acc, stack)) in tasks.enumerate() {
+                scope.spawn(async move {
+                    for (k, a) in acc.iter_mut().enumerate() {
+                        let i = chunk * chunk_size + k;
+                        *a = acceleration(nodes, stack, i, g, eps, theta, mass, pos);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Field of the tree at each of `points`, the acceleration a body there would feel,
+    /// traversed for each point in parallel
+    pub fn field(
+        &mut self,
+        pool: &TaskPool,
+        g: f32,
+        eps: f32,
+        theta: f32,
+        points: &[Vec3],
+        acc: &mut [Vec3],
+    ) {
+        let chunk_size = chunk_size(pool, points.len());
+        let chunks = acc.len().div_ceil(chunk_size);
+        if self.stacks.len() < chunks {
+            self.stacks.resize_with(chunks, Vec::new);
+        }
+        let (nodes, stacks) = (&self.nodes, &mut self.stacks);
+        pool.scope(|scope| {
+            let tasks = acc.chunks_mut(chunk_size).zip(stacks.iter_mut());
+            for (chunk, (acc, stack)) in tasks.enumerate() {
+                scope.spawn(async move {
+                    for (k, a) in acc.iter_mut().enumerate() {
+                        let point = points[chunk * chunk_size + k];
+                        *a = point_field(nodes, stack, point, g, eps, theta);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Potential of the tree at each of `points`, traversed for each point in parallel
+    pub fn potentials(
+        &mut self,
+        pool: &TaskPool,
+        g: f32,
+        eps: f32,
+        theta: f32,
+        points: &[Vec3],
+        phi: &mut [f32],
+    ) {
+        let chunk_size = chunk_size(pool, points.len());
+        let chunks = phi.len().div_ceil(chunk_size);
+        if self.stacks.len() < chunks {
+            self.stacks.resize_with(chunks, Vec::new);
+        }
+        let (nodes, stacks) = (&self.nodes, &mut self.stacks);
+        pool.scope(|scope| {
+            let tasks = phi.chunks_mut(chunk_size).zip(stacks.iter_mut());
+            for (chunk, (phi, stack)) in tasks.enumerate() {
+                scope.spawn(async move {
+                    for (k, p) in phi.iter_mut().enumerate() {
+                        let point = points[chunk * chunk_size + k];
+                        *p = point_potential(nodes, stack, point, g, eps, theta);
+                    }
+                });
+            }
+        });
+    }
+}
+
+fn point_potential(
+    nodes: &[Node],
+    stack: &mut Vec<usize>,
+    point: Vec3,
+    g: f32,
+    eps: f32,
+    theta: f32,
+) -> f32 {
+    let mut phi = 0.0;
+    if nodes.is_empty() {
+        return phi;
+    }
+
+    stack.clear();
+    stack.push(0);
+    while let Some(n) = stack.pop() {
+        let node = &nodes[n];
+        if node.mass <= 0.0 {
+            continue;
+        }
+
+        let diff = point - node.moment / node.mass;
+        let size = 2.0 * node.half_size;
+        if node.children == 0 || size * size < theta * theta * diff.length_squared() {
+            phi += potential(g, eps, diff) * node.mass;
+        } else {
+            stack.extend(node.children..node.children + 8);
+        }
+    }
+    phi
+}
+
+fn point_field(
+    nodes: &[Node],
+    stack: &mut Vec<usize>,
+    point: Vec3,
+    g: f32,
+    eps: f32,
+    theta: f32,
+) -> Vec3 {
+    let mut acc = Vec3::ZERO;
+    if nodes.is_empty() {
+        return acc;
+    }
+
+    stack.clear();
+    stack.push(0);
+    while let Some(n) = stack.pop() {
+        let node = &nodes[n];
+        if node.mass <= 0.0 {
+            continue;
+        }
+
+        let diff = node.moment / node.mass - point;
+        let size = 2.0 * node.half_size;
+        if node.children == 0 || size * size < theta * theta * diff.length_squared() {
+            acc += attraction(g, eps, diff) * node.mass;
+        } else {
+            stack.extend(node.children..node.children + 8);
+        }
+    }
+    acc
+}
+
+#[allow(clippy::too_many_arguments)]
+fn acceleration(
+    nodes: &[Node],
+    stack: &mut Vec<usize>,
+    i: usize,
+    g: f32,
+    eps: f32,
+    theta: f32,
+    mass: &[f32],
+    pos: &[Vec3],
+) -> Vec3 {
+    let mut acc = Vec3::ZERO;
+    if nodes.is_empty() {
+        return acc;
+    }
+
+    stack.clear();
+    stack.push(0);
+    while let Some(n) = stack.pop() {
+        let node = &nodes[n];
+        if node.mass <= 0.0 {
+            continue;
+        }
+
+        if node.children == 0 {
+            // A body does not attract itself
+            let (m, moment) = if node.body == Some(i) {
+                (node.mass - mass[i], node.moment - pos[i] * mass[i])
+            } else {
+                (node.mass, node.moment)
+            };
+            if m > 0.0 {
+                acc += attraction(g, eps, moment / m - pos[i]) * m;
+            }
+            continue;
+        }
+
+        let diff = node.moment / node.mass - pos[i];
+        let size = 2.0 * node.half_size;
+        if size * size < theta * theta * diff.length_squared() {
+            acc += attraction(g, eps, diff) * node.mass;
+        } else {
+            stack.extend(node.children..node.children + 8);
+        }
+    }
+    acc
+}
@@ -0,0 +1,124 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::plugins::summary::Summary;
+use crate::scene::SceneDescription;
+
+/// A row per scene of `--batch`
+const HEADER: &str = "scene,bodies,steps,time,energy_drift,collisions,ejections";
+
+/// Whether `name` matches `pattern`, where `*` matches any characters and `?` any one
+fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+        Some((&p, rest)) => match name.split_first() {
+            Some((&c, name)) => (p == '?' || p == c) && matches(rest, name),
+            None => false,
+        },
+    }
+}
+
+/// Paths of a comma-separated list, whose file names can contain wildcards, e.g.
+/// `scenes/*.ron,other.ron`. The matches of a pattern are sorted.
+fn expand(list: &str) -> Result<Vec<PathBuf>, String> {
+    let mut paths = Vec::new();
+    for item in list
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let path = Path::new(item);
+        let pattern = path.file_name().map(|name| name.to_string_lossy());
+        let pattern: Vec<char> = match pattern {
+            Some(pattern) if pattern.contains(['*', '?']) => pattern.chars().collect(),
+            _ => {
+                paths.push(path.to_path_buf());
+                continue;
+            }
+        };
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let entries =
+            fs::read_dir(dir).map_err(|e| format!("cannot list `{}`: {}", dir.display(), e))?;
+        let mut matched: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name().is_some_and(|name| {
+                    let name: Vec<char> = name.to_string_lossy().chars().collect();
+                    matches(&pattern, &name)
+                })
+            })
+            .collect();
+        if matched.is_empty() {
+            return Err(format!("no scene matches `{}`", item));
+        }
+        matched.sort();
+        paths.append(&mut matched);
+    }
+    Ok(paths)
+}
+
+/// Runs each scene of `list` headless in an app of its own, with the other flags of the
+/// command line, and writes their [`Summary`] to `output`. Scenes that cannot be loaded
+/// are skipped.
+pub fn run(list: &str, output: &str) -> Result<(), String> {
+    let scenes = expand(list)?;
+    let error = |e: std::io::Error| format!("cannot write `{}`: {}", output, e);
+    let mut writer = BufWriter::new(File::create(output).map_err(error)?);
+    writeln!(writer, "{}", HEADER).map_err(error)?;
+
+    for path in scenes {
+        let name = path.display().to_string();
+        if let Err(e) = SceneDescription::load(&path) {
+            eprintln!("skipping {}", e);
+            continue;
+        }
+        println!("{}", name);
+        // Flags are not `Clone`, they are parsed again for each run
+        let mut args = crate::flags();
+        args.scene = Some(name.clone());
+        args.import_csv = None;
+        args.load_state = None;
+        args.headless = true;
+        let summary = Arc::new(Mutex::new(None));
+        crate::run(args, Some(summary.clone()));
+
+        let summary = summary.lock().unwrap().take();
+        let Summary {
+            bodies,
+            steps,
+            time,
+            energy_drift,
+            collisions,
+            ejections,
+        } = match summary {
+            Some(summary) => summary,
+            None => {
+                eprintln!("no summary of {}", name);
+                continue;
+            }
+        };
+        writeln!(
+            writer,
+            "{},{},{},{},{:e},{},{}",
+            crate::export::csv_field(&name),
+            bodies,
+            steps,
+            time,
+            energy_drift,
+            collisions,
+            ejections
+        )
+        .and_then(|_| writer.flush())
+        .map_err(error)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::plugins::nbody::{Mass, Position, Radius, SimState, Velocity};
+use crate::AU_TO_UNIT_SCALE;
+
+/// Trajectories of all the bodies written to a CSV file, a row per body and exported step:
+/// `time,name,mass,radius,x,y,z,vx,vy,vz,color`, with positions in AU and velocities in AU
+/// per unit of time, the units of the scene files. Masses, radii and colors make it a
+/// scene that `--import-csv` reads back.
+pub struct CsvExport {
+    writer: BufWriter<File>,
+    /// Steps between two exported states
+    every: u64,
+    /// Last exported step
+    last: Option<u64>,
+}
+
+const HEADER: &str = "time,name,mass,radius,x,y,z,vx,vy,vz,color";
+
+impl CsvExport {
+    pub fn create(path: impl AsRef<Path>, every: u64) -> Result<Self, String> {
+        let path = path.as_ref();
+        let error = |e: std::io::Error| format!("cannot write `{}`: {}", path.display(), e);
+        let mut writer = BufWriter::new(File::create(path).map_err(error)?);
+        writeln!(writer, "{}", HEADER).map_err(error)?;
+        Ok(CsvExport {
+            writer,
+            every: every.max(1),
+            last: None,
+        })
+    }
+}
+
+/// Names are quoted when they contain a separator or a quote
+pub fn csv_field(name: &str) -> String {
+    if name.contains([',', '"', '\n']) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.to_string()
+    }
+}
+
+/// `#rrggbb`
+fn hex(color: Color) -> String {
+    let [r, g, b, _] = color.as_rgba_f32();
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+/// Export the state after the physics steps of the frame, when they cross a multiple of
+/// `every`: with several steps per frame, only the last one is seen, the rows are exact
+/// with `--headless` which runs one step per frame. Rows are in a stable order, that of
+/// the spawn of the bodies, and flushed once per frame.
+#[allow(clippy::type_complexity)]
+pub fn export_csv(
+    mut export: ResMut<CsvExport>,
+    state: Res<SimState>,
+    materials: Res<Assets<StandardMaterial>>,
+    query: Query<(
+        Entity,
+        Option<&Name>,
+        &Mass,
+        &Radius,
+        &Position,
+        &Velocity,
+        Option<&Handle<StandardMaterial>>,
+    )>,
+) {
+    let due = match export.last {
+        None => true,
+        Some(last) => last / export.every != state.steps / export.every,
+    };
+    if !due {
+        return;
+    }
+    export.last = Some(state.steps);
+
+    let mut bodies: Vec<_> = query.iter().collect();
+    bodies.sort_by_key(|(entity, ..)| entity.id());
+    let mut rows = String::new();
+    for (entity, name, mass, radius, pos, vel, material) in bodies {
+        let name = name.map_or_else(|| format!("body {}", entity.id()), |n| n.to_string());
+        let (pos, vel) = (pos.0 / AU_TO_UNIT_SCALE, vel.0 / AU_TO_UNIT_SCALE);
+        let color = material
+            .and_then(|material| materials.get(material))
+            .map_or(Color::WHITE, |material| material.base_color);
+        rows += &format!(
+            "{},{},{},{},{},{},{},{},{},{},{}\n",
+            state.time,
+            csv_field(&name),
+            mass.0,
+            radius.0,
+            pos.x,
+            pos.y,
+            pos.z,
+            vel.x,
+            vel.y,
+            vel.z,
+            hex(color),
+        );
+    }
+    let written = export
+        .writer
+        .write_all(rows.as_bytes())
+        .and_then(|_| export.writer.flush());
+    if let Err(e) = written {
+        error!("cannot export the trajectories: {}", e);
+    }
+}
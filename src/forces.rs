@@ -0,0 +1,476 @@
+//! Gravitational accelerations of a set of bodies, independent of the ECS so that they can
+//! be benchmarked on their own (`cargo bench`).
+use bevy::{math::Vec3, tasks::TaskPool};
+use std::str::FromStr;
+
+use crate::barnes_hut::Octree;
+
+/// How the pairwise gravitational accelerations are computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    /// Exact `O(N^2)` sum over all pairs
+    #[default]
+    BruteForce,
+    /// `O(N log N)` octree approximation, see [`Forces::theta`]
+    BarnesHut,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "brute" => Ok(Algorithm::BruteForce),
+            "barnes-hut" => Ok(Algorithm::BarnesHut),
+            _ => Err(format!("unknown algorithm `{}`", s)),
+        }
+    }
+}
+
+/// Linear drag of a medium at rest, `a = -k v`, e.g. gas around the bodies: it takes
+/// energy from the orbits, which shrink, the bodies spiralling inwards
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Drag {
+    /// Coefficient `k`, per unit of time, 0 for no drag
+    pub k: f32,
+    /// Only the test particles feel it, e.g. dust in a disk, unlike the massive bodies
+    pub particles_only: bool,
+}
+
+/// Newton's law of universal gravitation
+/// ```text
+/// F = G*m1*m2/r^2
+/// ```
+/// where:
+/// - `F` is the gravitational force acting between two objects
+/// - `G` is the gravitational constant
+/// - `m1` and `m2` are the masses of the objects
+/// - `r` is the distance between the centers of their masses
+///
+/// With Newton's second law of motion (`F = ma => a = F/m`), the acceleration of a body
+/// only depends on the masses of the others.
+/// `r^2` is softened into `r^2 + eps^2`, which is the exact law when `eps = 0`.
+///
+/// Returns the acceleration `G * diff / |diff|^3` per unit of attracting mass, where
+/// `diff` goes from the attracted body to the attracting one.
+pub fn attraction(g: f32, eps: f32, diff: Vec3) -> Vec3 {
+    let r2 = diff.length_squared() + eps * eps;
+    if r2 > 0.0 {
+        diff * (g / (r2 * r2.sqrt()))
+    } else {
+        Vec3::ZERO
+    }
+}
+
+/// Total potential energy `-Σ G*m1*m2/r` over all pairs, using the same softening as
+/// [`attraction`] (`r = sqrt(r^2 + eps^2)`) so that it is consistent with the forces
+pub fn potential_energy(g: f32, eps: f32, mass: &[f32], pos: &[Vec3]) -> f64 {
+    let mut energy = 0.0;
+    for i in 0..pos.len() {
+        for j in i + 1..pos.len() {
+            let r = ((pos[j] - pos[i]).length_squared() + eps * eps).sqrt();
+            if r > 0.0 {
+                energy -= (g * mass[i] * mass[j] / r) as f64;
+            }
+        }
+    }
+    energy
+}
+
+/// Potential `-G / |diff|` per unit of attracting mass, `diff` going from the attracting
+/// body to the point, softened like [`attraction`]
+pub fn potential(g: f32, eps: f32, diff: Vec3) -> f32 {
+    let r = (diff.length_squared() + eps * eps).sqrt();
+    if r > 0.0 {
+        -g / r
+    } else {
+        0.0
+    }
+}
+
+/// Below this many bodies, the brute-force sum is faster on a single thread
+const PARALLEL_THRESHOLD: usize = 128;
+
+/// Number of bodies per task to spread `n` bodies over the threads of `pool`
+pub fn chunk_size(pool: &TaskPool, n: usize) -> usize {
+    n.div_ceil(pool.thread_num().max(1)).max(1)
+}
+
+/// Indices of the bodies that attract the others, and of the test particles, massless, which
+/// only feel their attraction. The cost of the forces is `O(N * M)` for `M` massive bodies.
+#[derive(Default)]
+pub struct Partition {
+    pub massive: Vec<usize>,
+    pub particles: Vec<usize>,
+    /// Group of each body in the [`Interactions`], empty when they are all in group 0
+    pub groups: Vec<usize>,
+}
+
+impl Partition {
+    pub fn group(&self, i: usize) -> usize {
+        self.groups.get(i).copied().unwrap_or(0)
+    }
+}
+
+/// Which groups of bodies attract which, e.g. asteroids attracted by the planets but not by
+/// each other, as a matrix of the groups attracting by the groups attracted. Groups are
+/// numbered from 0, and bodies of groups left out of the matrix attract and are attracted by
+/// all the others, as in Newtonian gravity. The pairs are then summed one by one, even with
+/// Barnes-Hut, whose nodes lump the groups together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Interactions {
+    attracts: Vec<Vec<bool>>,
+}
+
+impl Interactions {
+    /// Whether the bodies of group `attracting` attract those of group `attracted`
+    pub fn attracts(&self, attracting: usize, attracted: usize) -> bool {
+        self.attracts
+            .get(attracting)
+            .and_then(|row| row.get(attracted))
+            .copied()
+            .unwrap_or(true)
+    }
+
+    /// Turn the gravity of the bodies of group `attracting` on the bodies of group
+    /// `attracted` on or off, e.g. `set(1, 1, false)` for the bodies of group 1 not to
+    /// attract each other
+    pub fn set(&mut self, attracting: usize, attracted: usize, enabled: bool) {
+        let groups = self.attracts.len().max(attracting + 1).max(attracted + 1);
+        self.attracts.resize_with(groups, Vec::new);
+        for row in self.attracts.iter_mut() {
+            row.resize(groups, true);
+        }
+        self.attracts[attracting][attracted] = enabled;
+    }
+
+    /// Whether all the groups attract each other, as if there were none
+    pub fn all(&self) -> bool {
+        self.attracts.iter().flatten().all(|&enabled| enabled)
+    }
+}
+
+/// Position and mass of a body, all that the forces depend on
+#[derive(Debug, Clone, Copy)]
+pub struct BodyState {
+    pub pos: Vec3,
+    pub mass: f32,
+}
+
+/// Brute-force accelerations, each task summing the attractions on a range of bodies.
+/// Pairs are visited twice, unlike the serial version, but no synchronisation is needed.
+/// Body `j` only attracts body `i` if `attracts(j, i)`.
+#[allow(clippy::too_many_arguments)]
+fn par_accelerations(
+    pool: &TaskPool,
+    g: f32,
+    eps: f32,
+    mass: &[f32],
+    pos: &[Vec3],
+    massive: &[usize],
+    attracts: impl Fn(usize, usize) -> bool + Copy + Send + Sync,
+    acc: &mut [Vec3],
+) {
+    let chunk_size = chunk_size(pool, pos.len());
+    pool.scope(|scope| {
+        for (chunk, acc) in acc.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(async move {
+                for (k, a) in acc.iter_mut().enumerate() {
+                    let i = chunk * chunk_size + k;
+                    *a = Vec3::ZERO;
+                    for &j in massive {
+                        if j != i && attracts(j, i) {
+                            *a += attraction(g, eps, pos[j] - pos[i]) * mass[j];
+                        }
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn accelerations(
+    g: f32,
+    eps: f32,
+    mass: &[f32],
+    pos: &[Vec3],
+    partition: &Partition,
+    attracts: impl Fn(usize, usize) -> bool,
+    acc: &mut [Vec3],
+) {
+    acc.iter_mut().for_each(|a| *a = Vec3::ZERO);
+    let massive = &partition.massive;
+    for (k, &i) in massive.iter().enumerate() {
+        for &j in &massive[k + 1..] {
+            let field = attraction(g, eps, pos[j] - pos[i]);
+            if attracts(j, i) {
+                acc[i] += field * mass[j];
+            }
+            if attracts(i, j) {
+                acc[j] -= field * mass[i];
+            }
+        }
+    }
+    for &i in &partition.particles {
+        for &j in massive.iter().filter(|&&j| attracts(j, i)) {
+            acc[i] += attraction(g, eps, pos[j] - pos[i]) * mass[j];
+        }
+    }
+}
+
+/// Parameters of the force computation
+pub struct Forces<'a> {
+    pub g: f32,
+    pub softening: f32,
+    pub algorithm: Algorithm,
+    /// Opening angle of the Barnes-Hut approximation: a group of bodies of size `s` at a
+    /// distance `d` is treated as a single mass when `s/d < θ`
+    pub theta: f32,
+    /// Speed of light of the post-Newtonian correction, see [`Forces::post_newtonian`],
+    /// `None` for Newtonian gravity only
+    pub speed_of_light: Option<f32>,
+    /// Uniform acceleration of every body on top of their gravity, e.g. a constant field
+    /// pulling down, zero for none
+    pub external_field: Vec3,
+    pub drag: Drag,
+    /// Which bodies attract which, by the groups of the [`Partition`]
+    pub interactions: &'a Interactions,
+    pub pool: &'a TaskPool,
+}
+
+/// Interactions of bodies without groups, for [`Forces::new`]
+static EVERY_PAIR: Interactions = Interactions {
+    attracts: Vec::new(),
+};
+
+impl<'a> Forces<'a> {
+    /// Newtonian gravity of constant `g` and nothing else, summed exactly: no softening,
+    /// correction, external field, drag nor groups, each of which can be set on top e.g.
+    /// with `Forces { softening: 0.1, ..Forces::new(1.0, &pool) }`
+    pub fn new(g: f32, pool: &'a TaskPool) -> Self {
+        Forces {
+            g,
+            softening: 0.0,
+            algorithm: Algorithm::BruteForce,
+            theta: 0.5,
+            speed_of_light: None,
+            external_field: Vec3::ZERO,
+            drag: Drag::default(),
+            interactions: &EVERY_PAIR,
+            pool,
+        }
+    }
+}
+
+impl Forces<'_> {
+    /// Accelerations of all the bodies at `pos`, into `acc`, the external field included.
+    /// `tree` is only used by Barnes-Hut, it is passed in so that its storage is reused
+    /// from one call to the next.
+    pub fn accelerations(
+        &self,
+        tree: &mut Octree,
+        mass: &[f32],
+        pos: &[Vec3],
+        partition: &Partition,
+        acc: &mut [Vec3],
+    ) {
+        let (g, eps) = (self.g, self.softening);
+        let interactions = self.interactions;
+        let by_group =
+            |j: usize, i: usize| interactions.attracts(partition.group(j), partition.group(i));
+        let all = |_: usize, _: usize| true;
+        let massive = &partition.massive;
+        match (self.algorithm, interactions.all()) {
+            (_, false) if pos.len() < PARALLEL_THRESHOLD => {
+                accelerations(g, eps, mass, pos, partition, by_group, acc)
+            }
+            (_, false) => par_accelerations(self.pool, g, eps, mass, pos, massive, by_group, acc),
+            (Algorithm::BruteForce, true) if pos.len() < PARALLEL_THRESHOLD => {
+                accelerations(g, eps, mass, pos, partition, all, acc)
+            }
+            (Algorithm::BruteForce, true) => {
+                par_accelerations(self.pool, g, eps, mass, pos, massive, all, acc)
+            }
+            (Algorithm::BarnesHut, true) => {
+                tree.build(mass, pos, massive);
+                tree.accelerations(self.pool, g, eps, self.theta, mass, pos, acc);
+            }
+        }
+        if self.external_field != Vec3::ZERO {
+            acc.iter_mut().for_each(|a| *a += self.external_field);
+        }
+    }
+
+    /// First post-Newtonian correction to the accelerations of the bodies at `pos` moving at
+    /// `vel`, written to `acc`, or zeros without a [`Forces::speed_of_light`]. It is the
+    /// correction for a body of negligible mass around the heaviest one, of mass `M`:
+    /// ```text
+    /// a = G*M / (c^2 * r^3) * ((4*G*M/r - v^2) * r + 4 * (r.v) * v)
+    /// ```
+    /// with `r` and `v` relative to the heaviest body, which is left alone. It makes the
+    /// periapsis of close, fast orbits precess, Mercury's by 43" per century.
+    /// Being about `G*M / (c^2 * r)` times the Newtonian acceleration, so 10^-7 times for
+    /// Mercury, it cannot be added to it in single precision without being rounded away.
+    pub fn post_newtonian(&self, mass: &[f32], pos: &[Vec3], vel: &[Vec3], acc: &mut [Vec3]) {
+        acc.iter_mut().for_each(|a| *a = Vec3::ZERO);
+        let c = match self.speed_of_light {
+            Some(c) => c,
+            None => return,
+        };
+        let central = match (0..mass.len()).max_by(|&i, &j| mass[i].total_cmp(&mass[j])) {
+            Some(central) => central,
+            None => return,
+        };
+        let mu = self.g * mass[central];
+        for i in (0..pos.len()).filter(|&i| i != central) {
+            let r = pos[i] - pos[central];
+            let v = vel[i] - vel[central];
+            let distance = r.length();
+            if distance <= 0.0 {
+                continue;
+            }
+            acc[i] = (r * (4.0 * mu / distance - v.length_squared()) + v * 4.0 * r.dot(v))
+                * (mu / (c * c * distance.powi(3)));
+        }
+    }
+
+    /// Drag of the bodies moving at `vel`, added to their accelerations in `acc`, or to those
+    /// of the test particles of `partition` only, see [`Drag`]
+    pub fn drag(&self, vel: &[Vec3], partition: &Partition, acc: &mut [Vec3]) {
+        let k = self.drag.k;
+        if k == 0.0 {
+            return;
+        }
+        if self.drag.particles_only {
+            for &i in partition.particles.iter() {
+                acc[i] -= vel[i] * k;
+            }
+        } else {
+            for (a, v) in acc.iter_mut().zip(vel) {
+                *a -= *v * k;
+            }
+        }
+    }
+
+    /// Radiation pressure of the heaviest body, the Sun, added to the accelerations in `acc`
+    /// of the bodies of `irradiated`, each with its `beta`, the ratio of the pressure to the
+    /// gravity of the Sun. Both falling off as `1/r^2`, it is `beta` times their attraction
+    /// towards the Sun, away from it:
+    /// ```text
+    /// a = beta * G*M / r^2
+    /// ```
+    /// Dust grains of about a micrometer have a `beta` close to 1 and are blown away from a
+    /// comet into its tail, while bodies larger than a few centimeters are barely pushed.
+    pub fn radiation_pressure(
+        &self,
+        mass: &[f32],
+        pos: &[Vec3],
+        irradiated: &[(usize, f32)],
+        acc: &mut [Vec3],
+    ) {
+        let sun = match (0..mass.len()).max_by(|&i, &j| mass[i].total_cmp(&mass[j])) {
+            Some(sun) => sun,
+            None => return,
+        };
+        for &(i, beta) in irradiated.iter().filter(|&&(i, _)| i != sun) {
+            acc[i] -= attraction(self.g, self.softening, pos[sun] - pos[i]) * mass[sun] * beta;
+        }
+    }
+
+    /// Gravitational potential of the bodies of index `massive` at each of `points`, into
+    /// `phi`, with the same approximation as the accelerations. Points are not bodies, none
+    /// of them is left out.
+    pub fn potentials(
+        &self,
+        tree: &mut Octree,
+        mass: &[f32],
+        pos: &[Vec3],
+        massive: &[usize],
+        points: &[Vec3],
+        phi: &mut [f32],
+    ) {
+        let (g, eps) = (self.g, self.softening);
+        match self.algorithm {
+            Algorithm::BruteForce => {
+                let chunk_size = chunk_size(self.pool, points.len());
+                self.pool.scope(|scope| {
+                    for (chunk, phi) in phi.chunks_mut(chunk_size).enumerate() {
+                        scope.spawn(async move {
+                            for (k, p) in phi.iter_mut().enumerate() {
+                                let point = points[chunk * chunk_size + k];
+                                *p = massive
+                                    .iter()
+                                    .map(|&j| potential(g, eps, point - pos[j]) * mass[j])
+                                    .sum();
+                            }
+                        });
+                    }
+                });
+            }
+            Algorithm::BarnesHut => {
+                tree.build(mass, pos, massive);
+                tree.potentials(self.pool, g, eps, self.theta, points, phi);
+            }
+        }
+    }
+
+    /// Gravitational field of the bodies of index `massive` at each of `points`, the
+    /// acceleration a test particle would feel there, into `acc`, with the same
+    /// approximation as the accelerations and the external field included. Points are not
+    /// bodies, none of them is left out.
+    pub fn field(
+        &self,
+        tree: &mut Octree,
+        mass: &[f32],
+        pos: &[Vec3],
+        massive: &[usize],
+        points: &[Vec3],
+        acc: &mut [Vec3],
+    ) {
+        let (g, eps) = (self.g, self.softening);
+        match self.algorithm {
+            Algorithm::BruteForce => {
+                let chunk_size = chunk_size(self.pool, points.len());
+                self.pool.scope(|scope| {
+                    for (chunk, acc) in acc.chunks_mut(chunk_size).enumerate() {
+                        scope.spawn(async move {
+                            for (k, a) in acc.iter_mut().enumerate() {
+                                let point = points[chunk * chunk_size + k];
+                                *a = massive
+                                    .iter()
+                                    .map(|&j| attraction(g, eps, pos[j] - point) * mass[j])
+                                    .fold(Vec3::ZERO, |total, a| total + a);
+                            }
+                        });
+                    }
+                });
+            }
+            Algorithm::BarnesHut => {
+                tree.build(mass, pos, massive);
+                tree.field(self.pool, g, eps, self.theta, points, acc);
+            }
+        }
+        if self.external_field != Vec3::ZERO {
+            acc.iter_mut().for_each(|a| *a += self.external_field);
+        }
+    }
+
+    /// Accelerations of `bodies`, bodies without mass being test particles. Allocates its
+    /// buffers on every call, simulations should rather reuse them with
+    /// [`Forces::accelerations`].
+    pub fn compute(&self, bodies: &[BodyState]) -> Vec<Vec3> {
+        let mass: Vec<f32> = bodies.iter().map(|body| body.mass).collect();
+        let pos: Vec<Vec3> = bodies.iter().map(|body| body.pos).collect();
+        let mut partition = Partition::default();
+        for (i, body) in bodies.iter().enumerate() {
+            if body.mass > 0.0 {
+                partition.massive.push(i);
+            } else {
+                partition.particles.push(i);
+            }
+        }
+        let mut acc = vec![Vec3::ZERO; bodies.len()];
+        self.accelerations(&mut Octree::default(), &mass, &pos, &partition, &mut acc);
+        acc
+    }
+}
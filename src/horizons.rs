@@ -0,0 +1,125 @@
+//! Initial conditions of the Solar System at any date, from the state vectors of the JPL
+//! Horizons API (<https://ssd.jpl.nasa.gov/api/horizons.api>). Responses are cached on disk
+//! by date, so that only the first launch for a date needs the network.
+use std::{env, fs, path::PathBuf};
+
+use crate::calendar::Date;
+use crate::scene::SceneDescription;
+
+const API: &str = "https://ssd.jpl.nasa.gov/api/horizons.api";
+
+/// Same bodies, masses, radii and colors as `--startup solar`, only their positions and
+/// velocities are replaced
+const TEMPLATE: &str = include_str!("../scenes/solar.ron");
+
+/// Horizons IDs of the bodies of the template, the Sun being the origin
+const IDS: [(&str, &str); 9] = [
+    ("Mercury", "199"),
+    ("Venus", "299"),
+    ("Earth", "399"),
+    ("Mars", "499"),
+    ("Jupiter", "599"),
+    ("Saturn", "699"),
+    ("Uranus", "799"),
+    ("Neptune", "899"),
+    ("Pluto", "999"),
+];
+
+/// `$XDG_CACHE_HOME/nbody/horizons`, `~/.cache/nbody/horizons` without it, or in the
+/// temporary directory without a home
+fn cache_dir() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(env::temp_dir)
+        .join("nbody")
+        .join("horizons")
+}
+
+/// Vectors of a body relative to the Sun, in AU and AU per day in the ecliptic plane, at
+/// 00:00 TDB of `date`, from the cache or else from the API
+fn response(id: &str, date: Date) -> Result<String, String> {
+    let path = cache_dir()
+        .join(date.to_string())
+        .join(format!("{}.txt", id));
+    if let Ok(cached) = fs::read_to_string(&path) {
+        return Ok(cached);
+    }
+
+    let quoted = |value: String| format!("'{}'", value);
+    let response = ureq::get(API)
+        .query("format", "text")
+        .query("COMMAND", &quoted(id.to_string()))
+        .query("OBJ_DATA", "NO")
+        .query("EPHEM_TYPE", "VECTORS")
+        .query("CENTER", "'500@10'")
+        .query("REF_PLANE", "ECLIPTIC")
+        .query("START_TIME", &quoted(date.to_string()))
+        .query(
+            "STOP_TIME",
+            &quoted(Date::from_days(date.days() + 1).to_string()),
+        )
+        .query("STEP_SIZE", "'1 d'")
+        .query("VEC_TABLE", "2")
+        .query("OUT_UNITS", "AU-D")
+        .query("CSV_FORMAT", "YES")
+        .call()
+        .map_err(|e| format!("cannot reach JPL Horizons: {}", e))?
+        .into_string()
+        .map_err(|e| format!("cannot read the response of JPL Horizons: {}", e))?;
+    // Only valid responses are cached, a failed cache write only costs a new request
+    if vectors(&response).is_ok() {
+        let _ =
+            fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, &response));
+    }
+    Ok(response)
+}
+
+/// Position and velocity
+type Vectors = ((f32, f32, f32), (f32, f32, f32));
+
+/// Position and velocity of the first line of the ephemeris, between `$$SOE` and `$$EOE`:
+/// `JDTDB, Calendar Date, X, Y, Z, VX, VY, VZ,`
+fn vectors(response: &str) -> Result<Vectors, String> {
+    let line = response
+        .lines()
+        .skip_while(|line| line.trim() != "$$SOE")
+        .nth(1)
+        .filter(|line| line.trim() != "$$EOE")
+        .ok_or_else(|| {
+            // Errors of the API come as a message instead of the ephemeris
+            let message = response.lines().find(|line| !line.trim().is_empty());
+            format!(
+                "no state vectors from JPL Horizons: {}",
+                message.unwrap_or("")
+            )
+        })?;
+    let values = line
+        .split(',')
+        .skip(2)
+        .take(6)
+        .map(|value| value.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid state vectors from JPL Horizons `{}`: {}", line, e))?;
+    match values[..] {
+        [x, y, z, vx, vy, vz] => Ok(((x, y, z), (vx, vy, vz))),
+        _ => Err(format!(
+            "incomplete state vectors from JPL Horizons `{}`",
+            line
+        )),
+    }
+}
+
+/// The Solar System of `--startup solar` at `date`
+pub fn solar_system(date: Date) -> Result<SceneDescription, String> {
+    let mut scene: SceneDescription = ron::from_str(TEMPLATE).expect("invalid `scenes/solar.ron`");
+    for (name, id) in IDS.iter() {
+        let (position, velocity) =
+            vectors(&response(id, date)?).map_err(|e| format!("{} at {}: {}", name, date, e))?;
+        if let Some(body) = scene.bodies.iter_mut().find(|body| body.name == *name) {
+            body.position = position;
+            body.velocity = velocity;
+        }
+    }
+    Ok(scene)
+}
@@ -0,0 +1,146 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::scene::{BodyDescription, SceneDescription, SceneError};
+use crate::{units, AU_TO_UNIT_SCALE};
+
+/// Columns every row must have, in any order
+const REQUIRED: [&str; 9] = ["name", "mass", "radius", "x", "y", "z", "vx", "vy", "vz"];
+
+const UNITS: &str = "expected a header with name, mass (10^24 kg), radius (scene units), \
+                     x, y, z (AU), vx, vy, vz (AU per day) and optionally color (#rrggbb)";
+
+/// Fields of a CSV line, which can be quoted to contain commas, `""` being a quote
+fn fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// `#rrggbb`, the `#` being optional
+fn parse_color(s: &str) -> Option<(f32, f32, f32)> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|c| c as f32 / 255.0)
+    };
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Bodies of a CSV file of state vectors, e.g. from JPL Horizons, in the units of the scene
+/// files: the scene reads the same as `scenes/solar.ron` would. Columns are found by the
+/// names in the header and others are ignored, so that the output of `--export-csv` can be
+/// read back: when there is a `time` column, only the rows of the last time are kept.
+pub fn load_csv(path: impl AsRef<Path>) -> Result<SceneDescription, SceneError> {
+    let path = path.as_ref();
+    let name = path.display().to_string();
+    let content = fs::read_to_string(path).map_err(|e| SceneError::Io(name.clone(), e))?;
+    let error = |line: usize, message: String| {
+        SceneError::Csv(
+            name.clone(),
+            format!("line {}: {} ({})", line, message, UNITS),
+        )
+    };
+
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+    let header: HashMap<String, usize> = match lines.next() {
+        Some((_, line)) => fields(line)
+            .into_iter()
+            .enumerate()
+            .map(|(i, column)| (column.to_lowercase(), i))
+            .collect(),
+        None => return Err(error(1, "empty file".to_string())),
+    };
+    if let Some(missing) = REQUIRED
+        .iter()
+        .find(|column| !header.contains_key(**column))
+    {
+        return Err(error(1, format!("missing column `{}`", missing)));
+    }
+
+    let mut rows = Vec::new();
+    for (number, line) in lines {
+        let row = fields(line);
+        let field = |column: &str| header.get(column).and_then(|&i| row.get(i));
+        let number_field = |column: &str| {
+            let value = field(column).ok_or_else(|| error(number, format!("no `{}`", column)))?;
+            value
+                .parse::<f32>()
+                .map_err(|_| error(number, format!("invalid {} `{}`", column, value)))
+        };
+        let time = match field("time") {
+            Some(time) => Some(
+                time.parse::<f64>()
+                    .map_err(|_| error(number, format!("invalid time `{}`", time)))?,
+            ),
+            None => None,
+        };
+        let color = match field("color").filter(|color| !color.is_empty()) {
+            Some(color) => parse_color(color)
+                .ok_or_else(|| error(number, format!("invalid color `{}`", color)))?,
+            None => (0.7, 0.7, 0.7),
+        };
+        let mass = number_field("mass")?;
+        let body = BodyDescription {
+            name: field("name").cloned().unwrap_or_default(),
+            mass,
+            position: (number_field("x")?, number_field("y")?, number_field("z")?),
+            velocity: (
+                number_field("vx")?,
+                number_field("vy")?,
+                number_field("vz")?,
+            ),
+            orbit: None,
+            radius: number_field("radius")?,
+            density: None,
+            true_radius: None,
+            color,
+            light: None,
+            rings: None,
+            test_particle: mass <= 0.0,
+            beta: None,
+            texture: None,
+            group: None,
+        };
+        rows.push((time, body));
+    }
+
+    let last = rows
+        .iter()
+        .filter_map(|(time, _)| *time)
+        .fold(None, |last: Option<f64>, time| {
+            Some(last.map_or(time, |last| last.max(time)))
+        });
+    Ok(SceneDescription {
+        g_scale: units::solar_g_scale(AU_TO_UNIT_SCALE),
+        unit_scale: AU_TO_UNIT_SCALE,
+        bodies: rows
+            .into_iter()
+            .filter(|(time, _)| last.is_none() || *time == last)
+            .map(|(_, body)| body)
+            .collect(),
+        no_gravity: Vec::new(),
+    })
+}
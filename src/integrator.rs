@@ -0,0 +1,353 @@
+//! Integrators advancing the bodies by a step under their gravitational accelerations,
+//! independent of the ECS so that they can be tested on plain data
+use bevy::math::{DVec3, Vec3};
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::{Add, AddAssign},
+    str::FromStr,
+};
+
+use crate::{
+    barnes_hut::Octree,
+    forces::{Forces, Partition},
+};
+
+/// Numerical scheme used to advance the bodies by one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Integrator {
+    /// Semi-implicit Euler: one acceleration evaluation per step
+    #[default]
+    Euler,
+    /// Classic fourth-order Runge-Kutta: four acceleration evaluations per step
+    Rk4,
+    /// Symplectic kick-drift-kick leapfrog (velocity Verlet): bounded energy error
+    Leapfrog,
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "euler" => Ok(Integrator::Euler),
+            "rk4" => Ok(Integrator::Rk4),
+            "leapfrog" => Ok(Integrator::Leapfrog),
+            _ => Err(format!("unknown integrator `{}`", s)),
+        }
+    }
+}
+
+/// Number of integrations each physics step is divided into.
+/// When `adaptive`, more are used on steps where some pair of bodies would move by more than
+/// [`MAX_RELATIVE_MOTION`] of their separation, e.g. during a close flyby.
+#[derive(Clone, Copy)]
+pub struct Substeps {
+    pub count: u32,
+    pub adaptive: bool,
+}
+
+/// Largest fraction of the separation of two bodies they may cover relative to each other
+/// in one adaptive substep
+const MAX_RELATIVE_MOTION: f32 = 0.01;
+/// Upper bound of the adaptive substeps, so that a near-collision cannot stall the app
+const MAX_SUBSTEPS: u32 = 256;
+
+/// Storage of the positions and velocities of the integrators.
+/// Accelerations are always evaluated in single precision: only their accumulation over
+/// many steps needs more.
+pub trait Vector: Copy + Default + Add<Output = Self> + AddAssign {
+    fn from_vec3(v: Vec3) -> Self;
+    fn to_vec3(self) -> Vec3;
+    fn scale(self, s: f64) -> Self;
+}
+
+impl Vector for Vec3 {
+    fn from_vec3(v: Vec3) -> Self {
+        v
+    }
+
+    fn to_vec3(self) -> Vec3 {
+        self
+    }
+
+    fn scale(self, s: f64) -> Self {
+        self * s as f32
+    }
+}
+
+impl Vector for DVec3 {
+    fn from_vec3(v: Vec3) -> Self {
+        v.as_f64()
+    }
+
+    fn to_vec3(self) -> Vec3 {
+        self.as_f32()
+    }
+
+    fn scale(self, s: f64) -> Self {
+        self * s
+    }
+}
+
+/// State of all the bodies, e.g. gathered from the ECS, so that the integrators can
+/// evaluate accelerations at intermediate positions without touching the transforms.
+/// Buffers are kept between steps to avoid reallocating them.
+#[derive(Default)]
+pub struct Bodies<V> {
+    pub mass: Vec<f32>,
+    pub pos: Vec<V>,
+    pub vel: Vec<V>,
+    pub acc: Vec<Vec3>,
+    /// Post-Newtonian part of the accelerations, kept apart from `acc`, see
+    /// [`Forces::post_newtonian`]
+    pub pn: Vec<Vec3>,
+    partition: Partition,
+    /// Bodies pushed away by the Sun, with their `beta`, see [`Forces::radiation_pressure`]
+    irradiated: Vec<(usize, f32)>,
+    // RK4 scratch buffers
+    tmp_pos: Vec<V>,
+    tmp_vel: Vec<V>,
+    tmp_acc: Vec<Vec3>,
+    tmp_pn: Vec<Vec3>,
+    dpos: Vec<V>,
+    dvel: Vec<V>,
+    eval: Evaluation,
+}
+
+/// Buffers of the force computation, reused from one evaluation to the next
+#[derive(Default)]
+struct Evaluation {
+    /// Single-precision positions and velocities the accelerations are evaluated at
+    pos: Vec<Vec3>,
+    vel: Vec<Vec3>,
+    tree: Octree,
+}
+
+impl Evaluation {
+    /// Accelerations at `pos` moving at `vel`, written to `acc` and `pn`, radiation pressure
+    /// and drag being added to gravity in `acc`
+    #[allow(clippy::too_many_arguments)]
+    fn run<V: Vector>(
+        &mut self,
+        forces: &Forces,
+        mass: &[f32],
+        pos: &[V],
+        vel: &[V],
+        partition: &Partition,
+        irradiated: &[(usize, f32)],
+        acc: &mut [Vec3],
+        pn: &mut [Vec3],
+    ) {
+        self.pos.clear();
+        self.pos.extend(pos.iter().map(|p| p.to_vec3()));
+        forces.accelerations(&mut self.tree, mass, &self.pos, partition, acc);
+        forces.radiation_pressure(mass, &self.pos, irradiated, acc);
+        if depends_on_velocity(forces) {
+            self.vel.clear();
+            self.vel.extend(vel.iter().map(|v| v.to_vec3()));
+        }
+        forces.drag(&self.vel, partition, acc);
+        forces.post_newtonian(mass, &self.pos, &self.vel, pn);
+    }
+}
+
+/// Whether the accelerations depend on the velocities, with drag or the post-Newtonian
+/// correction, and not only on the positions
+fn depends_on_velocity(forces: &Forces) -> bool {
+    forces.speed_of_light.is_some() || forces.drag.k != 0.0
+}
+
+/// Velocity change over `dt` under the acceleration `acc + pn`, whose parts are added in
+/// the precision of `V` so that the small one is not rounded away
+fn kick<V: Vector>(acc: Vec3, pn: Vec3, dt: f64) -> V {
+    V::from_vec3(acc).scale(dt) + V::from_vec3(pn).scale(dt)
+}
+
+impl<V: Vector> Bodies<V> {
+    pub fn clear(&mut self) {
+        self.mass.clear();
+        self.pos.clear();
+        self.vel.clear();
+        self.partition.massive.clear();
+        self.partition.particles.clear();
+        self.partition.groups.clear();
+        self.irradiated.clear();
+    }
+
+    /// Add a body, which attracts the others unless it is a test particle
+    pub fn push(&mut self, mass: f32, pos: V, vel: V, particle: bool) {
+        if particle {
+            self.partition.particles.push(self.mass.len());
+        } else {
+            self.partition.massive.push(self.mass.len());
+        }
+        self.mass.push(mass);
+        self.pos.push(pos);
+        self.vel.push(vel);
+    }
+
+    /// Push the last added body away from the Sun, with `beta` times its gravity
+    pub fn irradiate(&mut self, beta: f32) {
+        if let Some(last) = self.mass.len().checked_sub(1) {
+            self.irradiated.push((last, beta));
+        }
+    }
+
+    /// Put the last added body in `group` of the [`Forces::interactions`], rather than in
+    /// group 0
+    pub fn join(&mut self, group: usize) {
+        if let Some(last) = self.mass.len().checked_sub(1) {
+            self.partition.groups.resize(self.mass.len(), 0);
+            self.partition.groups[last] = group;
+        }
+    }
+
+    /// Advance all the bodies by `dt`, leaving in `acc` the last evaluated acceleration
+    pub fn step(&mut self, integrator: Integrator, forces: &Forces, dt: f64) {
+        self.substep(integrator, forces, dt, false);
+    }
+
+    /// [`Bodies::step`], whose leapfrog starts from the accelerations left in `acc` and
+    /// `pn` by the previous step if `reuse`
+    fn substep(&mut self, integrator: Integrator, forces: &Forces, dt: f64, reuse: bool) {
+        self.acc.resize(self.pos.len(), Vec3::ZERO);
+        self.pn.resize(self.pos.len(), Vec3::ZERO);
+        match integrator {
+            Integrator::Euler => self.euler(forces, dt),
+            Integrator::Rk4 => self.rk4(forces, dt),
+            Integrator::Leapfrog => self.leapfrog(forces, dt, reuse),
+        }
+    }
+
+    /// Advance all the bodies by `dt` in as many substeps as `substeps` asks for
+    pub fn advance(
+        &mut self,
+        integrator: Integrator,
+        forces: &Forces,
+        dt: f64,
+        substeps: Substeps,
+    ) {
+        let mut count = substeps.count;
+        if substeps.adaptive {
+            let needed = (self.max_relative_motion(dt) / MAX_RELATIVE_MOTION).ceil() as u32;
+            count = count.max(needed.min(MAX_SUBSTEPS));
+        }
+        // A leapfrog substep ends with the accelerations at the positions the next one starts
+        // from, unless they depend on the velocities, which its last half-kick changed
+        let reuse = !depends_on_velocity(forces);
+        for substep in 0..count {
+            self.substep(integrator, forces, dt / count as f64, reuse && substep > 0);
+        }
+    }
+
+    /// Largest `|v_i - v_j| * dt / |x_i - x_j|` over all pairs with a massive body
+    fn max_relative_motion(&self, dt: f64) -> f32 {
+        let mut max = 0.0f32;
+        for &i in &self.partition.massive {
+            for j in (0..self.pos.len()).filter(|&j| j != i) {
+                let distance = (self.pos[j].to_vec3() - self.pos[i].to_vec3()).length();
+                let speed = (self.vel[j].to_vec3() - self.vel[i].to_vec3()).length();
+                if distance > 0.0 {
+                    max = max.max(speed * dt as f32 / distance);
+                }
+            }
+        }
+        max
+    }
+
+    fn accelerations(&mut self, forces: &Forces) {
+        let Self {
+            eval,
+            mass,
+            pos,
+            vel,
+            partition,
+            irradiated,
+            acc,
+            pn,
+            ..
+        } = self;
+        eval.run(forces, mass, pos, vel, partition, irradiated, acc, pn);
+    }
+
+    fn euler(&mut self, forces: &Forces, dt: f64) {
+        self.accelerations(forces);
+        for i in 0..self.pos.len() {
+            self.vel[i] += kick(self.acc[i], self.pn[i], dt);
+            self.pos[i] += self.vel[i].scale(dt);
+        }
+    }
+
+    /// Half-kick the velocities, drift the positions, then half-kick again with the
+    /// accelerations at the new positions. Being symplectic, the energy oscillates
+    /// around its initial value instead of drifting away. Velocity-dependent corrections
+    /// are evaluated at the half-kicked velocities.
+    /// The accelerations are evaluated twice per step, or once when `reuse` gives the first
+    /// half-kick those the previous step ended with, between substeps.
+    fn leapfrog(&mut self, forces: &Forces, dt: f64, reuse: bool) {
+        if !reuse {
+            self.accelerations(forces);
+        }
+        for i in 0..self.pos.len() {
+            self.vel[i] += kick(self.acc[i], self.pn[i], dt / 2.0);
+            self.pos[i] += self.vel[i].scale(dt);
+        }
+        self.accelerations(forces);
+        for i in 0..self.pos.len() {
+            self.vel[i] += kick(self.acc[i], self.pn[i], dt / 2.0);
+        }
+    }
+
+    /// For `x'' = a(x, x')`, stage `s + 1` is evaluated at
+    /// `x_s+1 = x + h * dt * v_s` and `v_s+1 = v + h * dt * a(x_s, v_s)`
+    /// and the step is the weighted sum `(k1 + 2*k2 + 2*k3 + k4) / 6` of all stages.
+    fn rk4(&mut self, forces: &Forces, dt: f64) {
+        let n = self.pos.len();
+        self.accelerations(forces);
+        self.tmp_pos.resize(n, V::default());
+        self.tmp_vel.clone_from(&self.vel);
+        self.tmp_acc.clone_from(&self.acc);
+        self.tmp_pn.clone_from(&self.pn);
+        self.dpos.clear();
+        self.dpos.resize(n, V::default());
+        self.dvel.clear();
+        self.dvel.resize(n, V::default());
+
+        for &(weight, next) in &[
+            (1.0, Some(0.5)),
+            (2.0, Some(0.5)),
+            (2.0, Some(1.0)),
+            (1.0, None),
+        ] {
+            for i in 0..n {
+                self.dpos[i] += self.tmp_vel[i].scale(weight);
+                self.dvel[i] += kick(self.tmp_acc[i], self.tmp_pn[i], weight);
+            }
+            if let Some(h) = next {
+                for i in 0..n {
+                    self.tmp_pos[i] = self.pos[i] + self.tmp_vel[i].scale(h * dt);
+                    self.tmp_vel[i] = self.vel[i] + kick(self.tmp_acc[i], self.tmp_pn[i], h * dt);
+                }
+                let Self {
+                    eval,
+                    mass,
+                    tmp_pos,
+                    tmp_vel,
+                    partition,
+                    irradiated,
+                    tmp_acc,
+                    tmp_pn,
+                    ..
+                } = self;
+                eval.run(
+                    forces, mass, tmp_pos, tmp_vel, partition, irradiated, tmp_acc, tmp_pn,
+                );
+            }
+        }
+
+        for i in 0..n {
+            self.pos[i] += self.dpos[i].scale(dt / 6.0);
+            self.vel[i] += self.dvel[i].scale(dt / 6.0);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Two-body orbits: osculating orbital elements of a body around a central mass.
+//!
+//! The reference plane is XY (the ecliptic in the solar system) and the reference
+//! direction is X. Computations are done in double precision since the elements of
+//! nearly circular or equatorial orbits are sensitive to rounding.
+use bevy::math::{DQuat, DVec3, Vec3};
+use std::f64::consts::TAU;
+
+/// Below this, orbits are considered circular or equatorial, and the angles that are
+/// undefined for them are measured from the reference direction instead
+const EPSILON: f64 = 1e-9;
+
+/// Classic Keplerian elements, angles in radians
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitalElements {
+    /// Negative for hyperbolic orbits, infinite for parabolic ones
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    /// Angle from the reference direction to the ascending node, `Ω`
+    pub longitude_of_ascending_node: f64,
+    /// Angle from the ascending node to the periapsis, `ω`
+    pub argument_of_periapsis: f64,
+    /// Angle from the periapsis to the body, `ν`
+    pub true_anomaly: f64,
+}
+
+impl OrbitalElements {
+    pub fn is_bound(&self) -> bool {
+        self.eccentricity < 1.0
+    }
+
+    /// Rotation from the plane of the orbit, with X towards the periapsis, to the reference
+    /// frame
+    fn rotation(&self) -> DQuat {
+        DQuat::from_rotation_z(self.longitude_of_ascending_node)
+            * DQuat::from_rotation_x(self.inclination)
+            * DQuat::from_rotation_z(self.argument_of_periapsis)
+    }
+
+    /// Distance to the central mass at true anomaly `nu`
+    fn radius_at(&self, nu: f64) -> f64 {
+        let e = self.eccentricity;
+        self.semi_major_axis * (1.0 - e * e) / (1.0 + e * nu.cos())
+    }
+
+    /// Position relative to the central mass at true anomaly `nu`, for sampling the orbit
+    pub fn position_at(&self, nu: f64) -> Vec3 {
+        let r = self.radius_at(nu);
+        (self.rotation() * DVec3::new(r * nu.cos(), r * nu.sin(), 0.0)).as_f32()
+    }
+
+    /// Distance to the central mass at the periapsis, `q = a (1 - e)`
+    pub fn periapsis(&self) -> f64 {
+        self.radius_at(0.0)
+    }
+
+    /// Distance to the central mass at the apoapsis, `Q = a (1 + e)`, `None` if the orbit is
+    /// not bound
+    pub fn apoapsis(&self) -> Option<f64> {
+        if self.is_bound() {
+            Some(self.radius_at(std::f64::consts::PI))
+        } else {
+            None
+        }
+    }
+
+    /// Unbound orbits stay within `max_distance` of the central mass for true anomalies in
+    /// `[-max, max]`, always short of their asymptotes. `None` for bound orbits.
+    pub fn max_true_anomaly(&self, max_distance: f64) -> Option<f64> {
+        if self.is_bound() {
+            return None;
+        }
+        let e = self.eccentricity;
+        let p = self.semi_major_axis * (1.0 - e * e);
+        Some(((p / max_distance - 1.0) / e).clamp(-1.0, 1.0).acos())
+    }
+
+    /// Mean anomaly of a bound orbit at true anomaly `nu`: unlike the true anomaly, it grows
+    /// uniformly with time, by `2π` per period
+    pub fn mean_anomaly(&self, nu: f64) -> f64 {
+        let e = self.eccentricity;
+        let eccentric =
+            2.0 * ((1.0 - e).sqrt() * (nu / 2.0).sin()).atan2((1.0 + e).sqrt() * (nu / 2.0).cos());
+        eccentric - e * eccentric.sin()
+    }
+
+    /// True anomaly of a bound orbit at mean anomaly `mean`, from Kepler's equation
+    /// `M = E - e * sin(E)` solved for the eccentric anomaly `E` with Newton's method
+    pub fn true_anomaly_at(&self, mean: f64) -> f64 {
+        let e = self.eccentricity;
+        let mean = mean.rem_euclid(TAU);
+        // Starting from π converges for all eccentricities
+        let mut eccentric = if e < 0.8 { mean } else { std::f64::consts::PI };
+        for _ in 0..50 {
+            let delta = (eccentric - e * eccentric.sin() - mean) / (1.0 - e * eccentric.cos());
+            eccentric -= delta;
+            if delta.abs() < EPSILON {
+                break;
+            }
+        }
+        2.0 * ((1.0 + e).sqrt() * (eccentric / 2.0).sin())
+            .atan2((1.0 - e).sqrt() * (eccentric / 2.0).cos())
+    }
+
+    /// Time to go around the orbit once from Kepler's third law `T = 2π sqrt(a^3 / mu)`,
+    /// `None` if it is not bound
+    pub fn period(&self, mu: f64) -> Option<f64> {
+        if self.is_bound() && mu > 0.0 {
+            Some(TAU * (self.semi_major_axis.powi(3) / mu).sqrt())
+        } else {
+            None
+        }
+    }
+}
+
+/// Signed angle from `from` to `to` around `axis`, in `[0, 2π)`
+fn angle(from: DVec3, to: DVec3, axis: DVec3) -> f64 {
+    let angle = from.cross(to).dot(axis).atan2(from.dot(to));
+    angle.rem_euclid(TAU)
+}
+
+/// Of `bodies`, given with their masses and positions, the heavier than `mass` pulling the
+/// hardest, `m / r^2`, on a body at `pos`: the central mass of its orbit, e.g. the Sun for a
+/// planet and the planet for its moons. `None` if none is heavier.
+pub fn dominant_attractor<T>(
+    mass: f32,
+    pos: Vec3,
+    bodies: impl IntoIterator<Item = (f32, Vec3, T)>,
+) -> Option<T> {
+    bodies
+        .into_iter()
+        .filter(|(m, ..)| *m > mass)
+        .map(|(m, p, body)| (m / p.distance_squared(pos), body))
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, body)| body)
+}
+
+/// Elements of the orbit of a body at `pos` moving at `vel`, relative to the central mass,
+/// with `mu = G * (M + m)`
+pub fn orbital_elements(pos: Vec3, vel: Vec3, mu: f32) -> OrbitalElements {
+    let (r, v, mu) = (pos.as_f64(), vel.as_f64(), mu as f64);
+    let h = r.cross(v);
+    let h_dir = h.normalize_or_zero();
+    let energy = v.length_squared() / 2.0 - mu / r.length();
+    let e = ((v.length_squared() - mu / r.length()) * r - r.dot(v) * v) / mu;
+    let eccentricity = e.length();
+
+    // Vis-viva: the specific orbital energy is `-mu / (2 * a)`
+    let semi_major_axis = if energy.abs() < EPSILON {
+        f64::INFINITY
+    } else {
+        -mu / (2.0 * energy)
+    };
+    let inclination = if h.length() > 0.0 {
+        (h.z / h.length()).clamp(-1.0, 1.0).acos()
+    } else {
+        0.0
+    };
+
+    // Line of nodes, along the reference direction for equatorial orbits
+    let node = DVec3::Z.cross(h);
+    let node = if node.length() > EPSILON * h.length() {
+        node.normalize()
+    } else {
+        DVec3::X
+    };
+    // Direction of the periapsis, on the ascending node for circular orbits
+    let periapsis = if eccentricity > EPSILON {
+        e / eccentricity
+    } else {
+        node
+    };
+
+    OrbitalElements {
+        semi_major_axis,
+        eccentricity,
+        inclination,
+        longitude_of_ascending_node: angle(DVec3::X, node, DVec3::Z),
+        argument_of_periapsis: angle(node, periapsis, h_dir),
+        true_anomaly: angle(periapsis, r, h_dir),
+    }
+}
+
+/// Position and velocity relative to the central mass of a body on the orbit described by
+/// `elements`, with `mu = G * (M + m)`. Parabolic orbits, of infinite semi-major axis,
+/// cannot be described this way.
+pub fn state_from_elements(elements: &OrbitalElements, mu: f32) -> (Vec3, Vec3) {
+    let e = elements.eccentricity;
+    let nu = elements.true_anomaly;
+    // Semi-latus rectum
+    let p = elements.semi_major_axis * (1.0 - e * e);
+    let r = elements.radius_at(nu);
+    let speed = (mu as f64 / p).sqrt();
+
+    // In the plane of the orbit, with X towards the periapsis
+    let pos = DVec3::new(r * nu.cos(), r * nu.sin(), 0.0);
+    let vel = DVec3::new(-speed * nu.sin(), speed * (e + nu.cos()), 0.0);
+    let rotation = elements.rotation();
+    ((rotation * pos).as_f32(), (rotation * vel).as_f32())
+}
@@ -0,0 +1,65 @@
+//! Lagrange points of a pair of bodies on a circular orbit around each other: where a test
+//! particle co-rotating with them feels no net force. L1, L2 and L3 are on the line of the
+//! bodies, unstable, and L4 and L5 are at the third corners of the two equilateral
+//! triangles with them, stable for a mass ratio below 1/25, e.g. the Trojans of Jupiter.
+use bevy::math::{DVec3, Vec3};
+
+/// Bisection iterations for L1 to L3, far more than needed to reach double precision
+const ITERATIONS: usize = 200;
+
+/// Net acceleration along the line of the bodies in their co-rotating frame, in units
+/// where their separation, total mass and `G` are 1, at `x` from their barycenter: the
+/// primary is at `-mu` and the secondary at `1 - mu`, `mu` being the share of its mass
+fn collinear_force(x: f64, mu: f64) -> f64 {
+    let (d1, d2) = (x + mu, x - 1.0 + mu);
+    x - (1.0 - mu) * d1 / d1.abs().powi(3) - mu * d2 / d2.abs().powi(3)
+}
+
+/// Root of [`collinear_force`] between `low` and `high`, where it goes from negative to
+/// positive
+fn collinear_point(mut low: f64, mut high: f64, mu: f64) -> f64 {
+    for _ in 0..ITERATIONS {
+        let mid = (low + high) / 2.0;
+        if collinear_force(mid, mu) < 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// L1 to L5 of a `primary` and a `secondary` at their positions, orbiting around `normal`
+/// counterclockwise: L4 leads the secondary by 60° on its orbit and L5 trails it
+pub fn lagrange_points(
+    primary_mass: f32,
+    primary: Vec3,
+    secondary_mass: f32,
+    secondary: Vec3,
+    normal: Vec3,
+) -> [Vec3; 5] {
+    let (m1, m2) = (primary_mass as f64, secondary_mass as f64);
+    let mu = m2 / (m1 + m2);
+    let separation = (secondary - primary).as_f64();
+    let distance = separation.length();
+    let x = separation / distance;
+    let y = normal.as_f64().normalize_or_zero().cross(x);
+    let barycenter = primary.as_f64() + separation * mu;
+
+    // The force changes sign once between the bodies and once beyond each of them, where
+    // it tends to infinity: the brackets stop just short of the bodies
+    let margin = 1e-9;
+    let l1 = collinear_point(-mu + margin, 1.0 - mu - margin, mu);
+    let l2 = collinear_point(1.0 - mu + margin, 2.0, mu);
+    let l3 = collinear_point(-2.0, -mu - margin, mu);
+    let on_line = |offset: f64| barycenter + x * offset * distance;
+    let corner = |side: f64| primary.as_f64() + (x * 0.5 + y * side * 3f64.sqrt() / 2.0) * distance;
+    let points: [DVec3; 5] = [
+        on_line(l1),
+        on_line(l2),
+        on_line(l3),
+        corner(1.0),
+        corner(-1.0),
+    ];
+    points.map(|point| point.as_f32())
+}
@@ -0,0 +1,76 @@
+//! Parts of the simulation that do not depend on the ECS, and the Bevy plugins simulating
+//! the bodies and orbiting the camera around them, for other apps to embed the simulation.
+//!
+//! Other systems can react to what happens in the simulation, e.g. to log it or play a
+//! sound, with the events the plugins send, headless or not: a [`CollisionEvent`] from the
+//! [`NBody`] plugin, an [`EjectionEvent`] from the [`EscapesPlugin`], and a
+//! [`CloseApproachEvent`] from the [`ApproachPlugin`], for the bodies of its
+//! [`ApproachPair`](plugins::approach::ApproachPair).
+//!
+//! Three bodies on the figure-8 orbit, drawn as spheres of their colors:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use copernicus::{BodyBundle, Gravity, NBody, PanOrbitCamera, PanOrbitCameraPlugin};
+//!
+//! fn main() {
+//!     App::build()
+//!         .add_plugins(DefaultPlugins)
+//!         .add_plugin(NBody::default())
+//!         .add_plugin(PanOrbitCameraPlugin::default())
+//!         // In the units of the positions, velocities and masses, instead of the Solar System
+//!         .insert_resource(Gravity(1.0))
+//!         .add_startup_system(spawn.system())
+//!         .run();
+//! }
+//!
+//! fn spawn(mut commands: Commands) {
+//!     let x1 = Vec3::new(0.970_004_4, -0.243_087_5, 0.0);
+//!     let v3 = Vec3::new(-0.932_407_4, -0.864_731_5, 0.0);
+//!     let bodies = [
+//!         (x1, -v3 / 2.0, Color::RED),
+//!         (-x1, -v3 / 2.0, Color::GREEN),
+//!         (Vec3::ZERO, v3, Color::BLUE),
+//!     ];
+//!     for &(pos, vel, color) in bodies.iter() {
+//!         let body = BodyBundle::new(1.0, pos, vel).with_radius(0.05).with_color(color);
+//!         commands.spawn_bundle(body);
+//!     }
+//!
+//!     commands.spawn_bundle(LightBundle {
+//!         transform: Transform::from_xyz(0.0, 0.0, 5.0),
+//!         ..Default::default()
+//!     });
+//!     commands
+//!         .spawn_bundle(PerspectiveCameraBundle {
+//!             transform: Transform::from_xyz(0.0, 0.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+//!             ..Default::default()
+//!         })
+//!         .insert(PanOrbitCamera {
+//!             radius: 3.0,
+//!             ..Default::default()
+//!         });
+//! }
+//! ```
+
+// `#[derive(Bundle)]` in bevy 0.5 forgets its (non-`Drop`) fields
+#![allow(clippy::forget_non_drop)]
+
+pub mod barnes_hut;
+pub mod forces;
+pub mod integrator;
+pub mod kepler;
+pub mod units;
+
+pub mod plugins {
+    pub mod approach;
+    pub mod escapes;
+    pub mod nbody;
+    pub mod pan_orbit_camera;
+    pub mod restart;
+}
+
+pub use plugins::approach::{ApproachPlugin, CloseApproachEvent};
+pub use plugins::escapes::{EjectionEvent, EscapesPlugin};
+pub use plugins::nbody::{BodyBundle, CollisionEvent, Gravity, NBody};
+pub use plugins::pan_orbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
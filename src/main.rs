@@ -1,38 +1,689 @@
+// `#[derive(Bundle)]` in bevy 0.5 forgets its (non-`Drop`) fields
+#![allow(clippy::forget_non_drop)]
+
 use argh::FromArgs;
 use bevy::prelude::*;
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     pbr::AmbientLight,
 };
+use copernicus::{
+    forces::{Algorithm, Drag},
+    integrator::Integrator,
+    kepler, units,
+};
+use rand::Rng;
+use rand_distr::{Distribution, UnitSphere};
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
+mod batch;
+mod calendar;
+mod export;
+#[cfg(feature = "horizons")]
+mod horizons;
+mod import;
+mod lagrange;
 mod plugins;
-use plugins::nbody::{BodyBundle, Gravity, NBody};
-use plugins::pan_orbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
+mod rings;
+mod scene;
+mod snapshot;
+use calendar::Date;
+use export::{export_csv, CsvExport};
+use plugins::approach::{ApproachPlugin, BodyPair};
+use plugins::apsides::ApsidesPlugin;
+use plugins::asteroids::AsteroidsPlugin;
+use plugins::clock::ClockPlugin;
+use plugins::colors::{ColorBy, ColorsPlugin};
+use plugins::comet::CometPlugin;
+use plugins::compare::{ComparePlugin, Comparison};
+use plugins::diagnostics::NBodyDiagnosticsPlugin;
+use plugins::eclipses::EclipsesPlugin;
+use plugins::escapes::EscapesPlugin;
+use plugins::field::{FieldLinesPlugin, FieldPlugin};
+use plugins::headless::HeadlessPlugin;
+use plugins::hohmann::HohmannPlugin;
+use plugins::hud::HudPlugin;
+use plugins::inclinations::InclinationsPlugin;
+use plugins::labels::LabelsPlugin;
+use plugins::lagrange::LagrangePlugin;
+use plugins::lines::LinesPlugin;
+use plugins::lod::LodPlugin;
+use plugins::minimap::MinimapPlugin;
+use plugins::nbody::{
+    BodyBundle, Collisions, ExternalField, Gravity, NBody, Pacing, PhysicsSystem, Precision,
+    ReferenceFrame, TestParticle, TrueRadius,
+};
+use plugins::orbits::OrbitsPlugin;
+use plugins::pan_orbit_camera::{CameraFocus, PanOrbitCamera, PanOrbitCameraPlugin, Projection};
+use plugins::panel::ControlPanelPlugin;
+use plugins::picking::PickingPlugin;
+use plugins::planets::{PlanetDescription, PlanetsPlugin};
+use plugins::plot::PlotPlugin;
+use plugins::random::{RandomPlugin, SimRng};
+use plugins::replay::{RecordPlugin, Recorder, Recording, ReplayPlugin};
+use plugins::restart::RestartPlugin;
+use plugins::sandbox::SandboxPlugin;
+use plugins::screenshot::ScreenshotPlugin;
+use plugins::sizes::{SizeScale, SizesPlugin};
+use plugins::slingshot::SlingshotPlugin;
+#[cfg(feature = "native")]
+use plugins::sound::SoundPlugin;
+use plugins::spacecraft::SpacecraftPlugin;
+use plugins::spin::{Spin, SpinPlugin};
+use plugins::starfield::StarfieldPlugin;
+use plugins::stop::StopPlugin;
+use plugins::summary::{Summary, SummaryPlugin};
+use plugins::supernova::{NovaPlugin, SupernovaPlugin};
+use plugins::textures::{SurfaceTexture, TexturesPlugin};
+use plugins::tidal::TidalPlugin;
+use plugins::trails::TrailsPlugin;
+use plugins::trojans::TrojansPlugin;
+use plugins::ui::UiCameraPlugin;
+use plugins::vectors::VectorsPlugin;
+use rings::{spawn_rings, Rings};
+use scene::{spawn_body, spawn_scene, Appearance, SceneDescription};
+use snapshot::{restore_snapshot, save_snapshot, SavePath, Snapshot};
 
 /**
 Several `--startup` options:
+* solar: planets of the Solar System - and Pluto :'( - with data from JPL Horizons as-of 2021-04-18, the Moon, the Galilean moons and Titan.
 * figure8: stable figure-8 three-body solution
+* binary: a planet orbiting a pair of stars
+* cluster: `--bodies` stars of a Plummer-sphere star cluster
+* disk: a star surrounded by a thin disk of `--bodies` planetesimals and dust, which
+  collide and merge into clumps, a toy model of planet formation
+* slingshot: a spacecraft gaining speed in a flyby of a planet, a gravity assist
+* trojans: `--bodies` asteroids around the L4 and L5 points of the Sun and Jupiter, seen
+  in their co-rotating frame
+* comet: Halley's comet, releasing dust blown into a tail by radiation pressure near the
+  Sun, `--comet-planets` adding the planets of the solar startup
+* nova: a star with planets on eccentric orbits, going supernova after a few orbits, which
+  unbinds most of them
 
 Mouse controls:
+* left-click a body to inspect it in the control panel
 * right-click & drag to orbit the camera
+* middle-click & drag, or shift & right-click & drag, to pan the camera
 * scroll to zoom
 
+Touch controls:
+* drag to orbit the camera
+* pinch to zoom, drag with two fingers to pan
+
+Keyboard controls:
+* WASD to orbit the camera, +/- to zoom, C to reset it
+* F to fit all the bodies in view
+* T to look down on the XY plane (the ecliptic), E to look at it edge-on
+* P to switch between the perspective and orthographic projections
+* space to pause/resume the simulation
+* right arrow to advance by a single step while paused
+* up/down arrows to burn the `--spacecraft` prograde/retrograde, left/right arrows along
+  the normal of its orbit
+* F5 to save the state of the simulation to `--save-state`
+* R to restart the simulation from its initial conditions and seed, resetting the camera
+* 1-9 to follow a body with the camera, 0 to go back to the barycenter
+* Tab to show/hide the control panel
+* F3 to show/hide the frame rate, the number of bodies, the simulated time and the speed
+  factor in the bottom-left corner, and how many bodies are bound to the star of the last
+  supernova
+* N to make the selected body, or the heaviest one, go supernova with `--sandbox` or the
+  nova startup
+* F12 to save a screenshot in the working directory
+
 */
 #[derive(FromArgs)]
 struct Flags {
+    /// startup system [solar (default)|figure8|binary|cluster|disk|slingshot|trojans|comet|
+    /// nova]
+    #[argh(option, default = "Startup::Solar")]
+    startup: Startup,
+
+    /// number of stars of the cluster startup, bodies of the disk one, or asteroids of the
+    /// trojans one [default: 500]
+    #[argh(option, default = "500")]
+    bodies: usize,
+
+    /// add the planets of the solar startup around the Sun of the comet one
+    #[argh(switch)]
+    comet_planets: bool,
+
+    /// RON scene file to load instead of the startup system
+    #[argh(option)]
+    scene: Option<String>,
+
+    /// date of the solar startup, YYYY-MM-DD, with the state vectors of JPL Horizons at this
+    /// date instead of 2021-04-18, cached for the next launches
+    #[cfg(feature = "horizons")]
+    #[argh(option)]
+    horizons: Option<Date>,
+
+    /// file where F5 saves the state of the simulation, as do headless runs when they stop
+    #[argh(option)]
+    save_state: Option<String>,
+
+    /// state saved with F5 to restore, replacing the startup system, the speed, integrator,
+    /// substeps, time step and precision
+    #[argh(option)]
+    load_state: Option<String>,
+
+    /// CSV file of bodies to load instead of the startup system, with columns name, mass,
+    /// radius, x, y, z, vx, vy, vz and color, in the units of the scene files
+    #[argh(option)]
+    import_csv: Option<String>,
+
+    /// CSV file where the positions and velocities of all the bodies are written, in AU and
+    /// AU per unit of time
+    #[argh(option)]
+    export_csv: Option<String>,
+
+    /// steps between two rows of `--export-csv` [default: 1]
+    #[argh(option, default = "1")]
+    export_every: u64,
+
     /// speed of the simulation [default: 1.0x]
     #[argh(option, default = "1.0")]
     speed: f32,
 
+    /// physics steps per second of real time, whatever the frame rate [default: 60]
+    #[argh(option, default = "60.0")]
+    physics_hz: f64,
+
+    /// simulated time per physics step, in days for the solar startup, steps then running
+    /// --speed / dt times per second rather than --physics-hz
+    #[argh(option)]
+    dt: Option<f64>,
+
+    /// integration scheme [euler (default)|rk4|leapfrog]
+    #[argh(option, default = "Integrator::Euler")]
+    integrator: Integrator,
+
+    /// run the bodies a second time with another integrator, drawn as faded ghosts, e.g.
+    /// `euler,rk4` for Euler ghosts of bodies run with RK4, overriding --integrator
+    #[argh(option)]
+    compare: Option<Comparison>,
+
+    /// gravitational softening length, in scene units [default: 0.0]
+    #[argh(option, default = "0.0")]
+    softening: f32,
+
+    /// force computation [brute (default)|barnes-hut]
+    #[argh(option, default = "Algorithm::BruteForce")]
+    algorithm: Algorithm,
+
+    /// integrations per physics step [default: 1]
+    #[argh(option, default = "1")]
+    substeps: u32,
+
+    /// add substeps on steps where bodies come close to each other, always on for the solar
+    /// startup and its moons, and the slingshot, comet and nova ones
+    #[argh(switch)]
+    adaptive_substeps: bool,
+
+    /// keep positions and velocities in double precision, for long runs, always on for the
+    /// solar startup and its moons
+    #[argh(switch)]
+    double_precision: bool,
+
+    /// ramp in and out of a slow motion with M, e.g. for a flyby, which adds substeps to
+    /// stay accurate
+    #[argh(switch)]
+    cinematic: bool,
+
+    /// add a belt of N asteroids between Mars and Jupiter
+    #[argh(option)]
+    asteroids: Option<usize>,
+
+    /// add a planet around the heaviest body from its orbital elements, e.g.
+    /// `a=2.5,e=0.1,i=5,mass=6` (AU, degrees and 10^24 kg), can be repeated
+    #[argh(option)]
+    planet: Vec<PlanetDescription>,
+
+    /// add N asteroids as massless test particles, which only feel gravity
+    #[argh(option, default = "0")]
+    particles: usize,
+
+    /// seed of the random scenarios, e.g. the asteroid orbits [default: random, logged]
+    #[argh(option)]
+    seed: Option<u64>,
+
+    /// what happens when bodies overlap [off (default, merge for the disk startup)|merge|
+    /// bounce]
+    #[argh(option, default = "Collisions::Off")]
+    collisions: Collisions,
+
+    /// coefficient of restitution of bouncing collisions, from 0 (the bodies move on
+    /// together) to 1 (elastic) [default: 1]
+    #[argh(option, default = "1.0")]
+    restitution: f32,
+
+    /// tear apart the bodies passing within the Roche limit of a heavier one, from the
+    /// densities of their true sizes, into `--fragments` bodies
+    #[argh(switch)]
+    tidal_breakup: bool,
+
+    /// number of fragments of `--tidal-breakup` [default: 8]
+    #[argh(option, default = "8")]
+    fragments: usize,
+
+    /// add the first post-Newtonian correction of general relativity around the heaviest
+    /// body, which makes orbits close to it precess (best with `--double-precision`)
+    #[argh(switch)]
+    relativistic: bool,
+
+    /// speed of light of `--relativistic` in scene units per unit of time [default: 0.1 AU
+    /// per day, the units of the solar startup]
+    #[argh(option, default = "solar_speed_of_light()")]
+    speed_of_light: f32,
+
+    /// push the bodies with a `beta` in their scene, e.g. comet dust, away from the heaviest
+    /// body by `beta` times its gravity, always on for the comet startup
+    #[argh(switch)]
+    radiation_pressure: bool,
+
+    /// uniform acceleration of every body on top of gravity, in scene units per unit of
+    /// time squared, e.g. `0,0,-0.01` [default: 0,0,0]
+    #[argh(option, default = "ExternalField::default()")]
+    external_field: ExternalField,
+
+    /// drag coefficient `k` of a medium slowing every body down by `k` times its velocity,
+    /// per unit of time, so that orbits decay [default: 0]
+    #[argh(option, default = "0.0")]
+    drag: f32,
+
+    /// only the test particles feel `--drag`, not the massive bodies
+    #[argh(switch)]
+    drag_particles_only: bool,
+
+    /// turn the bodies of a group of the scene into test particles, which feel the massive
+    /// bodies but attract neither them nor each other: N particles cost O(N * M) with M
+    /// massive bodies instead of O(N^2), e.g. for a dense cloud tracing the field, can be
+    /// repeated
+    #[argh(option)]
+    no_self_gravity: Vec<String>,
+
+    /// despawn unbound bodies once they are `--cull-distance` away from the barycenter
+    #[argh(switch)]
+    cull_escaped: bool,
+
+    /// distance, in scene units (0.1 AU), past which escaped bodies are culled [default: 500]
+    #[argh(option, default = "500.0")]
+    cull_distance: f32,
+
+    /// calendar date at time 0 of a scene in days, YYYY-MM-DD [default: 2021-04-18 for the
+    /// solar startup]
+    #[argh(option)]
+    epoch: Option<Date>,
+
+    /// origin of the view, the physics is unchanged, the trojans startup rotating with the Sun
+    /// and Jupiter [origin (default)|barycenter|body:<name>|rotating:<a>,<b>|
+    /// corotating:<primary>:<secondary>]
+    #[argh(option, default = "ReferenceFrame::Origin")]
+    frame: ReferenceFrame,
+
+    /// draw the Hohmann transfer between the orbits of two named bodies around the heaviest
+    /// one, e.g. `Earth,Mars`
+    #[argh(option)]
+    hohmann: Option<BodyPair>,
+
+    /// launch a spacecraft around the named body, steered with the arrow keys
+    #[argh(option)]
+    spacecraft: Option<String>,
+
+    /// log the closest approaches of two named bodies, e.g. `Earth,Mars`
+    #[argh(option)]
+    approach: Option<BodyPair>,
+
+    /// log the eclipses of bodies passing into the shadow of another one, cast by the stars
+    #[argh(switch)]
+    eclipses: bool,
+
+    /// mark the Lagrange points of two named bodies, e.g. `Sun,Jupiter`, which the trojans
+    /// startup does
+    #[argh(option)]
+    lagrange: Option<BodyPair>,
+
+    /// draw a fading trail behind each body
+    #[argh(switch)]
+    trails: bool,
+
+    /// number of positions kept in each trail [default: 500]
+    #[argh(option, default = "500")]
+    trail_length: usize,
+
+    /// draw no background of distant stars
+    #[argh(switch)]
+    no_stars: bool,
+
+    /// show the name of each body next to it
+    #[argh(switch)]
+    labels: bool,
+
+    /// show the orbital period of each body under its label, with --labels
+    #[argh(switch)]
+    label_periods: bool,
+
+    /// sizes of the bodies, Z switches between them [realistic|exaggerated (default)]
+    #[argh(option, default = "SizeScale::Exaggerated")]
+    scale_sizes: SizeScale,
+
+    /// draw distant bodies with coarser spheres
+    #[argh(switch)]
+    lod: bool,
+
+    /// draw the images of `assets/textures` on the bodies of the solar startup, e.g.
+    /// `earth.png`, or on those with a `texture` in their scene
+    #[argh(switch)]
+    textures: bool,
+
+    /// color the bodies by a property rather than as the scene does, the speed updating live
+    /// [fixed (default)|mass|speed|temperature]
+    #[argh(option, default = "ColorBy::Fixed")]
+    color_by: ColorBy,
+
+    /// show the whole system from above in an inset in the bottom-right corner, with the
+    /// focus of the camera in red
+    #[argh(switch)]
+    minimap: bool,
+
+    /// plot the drift of the total energy over time in an inset in the bottom-left corner
+    #[argh(switch)]
+    energy_plot: bool,
+
+    /// plot the Poincaré section of the selected body next to the energy, its position and
+    /// velocity along x as it crosses y = 0 upwards
+    #[argh(switch)]
+    poincare: bool,
+
+    /// play a sound on collisions, louder the harder the impact
+    #[cfg(feature = "native")]
+    #[argh(switch)]
+    sound: bool,
+
+    /// 16-bit PCM WAV file of the sound of `--sound` [default: assets/sounds/impact.wav]
+    #[cfg(feature = "native")]
+    #[argh(option, default = "String::from(\"assets/sounds/impact.wav\")")]
+    impact_sound: String,
+
+    /// draw the two-body orbit of each body around its main attractor
+    #[argh(switch)]
+    orbits: bool,
+
+    /// mark the periapsis and apoapsis of each orbit, with their distances
+    #[argh(switch)]
+    apsides: bool,
+
+    /// draw the plane of each orbit as a disk, tilted from the ecliptic by its inclination
+    #[argh(switch)]
+    inclinations: bool,
+
+    /// draw velocity and acceleration arrows on each body
+    #[argh(switch)]
+    vectors: bool,
+
+    /// draw the gravitational potential on the XY plane as a heatmap
+    #[argh(switch)]
+    field_plane: bool,
+
+    /// samples along each side of the grid of `--field-plane` [default: 64]
+    #[argh(option, default = "64")]
+    field_resolution: usize,
+
+    /// draw streamlines of the gravitational field on the XY plane, arrows towards where
+    /// bodies would fall
+    #[argh(switch)]
+    field_lines: bool,
+
+    /// spawn bodies by left-click and drag on the ecliptic, thrown along the drag, their
+    /// mass set with the scroll wheel or 0-9 while dragging, delete them with Alt and a
+    /// left click, and make the selected one go supernova with N
+    #[argh(switch)]
+    sandbox: bool,
+
+    /// initial distance of the camera from what it looks at, in scene units [default: set by
+    /// the startup]
+    #[argh(option)]
+    camera_distance: Option<f32>,
+
+    /// what the camera looks at initially, followed if it is a body [<name>|origin|barycenter,
+    /// default: set by the startup]
+    #[argh(option)]
+    camera_target: Option<CameraFocus>,
+
+    /// how slowly the camera eases towards where the mouse, the keys and the presets move
+    /// it, from 0 (instantly) to 1 [default: 0.3]
+    #[argh(option, default = "0.3")]
+    camera_smoothing: f32,
+
+    /// projection of the camera, switched with P [perspective (default)|ortho]
+    #[argh(option, default = "Projection::Perspective")]
+    projection: Projection,
+
+    /// speed of the touch gestures of the camera, relative to the mouse [default: 1]
+    #[argh(option, default = "1.0")]
+    touch_sensitivity: f32,
+
+    /// also save a screenshot every N frames, e.g. for animations
+    #[argh(option)]
+    screenshot_every: Option<u32>,
+
+    /// file where the inputs and the camera of each frame are written, for --replay
+    #[argh(option)]
+    record: Option<String>,
+
+    /// file of --record whose inputs are played back, with its seed, over the same options
+    #[argh(option)]
+    replay: Option<String>,
+
+    /// exit once the simulated time reaches this many days (units of time of a scene)
+    #[argh(option)]
+    run_until: Option<f64>,
+
+    /// exit after this many steps, or at `--run-until` if it comes first
+    #[argh(option)]
+    max_steps: Option<u64>,
+
+    /// run without a window for `--steps` steps and print how long they took
+    #[argh(switch)]
+    headless: bool,
+
+    /// number of steps simulated with `--headless`, without `--run-until` or `--max-steps`
+    /// [default: 1000]
+    #[argh(option, default = "1000")]
+    steps: u64,
+
+    /// scene files to run one after the other, headless, e.g. `scenes/*.ron,other.ron`
+    #[argh(option)]
+    batch: Option<String>,
+
+    /// CSV file where `--batch` writes the energy drift, collisions and ejections of each
+    /// scene [default: batch.csv]
+    #[argh(option, default = "String::from(\"batch.csv\")")]
+    batch_output: String,
+
     /// enable diagnostics in the console
     #[argh(switch, short = 'd')]
     debug: bool,
 }
 
+enum Startup {
+    Solar,
+    Figure8,
+    Binary,
+    Cluster,
+    Disk,
+    Slingshot,
+    Trojans,
+    Comet,
+    Nova,
+}
+
+impl FromStr for Startup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solar" => Ok(Startup::Solar),
+            "figure8" => Ok(Startup::Figure8),
+            "binary" => Ok(Startup::Binary),
+            "cluster" => Ok(Startup::Cluster),
+            "disk" => Ok(Startup::Disk),
+            "slingshot" => Ok(Startup::Slingshot),
+            "trojans" => Ok(Startup::Trojans),
+            "comet" => Ok(Startup::Comet),
+            "nova" => Ok(Startup::Nova),
+            _ => Err(format!("unknown startup system `{}`", s)),
+        }
+    }
+}
+
 fn no_op_system() {}
 
+/// Flags of the command line
+#[cfg(not(target_arch = "wasm32"))]
+fn flags() -> Flags {
+    argh::from_env()
+}
+
+/// Flags of the query string of the page, e.g. `?startup=figure8&trails&speed=10` for
+/// `--startup figure8 --trails --speed 10`
+#[cfg(target_arch = "wasm32")]
+fn flags() -> Flags {
+    let query = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    let args = query_args(&query);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Flags::from_args(&["nbody"], &args).unwrap_or_else(|early_exit| {
+        // An invalid parameter, or `?help`: the page starts with the defaults
+        web_sys::console::error_1(&early_exit.output.into());
+        Flags::from_args(&["nbody"], &[]).unwrap()
+    })
+}
+
+/// Arguments of the parameters of a query string, `_` standing for `-` in their names.
+/// Switches have no value.
+#[cfg(target_arch = "wasm32")]
+fn query_args(query: &str) -> Vec<String> {
+    // `+` is a space and `%XX` an escaped byte
+    let decode = |s: &str| {
+        let mut bytes = Vec::new();
+        let mut rest = s.as_bytes();
+        while let Some((&byte, tail)) = rest.split_first() {
+            let escaped = (byte == b'%')
+                .then(|| tail.get(..2))
+                .flatten()
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+            match (byte, escaped) {
+                (_, Some(escaped)) => {
+                    bytes.push(escaped);
+                    rest = &tail[2..];
+                    continue;
+                }
+                (b'+', None) => bytes.push(b' '),
+                (byte, None) => bytes.push(byte),
+            }
+            rest = tail;
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .flat_map(|parameter| {
+            let (name, value) = match parameter.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (parameter, None),
+            };
+            let mut args = vec![format!("--{}", decode(name).replace('_', "-"))];
+            args.extend(value.map(decode));
+            args
+        })
+        .collect()
+}
+
 fn main() {
-    let args: Flags = argh::from_env();
+    let args = flags();
+    if let Some(list) = &args.batch {
+        if let Err(e) = batch::run(list, &args.batch_output) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    run(args, None);
+}
+
+/// Build and run the app of `args`, until it exits. With a `summary`, the outcome of the
+/// run is written to it on exit.
+fn run(args: Flags, summary: Option<Arc<Mutex<Option<Summary>>>>) {
+    let scene = args
+        .scene
+        .as_ref()
+        .map(SceneDescription::load)
+        .or_else(|| args.import_csv.as_ref().map(import::load_csv))
+        .map(|scene| {
+            scene.unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            })
+        });
+    let recording = args.replay.as_ref().map(|path| {
+        Recording::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    });
+    let seed = recording
+        .as_ref()
+        .map(|recording| recording.seed)
+        .or(args.seed)
+        .unwrap_or_else(rand::random);
+    let snapshot = args.load_state.as_ref().map(|path| {
+        Snapshot::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    });
+    // Falls back to the vectors of `solar_system` without the network
+    #[cfg(feature = "horizons")]
+    let (scene, horizons_epoch) = match args.horizons {
+        Some(date) if scene.is_none() && snapshot.is_none() => match horizons::solar_system(date) {
+            Ok(solar) => (Some(solar), Some(date)),
+            Err(e) => {
+                eprintln!("warning: {}, starting from {} instead", e, SOLAR_EPOCH);
+                (None, None)
+            }
+        },
+        _ => (scene, None),
+    };
+    #[cfg(not(feature = "horizons"))]
+    let horizons_epoch = None;
+    let speed = snapshot.as_ref().map_or(args.speed, |s| s.speed_factor);
+    let solar = matches!(args.startup, Startup::Solar) && scene.is_none() && snapshot.is_none();
+    let comet = matches!(args.startup, Startup::Comet) && scene.is_none() && snapshot.is_none();
+    let comet_planets = comet && args.comet_planets;
+    let epoch = if solar || comet_planets {
+        args.epoch.or(Some(SOLAR_EPOCH))
+    } else {
+        args.epoch.or(horizons_epoch)
+    };
+    // The moons are lost in a few orbits in single precision, or with too few steps per orbit
+    let moons = solar || comet_planets || horizons_epoch.is_some();
+    if moons && !(args.double_precision && args.adaptive_substeps) {
+        eprintln!(
+            "warning: --double-precision and --adaptive-substeps are turned on for the moons"
+        );
+    }
+    let slingshot =
+        matches!(args.startup, Startup::Slingshot) && scene.is_none() && snapshot.is_none();
+    let trojans = matches!(args.startup, Startup::Trojans) && scene.is_none() && snapshot.is_none();
+    let disk = matches!(args.startup, Startup::Disk) && scene.is_none() && snapshot.is_none();
+    let nova = matches!(args.startup, Startup::Nova) && scene.is_none() && snapshot.is_none();
+    let sun_jupiter = || BodyPair("Sun".to_string(), "Jupiter".to_string());
 
     let mut app = App::build();
     app.insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.1)))
@@ -40,24 +691,292 @@ fn main() {
             color: Color::WHITE,
             brightness: 2.0,
         })
-        .insert_resource(Msaa { samples: 4 })
-        .add_plugins(DefaultPlugins);
+        .insert_resource(Msaa { samples: 4 });
+    // In the `<canvas id="nbody">` of `web/index.html`
+    #[cfg(target_arch = "wasm32")]
+    app.insert_resource(WindowDescriptor {
+        title: "nbody".to_string(),
+        canvas: Some("#nbody".to_string()),
+        ..Default::default()
+    });
+
+    if args.headless {
+        app.add_plugins(MinimalPlugins).add_plugin(HeadlessPlugin);
+    } else {
+        #[cfg(not(feature = "web"))]
+        app.add_plugins(DefaultPlugins);
+        #[cfg(feature = "web")]
+        app.add_plugins(bevy_webgl2::DefaultPlugins);
+        app.add_plugin(LinesPlugin)
+            .add_plugin(UiCameraPlugin)
+            .add_plugin(ClockPlugin { epoch })
+            // For the frame rate of the HUD
+            .add_plugin(FrameTimeDiagnosticsPlugin)
+            .add_plugin(HudPlugin)
+            .add_plugin(RestartPlugin);
+        if !args.no_stars {
+            app.add_plugin(StarfieldPlugin::default());
+        }
+        app.add_plugin(TrailsPlugin {
+            length: args.trail_length,
+            enabled: args.trails,
+        })
+        .add_plugin(LabelsPlugin {
+            enabled: args.labels,
+            periods: args.label_periods,
+        })
+        .add_plugin(OrbitsPlugin {
+            enabled: args.orbits,
+        })
+        .add_plugin(ApsidesPlugin {
+            enabled: args.apsides,
+        })
+        .add_plugin(InclinationsPlugin {
+            enabled: args.inclinations,
+        })
+        .add_plugin(VectorsPlugin {
+            enabled: args.vectors,
+        })
+        .add_plugin(SizesPlugin {
+            scale: args.scale_sizes,
+        })
+        .add_plugin(SpinPlugin)
+        .add_plugin(PickingPlugin)
+        .add_plugin(ControlPanelPlugin)
+        .add_plugin(PanOrbitCameraPlugin {
+            touch_sensitivity: args.touch_sensitivity,
+            distance: args.camera_distance,
+            target: args.camera_target,
+            smoothing: args.camera_smoothing,
+            projection: args.projection,
+        })
+        .add_plugin(ScreenshotPlugin {
+            every: args.screenshot_every,
+        });
+        if args.lod {
+            app.add_plugin(LodPlugin);
+        }
+        if args.textures {
+            app.add_plugin(TexturesPlugin);
+        }
+        if args.color_by != ColorBy::Fixed {
+            app.add_plugin(ColorsPlugin { by: args.color_by });
+        }
+        if args.minimap {
+            app.add_plugin(MinimapPlugin);
+        }
+        if let Some(compare) = args.compare {
+            app.add_plugin(ComparePlugin {
+                integrator: compare.ghosts,
+            });
+        }
+        if args.energy_plot || args.poincare {
+            app.add_plugin(PlotPlugin {
+                section: args.poincare,
+            });
+        }
+        #[cfg(feature = "native")]
+        if args.sound {
+            app.add_plugin(SoundPlugin {
+                path: args.impact_sound.clone(),
+            });
+        }
+        if let Some(bodies) = args.lagrange.clone().or_else(|| trojans.then(sun_jupiter)) {
+            app.add_plugin(LagrangePlugin { bodies });
+        }
+        if args.field_plane {
+            app.add_plugin(FieldPlugin {
+                resolution: args.field_resolution,
+            });
+        }
+        if args.field_lines {
+            app.add_plugin(FieldLinesPlugin);
+        }
+        if args.sandbox {
+            app.add_plugin(SandboxPlugin);
+        }
+    }
 
     if args.debug {
-        app.add_plugin(FrameTimeDiagnosticsPlugin::default())
-            .add_plugin(LogDiagnosticsPlugin::default());
+        if args.headless {
+            app.add_plugin(FrameTimeDiagnosticsPlugin);
+        }
+        app.add_plugin(LogDiagnosticsPlugin::default());
     };
+    // Energies of the plots, logged too with -d
+    if args.debug || (!args.headless && (args.energy_plot || args.poincare)) {
+        app.add_plugin(NBodyDiagnosticsPlugin);
+    }
+
+    app.add_plugin(NBody {
+        speed_factor: speed,
+        physics_hz: args.physics_hz,
+        dt: args.dt,
+        integrator: args
+            .compare
+            .map_or(args.integrator, |compare| compare.bodies),
+        softening: args.softening,
+        algorithm: args.algorithm,
+        substeps: args.substeps,
+        adaptive_substeps: args.adaptive_substeps || moons || slingshot || comet || nova,
+        collisions: match args.collisions {
+            Collisions::Off if disk => Collisions::Merge,
+            collisions => collisions,
+        },
+        restitution: args.restitution,
+        speed_of_light: args.relativistic.then_some(args.speed_of_light),
+        radiation_pressure: args.radiation_pressure || comet,
+        external_field: args.external_field.0,
+        drag: Drag {
+            k: args.drag,
+            particles_only: args.drag_particles_only,
+        },
+        particle_groups: args.no_self_gravity.clone(),
+        precision: if args.double_precision || moons {
+            Precision::Double
+        } else {
+            Precision::Single
+        },
+        pacing: if args.headless {
+            Pacing::EveryFrame
+        } else {
+            Pacing::RealTime
+        },
+        frame: match args.frame {
+            ReferenceFrame::Origin if trojans => {
+                let BodyPair(sun, jupiter) = sun_jupiter();
+                ReferenceFrame::Rotating(sun, jupiter)
+            }
+            frame => frame,
+        },
+        cinematic: args.cinematic,
+        ..Default::default()
+    })
+    .add_plugin(StopPlugin {
+        until: args.run_until,
+        max_steps: match (args.max_steps, args.run_until) {
+            (None, None) if args.headless => Some(args.steps),
+            (max_steps, _) => max_steps,
+        },
+    })
+    .add_plugin(EscapesPlugin {
+        cull_distance: args.cull_escaped.then_some(args.cull_distance),
+    })
+    .add_plugin(ApproachPlugin {
+        bodies: args.approach,
+    })
+    .add_plugin(RandomPlugin { seed })
+    .add_startup_system(no_op_system.system());
+
+    if args.eclipses {
+        app.add_plugin(EclipsesPlugin);
+    }
+
+    if nova || (args.sandbox && !args.headless) {
+        app.add_plugin(SupernovaPlugin);
+    }
+
+    if args.tidal_breakup {
+        app.add_plugin(TidalPlugin {
+            fragments: args.fragments,
+        });
+    }
+
+    if let Some(bodies) = args.hohmann {
+        app.add_plugin(HohmannPlugin { bodies });
+    }
 
-    app.add_plugin(PanOrbitCameraPlugin)
-        .add_plugin(NBody {
-            speed_factor: args.speed,
+    if let Some(around) = args.spacecraft {
+        app.add_plugin(SpacecraftPlugin { around });
+    }
+
+    if !args.planet.is_empty() {
+        app.add_plugin(PlanetsPlugin {
+            planets: args.planet,
+        });
+    }
+
+    if args.asteroids.is_some() || args.particles > 0 {
+        app.add_plugin(AsteroidsPlugin {
+            count: args.asteroids.unwrap_or(0),
+            particles: args.particles,
+            render: !args.headless,
+        });
+    }
+
+    if let Some(path) = args.export_csv {
+        let export = CsvExport::create(&path, args.export_every).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        app.insert_resource(export)
+            .add_system(export_csv.system().after(PhysicsSystem::Collisions));
+    }
+
+    if let Some(path) = &args.record {
+        let recorder = Recorder::create(path, seed).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        app.insert_resource(recorder).add_plugin(RecordPlugin);
+    }
+    if let Some(recording) = recording {
+        app.insert_resource(recording).add_plugin(ReplayPlugin);
+    }
+
+    if let Some(path) = args.save_state {
+        app.insert_resource(SavePath {
+            path: path.into(),
+            on_exit: args.headless,
         })
-        .add_startup_system(no_op_system.system());
+        .add_system_to_stage(CoreStage::PostUpdate, save_snapshot.system());
+    }
+
+    if let Some(snapshot) = snapshot {
+        app.insert_resource(snapshot)
+            .add_startup_system(restore_snapshot.system());
+    } else if let Some(scene) = scene {
+        app.insert_resource(scene)
+            .add_startup_system(spawn_scene.system());
+    } else {
+        match args.startup {
+            Startup::Solar => app.add_startup_system(solar_system.system()),
+            Startup::Figure8 => app.add_startup_system(figure8.system()),
+            Startup::Binary => app.add_startup_system(binary_star.system()),
+            Startup::Cluster => app
+                .insert_resource(ClusterSize(args.bodies))
+                .add_startup_system(cluster.system()),
+            Startup::Disk => app
+                .insert_resource(DiskSize(args.bodies))
+                .add_startup_system(accretion_disk.system()),
+            Startup::Slingshot => app.add_plugin(SlingshotPlugin),
+            Startup::Trojans => app.add_plugin(TrojansPlugin { count: args.bodies }),
+            Startup::Comet => app.add_plugin(CometPlugin {
+                planets: args.comet_planets,
+            }),
+            Startup::Nova => app.add_plugin(NovaPlugin),
+        };
+    }
 
-    app.add_startup_system(solar_system.system());
+    if let Some(summary) = summary {
+        app.add_plugin(SummaryPlugin { summary });
+    }
     app.run();
 }
 
+/// Scale for rendering: 1 unit = 0.1 AU
+pub const AU_TO_UNIT_SCALE: f32 = 10.0;
+
+/// Radius in scene units of a body `km` kilometers wide
+fn km_to_units(km: f32) -> f32 {
+    AU_TO_UNIT_SCALE * units::meters_to_au(km as f64 * units::KM) as f32
+}
+
+/// Speed of light in the units of [`solar_system`]: 0.1 AU per day
+fn solar_speed_of_light() -> f32 {
+    AU_TO_UNIT_SCALE * units::meters_per_second_to_au_per_day(units::SPEED_OF_LIGHT) as f32
+}
+
 fn spawn_z_camera(commands: &mut Commands, z: f32) {
     commands
         .spawn_bundle(PerspectiveCameraBundle {
@@ -70,10 +989,17 @@ fn spawn_z_camera(commands: &mut Commands, z: f32) {
         });
 }
 
+/// Date of the initial conditions of [`solar_system`]
+const SOLAR_EPOCH: Date = Date {
+    year: 2021,
+    month: 4,
+    day: 18,
+};
+
 /// Add the sun and all the planets of the Solar system (+ Pluto)
 /// Units are scaled:
 /// Mass = 10^24 kg
-/// Distance = AU (= 1.5 x 10^11 m)
+/// Distance = AU (= 1.496 x 10^11 m)
 /// Velocity = AU / Day
 /// Acceleration = AU / DAY^2
 pub fn solar_system(
@@ -82,17 +1008,10 @@ pub fn solar_system(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut g: ResMut<Gravity>,
 ) {
-    // Scale for rendering: 1 unit = 0.1 AU
-    const AU_TO_UNIT_SCALE: f32 = 10.0;
-    const DAY: f32 = 86_400.0;
-
-    // Scale the gravitational constant accordingly to account for the units scaling
-    // ```
-    // G = m^3 / kg / s^2
-    // G = (1.5^3 * 10^11 / 10 m)^3 / 10^24 kg / Day^2
-    // G' = G * Day^2 * 10-6 / 1.5^3
-    // ```
-    g.0 *= DAY * DAY * 10.0f32.powi(-6) / 1.5f32.powi(3);
+    // Scale the gravitational constant accordingly to account for the units scaling. A
+    // circular orbit at 1 AU then lasts `2 * PI * sqrt(AU^3 / (G * M_sun))` = 365.25 days.
+    g.0 *= units::solar_g_scale(AU_TO_UNIT_SCALE);
+    let sun_mass = units::kg_to_mass_units(units::SOLAR_MASS) as f32;
 
     commands
         .spawn_bundle(PbrBundle {
@@ -101,7 +1020,7 @@ pub fn solar_system(
                 subdivisions: 10,
             })),
             material: materials.add(StandardMaterial {
-                base_color: Color::YELLOW.into(),
+                base_color: Color::YELLOW,
                 roughness: 0.6,
                 emissive: Color::YELLOW,
                 ..Default::default()
@@ -113,12 +1032,22 @@ pub fn solar_system(
             intensity: 50_000.0,
             range: 2000.0,
             ..Default::default()
-        });
+        })
+        // The Sun is not to scale, its radius would be ~70 units
+        .insert_bundle(BodyBundle::new(sun_mass, Vec3::ZERO, Vec3::ZERO).with_radius(2.8))
+        .insert(TrueRadius(km_to_units(695_700.0)))
+        .insert(Spin::new(25.38, 7.25))
+        .insert(SurfaceTexture("textures/sun.png".to_string()))
+        .insert(Name::new("Sun"));
 
+    // `$name` is bound to the position and velocity of the body, which those of its moons,
+    // given `around` it, are relative to
     macro_rules! spawn_planet {
-    ($name:ident, m=$mass:literal, pos=($($pos:literal),+), vel=($($vel:literal),+), r=$radius:literal, col=$col:expr $(,)?) => {
-        let $name = BodyBundle::new($mass, AU_TO_UNIT_SCALE * Vec3::new($($pos),+), AU_TO_UNIT_SCALE * Vec3::new($($vel),+));
-        commands
+    ($name:ident $(, around=$around:ident)?, m=$mass:literal, pos=($($pos:literal),+), vel=($($vel:literal),+), r=$radius:literal, col=$col:expr $(, spin=($period:literal, $obliquity:literal))? $(, rings=$rings:expr)? $(,)?) => {
+        let $name = (AU_TO_UNIT_SCALE * Vec3::new($($pos),+), AU_TO_UNIT_SCALE * Vec3::new($($vel),+));
+        $(let $name = ($around.0 + $name.0, $around.1 + $name.1);)?
+        let bundle = BodyBundle::new($mass, $name.0, $name.1).with_radius($radius / 10_000.0);
+        let _entity = commands
             .spawn_bundle(PbrBundle {
                 mesh: meshes.add(Mesh::from(shape::Icosphere {
                     radius: $radius / 10_000.0,
@@ -132,110 +1061,528 @@ pub fn solar_system(
                 }),
                 ..Default::default()
             })
-            .insert_bundle($name);
+            .insert_bundle(bundle)
+            .insert(TrueRadius(km_to_units($radius)))
+            .insert(Name::new(capitalize(stringify!($name))))
+            .id();
+        $(commands.entity(_entity).insert(Spin::new($period / 24.0, $obliquity));)?
+        commands
+            .entity(_entity)
+            .insert(SurfaceTexture(format!("textures/{}.png", stringify!($name))));
+        $(spawn_rings(&mut commands, &mut meshes, _entity, $rings, $col);)?
     };
 }
-    // Data pulled from JPL Horizons as of 2021-04-18
-    // https://ssd.jpl.nasa.gov/horizons.cgi
-    #[rustfmt::skip]
-    spawn_planet!(
-        sun,
-        m=1988500.0,
-        pos=(0.0, 0.0, 0.0),
-        vel=(0.0, 0.0, 0.0),
-        r=696_340.0,
-        col=Color::YELLOW,
-    );
-
+    // State of 2021-04-18, from the approximate Keplerian elements of JPL
+    // (https://ssd.jpl.nasa.gov/planets/approx_pos.html), which agree with JPL Horizons
+    // (https://ssd.jpl.nasa.gov/horizons.cgi) to the 3 decimals used before. Velocities need
+    // more: Earth's year was 357 days with them.
     #[rustfmt::skip]
     spawn_planet!(
         mercury,
         m=0.330,
-        pos=(3.044, 0.130, -0.017),
-        vel=(-0.016, 0.027, 0.004),
+        pos=(0.30443, 0.12949, -0.01734),
+        vel=(-0.016484, 0.027137, 0.003730),
         r=2440.0,
         col=Color::ORANGE_RED,
+        spin=(1407.6, 0.03),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         venus,
         m=4.868,
-        pos=(0.539, 0.482, -0.024),
-        vel=(-0.014, 0.015, 0.001),
+        pos=(0.53871, 0.48201, -0.02447),
+        vel=(-0.013548, 0.014987, 0.000988),
         r=6051.84,
         col=Color::ORANGE,
+        spin=(5832.6, 177.36),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         earth,
         m=5.972,
-        pos=(-0.887, -0.470, 0.000),
-        vel=(0.008, -0.015, 0.000),
+        pos=(-0.88737, -0.46974, 0.00002),
+        vel=(0.007769, -0.015270, 0.000001),
         r=6371.01,
         col=Color::BLUE,
+        spin=(23.934, 23.44),
+    );
+
+    // Moons on circular orbits at their mean distance from their planet, inclined like their
+    // actual orbits, Titan in the plane of Saturn's rings. Their phases are arbitrary.
+    #[rustfmt::skip]
+    spawn_planet!(
+        moon,
+        around=earth,
+        m=0.073,
+        pos=(1.817e-3, 1.810e-3, 1.629e-4),
+        vel=(-4.184e-4, 4.168e-4, 3.752e-5),
+        r=1737.4,
+        col=Color::SILVER,
+        spin=(655.72, 6.68),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         mars,
         m=0.642,
-        pos=(-0.767, 1.438, 0.049),
-        vel=(-0.012, -0.005, 0.000),
+        pos=(-0.76707, 1.43777, 0.04895),
+        vel=(-0.011817, -0.005397, 0.000177),
         r=3389.92,
         col=Color::RED,
+        spin=(24.623, 25.19),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         jupiter,
         m=1898.187,
-        pos=(3.638, -3.517, -0.067),
-        vel=(0.005, 0.006, -0.000),
+        pos=(3.63899, -3.52088, -0.06681),
+        vel=(0.005155, 0.005780, -0.000139),
         r=69911.0,
         col=Color::BISQUE,
+        spin=(9.925, 3.13),
+    );
+
+    #[rustfmt::skip]
+    spawn_planet!(
+        io,
+        around=jupiter,
+        m=0.089,
+        pos=(2.819e-3, 0.0, 0.0),
+        vel=(0.0, 1.001e-2, 0.0),
+        r=1821.6,
+        col=Color::YELLOW,
+        spin=(42.459, 0.0),
+    );
+
+    #[rustfmt::skip]
+    spawn_planet!(
+        europa,
+        around=jupiter,
+        m=0.048,
+        pos=(0.0, 4.486e-3, 0.0),
+        vel=(-7.936e-3, 0.0, 0.0),
+        r=1560.8,
+        col=Color::BEIGE,
+        spin=(85.228, 0.1),
+    );
+
+    #[rustfmt::skip]
+    spawn_planet!(
+        ganymede,
+        around=jupiter,
+        m=0.148,
+        pos=(-6.724e-3, -2.447e-3, 0.0),
+        vel=(2.149e-3, -5.905e-3, 0.0),
+        r=2634.1,
+        col=Color::GRAY,
+        spin=(171.71, 0.2),
+    );
+
+    #[rustfmt::skip]
+    spawn_planet!(
+        callisto,
+        around=jupiter,
+        m=0.108,
+        pos=(6.293e-3, -1.090e-2, 0.0),
+        vel=(4.103e-3, 2.369e-3, 0.0),
+        r=2410.3,
+        col=Color::DARK_GRAY,
+        spin=(400.54, 0.0),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         saturn,
         m=568.340,
-        pos=(5.947, -8.001, -0.098),
-        vel=(0.004, 0.003, -0.000),
+        pos=(5.94369, -7.98079, -0.09776),
+        vel=(0.004169, 0.003318, -0.000224),
         r=58232.0,
         col=Color::GOLD,
+        spin=(10.656, 26.73),
+        // Main rings, from the inner edge of the C ring to the outer edge of the A ring
+        rings=Rings { inner: 7.4658, outer: 13.6775, tilt: 26.73 },
+    );
+
+    #[rustfmt::skip]
+    spawn_planet!(
+        titan,
+        around=saturn,
+        m=0.135,
+        pos=(-5.775e-3, 5.158e-3, 2.598e-3),
+        vel=(-2.276e-3, -2.032e-3, -1.024e-3),
+        r=2574.7,
+        col=Color::ORANGE,
+        spin=(382.69, 0.3),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         uranus,
         m=86.813,
-        pos=(15.079, 12.767, -0.148),
-        vel=(-0.003, 0.003, 0.000),
+        pos=(15.07014, 12.7727, -0.14788),
+        vel=(-0.002570, 0.002816, 0.000044),
         r=25362.0,
         col=Color::AQUAMARINE,
+        spin=(17.24, 97.77),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         neptune,
         m=102.413,
-        pos=(29.516, -4.898, -0.579),
-        vel=(0.001, 0.003, -0.000),
+        pos=(29.50922, -4.89199, -0.57929),
+        vel=(0.000492, 0.003113, -0.000075),
         r=24622.0,
         col=Color::BLUE,
+        spin=(16.11, 28.32),
     );
 
     #[rustfmt::skip]
     spawn_planet!(
         pluto,
         m=0.013,
-        pos=(14.375, -31.090, -0.830),
-        vel=(0.003, 0.001, -0.001),
+        pos=(14.36754, -31.08593, -0.82945),
+        vel=(0.002921, 0.000642, -0.000914),
         r=11880.3,
         col=Color::GRAY,
+        spin=(153.29, 122.53),
     );
 
     spawn_z_camera(&mut commands, 200.0);
 }
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Stable figure-8 solution of the three-body problem (Chenciner & Montgomery, 2000)
+/// The canonical initial conditions assume `G = 1` and unit masses.
+/// Positions are scaled by `L` and velocities by `V` for rendering, which preserves
+/// the orbit as long as `G' = L * V^2`. The period becomes `6.3259 * L / V`.
+pub fn figure8(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    const L: f32 = 10.0;
+    const V: f32 = 1.0;
+    g.0 = L * V * V;
+
+    let x1 = Vec3::new(0.970_004_4, -0.243_087_5, 0.0);
+    let v3 = Vec3::new(-0.932_407_4, -0.864_731_5, 0.0);
+    let bodies = [
+        (x1, -v3 / 2.0, Color::RED),
+        (-x1, -v3 / 2.0, Color::GREEN),
+        (Vec3::ZERO, v3, Color::BLUE),
+    ];
+
+    for (pos, vel, color) in bodies.iter() {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Icosphere {
+                    radius: 0.3,
+                    subdivisions: 5,
+                })),
+                material: materials.add(StandardMaterial {
+                    base_color: *color,
+                    roughness: 0.6,
+                    emissive: *color,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .insert_bundle(BodyBundle::new(1.0, L * *pos, V * *vel).with_radius(0.3));
+    }
+
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 50.0),
+        light: Light {
+            intensity: 5_000.0,
+            range: 200.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    spawn_z_camera(&mut commands, 40.0);
+}
+
+/// Two stars on a tight circular orbit around each other, and a planet on a wider
+/// circumbinary orbit. Circumbinary orbits are stable beyond a few times the separation of
+/// the stars, the planet starts at 5 times. Best watched with `--frame barycenter`.
+pub fn binary_star(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    const G: f32 = 10.0;
+    const MASS_A: f32 = 1.0;
+    const MASS_B: f32 = 0.7;
+    /// Distance between the stars
+    const SEPARATION: f32 = 4.0;
+    const PLANET_MASS: f32 = 1e-4;
+    /// Distance of the planet from the barycenter of the stars
+    const PLANET_DISTANCE: f32 = 20.0;
+    g.0 = G;
+
+    // Circular two-body orbit: the relative speed is `sqrt(G * M / a)`, shared between the
+    // stars in inverse proportion of their masses around their barycenter at the origin
+    let stars_mass = MASS_A + MASS_B;
+    let relative_speed = (G * stars_mass / SEPARATION).sqrt();
+    let (share_a, share_b) = (MASS_B / stars_mass, MASS_A / stars_mass);
+    // The planet orbits the combined mass of the stars, which recoil so that the total
+    // momentum is 0 and the barycenter of the whole system stays at the origin
+    let planet_vel = Vec3::Y * (G * (stars_mass + PLANET_MASS) / PLANET_DISTANCE).sqrt();
+    let recoil = -planet_vel * PLANET_MASS / stars_mass;
+
+    let bodies = [
+        (
+            "Star A",
+            MASS_A,
+            Vec3::X * -SEPARATION * share_a,
+            Vec3::Y * -relative_speed * share_a + recoil,
+            0.8,
+            Color::rgb(1.0, 0.85, 0.4),
+            Some(5_000.0),
+        ),
+        (
+            "Star B",
+            MASS_B,
+            Vec3::X * SEPARATION * share_b,
+            Vec3::Y * relative_speed * share_b + recoil,
+            0.6,
+            Color::rgb(1.0, 0.5, 0.3),
+            Some(3_000.0),
+        ),
+        (
+            "Planet",
+            PLANET_MASS,
+            Vec3::X * PLANET_DISTANCE,
+            planet_vel,
+            0.3,
+            Color::rgb(0.3, 0.6, 1.0),
+            None,
+        ),
+    ];
+
+    for &(name, mass, pos, vel, radius, color, light) in bodies.iter() {
+        let appearance = Appearance {
+            radius,
+            color,
+            emissive: if light.is_some() { color } else { Color::BLACK },
+            light,
+            rings: None,
+        };
+        let entity = spawn_body(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            BodyBundle::new(mass, pos, vel),
+            &appearance,
+        );
+        commands.entity(entity).insert(Name::new(name));
+    }
+
+    spawn_z_camera(&mut commands, 60.0);
+}
+
+/// Number of stars of the [`cluster`] startup
+pub struct ClusterSize(pub usize);
+
+/// Star cluster drawn from a Plummer sphere, whose density falls off as
+/// `(1 + r^2 / a^2)^(-5/2)`, with the method of Aarseth, Hénon & Wielen (1974): radii by
+/// inverting the cumulative mass profile, speeds by rejection sampling of the isotropic
+/// distribution function, which leaves the cluster in virial equilibrium. The stars are
+/// drawn from the [`SimRng`]. They pass close to one another, some `--softening` helps.
+pub fn cluster(
+    mut commands: Commands,
+    size: Res<ClusterSize>,
+    mut rng: ResMut<SimRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    const G: f32 = 1.0;
+    const TOTAL_MASS: f32 = 100.0;
+    /// Plummer radius `a`, enclosing about a third of the mass
+    const PLUMMER_RADIUS: f32 = 20.0;
+    /// Stars are drawn within this many Plummer radii, leaving out the sparse outskirts
+    const CUTOFF: f32 = 10.0;
+    const RADIUS: f32 = 0.2;
+    g.0 = G;
+
+    let rng = &mut rng.0;
+    let mut stars = Vec::with_capacity(size.0);
+    while stars.len() < size.0 {
+        // Fraction of the mass enclosed within `r`, `(r^2 / (r^2 + a^2))^(3/2)`, inverted
+        let enclosed: f32 = rng.gen();
+        let r = PLUMMER_RADIUS / (enclosed.powf(-2.0 / 3.0) - 1.0).sqrt();
+        if r > CUTOFF * PLUMMER_RADIUS {
+            continue;
+        }
+        // The distribution function of speeds `q = v / v_escape` is `q^2 (1 - q^2)^(7/2)`,
+        // whose maximum is below 0.1
+        let q = loop {
+            let (q, y): (f32, f32) = (rng.gen(), rng.gen_range(0.0..0.1));
+            if y < q * q * (1.0 - q * q).powf(3.5) {
+                break q;
+            }
+        };
+        let escape_speed = (2.0 * G * TOTAL_MASS / PLUMMER_RADIUS).sqrt()
+            * (1.0 + r * r / (PLUMMER_RADIUS * PLUMMER_RADIUS)).powf(-0.25);
+        let (radial, heading): ([f32; 3], [f32; 3]) =
+            (UnitSphere.sample(rng), UnitSphere.sample(rng));
+        stars.push((
+            r * Vec3::from(radial),
+            q * escape_speed * Vec3::from(heading),
+        ));
+    }
+
+    // Equal masses: the barycenter and its velocity are the means, moved to rest at the
+    // origin so that the cluster does not drift away
+    let n = stars.len().max(1) as f32;
+    let center = stars.iter().map(|(pos, _)| pos).sum::<Vec3>() / n;
+    let drift = stars.iter().map(|(_, vel)| vel).sum::<Vec3>() / n;
+
+    let mesh = meshes.add(Mesh::from(shape::Icosphere {
+        radius: RADIUS,
+        subdivisions: 2,
+    }));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.9, 0.7),
+        emissive: Color::rgb(1.0, 0.9, 0.7),
+        ..Default::default()
+    });
+    for (pos, vel) in stars {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                ..Default::default()
+            })
+            .insert_bundle(
+                BodyBundle::new(TOTAL_MASS / n, pos - center, vel - drift).with_radius(RADIUS),
+            );
+    }
+
+    spawn_z_camera(&mut commands, 8.0 * PLUMMER_RADIUS);
+}
+
+/// Number of bodies of the [`accretion_disk`] startup
+pub struct DiskSize(pub usize);
+
+/// Star surrounded by a thin disk of planetesimals, and of dust as [`TestParticle`]s, one
+/// body in five, on nearly circular orbits. Their surface density falls off as `1 / r`,
+/// and their small random velocities, a fraction of the orbital speed, let the neighbours
+/// meet slowly enough for their gravity to pull them into each other: they merge into
+/// clumps which sweep up what crosses their orbits, while some fall into the star. The
+/// bodies are drawn from the [`SimRng`].
+pub fn accretion_disk(
+    mut commands: Commands,
+    size: Res<DiskSize>,
+    mut rng: ResMut<SimRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    const G: f32 = 1.0;
+    const STAR_MASS: f32 = 1000.0;
+    const STAR_RADIUS: f32 = 2.0;
+    /// Of the planetesimals together, a twentieth of the star
+    const DISK_MASS: f32 = 50.0;
+    const INNER: f32 = 10.0;
+    const OUTER: f32 = 40.0;
+    /// Of the thickness of the disk, and of the random velocities, relative to the radius
+    /// and the orbital speed
+    const ASPECT: f32 = 0.01;
+    const DISPERSION: f32 = 0.01;
+    /// One body in this many is dust
+    const DUST_EVERY: usize = 5;
+    const PLANETESIMAL_RADIUS: f32 = 0.15;
+    const DUST_RADIUS: f32 = 0.05;
+    g.0 = G;
+
+    let rng = &mut rng.0;
+    let planetesimals = size.0 - size.0 / DUST_EVERY;
+    let mass = DISK_MASS / planetesimals.max(1) as f32;
+    let mut bodies = Vec::with_capacity(size.0);
+    for i in 0..size.0 {
+        let particle = (i + 1) % DUST_EVERY == 0;
+        // Uniform radii for a surface density in `1 / r`
+        let r = rng.gen_range(INNER..OUTER);
+        let longitude = rng.gen_range(0.0..std::f32::consts::TAU);
+        let radial = Vec3::new(longitude.cos(), longitude.sin(), 0.0);
+        let pos = r * radial + Vec3::Z * rng.gen_range(-1.0..1.0) * ASPECT * r;
+        let speed = (G * STAR_MASS / r).sqrt();
+        let kick = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        );
+        let vel = speed * (Vec3::Z.cross(radial) + DISPERSION * kick);
+        bodies.push((if particle { 0.0 } else { mass }, pos, vel, particle));
+    }
+
+    // The star recoils so that the barycenter of the whole system stays at the origin
+    let total = STAR_MASS + mass * planetesimals as f32;
+    let (moment, momentum) = bodies.iter().fold(
+        (Vec3::ZERO, Vec3::ZERO),
+        |(moment, momentum), &(m, pos, vel, _)| (moment + pos * m, momentum + vel * m),
+    );
+    let (center, drift) = (moment / total, momentum / total);
+    let star = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(STAR_MASS, -center, -drift),
+        &Appearance {
+            radius: STAR_RADIUS,
+            color: Color::rgb(1.0, 0.8, 0.4),
+            emissive: Color::rgb(1.0, 0.8, 0.4),
+            light: Some(20_000.0),
+            rings: None,
+        },
+    );
+    commands.entity(star).insert(Name::new("Star"));
+
+    let mut look = |radius: f32, color: Color| {
+        let mesh = meshes.add(Mesh::from(shape::Icosphere {
+            radius,
+            subdivisions: 2,
+        }));
+        let material = materials.add(StandardMaterial {
+            base_color: color,
+            roughness: 0.9,
+            ..Default::default()
+        });
+        (mesh, material)
+    };
+    let planetesimal = look(PLANETESIMAL_RADIUS, Color::rgb(0.8, 0.55, 0.35));
+    let dust = look(DUST_RADIUS, Color::rgb(0.55, 0.6, 0.7));
+    for (mass, pos, vel, particle) in bodies {
+        let ((mesh, material), radius) = if particle {
+            (&dust, DUST_RADIUS)
+        } else {
+            (&planetesimal, PLANETESIMAL_RADIUS)
+        };
+        let mut entity = commands.spawn_bundle(PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            ..Default::default()
+        });
+        entity.insert_bundle(BodyBundle::new(mass, pos - center, vel - drift).with_radius(radius));
+        if particle {
+            entity.insert(TestParticle);
+        }
+    }
+
+    spawn_z_camera(&mut commands, 2.5 * OUTER);
+}
@@ -0,0 +1,229 @@
+use bevy::{math::DVec3, prelude::*};
+use std::str::FromStr;
+
+use super::nbody::{Gravity, Mass, PhysicsSystem, Position, SimState, Velocity};
+use super::restart::reset_resource;
+
+/// Tracks the separation of a pair of bodies and records their closest approaches, the local
+/// minima of their distance, sending a [`CloseApproachEvent`] for each
+pub struct ApproachPlugin {
+    /// Names of the bodies of the initial [`ApproachPair`]
+    pub bodies: Option<BodyPair>,
+}
+
+impl Plugin for ApproachPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ApproachPair(None))
+            .init_resource::<Approaches>()
+            .add_event::<CloseApproachEvent>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<ApproachPair>.system(),
+            )
+            .add_system(track_approaches.system().after(PhysicsSystem::Collisions));
+        if let Some(bodies) = &self.bodies {
+            app.insert_resource(bodies.clone())
+                .add_startup_system_to_stage(StartupStage::PostStartup, find_pair.system());
+        }
+    }
+}
+
+/// Two body names separated by a comma, e.g. `Earth,Mars`
+#[derive(Clone)]
+pub struct BodyPair(pub String, pub String);
+
+impl FromStr for BodyPair {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(',') {
+            Some((a, b)) if !a.trim().is_empty() && !b.trim().is_empty() => {
+                Ok(BodyPair(a.trim().to_string(), b.trim().to_string()))
+            }
+            _ => Err(format!("expected two body names `a,b`, got `{}`", s)),
+        }
+    }
+}
+
+/// Bodies whose separation is tracked
+#[derive(Default)]
+pub struct ApproachPair(pub Option<(Entity, Entity)>);
+
+pub struct ClosestApproach {
+    /// Simulated time of the approach
+    pub time: f64,
+    pub distance: f32,
+}
+
+/// Closest approach of the bodies of the [`ApproachPair`]
+#[derive(Debug, Clone, Copy)]
+pub struct CloseApproachEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub distance: f32,
+    /// Simulated time of the approach, between two steps
+    pub time: f64,
+}
+
+/// Recent separations of the [`ApproachPair`] and the last closest approach found in them
+#[derive(Default)]
+pub struct Approaches {
+    pair: Option<(Entity, Entity)>,
+    /// Simulated times and distances of the last three samples, oldest first
+    history: Vec<(f64, f32)>,
+    pub last: Option<ClosestApproach>,
+    precession: Option<Precession>,
+}
+
+/// How far the periapsis of the second body of the pair around the first one turned since
+/// the first closest approach
+struct Precession {
+    start: f64,
+    /// Direction of the periapsis at the last closest approach
+    direction: DVec3,
+    /// Total angle, in radians
+    angle: f64,
+    orbits: u32,
+}
+
+/// Direction of the periapsis, that of the eccentricity vector
+/// `((v^2 - mu/r) * r - (r.v) * v) / mu`, and normal of the orbit of a body at `pos` moving
+/// at `vel` relative to the central mass. Unlike the closest sample, it does not depend on
+/// when the orbit is sampled.
+fn periapsis(pos: Vec3, vel: Vec3, mu: f64) -> (DVec3, DVec3) {
+    let (r, v) = (pos.as_f64(), vel.as_f64());
+    let e = (v.length_squared() - mu / r.length()) * r - r.dot(v) * v;
+    (e.normalize_or_zero(), r.cross(v).normalize_or_zero())
+}
+
+fn find_pair(
+    names: Res<BodyPair>,
+    mut pair: ResMut<ApproachPair>,
+    bodies: Query<(Entity, &Name), With<Position>>,
+) {
+    let find = |name: &str| {
+        let entity = bodies
+            .iter()
+            .find(|(_, body)| body.as_str() == name)
+            .map(|(entity, _)| entity);
+        if entity.is_none() {
+            warn!("no body named `{}` to track approaches of", name);
+        }
+        entity
+    };
+    if let (Some(a), Some(b)) = (find(&names.0), find(&names.1)) {
+        pair.0 = Some((a, b));
+    }
+}
+
+/// Parabola through three samples `(time, distance)`, the middle one being the smallest,
+/// for the time and distance of the minimum between two steps
+fn refine_minimum(samples: &[(f64, f32)]) -> (f64, f32) {
+    let (t1, d1) = samples[1];
+    let (u0, y0) = (samples[0].0 - t1, (samples[0].1 - d1) as f64);
+    let (u2, y2) = (samples[2].0 - t1, (samples[2].1 - d1) as f64);
+    // y = a * u^2 + b * u, with u = 0 at the middle sample
+    let det = u0 * u2 * (u0 - u2);
+    let a = (y0 * u2 - y2 * u0) / det;
+    let b = (y2 * u0 * u0 - y0 * u2 * u2) / det;
+    if a <= 0.0 || !a.is_finite() {
+        return (t1, d1);
+    }
+    let u = (-b / (2.0 * a)).clamp(u0, u2);
+    (t1 + u, (d1 as f64 + a * u * u + b * u).max(0.0) as f32)
+}
+
+/// Sample the distance once per frame, from the inertial positions and whatever the
+/// integrator. A closest approach is found when the distance stops decreasing.
+/// Each closest approach also measures how much the periapsis turned in the plane of the
+/// orbit since the last one: the precession of the orbit, from the perturbations of the
+/// other bodies or `--relativistic` corrections.
+fn track_approaches(
+    g: Res<Gravity>,
+    state: Res<SimState>,
+    pair: Res<ApproachPair>,
+    mut approaches: ResMut<Approaches>,
+    mut events: EventWriter<CloseApproachEvent>,
+    bodies: Query<(&Mass, &Position, &Velocity, Option<&Name>)>,
+) {
+    if approaches.pair != pair.0 {
+        *approaches = Approaches {
+            pair: pair.0,
+            ..Default::default()
+        };
+    }
+    let (a, b) = match pair.0 {
+        Some(pair) => pair,
+        None => return,
+    };
+    let ((mass_a, pos_a, vel_a, name_a), (mass_b, pos_b, vel_b, name_b)) =
+        match (bodies.get(a), bodies.get(b)) {
+            (Ok(a), Ok(b)) => (a, b),
+            // One of them is gone, e.g. merged into another body
+            _ => return,
+        };
+    // Nothing new while paused
+    if approaches.history.last().map(|(time, _)| *time) == Some(state.time) {
+        return;
+    }
+
+    let history = &mut approaches.history;
+    history.push((state.time, pos_a.0.distance(pos_b.0)));
+    if history.len() > 3 {
+        history.remove(0);
+    }
+    if history.len() == 3 && history[0].1 > history[1].1 && history[1].1 <= history[2].1 {
+        let (time, distance) = refine_minimum(history);
+        let name = |name: Option<&Name>, entity: Entity| {
+            name.map_or_else(|| format!("body {}", entity.id()), |n| n.to_string())
+        };
+        info!(
+            "closest approach of {} and {} at t = {:.2}: {:.4}",
+            name(name_a, a),
+            name(name_b, b),
+            time,
+            distance
+        );
+        approaches.last = Some(ClosestApproach { time, distance });
+        events.send(CloseApproachEvent {
+            a,
+            b,
+            distance,
+            time,
+        });
+
+        let mu = g.0 as f64 * (mass_a.0 + mass_b.0) as f64;
+        let (direction, normal) = periapsis(pos_b.0 - pos_a.0, vel_b.0 - vel_a.0, mu);
+        match &mut approaches.precession {
+            None => {
+                approaches.precession = Some(Precession {
+                    start: time,
+                    direction,
+                    angle: 0.0,
+                    orbits: 0,
+                })
+            }
+            Some(precession) => {
+                let previous = precession.direction;
+                let turn = previous
+                    .cross(direction)
+                    .dot(normal)
+                    .atan2(previous.dot(direction));
+                precession.direction = direction;
+                precession.angle += turn;
+                precession.orbits += 1;
+                let arcsec = |angle: f64| angle.to_degrees() * 3600.0;
+                info!(
+                    "periapsis of {} around {} turned by {:.3}\" over the last orbit, \
+                     {:.3}\" per orbit and {:.3}\" per 36525 time units (a century in days) \
+                     on average",
+                    name(name_b, b),
+                    name(name_a, a),
+                    arcsec(turn),
+                    arcsec(precession.angle / precession.orbits as f64),
+                    arcsec(precession.angle * 36_525.0 / (time - precession.start)),
+                );
+            }
+        }
+    }
+}
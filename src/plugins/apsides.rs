@@ -0,0 +1,215 @@
+use bevy::{prelude::*, render::camera::Camera, render::pipeline::PrimitiveTopology};
+use std::f64::consts::PI;
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut, to_local};
+use super::nbody::{FrameRotation, Gravity, Mass, PhysicsSystem, Position, TestParticle, Velocity};
+use super::pan_orbit_camera::{CameraSystem, PanOrbitCamera};
+use super::ui::UiFont;
+use crate::kepler::{dominant_attractor, orbital_elements};
+
+/// Marks the periapsis and apoapsis of the osculating orbit of every body with small
+/// crosses, labeled with their distances to the attractor, `q` and `Q`. The attractor is
+/// the one of the [`OrbitsPlugin`](super::orbits::OrbitsPlugin), and the apsides move as
+/// the orbits precess or are perturbed. Unbound orbits only have a periapsis.
+pub struct ApsidesPlugin {
+    /// Initial value of [`ShowApsides`]
+    pub enabled: bool,
+}
+
+impl Plugin for ApsidesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ShowApsides(self.enabled))
+            .add_system(add_apsides.system())
+            .add_system(remove_apsides.system())
+            .add_system(
+                update_apsides
+                    .system()
+                    .after(PhysicsSystem::Interpolate)
+                    .after(CameraSystem::Follow),
+            );
+    }
+}
+
+/// Apsides are drawn only while this is set
+pub struct ShowApsides(pub bool);
+
+const COLOR: Color = Color::rgba(1.0, 0.85, 0.4, 0.8);
+/// Size of the crosses, as a fraction of the distance of their apsis to the attractor
+const SIZE: f32 = 0.03;
+/// Vertices of a cross, a segment along each axis
+const CROSS_VERTICES: usize = 6;
+const FONT_SIZE: f32 = 12.0;
+/// Offset of the labels from their apsis on screen, in pixels
+const OFFSET: f32 = 4.0;
+
+/// Line list of the crosses at the periapsis and apoapsis of a body, as a child entity, and
+/// the texts of their distances
+struct Apsides {
+    mesh: Handle<Mesh>,
+    line: Entity,
+    labels: [Entity; 2],
+}
+
+/// Body whose apsis a text entity shows
+struct ApsisOf(Entity);
+
+/// Test particles are left out, there can be too many of them
+#[allow(clippy::type_complexity)]
+fn add_apsides(
+    mut commands: Commands,
+    show: Res<ShowApsides>,
+    font: Res<UiFont>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<Entity, (With<Mass>, Without<Apsides>, Without<TestParticle>)>,
+) {
+    if !show.0 {
+        return;
+    }
+    for entity in query.iter() {
+        let mesh = meshes.add(empty_mesh(PrimitiveTopology::LineList, 2 * CROSS_VERTICES));
+        let line = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+        let mut label = || {
+            commands
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: FONT_SIZE,
+                            color: COLOR,
+                        },
+                        Default::default(),
+                    ),
+                    visible: Visible {
+                        is_visible: false,
+                        is_transparent: true,
+                    },
+                    ..Default::default()
+                })
+                .insert(ApsisOf(entity))
+                .id()
+        };
+        let labels = [label(), label()];
+        commands
+            .entity(entity)
+            .push_children(&[line])
+            .insert(Apsides { mesh, line, labels });
+    }
+}
+
+/// Drop the apsides of the bodies that no longer exist, e.g. after a merge, or all of them
+/// once hidden
+fn remove_apsides(
+    mut commands: Commands,
+    show: Res<ShowApsides>,
+    labels: Query<(Entity, &ApsisOf)>,
+    bodies: Query<(Entity, &Apsides)>,
+    exists: Query<(), With<Mass>>,
+) {
+    for (entity, of) in labels.iter() {
+        if !show.0 || exists.get(of.0).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
+    if show.0 {
+        return;
+    }
+    for (entity, apsides) in bodies.iter() {
+        commands.entity(apsides.line).despawn_recursive();
+        commands.entity(entity).remove::<Apsides>();
+    }
+}
+
+/// Recompute the elements of every orbit from the current state, as for the orbits, and
+/// put the crosses at their apsides, in the local space of the body, and the labels next
+/// to them on screen. A cross and its label are hidden while their apsis does not exist.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_apsides(
+    gravity: Res<Gravity>,
+    rotation: Res<FrameRotation>,
+    windows: Res<Windows>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cameras: Query<(&Camera, &Transform), With<PanOrbitCamera>>,
+    bodies: Query<(&Mass, &Position, &Velocity, &Transform, &Apsides)>,
+    attractors: Query<(Entity, &Mass, &Position, &Velocity, &Transform), Without<TestParticle>>,
+    mut labels: Query<(&mut Style, &mut Text, &mut Visible), With<ApsisOf>>,
+) {
+    // The global transform of the camera is only computed at the end of the frame
+    let camera = cameras
+        .iter()
+        .next()
+        .map(|(camera, transform)| (camera, GlobalTransform::from(*transform)));
+    for (mass, pos, vel, transform, apsides) in bodies.iter() {
+        let attractor = dominant_attractor(
+            mass.0,
+            pos.0,
+            attractors.iter().map(|body| (body.1 .0, body.2 .0, body)),
+        );
+        // World position and distance of the periapsis, then of the apoapsis
+        let points: [Option<(Vec3, f64)>; 2] = match attractor {
+            None => [None, None],
+            Some((_, center_mass, center_pos, center_vel, center_transform)) => {
+                let rel = pos.0 - center_pos.0;
+                let mu = gravity.0 * (mass.0 + center_mass.0);
+                let elements = orbital_elements(rel, vel.0 - center_vel.0, mu);
+                let at =
+                    |nu: f64| center_transform.translation + rotation.0 * elements.position_at(nu);
+                [
+                    Some((at(0.0), elements.periapsis())),
+                    elements.apoapsis().map(|distance| (at(PI), distance)),
+                ]
+            }
+        };
+
+        if let Some(mesh) = meshes.get_mut(&apsides.mesh) {
+            let vertices: Vec<Vec3> = points
+                .iter()
+                .flat_map(|point| {
+                    let (center, size) =
+                        point.map_or((transform.translation, 0.0), |(p, d)| (p, SIZE * d as f32));
+                    [Vec3::X, Vec3::Y, Vec3::Z]
+                        .iter()
+                        .flat_map(move |&axis| [center - axis * size, center + axis * size])
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            for (vertex, pos) in positions_mut(mesh).iter_mut().zip(vertices) {
+                *vertex = to_local(transform, pos - transform.translation).into();
+            }
+            let colors = colors_mut(mesh);
+            for (i, color) in colors.iter_mut().enumerate() {
+                let shown = points[i / CROSS_VERTICES].is_some();
+                *color = if shown { COLOR.as_rgba_f32() } else { [0.0; 4] };
+            }
+        }
+
+        for (&label, (point, name)) in apsides.labels.iter().zip(points.iter().zip(["q", "Q"])) {
+            let (mut style, mut text, mut visible) = match labels.get_mut(label) {
+                Ok(label) => label,
+                Err(_) => continue,
+            };
+            let screen = point
+                .zip(camera.as_ref())
+                .and_then(|((p, d), (camera, global))| {
+                    camera
+                        .world_to_screen(&windows, global, p)
+                        .map(|screen| (screen, d))
+                });
+            let (screen, distance) = match screen {
+                Some(screen) => screen,
+                None => {
+                    visible.is_visible = false;
+                    continue;
+                }
+            };
+            visible.is_visible = true;
+            text.sections[0].value = format!("{} = {:.3}", name, distance);
+            style.position.left = Val::Px(screen.x + OFFSET);
+            style.position.bottom = Val::Px(screen.y + OFFSET);
+        }
+    }
+}
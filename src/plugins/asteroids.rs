@@ -0,0 +1,189 @@
+use bevy::{prelude::*, render::mesh::Indices, render::pipeline::PrimitiveTopology};
+use rand::Rng;
+use std::f32::consts::TAU;
+
+use super::lines::{empty_mesh, line_bundle};
+use super::nbody::{BodyBundle, Gravity, Mass, PhysicsSystem, Position, TestParticle, Velocity};
+use super::random::SimRng;
+use crate::AU_TO_UNIT_SCALE;
+
+/// Adds a belt of small bodies between Mars and Jupiter, around the heaviest body of the
+/// scene, once the startup systems have spawned it. The orbits are drawn from the
+/// [`SimRng`], the same belt is generated for the same seed.
+///
+/// All the asteroids are drawn as a single mesh, rebuilt every frame from their transforms,
+/// instead of a mesh each: tens of thousands of them take one draw call.
+pub struct AsteroidsPlugin {
+    pub count: usize,
+    /// Additional asteroids spawned as [`TestParticle`]s, massless and much cheaper
+    pub particles: usize,
+    /// Off without a window, where nothing is drawn
+    pub render: bool,
+}
+
+impl Plugin for AsteroidsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(AsteroidBelt {
+            count: self.count,
+            particles: self.particles,
+        })
+        .add_startup_system_to_stage(StartupStage::PostStartup, spawn_asteroids.system());
+        if self.render {
+            app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_belt_mesh.system())
+                .add_system(draw_asteroids.system().after(PhysicsSystem::Interpolate));
+        }
+    }
+}
+
+struct AsteroidBelt {
+    count: usize,
+    particles: usize,
+}
+
+/// Bodies drawn by the [`BeltMesh`]
+struct Asteroid;
+
+/// Triangle mesh of all the asteroids, in world space
+struct BeltMesh {
+    mesh: Handle<Mesh>,
+    entity: Entity,
+}
+
+/// Corners of a tetrahedron around the center of each asteroid, each corner being shaded
+/// differently to tell its faces apart without lighting
+const CORNERS: [[f32; 3]; 4] = [
+    [1.0, 1.0, 1.0],
+    [1.0, -1.0, -1.0],
+    [-1.0, 1.0, -1.0],
+    [-1.0, -1.0, 1.0],
+];
+const SHADES: [f32; 4] = [1.0, 0.75, 0.55, 0.35];
+const FACES: [u32; 12] = [0, 1, 2, 0, 3, 1, 0, 2, 3, 1, 3, 2];
+
+/// Heliocentric distances of the belt, in AU
+const INNER: f32 = 2.1;
+const OUTER: f32 = 3.3;
+const MAX_ECCENTRICITY: f32 = 0.1;
+/// In degrees, from the XY plane (the ecliptic in the solar system)
+const MAX_INCLINATION: f32 = 10.0;
+/// Light enough not to disturb the planets, in the mass unit of the solar system (10^24 kg)
+const MASS: f32 = 1e-6;
+const RADIUS: f32 = 0.05;
+
+/// Each asteroid starts at the periapsis of its orbit, where a speed of `sqrt(1 + e)` times
+/// the circular-orbit speed gives the eccentricity `e`
+fn spawn_asteroids(
+    mut commands: Commands,
+    belt: Res<AsteroidBelt>,
+    g: Res<Gravity>,
+    mut rng: ResMut<SimRng>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(&Mass, &Position, &Velocity)>,
+) {
+    let (sun_mass, sun_pos, sun_vel) = match bodies
+        .iter()
+        .max_by(|(a, ..), (b, ..)| a.0.partial_cmp(&b.0).unwrap())
+    {
+        Some((mass, pos, vel)) => (mass.0, pos.0, vel.0),
+        None => {
+            warn!("no body for the asteroids to orbit");
+            return;
+        }
+    };
+
+    // Not rendered, but the color of the asteroids for the trails and saved states
+    let material = materials.add(StandardMaterial {
+        base_color: Color::GRAY,
+        roughness: 0.9,
+        reflectance: 0.1,
+        ..Default::default()
+    });
+
+    let rng = &mut rng.0;
+    for i in 0..belt.count + belt.particles {
+        let particle = i >= belt.count;
+        let r = AU_TO_UNIT_SCALE * rng.gen_range(INNER..OUTER);
+        let longitude = rng.gen_range(0.0..TAU);
+        let eccentricity = rng.gen_range(0.0..MAX_ECCENTRICITY);
+        let inclination = rng
+            .gen_range(-MAX_INCLINATION..MAX_INCLINATION)
+            .to_radians();
+
+        let radial = Vec3::new(longitude.cos(), longitude.sin(), 0.0);
+        let speed = (g.0 * sun_mass / r).sqrt() * (1.0 + eccentricity).sqrt();
+        // Tilting the velocity about the radial direction tilts the whole orbit
+        let tangent = Quat::from_axis_angle(radial, inclination) * Vec3::Z.cross(radial);
+
+        let mass = if particle { 0.0 } else { MASS };
+        let mut entity = commands.spawn_bundle((material.clone(), GlobalTransform::default()));
+        entity
+            .insert_bundle(
+                BodyBundle::new(mass, sun_pos + r * radial, sun_vel + speed * tangent)
+                    .with_radius(RADIUS),
+            )
+            .insert(Asteroid);
+        if particle {
+            entity.insert(TestParticle);
+        }
+    }
+}
+
+fn spawn_belt_mesh(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let mesh = meshes.add(empty_mesh(PrimitiveTopology::TriangleList, 0));
+    let entity = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+    commands.insert_resource(BeltMesh { mesh, entity });
+}
+
+/// Refill the vertices of the belt mesh: a tetrahedron per asteroid, in the color of its
+/// material. The indices only change with the number of asteroids, e.g. after collisions.
+/// The mesh is hidden once all of them are gone, an empty mesh cannot be drawn.
+fn draw_asteroids(
+    belt: Option<Res<BeltMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    asteroids: Query<(&Transform, &Handle<StandardMaterial>), With<Asteroid>>,
+    mut visible: Query<&mut Visible>,
+) {
+    let belt = match belt {
+        Some(belt) => belt,
+        None => return,
+    };
+    let mesh = match meshes.get_mut(&belt.mesh) {
+        Some(mesh) => mesh,
+        None => return,
+    };
+    // Usually as many as last frame
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(mesh.count_vertices());
+    let mut colors = Vec::with_capacity(positions.capacity());
+    for (transform, material) in asteroids.iter() {
+        let [r, g, b, a] = materials
+            .get(material)
+            .map_or(Color::GRAY, |material| material.base_color)
+            .as_rgba_f32();
+        for (corner, shade) in CORNERS.iter().zip(SHADES.iter()) {
+            let offset = RADIUS * transform.scale * Vec3::from(*corner).normalize();
+            positions.push((transform.translation + offset).into());
+            colors.push([r * shade, g * shade, b * shade, a]);
+        }
+    }
+
+    let count = positions.len() / CORNERS.len();
+    if let Ok(mut visible) = visible.get_mut(belt.entity) {
+        visible.is_visible = count > 0;
+    }
+    if count == 0 {
+        return;
+    }
+    if mesh.count_vertices() != positions.len() {
+        let indices = (0..count as u32)
+            .flat_map(|i| {
+                FACES
+                    .iter()
+                    .map(move |corner| i * CORNERS.len() as u32 + corner)
+            })
+            .collect();
+        mesh.set_indices(Some(Indices::U32(indices)));
+    }
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use super::nbody::{SimState, SlowMotion};
+use super::ui::UiFont;
+use crate::calendar::Date;
+
+/// Shows the simulated time in the top-left corner, as a date for scenes whose time unit
+/// is the day, and the speed factor
+pub struct ClockPlugin {
+    /// See [`Epoch`]
+    pub epoch: Option<Date>,
+}
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Epoch(self.epoch))
+            .add_startup_system(spawn_clock.system())
+            .add_system(update_clock.system());
+    }
+}
+
+/// Date at time 0, `None` if the time unit of the scene is not the day
+pub struct Epoch(pub Option<Date>);
+
+struct ClockText;
+
+/// Days in a Julian year
+const YEAR: f64 = 365.25;
+
+/// `duration` in days, or in years past a thousand days, for the scenes whose time unit is
+/// the day, and in time units otherwise
+pub fn format_duration(duration: f64, epoch: &Epoch) -> String {
+    match epoch.0 {
+        Some(_) if duration >= 1000.0 => format!("{:.1} years", duration / YEAR),
+        Some(_) => format!("{:.1} days", duration),
+        None => format!("{:.2} time units", duration),
+    }
+}
+
+fn spawn_clock(mut commands: Commands, font: Res<UiFont>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(ClockText);
+}
+
+/// The time is the one the integrator advanced, by its time step scaled by the speed factor
+fn update_clock(
+    state: Res<SimState>,
+    slow: Res<SlowMotion>,
+    epoch: Res<Epoch>,
+    mut texts: Query<&mut Text, With<ClockText>>,
+) {
+    let value = match epoch.0 {
+        Some(epoch) => {
+            let date = Date::from_days(epoch.days() + state.time.floor() as i64);
+            format!("{}\n{:.1} days", date, state.time)
+        }
+        None => format!("t = {:.2}", state.time),
+    };
+    let value = if state.paused {
+        format!("{}\nPaused", value)
+    } else if state.speed_factor == 0.0 {
+        format!("{}\nFrozen", value)
+    } else if slow.factor < 1.0 {
+        let speed = state.speed_factor * slow.factor;
+        format!("{}\nSpeed: {:.2}x (slow motion)", value, speed)
+    } else {
+        format!("{}\nSpeed: {:.2}x", value, state.speed_factor)
+    };
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+use std::str::FromStr;
+
+use super::nbody::{Gravity, Mass, PhysicsSystem, Position, TestParticle, Velocity};
+use crate::kepler::dominant_attractor;
+use crate::units::{MASS_UNIT, SOLAR_MASS};
+
+/// Colors the bodies by their mass, speed or temperature instead of the colors of the scene.
+/// Each body gets a material of its own, the startups sharing them between similar bodies.
+/// Test particles keep their colors, there can be too many of them.
+pub struct ColorsPlugin {
+    pub by: ColorBy,
+}
+
+impl Plugin for ColorsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(self.by)
+            .init_resource::<SpeedRange>()
+            .add_system(own_materials.system())
+            .add_system(color_bodies.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+/// What the colors of the bodies show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorBy {
+    /// The colors of the scene
+    Fixed,
+    /// From light to heavy along a gradient, on a logarithmic scale
+    Mass,
+    /// Speed relative to the attractor, from slow to fast along the gradient of the masses
+    Speed,
+    /// Blackbody color of a main-sequence star of the mass of the body
+    Temperature,
+}
+
+impl FromStr for ColorBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(ColorBy::Fixed),
+            "mass" => Ok(ColorBy::Mass),
+            "speed" => Ok(ColorBy::Speed),
+            "temperature" => Ok(ColorBy::Temperature),
+            _ => Err(format!("unknown color-by `{}`", s)),
+        }
+    }
+}
+
+const LOW: Color = Color::rgb(0.15, 0.25, 0.9);
+const MIDDLE: Color = Color::rgb(0.2, 0.8, 0.45);
+const HIGH: Color = Color::rgb(1.0, 0.75, 0.15);
+
+/// Effective temperature of the Sun, in K
+const SUN_TEMPERATURE: f32 = 5772.0;
+
+/// Lowest and highest speeds so far, in the logarithmic scale of the colors: the range only
+/// grows, so that bodies do not change color as others speed up
+#[derive(Default)]
+struct SpeedRange(Option<(f32, f32)>);
+
+/// Body whose material is its own
+struct OwnMaterial;
+
+fn gradient(t: f32) -> Color {
+    let mix = |a: Color, b: Color, t: f32| {
+        let (a, b) = (Vec4::from(a.as_rgba_f32()), Vec4::from(b.as_rgba_f32()));
+        let [r, g, b, _]: [f32; 4] = (a + (b - a) * t).into();
+        Color::rgb(r, g, b)
+    };
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        mix(LOW, MIDDLE, t * 2.0)
+    } else {
+        mix(MIDDLE, HIGH, t * 2.0 - 1.0)
+    }
+}
+
+/// sRGB color of a black body at `kelvin`, from the fit of Tanner Helland to the CIE 1964
+/// color matching functions, between 1000 K and 40000 K
+fn blackbody(kelvin: f32) -> Color {
+    let t = kelvin.clamp(1000.0, 40_000.0) / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_17 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    let channel = |c: f32| (c / 255.0).clamp(0.0, 1.0);
+    Color::rgb(channel(r), channel(g), channel(b))
+}
+
+/// Position of `value` between `min` and `max` on a logarithmic scale, in the middle if
+/// they are the same
+fn log_scale(value: f32, min: f32, max: f32) -> f32 {
+    if max <= min {
+        return 0.5;
+    }
+    (value.ln() - min.ln()) / (max.ln() - min.ln())
+}
+
+#[allow(clippy::type_complexity)]
+fn own_materials(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut bodies: Query<
+        (Entity, &mut Handle<StandardMaterial>),
+        (With<Mass>, Without<TestParticle>, Without<OwnMaterial>),
+    >,
+) {
+    for (entity, mut material) in bodies.iter_mut() {
+        if let Some(copy) = materials.get(&*material).map(copy_material) {
+            *material = materials.add(copy);
+            commands.entity(entity).insert(OwnMaterial);
+        }
+    }
+}
+
+/// Materials of Bevy 0.5 are not `Clone`
+pub fn copy_material(material: &StandardMaterial) -> StandardMaterial {
+    StandardMaterial {
+        base_color: material.base_color,
+        base_color_texture: material.base_color_texture.clone(),
+        roughness: material.roughness,
+        metallic: material.metallic,
+        metallic_roughness_texture: material.metallic_roughness_texture.clone(),
+        reflectance: material.reflectance,
+        normal_map: material.normal_map.clone(),
+        double_sided: material.double_sided,
+        occlusion_texture: material.occlusion_texture.clone(),
+        emissive: material.emissive,
+        emissive_texture: material.emissive_texture.clone(),
+        unlit: material.unlit,
+    }
+}
+
+/// Masses are in the units of the Solar System scenes, 10^24 kg, unless the gravitational
+/// constant is 1: in those units of the other startups, they are in solar masses. The
+/// attractor a speed is relative to is the heavier body pulling the hardest, as for the
+/// orbits, and the heaviest body has the speed in the frame. Materials are only changed
+/// when their color does, for the others not to be sent to the GPU again.
+#[allow(clippy::type_complexity)]
+fn color_bodies(
+    by: Res<ColorBy>,
+    g: Res<Gravity>,
+    mut range: ResMut<SpeedRange>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<
+        (
+            Entity,
+            &Mass,
+            &Position,
+            &Velocity,
+            &Handle<StandardMaterial>,
+        ),
+        With<OwnMaterial>,
+    >,
+) {
+    let colors: Vec<(Handle<StandardMaterial>, Color)> = match *by {
+        ColorBy::Fixed => return,
+        ColorBy::Mass => {
+            let (min, max) = bodies.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min, max), (_, mass, ..)| (min.min(mass.0), max.max(mass.0)),
+            );
+            let min = min.max(f32::MIN_POSITIVE);
+            bodies
+                .iter()
+                .filter(|(_, mass, ..)| mass.0 > 0.0)
+                .map(|(_, mass, .., material)| {
+                    (material.clone(), gradient(log_scale(mass.0, min, max)))
+                })
+                .collect()
+        }
+        ColorBy::Speed => {
+            let speeds: Vec<(Handle<StandardMaterial>, f32)> = bodies
+                .iter()
+                .map(|(_, mass, pos, vel, material)| {
+                    let attractor = dominant_attractor(
+                        mass.0,
+                        pos.0,
+                        bodies.iter().map(|(_, m, p, v, _)| (m.0, p.0, v)),
+                    );
+                    let speed = match attractor {
+                        Some(center_vel) => vel.0.distance(center_vel.0),
+                        None => vel.0.length(),
+                    };
+                    (material.clone(), speed)
+                })
+                .filter(|(_, speed)| *speed > 0.0 && speed.is_finite())
+                .collect();
+            for &(_, speed) in speeds.iter() {
+                range.0 = Some(match range.0 {
+                    None => (speed, speed),
+                    Some((min, max)) => (min.min(speed), max.max(speed)),
+                });
+            }
+            let (min, max) = match range.0 {
+                Some(range) => range,
+                None => return,
+            };
+            speeds
+                .into_iter()
+                .map(|(material, speed)| (material, gradient(log_scale(speed, min, max))))
+                .collect()
+        }
+        ColorBy::Temperature => {
+            let solar_mass = if g.0 == 1.0 {
+                1.0
+            } else {
+                (SOLAR_MASS / MASS_UNIT) as f32
+            };
+            bodies
+                .iter()
+                .map(|(_, mass, .., material)| {
+                    let kelvin = SUN_TEMPERATURE * (mass.0.max(0.0) / solar_mass).sqrt();
+                    (material.clone(), blackbody(kelvin))
+                })
+                .collect()
+        }
+    };
+
+    for (handle, color) in colors {
+        if materials.get(&handle).map(|material| material.base_color) == Some(color) {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(&handle) {
+            material.base_color = color;
+            // Stars glow with their new color
+            if material.emissive != Color::BLACK {
+                material.emissive = color;
+            }
+        }
+    }
+}
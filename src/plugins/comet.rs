@@ -0,0 +1,222 @@
+use bevy::prelude::*;
+use rand::Rng;
+use rand_distr::{Distribution, UnitSphere};
+use std::collections::VecDeque;
+
+use super::nbody::{
+    Beta, BodyBundle, Gravity, Mass, PhysicsSystem, Position, SimState, TestParticle, TrueRadius,
+    Velocity, G,
+};
+use super::pan_orbit_camera::CameraTarget;
+use super::random::SimRng;
+use super::restart::reset_resource;
+use crate::kepler::{state_from_elements, OrbitalElements};
+use crate::scene::{spawn_body, Appearance};
+use crate::{km_to_units, solar_system, spawn_z_camera, units, AU_TO_UNIT_SCALE};
+
+/// A comet on the orbit of Halley's, diving from beyond Neptune to within the orbit of
+/// Venus every 75 years, followed by the camera. Close to the Sun it releases dust, which
+/// radiation pressure blows away into a tail, each grain with a random [`Beta`] drawn from
+/// the [`SimRng`].
+pub struct CometPlugin {
+    /// Around the Sun and planets of [`solar_system`], or the Sun alone
+    pub planets: bool,
+}
+
+impl Plugin for CometPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if self.planets {
+            app.add_startup_system(solar_system.system());
+        } else {
+            app.add_startup_system(sun.system());
+        }
+        app.init_resource::<Dust>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, reset_resource::<Dust>.system())
+            .add_startup_system_to_stage(StartupStage::PostStartup, comet.system())
+            .add_system(release_dust.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+/// Elements of 1P/Halley, with the semi-major axis in AU, starting on its way in
+const ELEMENTS: OrbitalElements = OrbitalElements {
+    semi_major_axis: 17.834,
+    eccentricity: 0.967,
+    inclination: 162.26 * std::f64::consts::PI / 180.0,
+    longitude_of_ascending_node: 58.42 * std::f64::consts::PI / 180.0,
+    argument_of_periapsis: 111.33 * std::f64::consts::PI / 180.0,
+    true_anomaly: -120.0 * std::f64::consts::PI / 180.0,
+};
+/// In 10^24 kg, massive so that its orbit can be drawn with `--orbits`
+const MASS: f32 = 2.2e-10;
+/// Exaggerated, the nucleus being 11 km long
+const RADIUS: f32 = 0.1;
+
+/// Distance to the Sun within which the comet is active, in AU
+const ACTIVE_DISTANCE: f32 = 3.0;
+/// Simulated days between two grains
+const RELEASE_INTERVAL: f64 = 0.25;
+/// Grains released in a frame at most, when the simulation runs fast
+const MAX_RELEASES: usize = 20;
+/// Grains kept at most, the oldest being despawned first
+const MAX_GRAINS: usize = 2000;
+/// Speed of the outgassing that ejects the grains, in AU per day (100 m/s), from the coma
+/// this far from the nucleus, in AU. Grains released at the nucleus would be so close to
+/// it that the adaptive substeps would all be spent on them.
+const EJECTION_SPEED: f32 = 5.8e-5;
+const COMA_RADIUS: f32 = 1e-3;
+/// Range of the [`Beta`] of the grains, below 1, past which they would not even be bound
+/// to the Sun
+const BETA: (f32, f32) = (0.05, 0.9);
+const GRAIN_RADIUS: f32 = 0.02;
+
+struct Comet;
+
+/// Grains of dust released so far, oldest first, and the time the next one is due
+#[derive(Default)]
+struct Dust {
+    grains: VecDeque<Entity>,
+    next: Option<f64>,
+    assets: Option<(Handle<Mesh>, Handle<StandardMaterial>)>,
+}
+
+fn sun(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    g.0 = G * units::solar_g_scale(AU_TO_UNIT_SCALE);
+    let sun = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(
+            units::kg_to_mass_units(units::SOLAR_MASS) as f32,
+            Vec3::ZERO,
+            Vec3::ZERO,
+        ),
+        &Appearance {
+            radius: 2.8,
+            color: Color::YELLOW,
+            emissive: Color::YELLOW,
+            light: Some(50_000.0),
+            rings: None,
+        },
+    );
+    commands
+        .entity(sun)
+        .insert(TrueRadius(km_to_units(695_700.0)))
+        .insert(Name::new("Sun"));
+    spawn_z_camera(&mut commands, 80.0);
+}
+
+/// Around the heaviest body, once [`sun`] or [`solar_system`] have spawned it
+fn comet(
+    mut commands: Commands,
+    g: Res<Gravity>,
+    mut dust: ResMut<Dust>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(&Mass, &Position, &Velocity)>,
+) {
+    let (sun_mass, sun_pos, sun_vel) = match bodies
+        .iter()
+        .max_by(|(a, ..), (b, ..)| a.0.partial_cmp(&b.0).unwrap())
+    {
+        Some((mass, pos, vel)) => (mass.0, pos.0, vel.0),
+        None => return,
+    };
+    let mut elements = ELEMENTS;
+    elements.semi_major_axis *= AU_TO_UNIT_SCALE as f64;
+    let (pos, vel) = state_from_elements(&elements, g.0 * (sun_mass + MASS));
+    let comet = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(MASS, sun_pos + pos, sun_vel + vel),
+        &Appearance {
+            radius: RADIUS,
+            color: Color::rgb(0.8, 0.9, 1.0),
+            emissive: Color::rgb(0.3, 0.35, 0.4),
+            light: None,
+            rings: None,
+        },
+    );
+    commands
+        .entity(comet)
+        .insert(Comet)
+        .insert(TrueRadius(km_to_units(5.5)))
+        .insert(Name::new("Comet"));
+    commands.insert_resource(CameraTarget(Some(comet)));
+
+    // Shared by all the grains
+    let mesh = meshes.add(Mesh::from(shape::Icosphere {
+        radius: GRAIN_RADIUS,
+        subdivisions: 1,
+    }));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.9, 0.85, 0.7),
+        emissive: Color::rgb(0.45, 0.42, 0.35),
+        ..Default::default()
+    });
+    dust.assets = Some((mesh, material));
+}
+
+/// Grains leave the coma in random directions at the [`EJECTION_SPEED`], every
+/// [`RELEASE_INTERVAL`] while it is within the [`ACTIVE_DISTANCE`] of the Sun
+#[allow(clippy::type_complexity)]
+fn release_dust(
+    mut commands: Commands,
+    state: Res<SimState>,
+    mut dust: ResMut<Dust>,
+    mut rng: ResMut<SimRng>,
+    comet: Query<(&Position, &Velocity), With<Comet>>,
+    bodies: Query<(&Mass, &Position), Without<Comet>>,
+) {
+    let ((pos, vel), (mesh, material)) = match (comet.single(), dust.assets.clone()) {
+        (Ok(comet), Some(assets)) => (comet, assets),
+        // Merged into the Sun, e.g. with `--collisions merge`
+        _ => return,
+    };
+    let sun = bodies
+        .iter()
+        .max_by(|(a, _), (b, _)| a.0.partial_cmp(&b.0).unwrap())
+        .map(|(_, pos)| pos.0);
+    let active = sun.is_some_and(|sun| pos.0.distance(sun) < ACTIVE_DISTANCE * AU_TO_UNIT_SCALE);
+    if !active {
+        dust.next = None;
+        return;
+    }
+
+    let rng = &mut rng.0;
+    let mut next = dust.next.unwrap_or(state.time);
+    for _ in 0..MAX_RELEASES {
+        if next > state.time {
+            break;
+        }
+        next += RELEASE_INTERVAL;
+        let direction: [f32; 3] = UnitSphere.sample(rng);
+        let direction = Vec3::from(direction);
+        let start = pos.0 + direction * COMA_RADIUS * AU_TO_UNIT_SCALE;
+        let ejection = direction * EJECTION_SPEED * AU_TO_UNIT_SCALE;
+        let beta = rng.gen_range(BETA.0..BETA.1);
+        let grain = commands
+            .spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                ..Default::default()
+            })
+            .insert_bundle(BodyBundle::new(0.0, start, vel.0 + ejection).with_radius(GRAIN_RADIUS))
+            .insert(TestParticle)
+            .insert(Beta(beta))
+            .id();
+        dust.grains.push_back(grain);
+        if dust.grains.len() > MAX_GRAINS {
+            if let Some(oldest) = dust.grains.pop_front() {
+                commands.entity(oldest).despawn_recursive();
+            }
+        }
+    }
+    // Not catching up on the grains a slow frame skipped
+    dust.next = Some(next.max(state.time));
+}
@@ -0,0 +1,200 @@
+use bevy::{math::DVec3, prelude::*};
+use std::str::FromStr;
+
+use super::colors::copy_material;
+use super::nbody::{
+    Beta, FrameRotation, GhostWorld, GravityGroups, Group, Mass, PhysicsSystem, Position,
+    PreciseState, PreviousPosition, RadiationPressure, RenderTime, SimState, TestParticle,
+    Velocity,
+};
+use copernicus::integrator::Integrator;
+
+/// Runs the bodies a second time with another integrator, in a [`GhostWorld`] sharing their
+/// initial conditions, and draws that copy as faded ghosts: the ghosts drift away from the
+/// solid bodies as the errors of their integrator build up, e.g. Euler against RK4. Only the
+/// bodies of the first step are copied, those spawned later have no ghost.
+pub struct ComparePlugin {
+    /// Integrator of the ghosts
+    pub integrator: Integrator,
+}
+
+impl Plugin for ComparePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(GhostWorld {
+            integrator: self.integrator,
+            ..Default::default()
+        })
+        .init_resource::<Ghosts>()
+        .add_startup_system_to_stage(StartupStage::PreStartup, reset_ghosts.system())
+        .add_system(fill_ghosts.system().before(PhysicsSystem::Integrate))
+        .add_system(add_ghost_meshes.system())
+        .add_system(draw_ghosts.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+/// Integrators of `--compare`, the one of the ghosts first, then the one of the bodies,
+/// e.g. `euler,rk4`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    pub ghosts: Integrator,
+    pub bodies: Integrator,
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ghosts, bodies) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected two integrators, e.g. `euler,rk4`, not `{}`", s))?;
+        Ok(Comparison {
+            ghosts: ghosts.trim().parse()?,
+            bodies: bodies.trim().parse()?,
+        })
+    }
+}
+
+/// Opacity of the ghosts
+const ALPHA: f32 = 0.35;
+
+/// Body each ghost of the [`GhostWorld`] was copied from, in its order, and the entity
+/// drawing the ghost once its body has a mesh
+#[derive(Default)]
+struct Ghosts {
+    twins: Vec<Entity>,
+    drawn: Vec<Option<Entity>>,
+}
+
+/// Index of the ghost an entity draws
+struct Ghost(usize);
+
+/// The ghosts of a restart are copied again from the new bodies, the entities drawing the
+/// old ones being despawned with everything else
+fn reset_ghosts(mut world: ResMut<GhostWorld>, mut ghosts: ResMut<Ghosts>) {
+    *world = GhostWorld {
+        integrator: world.integrator,
+        ..Default::default()
+    };
+    *ghosts = Ghosts::default();
+}
+
+/// Copy the bodies before the first step, in double precision
+#[allow(clippy::type_complexity)]
+fn fill_ghosts(
+    state: Res<SimState>,
+    radiation: Res<RadiationPressure>,
+    groups: Res<GravityGroups>,
+    mut world: ResMut<GhostWorld>,
+    mut ghosts: ResMut<Ghosts>,
+    bodies: Query<(
+        Entity,
+        &Mass,
+        &Position,
+        &Velocity,
+        Option<&PreciseState>,
+        Option<&TestParticle>,
+        Option<&Beta>,
+        Option<&Group>,
+    )>,
+) {
+    if state.steps != 0 || !ghosts.twins.is_empty() {
+        return;
+    }
+    for (entity, mass, pos, vel, precise, particle, beta, group) in bodies.iter() {
+        let (pos, vel) = precise.map_or((pos.0.as_f64(), vel.0.as_f64()), |p| (p.pos, p.vel));
+        world.bodies.push(mass.0, pos, vel, particle.is_some());
+        if let (true, Some(beta)) = (radiation.0, beta) {
+            world.bodies.irradiate(beta.0);
+        }
+        if let Some(group) = group {
+            world.bodies.join(groups.index(group));
+        }
+        ghosts.twins.push(entity);
+        ghosts.drawn.push(None);
+    }
+    world.previous = world.bodies.pos.clone();
+}
+
+/// Draw each ghost with the mesh of its body and a translucent copy of its material, once
+/// the body has them, e.g. from the frame after it was spawned
+fn add_ghost_meshes(
+    mut commands: Commands,
+    mut ghosts: ResMut<Ghosts>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(&Handle<Mesh>, &Handle<StandardMaterial>)>,
+) {
+    let ghosts = &mut *ghosts;
+    for (i, (&twin, drawn)) in ghosts.twins.iter().zip(ghosts.drawn.iter_mut()).enumerate() {
+        if drawn.is_some() {
+            continue;
+        }
+        let (mesh, material) = match bodies.get(twin) {
+            Ok(body) => body,
+            Err(_) => continue,
+        };
+        let mut material = match materials.get(material) {
+            Some(material) => copy_material(material),
+            None => continue,
+        };
+        material.base_color.set_a(ALPHA);
+        material.emissive.set_a(ALPHA);
+        let entity = commands
+            .spawn_bundle(PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(material),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(Ghost(i))
+            .id();
+        *drawn = Some(entity);
+    }
+}
+
+/// Place the ghosts between their last two states, as far from a body that still exists as
+/// they are in the ghost world, so that they follow the bodies into the frame they are drawn
+/// in. The ghosts stay hidden once all the bodies are gone.
+fn draw_ghosts(
+    state: Res<SimState>,
+    render_time: Res<RenderTime>,
+    rotation: Res<FrameRotation>,
+    world: Res<GhostWorld>,
+    ghosts: Res<Ghosts>,
+    bodies: Query<(&Position, &PreviousPosition, &Transform), Without<Ghost>>,
+    mut drawn: Query<(&Ghost, &mut Transform, &mut Visible)>,
+) {
+    // Fraction of the last step the bodies are drawn at
+    let alpha = if state.last_step > 0.0 {
+        (1.0 - (state.time - render_time.0) / state.last_step).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let ghost_at = |i: usize| world.previous[i].lerp(world.bodies.pos[i], alpha);
+    // Where a body is drawn, and where it is in the physics
+    let reference = ghosts.twins.iter().find_map(|&twin| {
+        bodies.get(twin).ok().map(|(pos, previous, transform)| {
+            let body = previous.0.as_f64().lerp(pos.0.as_f64(), alpha);
+            (transform.translation, body)
+        })
+    });
+    for (ghost, mut transform, mut visible) in drawn.iter_mut() {
+        let (origin, body) = match reference {
+            Some(reference) => reference,
+            None => {
+                visible.is_visible = false;
+                continue;
+            }
+        };
+        let relative: DVec3 = ghost_at(ghost.0) - body;
+        transform.translation = origin + rotation.0 * relative.as_f32();
+        visible.is_visible = true;
+        // Scaled and spun as its body, while it exists
+        if let Ok((.., twin)) = bodies.get(ghosts.twins[ghost.0]) {
+            transform.rotation = twin.rotation;
+            transform.scale = twin.scale;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+use copernicus::forces::potential_energy;
+
+use super::nbody::{ExternalField, Gravity, Mass, Position, Softening, TestParticle, Velocity};
+
+/// Adds conservation diagnostics of the N-body simulation to an App:
+/// kinetic, potential and total energy, and the magnitude of the total linear momentum.
+/// The potential energy includes the one in the [`ExternalField`], `-m a.r` for each body.
+/// Computed every frame in `O(N^2)`, so better kept for debugging.
+#[derive(Default)]
+pub struct NBodyDiagnosticsPlugin;
+
+impl Plugin for NBodyDiagnosticsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(Self::setup_system.system())
+            .add_system(Self::diagnostic_system.system());
+    }
+}
+
+/// Masses and positions gathered every frame, kept to avoid reallocating them
+#[derive(Default)]
+pub struct EnergyBuffers {
+    mass: Vec<f32>,
+    pos: Vec<Vec3>,
+}
+
+impl NBodyDiagnosticsPlugin {
+    pub const KINETIC_ENERGY: DiagnosticId =
+        DiagnosticId::from_u128(108218409843969479239964884234888379738);
+    pub const POTENTIAL_ENERGY: DiagnosticId =
+        DiagnosticId::from_u128(183538382092717022647224633910833737264);
+    pub const TOTAL_ENERGY: DiagnosticId =
+        DiagnosticId::from_u128(202234168210479300845950369118430962036);
+    pub const MOMENTUM: DiagnosticId =
+        DiagnosticId::from_u128(100323576276453585942675764075692740260);
+
+    pub fn setup_system(mut diagnostics: ResMut<Diagnostics>) {
+        diagnostics.add(Diagnostic::new(Self::KINETIC_ENERGY, "kinetic_energy", 20));
+        diagnostics.add(Diagnostic::new(
+            Self::POTENTIAL_ENERGY,
+            "potential_energy",
+            20,
+        ));
+        diagnostics.add(Diagnostic::new(Self::TOTAL_ENERGY, "total_energy", 20));
+        diagnostics.add(Diagnostic::new(Self::MOMENTUM, "momentum", 20));
+    }
+
+    pub fn diagnostic_system(
+        mut diagnostics: ResMut<Diagnostics>,
+        g: Res<Gravity>,
+        softening: Res<Softening>,
+        field: Res<ExternalField>,
+        mut buffers: Local<EnergyBuffers>,
+        query: Query<(&Mass, &Position, &Velocity), Without<TestParticle>>,
+    ) {
+        buffers.mass.clear();
+        buffers.pos.clear();
+        let mut kinetic = 0.0;
+        let mut momentum = Vec3::ZERO;
+        for (mass, pos, vel) in query.iter() {
+            kinetic += 0.5 * (mass.0 * vel.0.length_squared()) as f64;
+            momentum += vel.0 * mass.0;
+            buffers.mass.push(mass.0);
+            buffers.pos.push(pos.0);
+        }
+        let external: f64 = buffers
+            .mass
+            .iter()
+            .zip(&buffers.pos)
+            .map(|(&mass, pos)| -(mass * field.0.dot(*pos)) as f64)
+            .sum();
+        let potential = potential_energy(g.0, softening.0, &buffers.mass, &buffers.pos) + external;
+
+        diagnostics.add_measurement(Self::KINETIC_ENERGY, kinetic);
+        diagnostics.add_measurement(Self::POTENTIAL_ENERGY, potential);
+        diagnostics.add_measurement(Self::TOTAL_ENERGY, kinetic + potential);
+        diagnostics.add_measurement(Self::MOMENTUM, momentum.length() as f64);
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+
+use super::nbody::{PhysicsSystem, Position, Radius, SimState, TestParticle, TrueRadius};
+
+/// Flags the bodies that pass into the shadow of another one, cast by the bodies emitting a
+/// light, with [`Eclipsed`], and logs each eclipse once it is over. The shadows are those of
+/// spheres of the true sizes of the bodies, whatever the sizes they are drawn with.
+pub struct EclipsesPlugin;
+
+impl Plugin for EclipsesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(detect_eclipses.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+/// How deep a body is in the shadow of another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EclipseKind {
+    /// In the penumbra only, where the star is partly hidden
+    Penumbral,
+    /// Partly in the umbra, where the star is entirely hidden
+    Partial,
+    /// Entirely in the umbra
+    Total,
+}
+
+impl EclipseKind {
+    fn name(self) -> &'static str {
+        match self {
+            EclipseKind::Penumbral => "penumbral",
+            EclipseKind::Partial => "partial",
+            EclipseKind::Total => "total",
+        }
+    }
+}
+
+/// Body in the shadow of another one
+pub struct Eclipsed {
+    /// Body casting the shadow
+    pub by: Entity,
+    pub kind: EclipseKind,
+    /// Simulated time the eclipse began
+    start: f64,
+    /// Deepest kind reached so far
+    deepest: EclipseKind,
+}
+
+/// Where a body of `radius` at `target` is in the shadow that one of `occluder_radius` at
+/// `occluder` casts from a star. The umbra is the cone tangent to both spheres on the same
+/// side, closing behind the occluder, and the penumbra the cone tangent to them on opposite
+/// sides, opening behind it; past the apex of the umbra, the star is seen as a ring around
+/// the occluder, and the body is only in the penumbra.
+fn shadow(
+    (star, star_radius): (Vec3, f32),
+    (occluder, occluder_radius): (Vec3, f32),
+    (target, radius): (Vec3, f32),
+) -> Option<EclipseKind> {
+    let axis = occluder - star;
+    let distance = axis.length();
+    let axis = axis / distance;
+    let offset = target - occluder;
+    // Behind the occluder as seen from the star
+    let along = offset.dot(axis);
+    if along <= 0.0 {
+        return None;
+    }
+    let across = (offset - axis * along).length();
+    let umbra = occluder_radius - along * (star_radius - occluder_radius) / distance;
+    let penumbra = occluder_radius + along * (star_radius + occluder_radius) / distance;
+    if across + radius < umbra {
+        Some(EclipseKind::Total)
+    } else if across - radius < umbra {
+        Some(EclipseKind::Partial)
+    } else if across - radius < penumbra {
+        Some(EclipseKind::Penumbral)
+    } else {
+        None
+    }
+}
+
+fn body_name(entity: Entity, name: Option<&Name>) -> String {
+    name.map_or_else(|| format!("body {}", entity.id()), |n| n.to_string())
+}
+
+/// Test particles neither cast shadows nor get eclipsed, there may be too many of them for
+/// the pairs to be checked
+#[allow(clippy::type_complexity)]
+fn detect_eclipses(
+    mut commands: Commands,
+    state: Res<SimState>,
+    stars: Query<(&Position, &Radius, Option<&TrueRadius>), With<Light>>,
+    mut bodies: Query<
+        (
+            Entity,
+            &Position,
+            &Radius,
+            Option<&TrueRadius>,
+            Option<&Name>,
+            Option<&mut Eclipsed>,
+        ),
+        (Without<Light>, Without<TestParticle>),
+    >,
+) {
+    let true_radius = |radius: &Radius, true_radius: Option<&TrueRadius>| {
+        true_radius.map_or(radius.0, |true_radius| true_radius.0)
+    };
+    let stars: Vec<(Vec3, f32)> = stars
+        .iter()
+        .map(|(pos, radius, true_radius_of)| (pos.0, true_radius(radius, true_radius_of)))
+        .collect();
+    // Named up front, the query being borrowed mutably to flag the bodies
+    let spheres: Vec<(Entity, Vec3, f32, String)> = bodies
+        .iter_mut()
+        .map(|(entity, pos, radius, true_radius_of, name, _)| {
+            let radius = true_radius(radius, true_radius_of);
+            (entity, pos.0, radius, body_name(entity, name))
+        })
+        .collect();
+    let name_of = |entity: Entity| {
+        spheres
+            .iter()
+            .find(|(body, ..)| *body == entity)
+            .map_or_else(
+                || format!("body {}", entity.id()),
+                |(.., name)| name.clone(),
+            )
+    };
+
+    let shadows: Vec<(Entity, Option<(Entity, EclipseKind)>)> = spheres
+        .iter()
+        .map(|&(target, pos, radius, _)| {
+            let deepest = spheres
+                .iter()
+                .filter(|(occluder, ..)| *occluder != target)
+                .flat_map(|&(occluder, occluder_pos, occluder_radius, _)| {
+                    stars.iter().filter_map(move |&star| {
+                        shadow(star, (occluder_pos, occluder_radius), (pos, radius))
+                            .map(|kind| (occluder, kind))
+                    })
+                })
+                .max_by_key(|&(_, kind)| kind);
+            (target, deepest)
+        })
+        .collect();
+
+    for (target, shadow) in shadows {
+        let eclipsed = match bodies.get_mut(target) {
+            Ok((.., eclipsed)) => eclipsed,
+            Err(_) => continue,
+        };
+        let end = |eclipsed: &Eclipsed| {
+            info!(
+                "{} eclipse of {} by {} from t = {:.2} to {:.2}",
+                eclipsed.deepest.name(),
+                name_of(target),
+                name_of(eclipsed.by),
+                eclipsed.start,
+                state.time
+            );
+        };
+        match (eclipsed, shadow) {
+            (Some(mut eclipsed), Some((by, kind))) if eclipsed.by == by => {
+                eclipsed.kind = kind;
+                eclipsed.deepest = eclipsed.deepest.max(kind);
+            }
+            (eclipsed, Some((by, kind))) => {
+                if let Some(eclipsed) = eclipsed {
+                    end(&eclipsed);
+                }
+                commands.entity(target).insert(Eclipsed {
+                    by,
+                    kind,
+                    start: state.time,
+                    deepest: kind,
+                });
+            }
+            (Some(eclipsed), None) => {
+                end(&eclipsed);
+                commands.entity(target).remove::<Eclipsed>();
+            }
+            (None, None) => {}
+        }
+    }
+}
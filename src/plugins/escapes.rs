@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+use super::nbody::{Gravity, Mass, PhysicsSystem, Position, SimState, TestParticle, Velocity};
+use super::restart::reset_resource;
+
+/// Flags the bodies that are no longer bound to the rest of the system with [`Unbound`],
+/// sending an [`EjectionEvent`], and optionally despawns them once they are far away
+pub struct EscapesPlugin {
+    /// Distance from the barycenter past which unbound bodies are despawned
+    pub cull_distance: Option<f32>,
+}
+
+impl Plugin for EscapesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(CullDistance(self.cull_distance))
+            .init_resource::<Ejected>()
+            .add_event::<EjectionEvent>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<Ejected>.system(),
+            )
+            // Before the merges, whose despawns would come before the insertion of `Unbound`
+            .add_system(
+                flag_unbound
+                    .system()
+                    .after(PhysicsSystem::Integrate)
+                    .before(PhysicsSystem::Collisions),
+            );
+    }
+}
+
+struct CullDistance(Option<f32>);
+
+/// Body whose specific orbital energy relative to the barycenter is positive, and which
+/// does not orbit one of the heaviest bodies either: unless it meets another body, it
+/// escapes
+pub struct Unbound;
+
+/// Body flagged [`Unbound`], escaping the system. It may still be captured on its way out,
+/// or despawned past the cull distance. Also sent for a body deleted with the mouse in the
+/// sandbox, as it is despawned.
+#[derive(Debug, Clone, Copy)]
+pub struct EjectionEvent {
+    pub body: Entity,
+}
+
+/// Unbound bodies despawned past the cull distance since the start
+#[derive(Default)]
+pub struct Ejected(pub u64);
+
+/// Number of the heaviest bodies that the others may orbit, e.g. moons around their planet,
+/// whose speed relative to the barycenter can be more than its escape speed
+const HOSTS: usize = 16;
+
+fn body_name(entity: Entity, name: Option<&Name>) -> String {
+    name.map_or_else(|| format!("body {}", entity.id()), |n| n.to_string())
+}
+
+/// Test particles are flagged too, but do not count in the barycenter since they exert
+/// no gravity
+#[allow(clippy::type_complexity)]
+fn flag_unbound(
+    mut commands: Commands,
+    g: Res<Gravity>,
+    state: Res<SimState>,
+    cull: Res<CullDistance>,
+    mut ejected: ResMut<Ejected>,
+    mut events: EventWriter<EjectionEvent>,
+    bodies: Query<(
+        Entity,
+        &Mass,
+        &Position,
+        &Velocity,
+        Option<&Name>,
+        Option<&Unbound>,
+        Option<&TestParticle>,
+    )>,
+) {
+    let mut total_mass = 0.0;
+    let mut moment = Vec3::ZERO;
+    let mut momentum = Vec3::ZERO;
+    for (_, mass, pos, vel, .., particle) in bodies.iter() {
+        if particle.is_none() {
+            total_mass += mass.0;
+            moment += pos.0 * mass.0;
+            momentum += vel.0 * mass.0;
+        }
+    }
+    if total_mass <= 0.0 {
+        return;
+    }
+    let center = moment / total_mass;
+    let center_vel = momentum / total_mass;
+
+    let mut hosts: Vec<_> = bodies
+        .iter()
+        .filter(|(.., particle)| particle.is_none())
+        .map(|(entity, mass, pos, vel, ..)| (entity, mass.0, pos.0, vel.0))
+        .collect();
+    hosts.sort_by(|(_, a, ..), (_, b, ..)| b.partial_cmp(a).unwrap());
+    hosts.truncate(HOSTS);
+    // Negative two-body energy around one of the hosts, heavier than the body
+    let orbits_host = |entity, mass: f32, pos: Vec3, vel: Vec3| {
+        hosts.iter().any(|&(host, host_mass, host_pos, host_vel)| {
+            host != entity
+                && host_mass > mass
+                && vel.distance_squared(host_vel) / 2.0
+                    < g.0 * (host_mass + mass) / pos.distance(host_pos)
+        })
+    };
+
+    for (entity, mass, pos, vel, name, unbound, particle) in bodies.iter() {
+        // Pulled by the mass of all the other bodies, as if it were at the barycenter
+        let mass = if particle.is_some() { 0.0 } else { mass.0 };
+        let others = total_mass - mass;
+        if others <= 0.0 {
+            continue;
+        }
+        let distance = pos.0.distance(center);
+        let energy = vel.0.distance_squared(center_vel) / 2.0 - g.0 * others / distance;
+        let escaping = energy > 0.0 && !orbits_host(entity, mass, pos.0, vel.0);
+
+        match (escaping, unbound.is_some()) {
+            (true, false) => {
+                info!(
+                    "{} is escaping at t = {:.2}, {:.2} from the barycenter",
+                    body_name(entity, name),
+                    state.time,
+                    distance
+                );
+                commands.entity(entity).insert(Unbound);
+                events.send(EjectionEvent { body: entity });
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Unbound>();
+            }
+            _ => {}
+        }
+
+        if let Some(cull) = cull.0 {
+            if unbound.is_some() && escaping && distance > cull {
+                info!(
+                    "{} was ejected at t = {:.2}",
+                    body_name(entity, name),
+                    state.time
+                );
+                commands.entity(entity).despawn_recursive();
+                ejected.0 += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+    tasks::ComputeTaskPool,
+};
+use copernicus::{
+    barnes_hut::Octree,
+    forces::{Algorithm, Drag, Forces, Interactions},
+};
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut};
+use super::nbody::{ExternalField, Gravity, Mass, PhysicsSystem, Softening, Theta};
+
+/// Draws the gravitational potential of the massive bodies on the XY plane, the ecliptic
+/// of the solar system, as a heatmap sampled on a grid and updated every frame. The
+/// potential is computed like the forces, with Barnes-Hut when it is the `--algorithm`.
+pub struct FieldPlugin {
+    /// Samples along each side of the grid
+    pub resolution: usize,
+}
+
+impl Plugin for FieldPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(FieldResolution(self.resolution.max(2)))
+            .add_startup_system(spawn_field_plane.system())
+            .add_system(draw_field.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+/// Draws streamlines of the gravitational field on the XY plane, from a grid of tracers
+/// stepped a short way along it, each ending in an arrowhead towards where a body left there
+/// would start to fall. They are traced again every frame from where the bodies are drawn,
+/// with the field their forces come from, Barnes-Hut and the external field included. The
+/// tracers are only drawn: they are not bodies, the physics never sees them.
+pub struct FieldLinesPlugin;
+
+impl Plugin for FieldLinesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(spawn_field_lines.system())
+            .add_system(draw_field_lines.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+struct FieldResolution(usize);
+
+/// Triangle mesh of the grid, in world space
+struct FieldPlane {
+    mesh: Handle<Mesh>,
+    entity: Entity,
+}
+
+/// Line list of the streamlines, in world space
+struct FieldLines {
+    mesh: Handle<Mesh>,
+    entity: Entity,
+}
+
+/// The grids cover the bodies, with this much room around the farthest one
+const MARGIN: f32 = 1.25;
+/// Potential from the shallowest to the deepest sample, the bodies being in the wells
+const SHALLOW: Color = Color::rgba(0.05, 0.05, 0.3, 0.35);
+const MEDIUM: Color = Color::rgba(0.8, 0.1, 0.3, 0.5);
+const DEEP: Color = Color::rgba(1.0, 0.9, 0.4, 0.65);
+
+/// Tracers along each side of the grid of the streamlines, one in the middle of each cell
+const TRACERS: usize = 24;
+/// Steps of each streamline, and its length in cells of the grid
+const SEGMENTS: usize = 8;
+const LINE_LENGTH: f32 = 0.8;
+/// Sides of the arrowheads, as a fraction of the streamlines
+const HEAD: f32 = 0.25;
+/// At the head of the streamlines, fading out towards their tracer
+const LINE_COLOR: Color = Color::rgba(0.6, 0.8, 1.0, 0.6);
+/// Vertices of a streamline: two per segment, then the two sides of its arrowhead
+const LINE_VERTICES: usize = 2 * SEGMENTS + 4;
+
+fn spawn_field_plane(
+    mut commands: Commands,
+    resolution: Res<FieldResolution>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let n = resolution.0;
+    let mut mesh = empty_mesh(PrimitiveTopology::TriangleList, n * n);
+    // Two triangles per cell of the grid, the samples being its corners
+    let indices = (0..n as u32 - 1)
+        .flat_map(|row| (0..n as u32 - 1).map(move |column| row * n as u32 + column))
+        .flat_map(|i| {
+            let (right, up) = (i + 1, i + n as u32);
+            vec![i, right, up + 1, i, up + 1, up]
+        })
+        .collect();
+    mesh.set_indices(Some(Indices::U32(indices)));
+    let mesh = meshes.add(mesh);
+    let entity = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+    commands.insert_resource(FieldPlane { mesh, entity });
+}
+
+fn spawn_field_lines(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let mut mesh = empty_mesh(
+        PrimitiveTopology::LineList,
+        TRACERS * TRACERS * LINE_VERTICES,
+    );
+    let fade = |t: f32| {
+        let mut color = LINE_COLOR;
+        color.set_a(LINE_COLOR.a() * t);
+        color.as_rgba_f32()
+    };
+    for line in colors_mut(&mut mesh).chunks_mut(LINE_VERTICES) {
+        for (s, segment) in line[..2 * SEGMENTS].chunks_mut(2).enumerate() {
+            segment[0] = fade(s as f32 / SEGMENTS as f32);
+            segment[1] = fade((s + 1) as f32 / SEGMENTS as f32);
+        }
+        line[2 * SEGMENTS..].fill(fade(1.0));
+    }
+    let mesh = meshes.add(mesh);
+    let entity = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+    commands.insert_resource(FieldLines { mesh, entity });
+}
+
+/// Masses of the massive bodies, and where they are drawn
+fn massive_bodies(bodies: &Query<(&Transform, &Mass)>) -> (Vec<f32>, Vec<Vec3>) {
+    bodies
+        .iter()
+        .filter(|(_, mass)| mass.0 > 0.0)
+        .map(|(transform, mass)| (mass.0, transform.translation))
+        .unzip()
+}
+
+/// Half the side of the square the grids cover, centered on the origin
+fn half_size(pos: &[Vec3]) -> f32 {
+    pos.iter()
+        .map(|pos| pos.x.abs().max(pos.y.abs()))
+        .fold(f32::EPSILON, f32::max)
+        * MARGIN
+}
+
+/// Linear between the three colors, from `t = 0` to `t = 1`
+fn gradient(t: f32) -> [f32; 4] {
+    let mix = |a: Color, b: Color, t: f32| {
+        let (a, b) = (Vec4::from(a.as_rgba_f32()), Vec4::from(b.as_rgba_f32()));
+        (a + (b - a) * t).into()
+    };
+    if t < 0.5 {
+        mix(SHALLOW, MEDIUM, t * 2.0)
+    } else {
+        mix(MEDIUM, DEEP, t * 2.0 - 1.0)
+    }
+}
+
+/// Resample the potential where the bodies are drawn. The colors follow its logarithm,
+/// between the extremes of the grid, so that the wells of the planets show next to that
+/// of the Sun. The plane is hidden without a massive body, having nothing to show.
+#[allow(clippy::too_many_arguments)]
+fn draw_field(
+    plane: Option<Res<FieldPlane>>,
+    resolution: Res<FieldResolution>,
+    g: Res<Gravity>,
+    softening: Res<Softening>,
+    algorithm: Res<Algorithm>,
+    theta: Res<Theta>,
+    pool: Res<ComputeTaskPool>,
+    mut tree: Local<Octree>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Query<(&Transform, &Mass)>,
+    mut visible: Query<&mut Visible>,
+) {
+    let plane = match plane {
+        Some(plane) => plane,
+        None => return,
+    };
+    let mesh = match meshes.get_mut(&plane.mesh) {
+        Some(mesh) => mesh,
+        None => return,
+    };
+
+    let (mass, pos) = massive_bodies(&bodies);
+    if let Ok(mut visible) = visible.get_mut(plane.entity) {
+        visible.is_visible = !mass.is_empty();
+    }
+    if mass.is_empty() {
+        return;
+    }
+
+    let half_size = half_size(&pos);
+    let n = resolution.0;
+    let step = 2.0 * half_size / (n - 1) as f32;
+    let points: Vec<Vec3> = (0..n * n)
+        .map(|i| {
+            let (row, column) = (i / n, i % n);
+            Vec3::new(
+                column as f32 * step - half_size,
+                row as f32 * step - half_size,
+                0.0,
+            )
+        })
+        .collect();
+
+    let forces = Forces {
+        g: g.0,
+        softening: softening.0,
+        algorithm: *algorithm,
+        theta: theta.0,
+        speed_of_light: None,
+        external_field: Vec3::ZERO,
+        drag: Drag::default(),
+        interactions: &Interactions::default(),
+        pool: &pool,
+    };
+    let massive: Vec<usize> = (0..mass.len()).collect();
+    let mut phi = vec![0.0; points.len()];
+    forces.potentials(&mut tree, &mass, &pos, &massive, &points, &mut phi);
+
+    let depth: Vec<f32> = phi
+        .iter()
+        .map(|phi| (-phi).max(f32::MIN_POSITIVE).ln())
+        .collect();
+    let (low, high) = depth
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &d| {
+            (low.min(d), high.max(d))
+        });
+    let range = (high - low).max(f32::EPSILON);
+    for (color, depth) in colors_mut(mesh).iter_mut().zip(depth) {
+        *color = gradient((depth - low) / range);
+    }
+    for (position, point) in positions_mut(mesh).iter_mut().zip(points) {
+        *position = point.into();
+    }
+}
+
+/// Trace the streamlines from the tracers, in midpoint steps of a fixed length along the
+/// field, projected on the plane. The lines are hidden without a massive body.
+#[allow(clippy::too_many_arguments)]
+fn draw_field_lines(
+    lines: Option<Res<FieldLines>>,
+    g: Res<Gravity>,
+    softening: Res<Softening>,
+    algorithm: Res<Algorithm>,
+    theta: Res<Theta>,
+    external: Res<ExternalField>,
+    pool: Res<ComputeTaskPool>,
+    mut tree: Local<Octree>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Query<(&Transform, &Mass)>,
+    mut visible: Query<&mut Visible>,
+) {
+    let lines = match lines {
+        Some(lines) => lines,
+        None => return,
+    };
+    let mesh = match meshes.get_mut(&lines.mesh) {
+        Some(mesh) => mesh,
+        None => return,
+    };
+
+    let (mass, pos) = massive_bodies(&bodies);
+    if let Ok(mut visible) = visible.get_mut(lines.entity) {
+        visible.is_visible = !mass.is_empty();
+    }
+    if mass.is_empty() {
+        return;
+    }
+
+    let half_size = half_size(&pos);
+    let cell = 2.0 * half_size / TRACERS as f32;
+    let mut points: Vec<Vec3> = (0..TRACERS * TRACERS)
+        .map(|i| {
+            let (row, column) = (i / TRACERS, i % TRACERS);
+            Vec3::new(
+                (column as f32 + 0.5) * cell - half_size,
+                (row as f32 + 0.5) * cell - half_size,
+                0.0,
+            )
+        })
+        .collect();
+
+    let forces = Forces {
+        g: g.0,
+        softening: softening.0,
+        algorithm: *algorithm,
+        theta: theta.0,
+        speed_of_light: None,
+        external_field: external.0,
+        drag: Drag::default(),
+        interactions: &Interactions::default(),
+        pool: &pool,
+    };
+    let massive: Vec<usize> = (0..mass.len()).collect();
+    let direction = |a: Vec3| Vec3::new(a.x, a.y, 0.0).normalize_or_zero();
+    let step = LINE_LENGTH * cell / SEGMENTS as f32;
+    let (mut acc, mut middle) = (vec![Vec3::ZERO; points.len()], points.clone());
+    let mut path = vec![points.clone()];
+    for _ in 0..SEGMENTS {
+        forces.field(&mut tree, &mass, &pos, &massive, &points, &mut acc);
+        for ((middle, point), a) in middle.iter_mut().zip(&points).zip(&acc) {
+            *middle = *point + direction(*a) * (step / 2.0);
+        }
+        forces.field(&mut tree, &mass, &pos, &massive, &middle, &mut acc);
+        for (point, a) in points.iter_mut().zip(&acc) {
+            *point += direction(*a) * step;
+        }
+        path.push(points.clone());
+    }
+
+    for (line, vertices) in positions_mut(mesh).chunks_mut(LINE_VERTICES).enumerate() {
+        for (s, segment) in vertices[..2 * SEGMENTS].chunks_mut(2).enumerate() {
+            segment[0] = path[s][line].into();
+            segment[1] = path[s + 1][line].into();
+        }
+        let tip = path[SEGMENTS][line];
+        let back = (path[SEGMENTS - 1][line] - tip).normalize_or_zero() * HEAD * LINE_LENGTH * cell;
+        let side = Vec3::Z.cross(back) * 0.5;
+        vertices[2 * SEGMENTS..].copy_from_slice(&[
+            tip.into(),
+            (tip + back + side).into(),
+            tip.into(),
+            (tip + back - side).into(),
+        ]);
+    }
+}
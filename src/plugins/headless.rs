@@ -0,0 +1,66 @@
+use bevy::{app::AppExit, asset::AssetPlugin, input::InputPlugin, prelude::*};
+use copernicus::{forces::Algorithm, integrator::Integrator};
+use std::time::Instant;
+
+use super::nbody::{Mass, PhysicsSystem, SimState};
+use super::random::Seed;
+
+/// Runs the simulation without a window until the [`StopPlugin`] exits, then prints how long
+/// the steps took. To be added with `MinimalPlugins` instead of `DefaultPlugins` and the
+/// `NBody` plugin paced with `Pacing::EveryFrame`.
+///
+/// Meshes and materials are still registered as assets, so that the startup systems can
+/// spawn the bodies as usual, but nothing is ever rendered.
+///
+/// [`StopPlugin`]: super::stop::StopPlugin
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(AssetPlugin)
+            .add_plugin(InputPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .init_resource::<Benchmark>()
+            .add_system(start_benchmark.system().before(PhysicsSystem::Integrate))
+            // After the exit is sent during the update
+            .add_system_to_stage(CoreStage::PostUpdate, benchmark.system());
+    }
+}
+
+#[derive(Default)]
+struct Benchmark {
+    /// Time of the first step
+    start: Option<Instant>,
+}
+
+fn start_benchmark(mut bench: ResMut<Benchmark>) {
+    bench.start.get_or_insert_with(Instant::now);
+}
+
+fn benchmark(
+    bench: Res<Benchmark>,
+    state: Res<SimState>,
+    integrator: Res<Integrator>,
+    algorithm: Res<Algorithm>,
+    seed: Res<Seed>,
+    bodies: Query<(), With<Mass>>,
+    mut exit: EventReader<AppExit>,
+) {
+    let start = match bench.start {
+        Some(start) if exit.iter().next().is_some() => start,
+        _ => return,
+    };
+
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{} steps of {} bodies ({:?}, {:?}, seed {}) in {:.3}s: {:.1} steps/s",
+        state.steps,
+        bodies.iter().count(),
+        *integrator,
+        *algorithm,
+        seed.0,
+        elapsed,
+        state.steps as f64 / elapsed
+    );
+}
@@ -0,0 +1,205 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use std::f64::consts::PI;
+
+use super::approach::BodyPair;
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut, to_local};
+use super::nbody::{Gravity, Mass, PhysicsSystem, Position, SimState, Velocity};
+use crate::kepler::{orbital_elements, OrbitalElements};
+
+/// Plans a Hohmann transfer between the orbits of two bodies around the heaviest one, e.g.
+/// from Earth to Mars around the Sun, and draws it with a marker travelling along it.
+/// Both orbits are taken as circular, at the current distances of the bodies, and the
+/// transfer as a two-body orbit, unaffected by the other bodies.
+pub struct HohmannPlugin {
+    /// Names of the departure and arrival bodies
+    pub bodies: BodyPair,
+}
+
+impl Plugin for HohmannPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(TransferBodies(self.bodies.clone()))
+            .add_startup_system_to_stage(StartupStage::PostStartup, plan_transfer.system())
+            .add_system(draw_transfer.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+struct TransferBodies(BodyPair);
+
+/// Planned transfer, for the control panel
+pub struct Transfer {
+    pub from: String,
+    pub to: String,
+    /// Speed changes of the departure and arrival burns, prograde when positive
+    pub departure_burn: f32,
+    pub arrival_burn: f32,
+    /// Half the period of the transfer orbit
+    pub duration: f64,
+    /// Angle by which the arrival body must lead the departure body at departure, for it to
+    /// be there at arrival, and by which it currently leads, in degrees
+    pub phase: f64,
+    pub current_phase: f64,
+    /// Simulated time of the departure
+    pub start: f64,
+    elements: OrbitalElements,
+    /// Body whose orbits these are
+    center: Entity,
+    mesh: Handle<Mesh>,
+    marker: Entity,
+}
+
+/// Points sampled along the transfer
+const SAMPLES: usize = 64;
+const MARKER_RADIUS: f32 = 0.15;
+const COLOR: [f32; 4] = [0.3, 1.0, 1.0, 0.8];
+
+fn name(entity: Entity, name: Option<&Name>) -> String {
+    name.map_or_else(|| format!("body {}", entity.id()), |n| n.to_string())
+}
+
+/// The transfer leaves from the current position of the departure body, tangentially, with
+/// the speed of an ellipse whose apses are the radii of the two orbits
+#[allow(clippy::type_complexity)]
+fn plan_transfer(
+    mut commands: Commands,
+    bodies_names: Res<TransferBodies>,
+    g: Res<Gravity>,
+    state: Res<SimState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(Entity, &Mass, &Position, &Velocity, Option<&Name>)>,
+) {
+    let names = &bodies_names.0;
+    let find = |wanted: &str| {
+        let body = bodies
+            .iter()
+            .find(|(entity, _, _, _, n)| name(*entity, *n) == wanted);
+        if body.is_none() {
+            warn!("no body named `{}` for the Hohmann transfer", wanted);
+        }
+        body
+    };
+    let center = bodies
+        .iter()
+        .max_by(|(_, a, ..), (_, b, ..)| a.0.partial_cmp(&b.0).unwrap());
+    let (from, to, center) = match (find(&names.0), find(&names.1), center) {
+        (Some(from), Some(to), Some(center)) => (from, to, center),
+        _ => return,
+    };
+    let (center_entity, center_mass, center_pos, center_vel, _) = center;
+    if from.0 == center_entity || to.0 == center_entity {
+        warn!("the Hohmann transfer is around the heaviest body, not from or to it");
+        return;
+    }
+
+    let mu = g.0 * center_mass.0;
+    let r = from.2 .0 - center_pos.0;
+    let (r1, r2) = (r.length(), (to.2 .0 - center_pos.0).length());
+    // Departure along the current motion of the body, in its orbital plane
+    let rel_vel = from.3 .0 - center_vel.0;
+    let tangent = (rel_vel - r * r.dot(rel_vel) / r.length_squared()).normalize_or_zero();
+    let (v1, v2) = ((mu / r1).sqrt(), (mu / r2).sqrt());
+    let a = (r1 + r2) / 2.0;
+    // Vis-viva, at both ends of the transfer ellipse
+    let departure_speed = (mu * (2.0 / r1 - 1.0 / a)).sqrt();
+    let arrival_speed = (mu * (2.0 / r2 - 1.0 / a)).sqrt();
+    let elements = orbital_elements(r, tangent * departure_speed, mu);
+    let duration = PI * (a as f64).powi(3).sqrt() / (mu as f64).sqrt();
+
+    // The arrival body covers `duration * v2 / r2` radians during the transfer, and must be
+    // where the transfer ends, half a turn away from the departure
+    let phase = PI - duration * (v2 / r2) as f64;
+    let normal = r.cross(rel_vel).normalize_or_zero();
+    let to_r = to.2 .0 - center_pos.0;
+    let current_phase = (r.cross(to_r).dot(normal) as f64).atan2(r.dot(to_r) as f64);
+
+    let mesh = meshes.add(empty_mesh(PrimitiveTopology::LineStrip, SAMPLES + 1));
+    let line = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+    let marker = commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: MARKER_RADIUS,
+                subdivisions: 2,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(COLOR[0], COLOR[1], COLOR[2], COLOR[3]),
+                emissive: Color::rgb(COLOR[0], COLOR[1], COLOR[2]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .id();
+    commands
+        .entity(center_entity)
+        .push_children(&[line, marker]);
+
+    let transfer = Transfer {
+        from: name(from.0, from.4),
+        to: name(to.0, to.4),
+        departure_burn: departure_speed - v1,
+        arrival_burn: v2 - arrival_speed,
+        duration,
+        phase: phase.to_degrees(),
+        current_phase: current_phase.to_degrees(),
+        start: state.time,
+        elements,
+        center: center_entity,
+        mesh,
+        marker,
+    };
+    info!(
+        "Hohmann transfer from {} to {}: burns of {:.5} and {:.5}, {:.2} time units, \
+         with {} leading by {:.1}° at departure ({:.1}° now)",
+        transfer.from,
+        transfer.to,
+        transfer.departure_burn,
+        transfer.arrival_burn,
+        transfer.duration,
+        transfer.to,
+        transfer.phase,
+        transfer.current_phase,
+    );
+    commands.insert_resource(transfer);
+}
+
+/// Sample half of the ellipse, from the departure, in the local space of the central body,
+/// and move the marker along it with the simulated time since the departure, stopping at
+/// the arrival
+fn draw_transfer(
+    transfer: Option<Res<Transfer>>,
+    state: Res<SimState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let transfer = match transfer {
+        Some(transfer) => transfer,
+        None => return,
+    };
+    let center = match transforms.get_mut(transfer.center) {
+        Ok(center) => *center,
+        // Gone, e.g. merged into another body
+        Err(_) => return,
+    };
+    let elements = &transfer.elements;
+    let start = elements.true_anomaly;
+
+    if let Some(mesh) = meshes.get_mut(&transfer.mesh) {
+        for (i, vertex) in positions_mut(mesh).iter_mut().enumerate() {
+            let nu = start + PI * i as f64 / SAMPLES as f64;
+            *vertex = to_local(&center, elements.position_at(nu)).into();
+        }
+        for color in colors_mut(mesh).iter_mut() {
+            *color = COLOR;
+        }
+    }
+
+    let elapsed = (state.time - transfer.start).clamp(0.0, transfer.duration);
+    let mean = elements.mean_anomaly(start) + PI * elapsed / transfer.duration;
+    if let Ok(mut marker) = transforms.get_mut(transfer.marker) {
+        marker.translation = to_local(
+            &center,
+            elements.position_at(elements.true_anomaly_at(mean)),
+        );
+        // Keep its size whatever the scale of the central body
+        marker.scale = Vec3::ONE / center.scale;
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use super::clock::Epoch;
+use super::nbody::{Mass, SimState, TestParticle};
+use super::supernova::Supernova;
+use super::ui::UiFont;
+
+/// Shows the frame rate, the number of bodies, the simulated time and the speed factor in
+/// the bottom-left corner, toggled with F3, and after a supernova of the
+/// [`SupernovaPlugin`](super::supernova::SupernovaPlugin) the fractions of the other bodies
+/// bound to the star before it and now. The frame rate is the one measured by the
+/// [`FrameTimeDiagnosticsPlugin`], and the time unit the one of the
+/// [`ClockPlugin`](super::clock::ClockPlugin), which must both be added too.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(HudTimer(Timer::from_seconds(REFRESH, true)))
+            .add_startup_system(spawn_hud.system())
+            .add_system(toggle_hud.system())
+            .add_system(update_hud.system());
+    }
+}
+
+/// Seconds between two updates of the text
+const REFRESH: f32 = 0.25;
+
+struct HudTimer(Timer);
+
+struct HudText;
+
+fn spawn_hud(mut commands: Commands, font: Res<UiFont>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 14.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(HudText);
+}
+
+fn toggle_hud(
+    keys: Res<Input<KeyCode>>,
+    mut timer: ResMut<HudTimer>,
+    mut query: Query<&mut Visible, With<HudText>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut visible in query.iter_mut() {
+        visible.is_visible = !visible.is_visible;
+    }
+    // Filled in right away rather than at the next refresh
+    let duration = timer.0.duration();
+    timer.0.set_elapsed(duration);
+}
+
+/// Bodies are counted when the text is refreshed, so that merges and culled bodies show up
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_hud(
+    time: Res<Time>,
+    diagnostics: Res<Diagnostics>,
+    state: Res<SimState>,
+    epoch: Res<Epoch>,
+    supernova: Option<Res<Supernova>>,
+    mut timer: ResMut<HudTimer>,
+    bodies: Query<Option<&TestParticle>, With<Mass>>,
+    mut texts: Query<(&mut Text, &Visible), With<HudText>>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.average());
+    let particles = bodies.iter().filter(Option::is_some).count();
+    let massive = bodies.iter().count() - particles;
+    let mut value = match fps {
+        Some(fps) => format!("{:.0} FPS", fps),
+        None => "- FPS".to_string(),
+    };
+    value += &format!("\n{} bodies", massive);
+    if particles > 0 {
+        value += &format!(" + {} test particles", particles);
+    }
+    // Days for the scenes whose time unit is the day, as on the clock
+    value += &match epoch.0 {
+        Some(_) => format!("\n{:.1} days simulated", state.time),
+        None => format!("\nt = {:.2}", state.time),
+    };
+    value += &format!("\nSpeed: {:.2}x", state.speed_factor);
+    if let Some(Supernova {
+        star: Some(_),
+        before: Some(before),
+        now: Some(now),
+    }) = supernova.as_deref()
+    {
+        value += &format!(
+            "\nBound to the star: {:.0}% before the supernova, {:.0}% now",
+            100.0 * before,
+            100.0 * now
+        );
+    }
+    for (mut text, visible) in texts.iter_mut() {
+        if visible.is_visible {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use std::f64::consts::TAU;
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut, to_local};
+use super::nbody::{FrameRotation, Gravity, Mass, PhysicsSystem, Position, TestParticle, Velocity};
+use crate::kepler::{dominant_attractor, orbital_elements, OrbitalElements};
+
+/// Draws the plane of the osculating orbit of every body around its attractor as a
+/// translucent disk, next to a ring of the same size in the reference plane, the ecliptic
+/// at `z = 0`: they cross along the line of nodes, drawn from the attractor, brighter
+/// towards the ascending node. The attractor is the one of the
+/// [`OrbitsPlugin`](super::orbits::OrbitsPlugin), and the disks are as large as the orbits,
+/// or reach the body on unbound ones.
+pub struct InclinationsPlugin {
+    /// Initial value of [`ShowInclinations`]
+    pub enabled: bool,
+}
+
+impl Plugin for InclinationsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ShowInclinations(self.enabled))
+            .add_system(add_planes.system())
+            .add_system(remove_planes.system())
+            .add_system(update_planes.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+/// Orbital planes are drawn only while this is set
+pub struct ShowInclinations(pub bool);
+
+/// Sides of the disks and rings
+const SEGMENTS: usize = 64;
+const DISK_ALPHA: f32 = 0.12;
+const ECLIPTIC_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.25);
+const ASCENDING_ALPHA: f32 = 0.8;
+const DESCENDING_ALPHA: f32 = 0.3;
+
+/// Triangles of the disk and lines of the ring and of the nodes of a body, as child entities
+struct OrbitalPlane {
+    disk: Handle<Mesh>,
+    lines: Handle<Mesh>,
+    entities: [Entity; 2],
+    color: Color,
+}
+
+/// Test particles are left out, there can be too many of them
+#[allow(clippy::type_complexity)]
+fn add_planes(
+    mut commands: Commands,
+    show: Res<ShowInclinations>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    query: Query<
+        (Entity, Option<&Handle<StandardMaterial>>),
+        (With<Mass>, Without<OrbitalPlane>, Without<TestParticle>),
+    >,
+) {
+    if !show.0 {
+        return;
+    }
+    for (entity, material) in query.iter() {
+        let color = material
+            .and_then(|material| materials.get(material))
+            .map_or(Color::WHITE, |material| material.base_color);
+        let disk = meshes.add(empty_mesh(PrimitiveTopology::TriangleList, 3 * SEGMENTS));
+        // The ring, then the line of nodes on either side of the attractor
+        let lines = meshes.add(empty_mesh(PrimitiveTopology::LineList, 2 * SEGMENTS + 4));
+        let entities = [
+            commands.spawn_bundle(line_bundle(disk.clone())).id(),
+            commands.spawn_bundle(line_bundle(lines.clone())).id(),
+        ];
+        commands
+            .entity(entity)
+            .push_children(&entities)
+            .insert(OrbitalPlane {
+                disk,
+                lines,
+                entities,
+                color,
+            });
+    }
+}
+
+/// Drop the planes once they are hidden
+fn remove_planes(
+    mut commands: Commands,
+    show: Res<ShowInclinations>,
+    query: Query<(Entity, &OrbitalPlane)>,
+) {
+    if show.0 {
+        return;
+    }
+    for (entity, plane) in query.iter() {
+        for &child in plane.entities.iter() {
+            commands.entity(child).despawn_recursive();
+        }
+        commands.entity(entity).remove::<OrbitalPlane>();
+    }
+}
+
+/// Recompute the elements of every orbit from the current state, as for the orbits, and
+/// draw its plane around the attractor, in the local space of the body. The disk is the
+/// circular orbit of the same plane, sampled from the periapsis.
+#[allow(clippy::type_complexity)]
+fn update_planes(
+    gravity: Res<Gravity>,
+    rotation: Res<FrameRotation>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    planes: Query<(&Mass, &Position, &Velocity, &Transform, &OrbitalPlane)>,
+    attractors: Query<(Entity, &Mass, &Position, &Velocity, &Transform), Without<TestParticle>>,
+) {
+    for (mass, pos, vel, transform, plane) in planes.iter() {
+        let attractor = dominant_attractor(
+            mass.0,
+            pos.0,
+            attractors.iter().map(|body| (body.1 .0, body.2 .0, body)),
+        );
+        let [r, g, b, _] = plane.color.as_rgba_f32();
+        let (_, center_mass, center_pos, center_vel, center_transform) = match attractor {
+            Some(attractor) => attractor,
+            None => {
+                for mesh in [&plane.disk, &plane.lines] {
+                    if let Some(mesh) = meshes.get_mut(mesh) {
+                        colors_mut(mesh).iter_mut().for_each(|c| c[3] = 0.0);
+                    }
+                }
+                continue;
+            }
+        };
+
+        let rel = pos.0 - center_pos.0;
+        let mu = gravity.0 * (mass.0 + center_mass.0);
+        let elements = orbital_elements(rel, vel.0 - center_vel.0, mu);
+        let radius = if elements.is_bound() {
+            elements.semi_major_axis
+        } else {
+            rel.length() as f64
+        };
+        let circle = OrbitalElements {
+            semi_major_axis: radius,
+            eccentricity: 0.0,
+            ..elements
+        };
+        let origin = center_transform.translation - transform.translation;
+        let local = |offset: Vec3| -> [f32; 3] { to_local(transform, origin + offset).into() };
+        let angle = |i: usize| TAU * i as f64 / SEGMENTS as f64;
+        let radius = radius as f32;
+
+        if let Some(mesh) = meshes.get_mut(&plane.disk) {
+            let vertices = (0..SEGMENTS).flat_map(|i| {
+                [
+                    Vec3::ZERO,
+                    rotation.0 * circle.position_at(angle(i)),
+                    rotation.0 * circle.position_at(angle(i + 1)),
+                ]
+            });
+            for (vertex, offset) in positions_mut(mesh).iter_mut().zip(vertices) {
+                *vertex = local(offset);
+            }
+            for color in colors_mut(mesh).iter_mut() {
+                *color = [r, g, b, DISK_ALPHA];
+            }
+        }
+
+        if let Some(mesh) = meshes.get_mut(&plane.lines) {
+            let ecliptic = |i: usize| {
+                let angle = angle(i) as f32;
+                rotation.0 * Vec3::new(angle.cos(), angle.sin(), 0.0) * radius
+            };
+            let node = elements.longitude_of_ascending_node as f32;
+            let ascending = rotation.0 * Vec3::new(node.cos(), node.sin(), 0.0) * radius;
+            let vertices = (0..SEGMENTS)
+                .flat_map(|i| [ecliptic(i), ecliptic(i + 1)])
+                .chain([Vec3::ZERO, ascending, Vec3::ZERO, -ascending]);
+            for (vertex, offset) in positions_mut(mesh).iter_mut().zip(vertices) {
+                *vertex = local(offset);
+            }
+            let colors = colors_mut(mesh);
+            let (ring, nodes) = colors.split_at_mut(2 * SEGMENTS);
+            for color in ring.iter_mut() {
+                *color = ECLIPTIC_COLOR.as_rgba_f32();
+            }
+            nodes[0] = [r, g, b, ASCENDING_ALPHA];
+            nodes[1] = [r, g, b, ASCENDING_ALPHA];
+            nodes[2] = [r, g, b, DESCENDING_ALPHA];
+            nodes[3] = [r, g, b, DESCENDING_ALPHA];
+        }
+    }
+}
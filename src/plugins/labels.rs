@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+
+use super::clock::{format_duration, Epoch};
+use super::escapes::Unbound;
+use super::nbody::{Gravity, Mass, Position, Radius, TestParticle, Velocity};
+use super::pan_orbit_camera::{CameraSystem, PanOrbitCamera, Projection};
+use super::ui::UiFont;
+use crate::kepler::{dominant_attractor, orbital_elements};
+
+/// Draws the name of every named body next to it, on top of the scene, and optionally the
+/// period of its orbit
+pub struct LabelsPlugin {
+    /// Initial value of [`ShowLabels`]
+    pub enabled: bool,
+    /// See [`LabelPeriods`]
+    pub periods: bool,
+}
+
+impl Plugin for LabelsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ShowLabels(self.enabled))
+            .insert_resource(LabelPeriods(self.periods))
+            .add_system(add_labels.system())
+            .add_system(label_periods.system())
+            .add_system(update_labels.system().after(CameraSystem::Follow))
+            .add_system(remove_labels.system());
+    }
+}
+
+/// Font size of the labels of the bodies at the distance of the focus from the camera,
+/// larger nearer and smaller further, within the readable range of the two bounds
+const FONT_SIZE: f32 = 14.0;
+const MIN_FONT_SIZE: f32 = 10.0;
+const MAX_FONT_SIZE: f32 = 22.0;
+/// Labels start fading out at this distance from the camera, in multiples of its radius
+const FADE_START: f32 = 4.0;
+/// Labels are fully transparent past this distance from the camera, in multiples of its radius
+const FADE_END: f32 = 8.0;
+/// Offset of the label from the center of its body on screen, in pixels
+const OFFSET: f32 = 6.0;
+
+/// Labels are drawn only while this is set
+pub struct ShowLabels(pub bool);
+
+/// Whether the labels show the period of the osculating orbit of their body under its
+/// name, or that it is unbound
+pub struct LabelPeriods(pub bool);
+
+/// Text entity showing the name of a body
+pub struct BodyLabel(pub Entity);
+
+/// Body whose name a text entity shows
+struct LabelOf(Entity);
+
+#[allow(clippy::type_complexity)]
+fn add_labels(
+    mut commands: Commands,
+    show: Res<ShowLabels>,
+    font: Res<UiFont>,
+    query: Query<(Entity, &Name), (With<Mass>, Without<BodyLabel>)>,
+) {
+    if !show.0 {
+        return;
+    }
+    for (entity, name) in query.iter() {
+        let label = commands
+            .spawn_bundle(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    name.as_str(),
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            })
+            .insert(LabelOf(entity))
+            .id();
+        commands.entity(entity).insert(BodyLabel(label));
+    }
+}
+
+/// Move the labels to the screen position of their body, where they always face the
+/// camera, scaled with its distance. A label is hidden when its body is off-screen or
+/// behind a star, and fades out when the body is far from the camera.
+#[allow(clippy::type_complexity)]
+fn update_labels(
+    windows: Res<Windows>,
+    projection: Res<Projection>,
+    cameras: Query<(&Camera, &Transform, &PanOrbitCamera)>,
+    bodies: Query<(Entity, &Transform, &BodyLabel, Option<&Unbound>)>,
+    stars: Query<(Entity, &Transform, &Radius), With<Light>>,
+    mut labels: Query<(&mut Style, &mut Text, &mut Visible), With<LabelOf>>,
+) {
+    let (camera, camera_transform, pan_orbit) = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    // The global transform of the camera is only computed at the end of the frame
+    let camera_global = GlobalTransform::from(*camera_transform);
+    let eye = camera_transform.translation;
+    // Where the line of sight to a body starts, parallel to the view when orthographic
+    let forward = camera_transform.rotation * -Vec3::Z;
+    let sight = |pos: Vec3| match *projection {
+        Projection::Perspective => eye,
+        Projection::Orthographic => pos - forward * (pos - eye).dot(forward),
+    };
+
+    for (entity, transform, label, unbound) in bodies.iter() {
+        let (mut style, mut text, mut visible) = match labels.get_mut(label.0) {
+            Ok(label) => label,
+            Err(_) => continue,
+        };
+        let pos = transform.translation;
+        let screen = camera
+            .world_to_screen(&windows, &camera_global, pos)
+            .filter(|_| !occluded(sight(pos), pos, entity, &stars));
+        let screen = match screen {
+            Some(screen) => screen,
+            None => {
+                visible.is_visible = false;
+                continue;
+            }
+        };
+
+        let distance = (pos - eye).length() / pan_orbit.radius;
+        let alpha = ((FADE_END - distance) / (FADE_END - FADE_START)).clamp(0.0, 1.0);
+        visible.is_visible = alpha > 0.0;
+        // Escaping bodies stand out in red
+        let mut color = if unbound.is_some() {
+            Color::RED
+        } else {
+            Color::WHITE
+        };
+        color.set_a(alpha);
+        text.sections[0].style.color = color;
+        // Whole sizes only, each size of a font taking atlases of its own
+        text.sections[0].style.font_size = (FONT_SIZE / distance.max(f32::EPSILON))
+            .clamp(MIN_FONT_SIZE, MAX_FONT_SIZE)
+            .round();
+        style.position.left = Val::Px(screen.x + OFFSET);
+        style.position.bottom = Val::Px(screen.y + OFFSET);
+    }
+}
+
+/// Put the period of the orbit of each body under its name, updated every frame for the
+/// perturbations to show. The attractor is the heavier body pulling the hardest, as for the
+/// orbits, so that moons show their period around their planet.
+#[allow(clippy::type_complexity)]
+fn label_periods(
+    periods: Res<LabelPeriods>,
+    g: Res<Gravity>,
+    epoch: Option<Res<Epoch>>,
+    bodies: Query<(&Name, &Mass, &Position, &Velocity, &BodyLabel)>,
+    attractors: Query<(Entity, &Mass, &Position, &Velocity), Without<TestParticle>>,
+    mut labels: Query<&mut Text, With<LabelOf>>,
+) {
+    if !periods.0 {
+        return;
+    }
+    let epoch = epoch.map_or(Epoch(None), |epoch| Epoch(epoch.0));
+    for (name, mass, pos, vel, label) in bodies.iter() {
+        let attractor = dominant_attractor(
+            mass.0,
+            pos.0,
+            attractors.iter().map(|body| (body.1 .0, body.2 .0, body)),
+        );
+        let value = match attractor {
+            None => name.to_string(),
+            Some((_, center_mass, center_pos, center_vel)) => {
+                let mu = g.0 * (mass.0 + center_mass.0);
+                let elements = orbital_elements(pos.0 - center_pos.0, vel.0 - center_vel.0, mu);
+                match elements.period(mu as f64) {
+                    Some(period) => {
+                        format!("{}\n{}", name.as_str(), format_duration(period, &epoch))
+                    }
+                    None => format!("{}\nunbound", name.as_str()),
+                }
+            }
+        };
+        if let Ok(mut text) = labels.get_mut(label.0) {
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+        }
+    }
+}
+
+/// Whether a star other than `body` lies between the eye and `pos`
+fn occluded(
+    eye: Vec3,
+    pos: Vec3,
+    body: Entity,
+    stars: &Query<(Entity, &Transform, &Radius), With<Light>>,
+) -> bool {
+    let diff = pos - eye;
+    let distance = diff.length();
+    let dir = diff / distance;
+    stars.iter().any(|(star, transform, radius)| {
+        let to_star = transform.translation - eye;
+        let along = to_star.dot(dir);
+        star != body
+            && along > 0.0
+            && along < distance
+            && (to_star - dir * along).length_squared() < radius.0 * radius.0
+    })
+}
+
+/// Despawn the labels of the bodies that no longer exist, e.g. after a merge,
+/// or all of them once hidden
+fn remove_labels(
+    mut commands: Commands,
+    show: Res<ShowLabels>,
+    labels: Query<(Entity, &LabelOf)>,
+    bodies: Query<(), With<Mass>>,
+) {
+    for (entity, of) in labels.iter() {
+        let exists = bodies.get(of.0).is_ok();
+        if !exists || !show.0 {
+            commands.entity(entity).despawn();
+        }
+        if exists && !show.0 {
+            commands.entity(of.0).remove::<BodyLabel>();
+        }
+    }
+}
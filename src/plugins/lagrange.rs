@@ -0,0 +1,89 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+
+use super::approach::BodyPair;
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut};
+use super::nbody::{FrameRotation, Mass, PhysicsSystem, Velocity};
+use crate::lagrange::lagrange_points;
+
+/// Marks the five Lagrange points of a pair of named bodies with small crosses, from where
+/// the bodies are drawn, in any reference frame. They are exact for a circular orbit of the
+/// pair, and follow it when the orbit is not.
+pub struct LagrangePlugin {
+    /// Primary and secondary, e.g. `Sun,Jupiter`
+    pub bodies: BodyPair,
+}
+
+impl Plugin for LagrangePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(self.bodies.clone())
+            .add_startup_system(spawn_markers.system())
+            .add_system(draw_markers.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+/// Line list of the five crosses, in world space
+struct Markers {
+    mesh: Handle<Mesh>,
+    entity: Entity,
+}
+
+const COLOR: Color = Color::rgba(0.6, 0.8, 1.0, 0.8);
+/// Size of the crosses, as a fraction of the distance between the bodies
+const SIZE: f32 = 0.02;
+/// Vertices of a cross, a segment along each axis
+const CROSS_VERTICES: usize = 6;
+
+fn spawn_markers(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let mut mesh = empty_mesh(PrimitiveTopology::LineList, 5 * CROSS_VERTICES);
+    for color in colors_mut(&mut mesh).iter_mut() {
+        *color = COLOR.as_rgba_f32();
+    }
+    let mesh = meshes.add(mesh);
+    let entity = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+    commands.insert_resource(Markers { mesh, entity });
+}
+
+/// The markers are hidden while one of the bodies is missing, e.g. merged into another one
+fn draw_markers(
+    pair: Res<BodyPair>,
+    markers: Option<Res<Markers>>,
+    rotation: Res<FrameRotation>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Query<(&Name, &Mass, &Velocity, &Transform)>,
+    mut visible: Query<&mut Visible>,
+) {
+    let markers = match markers {
+        Some(markers) => markers,
+        None => return,
+    };
+    let find = |name: &str| bodies.iter().find(|(body, ..)| body.as_str() == name);
+    let found = find(&pair.0).zip(find(&pair.1));
+    if let Ok(mut visible) = visible.get_mut(markers.entity) {
+        visible.is_visible = found.is_some();
+    }
+    let ((_, primary_mass, primary_vel, primary), (_, secondary_mass, secondary_vel, secondary)) =
+        match found {
+            Some(pair) => pair,
+            None => return,
+        };
+    let mesh = match meshes.get_mut(&markers.mesh) {
+        Some(mesh) => mesh,
+        None => return,
+    };
+
+    let (primary, secondary) = (primary.translation, secondary.translation);
+    let separation = secondary - primary;
+    // The pair turns around its angular momentum, in the rendered frame
+    let normal = separation.cross(rotation.0 * (secondary_vel.0 - primary_vel.0));
+    let points = lagrange_points(primary_mass.0, primary, secondary_mass.0, secondary, normal);
+    let size = SIZE * separation.length();
+    let vertices = points.iter().flat_map(|&point| {
+        [Vec3::X, Vec3::Y, Vec3::Z]
+            .iter()
+            .flat_map(move |&axis| [point - axis * size, point + axis * size])
+            .collect::<Vec<_>>()
+    });
+    for (vertex, pos) in positions_mut(mesh).iter_mut().zip(vertices) {
+        *vertex = pos.into();
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::VertexAttributeValues,
+        pipeline::{CullMode, PipelineDescriptor, PrimitiveTopology, RenderPipeline},
+        shader::{ShaderStage, ShaderStages},
+    },
+};
+
+/// Offset from a body, in world space, in the local space of its children: the overlays
+/// keep their size when the body is rotated or scaled
+pub fn to_local(transform: &Transform, offset: Vec3) -> Vec3 {
+    transform.rotation.inverse() * offset / transform.scale
+}
+
+/// Unlit pipeline drawing meshes with per-vertex RGBA colors, used for all the overlays
+/// (trails, vectors, orbits...). The topology comes from the mesh, so the same pipeline
+/// draws line strips, line lists and triangles.
+pub const LINES_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x5f4e_3d2c_1b0a_9f8e);
+
+#[cfg(not(target_arch = "wasm32"))]
+const VERTEX_SHADER: &str = r#"
+#version 450
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec4 Vertex_Color;
+layout(location = 0) out vec4 v_color;
+
+layout(set = 0, binding = 0) uniform CameraViewProj {
+    mat4 ViewProj;
+};
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+void main() {
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.0);
+    v_color = Vertex_Color;
+}
+"#;
+
+#[cfg(not(target_arch = "wasm32"))]
+const FRAGMENT_SHADER: &str = r#"
+#version 450
+layout(location = 0) in vec4 v_color;
+layout(location = 0) out vec4 o_Target;
+
+void main() {
+    o_Target = v_color;
+}
+"#;
+
+/// Same shaders in GLSL ES for WebGL2, whose uniform blocks are bound by name
+#[cfg(target_arch = "wasm32")]
+const VERTEX_SHADER: &str = r#"
+#version 300 es
+precision highp float;
+in vec3 Vertex_Position;
+in vec4 Vertex_Color;
+out vec4 v_color;
+
+layout(std140) uniform CameraViewProj {
+    mat4 ViewProj;
+};
+layout(std140) uniform Transform {
+    mat4 Model;
+};
+void main() {
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.0);
+    v_color = Vertex_Color;
+}
+"#;
+
+/// The canvas is not sRGB, the colors are encoded in the shader
+#[cfg(target_arch = "wasm32")]
+const FRAGMENT_SHADER: &str = r#"
+#version 300 es
+precision highp float;
+in vec4 v_color;
+out vec4 o_Target;
+
+void main() {
+    vec3 linear = v_color.rgb;
+    vec3 srgb = mix(12.92 * linear, 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055,
+                    step(vec3(0.0031308), linear));
+    o_Target = vec4(srgb, v_color.a);
+}
+"#;
+
+pub struct LinesPlugin;
+
+impl Plugin for LinesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let world = app.world_mut().cell();
+        let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+        let mut pipelines = world
+            .get_resource_mut::<Assets<PipelineDescriptor>>()
+            .unwrap();
+
+        let mut descriptor = PipelineDescriptor::default_config(ShaderStages {
+            vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+            fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER))),
+        });
+        // Overlays are see-through: they should not hide what is drawn after them
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.depth_write_enabled = false;
+        }
+        descriptor.primitive.cull_mode = CullMode::None;
+        pipelines.set_untracked(LINES_PIPELINE_HANDLE, descriptor);
+    }
+}
+
+/// Mesh with `n` vertices, all at the origin and transparent, to be filled in later
+pub fn empty_mesh(topology: PrimitiveTopology, n: usize) -> Mesh {
+    let mut mesh = Mesh::new(topology);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; n]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.0f32; 4]; n]);
+    mesh
+}
+
+/// Positions of a mesh created with [`empty_mesh`], to update them in place
+pub fn positions_mut(mesh: &mut Mesh) -> &mut Vec<[f32; 3]> {
+    match mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float3(positions)) => positions,
+        _ => panic!("line meshes have Float3 positions"),
+    }
+}
+
+/// Colors of a mesh created with [`empty_mesh`], to update them in place
+pub fn colors_mut(mesh: &mut Mesh) -> &mut Vec<[f32; 4]> {
+    match mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) {
+        Some(VertexAttributeValues::Float4(colors)) => colors,
+        _ => panic!("line meshes have Float4 colors"),
+    }
+}
+
+/// Bundle drawing `mesh` with the line pipeline
+pub fn line_bundle(mesh: Handle<Mesh>) -> MeshBundle {
+    MeshBundle {
+        mesh,
+        render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
+            LINES_PIPELINE_HANDLE.typed(),
+        )]),
+        visible: Visible {
+            is_visible: true,
+            is_transparent: true,
+        },
+        ..Default::default()
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::{prelude::*, render::camera::PerspectiveProjection};
+use std::collections::HashMap;
+
+use super::nbody::{Mass, Radius};
+use super::pan_orbit_camera::PanOrbitCamera;
+
+/// Swaps the sphere of each body for coarser ones when it covers few pixels on screen.
+/// Only the meshes change, the physics is unaffected. Stars keep their detailed sphere,
+/// they are the largest bodies and the light of their scenes.
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<LodMeshes>()
+            .add_system(add_lod.system())
+            .add_system(update_lod.system());
+    }
+}
+
+/// Subdivisions of the coarser spheres, the finest level being the mesh the body was
+/// spawned with
+const SUBDIVISIONS: [usize; 2] = [1, 3];
+/// Radii on screen, in pixels, from which the next level is used
+const THRESHOLDS: [f32; 2] = [4.0, 24.0];
+
+/// Coarse spheres, shared by the bodies of the same radius, e.g. the stars of a cluster
+#[derive(Default)]
+struct LodMeshes(HashMap<(u32, usize), Handle<Mesh>>);
+
+/// Meshes of a body from the coarsest to the original one, and the one in use
+struct Lod {
+    levels: Vec<Handle<Mesh>>,
+    level: usize,
+}
+
+#[allow(clippy::type_complexity)]
+fn add_lod(
+    mut commands: Commands,
+    mut lod_meshes: ResMut<LodMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<(Entity, &Radius, &Handle<Mesh>), (With<Mass>, Without<Lod>, Without<Light>)>,
+) {
+    for (entity, radius, mesh) in query.iter() {
+        let vertices = match meshes.get(mesh) {
+            Some(mesh) => mesh.count_vertices(),
+            None => continue,
+        };
+        let coarse: Vec<_> = SUBDIVISIONS
+            .iter()
+            .map(|&subdivisions| {
+                lod_meshes
+                    .0
+                    .entry((radius.0.to_bits(), subdivisions))
+                    .or_insert_with(|| {
+                        meshes.add(Mesh::from(shape::Icosphere {
+                            radius: radius.0,
+                            subdivisions,
+                        }))
+                    })
+                    .clone()
+            })
+            .collect();
+        // Bodies spawned with a coarse sphere keep it up close
+        let mut levels: Vec<_> = coarse
+            .into_iter()
+            .filter(|coarse| meshes.get(coarse).unwrap().count_vertices() < vertices)
+            .collect();
+        levels.push(mesh.clone());
+        let level = levels.len() - 1;
+        commands.entity(entity).insert(Lod { levels, level });
+    }
+}
+
+/// The radius on screen is that of the drawn sphere, scaled by [`SizeScale`], seen from
+/// the distance of the camera with its vertical field of view
+///
+/// [`SizeScale`]: super::sizes::SizeScale
+fn update_lod(
+    windows: Res<Windows>,
+    cameras: Query<(&Transform, &PerspectiveProjection), With<PanOrbitCamera>>,
+    mut bodies: Query<(&Transform, &Radius, &mut Lod, &mut Handle<Mesh>)>,
+) {
+    let height = match windows.get_primary() {
+        Some(window) => window.height(),
+        None => return,
+    };
+    let (camera, projection) = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let pixels_per_unit = height / 2.0 / (projection.fov / 2.0).tan();
+
+    for (transform, radius, mut lod, mut mesh) in bodies.iter_mut() {
+        let distance = transform.translation.distance(camera.translation);
+        let pixels = radius.0 * transform.scale.x * pixels_per_unit / distance.max(f32::EPSILON);
+        // Coarsest levels first: with fewer levels, the original mesh comes earlier
+        let level = THRESHOLDS
+            .iter()
+            .filter(|&&threshold| pixels >= threshold)
+            .count()
+            .min(lod.levels.len() - 1);
+        // Only touch the handle on changes, which uploads the mesh of the new level
+        if level != lod.level {
+            lod.level = level;
+            *mesh = lod.levels[level].clone();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use super::nbody::{Mass, PhysicsSystem, TestParticle};
+use super::pan_orbit_camera::{CameraSystem, PanOrbitCamera};
+
+/// Inset in the bottom-right corner showing the whole system from above, each body as a dot
+/// of its color, and the focus of the camera as a red one. Cameras of Bevy 0.5 render to
+/// the whole window, so the inset is drawn with UI nodes rather than a second camera.
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(spawn_minimap.system())
+            .add_system(add_dots.system())
+            .add_system(
+                update_minimap
+                    .system()
+                    .after(PhysicsSystem::Interpolate)
+                    .after(CameraSystem::Follow),
+            )
+            .add_system(remove_dots.system());
+    }
+}
+
+/// Side of the inset, in pixels
+const SIZE: f32 = 200.0;
+const MARGIN: f32 = 10.0;
+const DOT_SIZE: f32 = 4.0;
+const FOCUS_SIZE: f32 = 6.0;
+
+/// Root node of the inset
+struct Minimap {
+    entity: Entity,
+    /// Distance from the origin to the edges of the inset, in scene units
+    extent: f32,
+}
+
+struct FocusDot;
+
+/// Dot showing a body on the minimap
+struct BodyDot(Entity);
+
+/// Body that a dot shows
+struct DotOf(Entity);
+
+fn dot(size: f32, material: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            size: Size::new(Val::Px(size), Val::Px(size)),
+            ..Default::default()
+        },
+        material,
+        ..Default::default()
+    }
+}
+
+fn spawn_minimap(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let focus = materials.add(Color::RED.into());
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(MARGIN),
+                    bottom: Val::Px(MARGIN),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(SIZE), Val::Px(SIZE)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(dot(FOCUS_SIZE, focus)).insert(FocusDot);
+        })
+        .id();
+    commands.insert_resource(Minimap {
+        entity,
+        extent: 0.0,
+    });
+}
+
+/// Test particles have no dots, there may be thousands of them
+#[allow(clippy::type_complexity)]
+fn add_dots(
+    mut commands: Commands,
+    minimap: Option<Res<Minimap>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    bodies: Query<
+        (Entity, Option<&Handle<StandardMaterial>>),
+        (With<Mass>, Without<TestParticle>, Without<BodyDot>),
+    >,
+) {
+    let minimap = match minimap {
+        Some(minimap) => minimap,
+        None => return,
+    };
+    for (entity, material) in bodies.iter() {
+        let color = material
+            .and_then(|material| standard_materials.get(material))
+            .map_or(Color::WHITE, |material| material.base_color);
+        let dot = commands
+            .spawn_bundle(dot(DOT_SIZE, materials.add(color.into())))
+            .insert(DotOf(entity))
+            .id();
+        commands.entity(minimap.entity).push_children(&[dot]);
+        commands.entity(entity).insert(BodyDot(dot));
+    }
+}
+
+/// Place the dots from above, in the rendered frame. The extent doubles or halves to keep
+/// the outermost body in the inset, so the scale does not change with each orbit.
+#[allow(clippy::type_complexity)]
+fn update_minimap(
+    minimap: Option<ResMut<Minimap>>,
+    cameras: Query<&PanOrbitCamera>,
+    bodies: Query<(&Transform, &BodyDot)>,
+    mut dots: Query<&mut Style, Or<(With<DotOf>, With<FocusDot>)>>,
+    focus: Query<Entity, With<FocusDot>>,
+) {
+    let mut minimap = match minimap {
+        Some(minimap) => minimap,
+        None => return,
+    };
+    let outermost = bodies
+        .iter()
+        .map(|(transform, _)| {
+            let pos = transform.translation;
+            pos.x.abs().max(pos.y.abs())
+        })
+        .fold(0.0, f32::max);
+    if outermost > 0.0 {
+        let extent = 2f32.powf(outermost.log2().ceil());
+        if extent.is_finite() {
+            minimap.extent = extent;
+        }
+    }
+    if minimap.extent <= 0.0 {
+        return;
+    }
+
+    let extent = minimap.extent;
+    let mut place = |entity: Entity, pos: Vec3, size: f32| {
+        if let Ok(mut style) = dots.get_mut(entity) {
+            let scale = (SIZE - size) / 2.0 / extent;
+            style.position.left = Val::Px(SIZE / 2.0 - size / 2.0 + pos.x * scale);
+            style.position.bottom = Val::Px(SIZE / 2.0 - size / 2.0 + pos.y * scale);
+        }
+    };
+    for (transform, dot) in bodies.iter() {
+        place(dot.0, transform.translation, DOT_SIZE);
+    }
+    if let (Some(camera), Ok(focus)) = (cameras.iter().next(), focus.single()) {
+        let pos = camera
+            .focus
+            .clamp(Vec3::splat(-extent), Vec3::splat(extent));
+        place(focus, pos, FOCUS_SIZE);
+    }
+}
+
+/// Despawn the dots of the bodies that no longer exist, e.g. after a merge
+fn remove_dots(
+    mut commands: Commands,
+    dots: Query<(Entity, &DotOf)>,
+    bodies: Query<(), With<Mass>>,
+) {
+    for (entity, of) in dots.iter() {
+        if bodies.get(of.0).is_err() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,2 +1,46 @@
-pub mod pan_orbit_camera;
-pub mod nbody;
\ No newline at end of file
+pub mod apsides;
+pub mod asteroids;
+pub mod clock;
+pub mod colors;
+pub mod comet;
+pub mod compare;
+pub mod diagnostics;
+pub mod eclipses;
+pub mod field;
+pub mod headless;
+pub mod hohmann;
+pub mod hud;
+pub mod inclinations;
+pub mod labels;
+pub mod lagrange;
+pub mod lines;
+pub mod lod;
+pub mod minimap;
+pub mod orbits;
+pub mod panel;
+pub mod picking;
+pub mod planets;
+pub mod plot;
+pub mod random;
+pub mod replay;
+pub mod sandbox;
+pub mod screenshot;
+pub mod sizes;
+pub mod slingshot;
+#[cfg(feature = "native")]
+pub mod sound;
+pub mod spacecraft;
+pub mod spin;
+pub mod starfield;
+pub mod stop;
+pub mod summary;
+pub mod supernova;
+pub mod textures;
+pub mod tidal;
+pub mod trails;
+pub mod trojans;
+pub mod ui;
+pub mod vectors;
+
+// Part of the library, for other apps to embed the simulation
+pub use ::copernicus::plugins::{approach, escapes, nbody, pan_orbit_camera, restart};
@@ -1,7 +1,24 @@
-use bevy::{core::FixedTimestep, prelude::*};
+use bevy::{
+    core::CoreSystem, ecs::schedule::ShouldRun, math::DVec3, prelude::*, tasks::ComputeTaskPool,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    f64::consts::TAU,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-pub const G: f32 = 6.67430e-11_f32;
-const DT: f32 = 0.01;
+use super::restart::reset_resource;
+use crate::forces::{Algorithm, Drag, Forces, Interactions};
+use crate::integrator::{Bodies, Integrator, Substeps};
+
+pub const G: f32 = crate::units::G as f32;
+
+/// Range of the speed factor set from the keyboard and the control panel, besides 0
+pub const MIN_SPEED: f32 = 0.1;
+pub const MAX_SPEED: f32 = 100.0;
+/// Factor of each speed up or slow down
+const SPEED_STEP: f32 = 1.5;
 
 // Resources
 pub struct Gravity(pub f32);
@@ -12,120 +29,1301 @@ impl Default for Gravity {
     }
 }
 
+/// Plummer softening length `ε`, in scene units (i.e. after the AU-to-unit scaling).
+/// Forces use `r^2 + ε^2` in the denominator so that close approaches stay finite.
+#[derive(Default, Clone, Copy)]
+pub struct Softening(pub f32);
+
+/// Floating-point precision of the positions and velocities carried between steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Precision {
+    #[default]
+    Single,
+    /// Positions and velocities are kept in a [`PreciseState`], `Position` and `Velocity`
+    /// only get rounded copies for rendering
+    Double,
+}
+
+/// Opening angle of the Barnes-Hut approximation: a group of bodies of size `s` at a
+/// distance `d` is treated as a single mass when `s/d < θ`
+#[derive(Clone, Copy)]
+pub struct Theta(pub f32);
+
+/// Cinematic slow motion, ramped in and out with M when enabled: each step covers `factor`
+/// times the simulated time, in `1 / factor` times the substeps. Frames then cost as much
+/// as before, spent on accuracy, so that a close encounter plays slowly and in detail.
+/// Lowering the speed factor instead shortens the steps but not their substeps, and lasts
+/// until the speed is changed back.
+pub struct SlowMotion {
+    pub enabled: bool,
+    /// Current factor, from [`SLOW_MOTION`] to 1
+    pub factor: f32,
+    /// Factor being ramped to
+    pub target: f32,
+}
+
+/// Factor of the simulated time per step in slow motion
+pub const SLOW_MOTION: f32 = 0.1;
+/// Time constant of the ramp in and out of slow motion, in seconds of real time
+const SLOW_MOTION_RAMP: f32 = 0.5;
+
+impl SlowMotion {
+    /// Substeps of the shorter steps, so that the integrations get as many times shorter
+    pub fn substeps(&self, substeps: Substeps) -> Substeps {
+        Substeps {
+            count: (substeps.count as f32 / self.factor).ceil() as u32,
+            ..substeps
+        }
+    }
+}
+
+/// Toggled with Space. While paused, Right arrow advances the simulation by one step.
+#[derive(Default)]
+pub struct SimState {
+    pub paused: bool,
+    /// Run a single step while paused, reset once done
+    pub step: bool,
+    /// Simulated time elapsed since the start
+    pub time: f64,
+    /// Simulated time covered by the last step
+    pub last_step: f64,
+    /// Number of steps run since the start
+    pub steps: u64,
+    pub speed_factor: f32,
+}
+
+/// What happens when two bodies overlap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collisions {
+    /// Bodies pass through each other
+    #[default]
+    Off,
+    /// Bodies merge into one, conserving mass and linear momentum
+    Merge,
+    /// Bodies bounce off each other, conserving linear momentum, and keeping the fraction
+    /// of their approach speed given by the [`Restitution`]
+    Bounce,
+}
+
+impl FromStr for Collisions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Collisions::Off),
+            "merge" => Ok(Collisions::Merge),
+            "bounce" => Ok(Collisions::Bounce),
+            _ => Err(format!("unknown collisions mode `{}`", s)),
+        }
+    }
+}
+
+/// Collisions since the start: one per body absorbed by a merge, one per bounce
+#[derive(Default)]
+pub struct CollisionCount(pub u64);
+
+/// Coefficient of restitution of [`Collisions::Bounce`], in `[0, 1]`: the ratio of the speeds
+/// at which the bodies separate and approach, along the line of their centers. 1 is
+/// perfectly elastic, at 0 they move on together along that line without merging.
+#[derive(Clone, Copy)]
+pub struct Restitution(pub f32);
+
+/// Speed of light in scene units per unit of time, for the post-Newtonian correction of
+/// general relativity, see [`Forces::post_newtonian`]. `None` for Newtonian gravity.
+#[derive(Clone, Copy)]
+pub struct Relativity(pub Option<f32>);
+
+/// Whether the bodies with a [`Beta`] are pushed away from the Sun, see
+/// [`Forces::radiation_pressure`]
+#[derive(Clone, Copy)]
+pub struct RadiationPressure(pub bool);
+
+/// Uniform acceleration of every body, in scene units per unit of time squared, see
+/// [`Forces::external_field`]. It moves a freely-falling system as a whole, along a
+/// parabola, leaving the motion of the bodies relative to each other unchanged.
+/// Parsed from `x,y,z`.
+#[derive(Default, Clone, Copy, PartialEq)]
+pub struct ExternalField(pub Vec3);
+
+impl FromStr for ExternalField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let components = s
+            .split(',')
+            .map(|c| {
+                c.trim()
+                    .parse::<f32>()
+                    .map_err(|_| format!("invalid number `{}` in the field", c))
+            })
+            .collect::<Result<Vec<f32>, String>>()?;
+        match components[..] {
+            [x, y, z] => Ok(ExternalField(Vec3::new(x, y, z))),
+            _ => Err(format!("expected `x,y,z`, got `{}`", s)),
+        }
+    }
+}
+
+/// Named groups of bodies, by their [`Group`], and which attract which, see [`Interactions`],
+/// e.g. set from the `no_gravity` of a scene. The bodies of the groups it does not mention,
+/// or of none, attract and are attracted by all the others.
+#[derive(Default, Clone)]
+pub struct GravityGroups {
+    /// Name of each group of the interactions from group 1, group 0 being all the others
+    names: Vec<String>,
+    pub interactions: Interactions,
+}
+
+impl GravityGroups {
+    /// Groups where the bodies of each first group do not attract those of the second, e.g.
+    /// `("asteroids", "asteroids")` for asteroids that do not attract each other
+    pub fn without_gravity<'a>(pairs: impl IntoIterator<Item = &'a (String, String)>) -> Self {
+        let mut groups = GravityGroups::default();
+        for (attracting, attracted) in pairs {
+            let (attracting, attracted) = (groups.add(attracting), groups.add(attracted));
+            groups.interactions.set(attracting, attracted, false);
+        }
+        groups
+    }
+
+    /// The pairs of groups without gravity, as given to [`GravityGroups::without_gravity`]
+    pub fn pairs_without_gravity(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        for (i, attracting) in self.names.iter().enumerate() {
+            for (j, attracted) in self.names.iter().enumerate() {
+                if !self.interactions.attracts(i + 1, j + 1) {
+                    pairs.push((attracting.clone(), attracted.clone()));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Index of the group of the bodies of a [`Group`] in the interactions
+    pub fn index(&self, group: &Group) -> usize {
+        self.names
+            .iter()
+            .position(|name| *name == group.0)
+            .map_or(0, |i| i + 1)
+    }
+
+    fn add(&mut self, name: &str) -> usize {
+        if let Some(i) = self.names.iter().position(|n| n == name) {
+            return i + 1;
+        }
+        self.names.push(name.to_string());
+        self.names.len()
+    }
+}
+
+/// Groups whose bodies are made [`TestParticle`]s as they are spawned, e.g. a dense cloud
+/// tracing the field of a few massive bodies: they feel the massive bodies but exert no
+/// gravity, so that their cost grows as `N * M` for `N` particles and `M` massive bodies,
+/// rather than `N^2`
+#[derive(Default, Clone)]
+pub struct ParticleGroups(pub Vec<String>);
+
+/// Number of physics steps per second of real time. Each step covers
+/// `speed_factor / rate` of simulated time, so that trajectories do not depend on the
+/// frame rate, unless the [`Timestep`] is fixed.
+#[derive(Clone, Copy)]
+pub struct PhysicsRate(pub f64);
+
+/// Simulated time covered by each physics step, in the time unit of the scene, e.g. days.
+/// When set, the speed factor sets how many steps are run per second of real time,
+/// `speed_factor / dt`, rather than their size: the trajectories then only depend on `dt`,
+/// whatever the speed. Otherwise the steps follow the [`PhysicsRate`].
+#[derive(Clone, Copy, Default)]
+pub struct Timestep(pub Option<f64>);
+
+/// Real time the frame counts for, in seconds, measured from [`Time`] at the start of each
+/// frame. The physics steps, the slow motion and the camera advance by it rather than by
+/// [`Time`] itself, so that replaying the frame times of a recording runs them again the
+/// same way, e.g. with `--replay`.
+#[derive(Clone, Copy, Default)]
+pub struct FrameTime(pub f64);
+
+/// How often the simulation steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pacing {
+    /// [`PhysicsRate`] steps per second of real time
+    #[default]
+    RealTime,
+    /// One step per frame, as fast as frames come, e.g. for benchmarks
+    EveryFrame,
+}
+
+/// Origin of the rendered positions. Only the transforms are moved, the physics always
+/// runs in the inertial frame of the scene.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ReferenceFrame {
+    /// Origin of the scene
+    #[default]
+    Origin,
+    /// Center of mass of the bodies, which keeps drifting systems in view
+    Barycenter,
+    /// Named body, e.g. `Earth` for a geocentric view
+    Body(String),
+    /// Co-rotating frame of a named pair, e.g. `Sun` and `Jupiter`: centered on their
+    /// barycenter, with the second body fixed on the X axis from the first one and Z along
+    /// their angular momentum, where the Lagrange points stand still
+    Rotating(String, String),
+    /// Frame of a named primary and secondary turning uniformly at the mean motion of their
+    /// orbit, from where they are when it is picked: both stand still on a circular orbit,
+    /// and trace small ellipses on an eccentric one, where test particles show their
+    /// epicycles and librations around the Lagrange points
+    Corotating(String, String),
+}
+
+/// Two body names separated by `separator`, e.g. `Sun,Jupiter`
+fn body_names(s: &str, separator: char) -> Option<(String, String)> {
+    let (a, b) = s.split_once(separator)?;
+    let (a, b) = (a.trim(), b.trim());
+    (!a.is_empty() && !b.is_empty()).then(|| (a.to_string(), b.to_string()))
+}
+
+impl FromStr for ReferenceFrame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "origin" => Ok(ReferenceFrame::Origin),
+            "barycenter" => Ok(ReferenceFrame::Barycenter),
+            _ => {
+                if let Some(name) = s.strip_prefix("body:").filter(|name| !name.is_empty()) {
+                    Ok(ReferenceFrame::Body(name.to_string()))
+                } else if let Some(pair) = s.strip_prefix("rotating:") {
+                    body_names(pair, ',')
+                        .map(|(a, b)| ReferenceFrame::Rotating(a, b))
+                        .ok_or_else(|| {
+                            format!("expected two body names `rotating:a,b`, got `{}`", s)
+                        })
+                } else if let Some(pair) = s.strip_prefix("corotating:") {
+                    body_names(pair, ':')
+                        .map(|(a, b)| ReferenceFrame::Corotating(a, b))
+                        .ok_or_else(|| {
+                            format!("expected two body names `corotating:a:b`, got `{}`", s)
+                        })
+                } else {
+                    Err(format!("unknown reference frame `{}`", s))
+                }
+            }
+        }
+    }
+}
+
 // Plugin
 pub struct NBody {
     pub speed_factor: f32,
+    /// Physics steps per second, see [`PhysicsRate`]
+    pub physics_hz: f64,
+    /// See [`Timestep`], ignored unless positive
+    pub dt: Option<f64>,
+    pub integrator: Integrator,
+    pub softening: f32,
+    pub algorithm: Algorithm,
+    pub theta: f32,
+    /// Integrations per physics step, see [`Substeps`]
+    pub substeps: u32,
+    pub adaptive_substeps: bool,
+    pub collisions: Collisions,
+    /// See [`Restitution`]
+    pub restitution: f32,
+    /// See [`Relativity`]
+    pub speed_of_light: Option<f32>,
+    /// See [`RadiationPressure`]
+    pub radiation_pressure: bool,
+    /// See [`ExternalField`]
+    pub external_field: Vec3,
+    /// Resistance of a medium, see [`Drag`]
+    pub drag: Drag,
+    /// See [`ParticleGroups`]
+    pub particle_groups: Vec<String>,
+    pub pacing: Pacing,
+    pub precision: Precision,
+    pub frame: ReferenceFrame,
+    /// See [`SlowMotion`]
+    pub cinematic: bool,
 }
 
 impl Default for NBody {
     fn default() -> Self {
-        Self { speed_factor: 1.0 }
+        Self {
+            speed_factor: 1.0,
+            physics_hz: 60.0,
+            dt: None,
+            integrator: Integrator::default(),
+            softening: 0.0,
+            algorithm: Algorithm::default(),
+            theta: 0.5,
+            substeps: 1,
+            adaptive_substeps: false,
+            collisions: Collisions::default(),
+            restitution: 1.0,
+            speed_of_light: None,
+            radiation_pressure: false,
+            external_field: Vec3::ZERO,
+            drag: Drag::default(),
+            particle_groups: Vec::new(),
+            pacing: Pacing::default(),
+            precision: Precision::default(),
+            frame: ReferenceFrame::default(),
+            cinematic: false,
+        }
     }
 }
 
 impl Plugin for NBody {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<Gravity>().add_system_set(
-            SystemSet::new()
-                .with_run_criteria(FixedTimestep::steps_per_second(
-                    (self.speed_factor / DT) as f64,
-                ))
-                .with_system(
-                    update_acceleration
-                        .system()
-                        .label(PhysicsSystem::UpdateAcceleration),
-                )
-                .with_system(
-                    update_velocity
-                        .system()
-                        .label(PhysicsSystem::UpdateVelocity)
-                        .after(PhysicsSystem::UpdateAcceleration),
-                )
-                .with_system(
-                    movement
-                        .system()
-                        .label(PhysicsSystem::Movement)
-                        .after(PhysicsSystem::UpdateVelocity),
-                ),
-        );
+        app.init_resource::<Gravity>()
+            .add_event::<CollisionEvent>()
+            .insert_resource(self.integrator)
+            .insert_resource(Softening(self.softening))
+            .insert_resource(self.algorithm)
+            .insert_resource(Theta(self.theta))
+            .insert_resource(Substeps {
+                count: self.substeps.max(1),
+                adaptive: self.adaptive_substeps,
+            })
+            .insert_resource(self.collisions)
+            .insert_resource(Relativity(self.speed_of_light))
+            .insert_resource(RadiationPressure(self.radiation_pressure))
+            .insert_resource(ExternalField(self.external_field))
+            .insert_resource(self.drag)
+            .init_resource::<GravityGroups>()
+            .insert_resource(ParticleGroups(self.particle_groups.clone()))
+            .insert_resource(Restitution(self.restitution.clamp(0.0, 1.0)))
+            .init_resource::<CollisionCount>()
+            .insert_resource(self.pacing)
+            .insert_resource(PhysicsRate(self.physics_hz))
+            .insert_resource(Timestep(self.dt.filter(|&dt| dt > 0.0)))
+            .init_resource::<PhysicsClock>()
+            .init_resource::<FrameTime>()
+            .insert_resource(self.precision)
+            .insert_resource(self.frame.clone())
+            .init_resource::<FrameRotation>()
+            .init_resource::<RenderTime>()
+            .init_resource::<Corotation>()
+            .insert_resource(SimState {
+                speed_factor: self.speed_factor,
+                ..Default::default()
+            })
+            .insert_resource(SlowMotion {
+                enabled: self.cinematic,
+                factor: 1.0,
+                target: 1.0,
+            })
+            .add_startup_system_to_stage(StartupStage::PreStartup, reset_time.system())
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<PhysicsClock>.system(),
+            )
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<CollisionCount>.system(),
+            )
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<Corotation>.system(),
+            )
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<FrameRotation>.system(),
+            )
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<RenderTime>.system(),
+            )
+            .add_system_to_stage(
+                CoreStage::First,
+                measure_frame.system().after(CoreSystem::Time),
+            )
+            .add_system_to_stage(CoreStage::PreUpdate, exert_no_gravity.system())
+            .add_system(sim_controls.system())
+            .add_system(add_meshes.system())
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(physics_steps.system())
+                    .with_system(integrate.system().label(PhysicsSystem::Integrate))
+                    .with_system(integrate_ghosts.system().after(PhysicsSystem::Integrate)),
+            )
+            .add_system(
+                merge_collisions
+                    .system()
+                    .label(PhysicsSystem::Collisions)
+                    .after(PhysicsSystem::Integrate),
+            )
+            .add_system(
+                bounce_collisions
+                    .system()
+                    .label(PhysicsSystem::Collisions)
+                    .after(PhysicsSystem::Integrate),
+            )
+            .add_system(
+                interpolate
+                    .system()
+                    .label(PhysicsSystem::Interpolate)
+                    .after(PhysicsSystem::Collisions),
+            );
     }
 }
 
+/// The clock starts over each time the startup systems run, see
+/// [`RestartPlugin`](super::restart::RestartPlugin), keeping the speed and the pause
+fn reset_time(mut state: ResMut<SimState>) {
+    state.step = false;
+    state.time = 0.0;
+    state.last_step = 0.0;
+    state.steps = 0;
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 pub enum PhysicsSystem {
-    UpdateAcceleration,
-    UpdateVelocity,
-    Movement,
+    Integrate,
+    Collisions,
+    /// Moves the transforms between the last two physics states
+    Interpolate,
 }
 
+/// Position of a body after the last physics step. `Transform` only holds where it is
+/// drawn, interpolated from [`PreviousPosition`].
 #[derive(Default)]
-struct Position(Vec3);
-
+pub struct Position(pub Vec3);
+/// Position of a body before the last physics step
+#[derive(Default)]
+pub struct PreviousPosition(pub Vec3);
 #[derive(Default)]
-struct Velocity(Vec3);
+pub struct Velocity(pub Vec3);
 #[derive(Default)]
-struct Acceleration(Vec3);
+pub struct Acceleration(pub Vec3);
+
+pub struct Mass(pub f32);
 
-struct Mass(f32);
+/// Body that feels the gravity of the others but exerts none, e.g. to trace the field or
+/// for dense clouds of small bodies. Test particles never merge.
+pub struct TestParticle;
 
+/// Ratio of the radiation pressure of the Sun on a body to its gravity, e.g. around 1 for
+/// the dust of a comet, felt with [`RadiationPressure`]
+#[derive(Clone, Copy)]
+pub struct Beta(pub f32);
+
+/// Named set of bodies, e.g. `asteroids`, whose gravity can be turned off between
+/// groups with the [`GravityGroups`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group(pub String);
+
+/// Double-precision state of a body with [`Precision::Double`]
+pub struct PreciseState {
+    pub pos: DVec3,
+    pub vel: DVec3,
+}
+
+/// Radius of the rendered sphere, used for collisions
+#[derive(Default)]
+pub struct Radius(pub f32);
+
+/// Actual radius of a body in scene units, when it differs from its [`Radius`], e.g. when
+/// its size is exaggerated to be seen
+pub struct TrueRadius(pub f32);
+
+/// Color of the sphere the [`NBody`] plugin draws a body spawned without a mesh with, if
+/// any, see [`BodyBundle::with_color`]
+#[derive(Default, Clone, Copy)]
+pub struct BodyColor(pub Option<Color>);
+
+/// Rotation of a body on itself, around `axis` at `rate` radians per unit of simulated time
+#[derive(Clone, Copy)]
+pub struct Spin {
+    pub axis: Vec3,
+    pub rate: f32,
+}
+
+impl Spin {
+    /// One counterclockwise turn per `period`, around an axis tilted from Z by `obliquity`
+    /// degrees around X, as the rings are so that they stay in the equatorial plane. Past
+    /// 90°, e.g. for Venus, the rotation is retrograde.
+    pub fn new(period: f32, obliquity: f32) -> Self {
+        Self {
+            axis: Quat::from_rotation_x(obliquity.to_radians()) * Vec3::Z,
+            rate: TAU as f32 / period,
+        }
+    }
+}
+
+/// Equirectangular map of the surface of a body, e.g. `textures/earth.png` in the assets
+/// folder: longitudes from left to right, from the north pole at the top to the south one
+#[derive(Clone)]
+pub struct SurfaceTexture(pub String);
+
+/// Order in which the bodies were spawned, unlike the ids of their entities, which are
+/// reused after a despawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpawnIndex(pub u64);
+
+/// Next [`SpawnIndex`], counted over the whole run, restarts included
+static SPAWNED: AtomicU64 = AtomicU64::new(0);
+
+/// Components of a simulated body, e.g. added to a [`PbrBundle`] for it to be drawn, or
+/// given a color to be drawn as a sphere
 #[derive(Bundle)]
 pub struct BodyBundle {
     mass: Mass,
+    radius: Radius,
+    color: BodyColor,
+    pos: Position,
+    previous_pos: PreviousPosition,
     transform: Transform,
     vel: Velocity,
     acc: Acceleration,
+    index: SpawnIndex,
 }
 
 impl BodyBundle {
     pub fn new(mass: f32, pos: Vec3, vel: Vec3) -> Self {
         Self {
             mass: Mass(mass),
+            radius: Radius::default(),
+            color: BodyColor::default(),
+            pos: Position(pos),
+            previous_pos: PreviousPosition(pos),
             transform: Transform::from_translation(pos),
             vel: Velocity(vel),
             acc: Acceleration::default(),
+            index: SpawnIndex(SPAWNED.fetch_add(1, Ordering::Relaxed)),
+        }
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = Radius(radius);
+        self
+    }
+
+    /// Drawn as a sphere of this color and of the radius of the body, unless spawned with a
+    /// mesh
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = BodyColor(Some(color));
+        self
+    }
+}
+
+/// Before the first step of the bodies of the [`ParticleGroups`]
+#[allow(clippy::type_complexity)]
+fn exert_no_gravity(
+    mut commands: Commands,
+    groups: Res<ParticleGroups>,
+    bodies: Query<(Entity, &Group), (Added<Group>, Without<TestParticle>)>,
+) {
+    for (entity, group) in bodies.iter() {
+        if groups.0.contains(&group.0) {
+            commands.entity(entity).insert(TestParticle);
+        }
+    }
+}
+
+/// Meshes are left out without the assets of the renderer, when headless
+#[allow(clippy::type_complexity)]
+fn add_meshes(
+    mut commands: Commands,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+    bodies: Query<
+        (Entity, &BodyColor, &Radius, &Transform),
+        (Added<BodyColor>, Without<Handle<Mesh>>),
+    >,
+) {
+    let (mut meshes, mut materials) = match (meshes, materials) {
+        (Some(meshes), Some(materials)) => (meshes, materials),
+        _ => return,
+    };
+    for (entity, color, radius, transform) in bodies.iter() {
+        let color = match color.0 {
+            Some(color) => color,
+            None => continue,
+        };
+        commands.entity(entity).insert_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: radius.0,
+                subdivisions: 5,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                roughness: 0.6,
+                reflectance: 0.1,
+                ..Default::default()
+            }),
+            transform: *transform,
+            ..Default::default()
+        });
+    }
+}
+
+fn measure_frame(time: Res<Time>, mut frame: ResMut<FrameTime>) {
+    frame.0 = time.delta_seconds_f64();
+}
+
+/// Pausing keeps the speed factor, and a frozen simulation (speed 0) still steps, with no
+/// motion, so the two can be combined in any order
+fn sim_controls(
+    frame: Res<FrameTime>,
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<SimState>,
+    mut slow: ResMut<SlowMotion>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        state.paused = !state.paused;
+    }
+    if state.paused && keys.just_pressed(KeyCode::Right) {
+        state.step = true;
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        state.speed_factor = (state.speed_factor * SPEED_STEP).clamp(MIN_SPEED, MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::Comma) {
+        state.speed_factor = (state.speed_factor / SPEED_STEP).clamp(MIN_SPEED, MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::Slash) {
+        state.speed_factor = 1.0;
+    }
+    if keys.just_pressed(KeyCode::X) {
+        state.speed_factor = 0.0;
+    }
+
+    if slow.enabled && keys.just_pressed(KeyCode::M) {
+        slow.target = if slow.target < 1.0 { 1.0 } else { SLOW_MOTION };
+    }
+    if slow.factor != slow.target {
+        let ramp = 1.0 - (-frame.0 as f32 / SLOW_MOTION_RAMP).exp();
+        slow.factor += (slow.target - slow.factor) * ramp;
+        if (slow.target - slow.factor).abs() < 1e-3 {
+            slow.factor = slow.target;
+        }
+    }
+}
+
+/// Progress towards the next physics step, also used to interpolate the transforms
+#[derive(Default)]
+struct PhysicsClock {
+    /// Real time not yet covered by a step, in seconds
+    accumulator: f64,
+    /// Whether the criterion was already evaluated this frame
+    looping: bool,
+}
+
+/// Longest frame time caught up on, so that a slow frame (e.g. while the window is moved)
+/// does not trigger a burst of steps that makes the next frame slower still
+const MAX_FRAME_TIME: f64 = 0.25;
+
+/// Seconds of real time between two physics steps, and simulated time covered by each.
+/// A frozen simulation keeps stepping at the [`PhysicsRate`], by steps of no time.
+fn step_size(
+    rate: &PhysicsRate,
+    timestep: &Timestep,
+    state: &SimState,
+    slow: &SlowMotion,
+) -> (f64, f64) {
+    let speed = (state.speed_factor * slow.factor) as f64;
+    match timestep.0 {
+        Some(dt) if speed > 0.0 => (dt / speed, dt),
+        _ => (1.0 / rate.0, speed / rate.0),
+    }
+}
+
+/// Fixed timestep, run [`PhysicsRate`] times per second of real time whatever the frame
+/// rate, or as often as the [`Timestep`] asks for. Contrary to `FixedTimestep`, the speed
+/// factor can change at runtime.
+fn physics_steps(
+    frame: Res<FrameTime>,
+    (rate, timestep): (Res<PhysicsRate>, Res<Timestep>),
+    state: Res<SimState>,
+    slow: Res<SlowMotion>,
+    pacing: Res<Pacing>,
+    mut clock: ResMut<PhysicsClock>,
+) -> ShouldRun {
+    if *pacing == Pacing::EveryFrame {
+        clock.looping = !clock.looping;
+        return if clock.looping {
+            ShouldRun::YesAndCheckAgain
+        } else {
+            ShouldRun::No
+        };
+    }
+
+    if !clock.looping {
+        clock.accumulator += frame.0.min(MAX_FRAME_TIME);
+    }
+
+    let (period, _) = step_size(&rate, &timestep, &state, &slow);
+    if clock.accumulator >= period {
+        clock.accumulator -= period;
+        clock.looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        clock.looping = false;
+        ShouldRun::No
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn integrate(
+    mut commands: Commands,
+    mut state: ResMut<SimState>,
+    g: Res<Gravity>,
+    softening: Res<Softening>,
+    algorithm: Res<Algorithm>,
+    theta: Res<Theta>,
+    // Past the 16 parameters of a system, the forces besides Newtonian gravity together
+    (relativity, radiation, external, drag, groups): (
+        Res<Relativity>,
+        Res<RadiationPressure>,
+        Res<ExternalField>,
+        Res<Drag>,
+        Res<GravityGroups>,
+    ),
+    integrator: Res<Integrator>,
+    substeps: Res<Substeps>,
+    precision: Res<Precision>,
+    (rate, timestep): (Res<PhysicsRate>, Res<Timestep>),
+    slow: Res<SlowMotion>,
+    pool: Res<ComputeTaskPool>,
+    mut bodies: Local<Bodies<Vec3>>,
+    mut precise_bodies: Local<Bodies<DVec3>>,
+    mut query: Query<(
+        Entity,
+        &Mass,
+        &mut Position,
+        &mut PreviousPosition,
+        &mut Velocity,
+        &mut Acceleration,
+        Option<&mut PreciseState>,
+        Option<&TestParticle>,
+        Option<&Beta>,
+        Option<&Group>,
+    )>,
+) {
+    // Interpolation starts from where the bodies are now, and stays there if paused
+    for (_, _, pos, mut previous, ..) in query.iter_mut() {
+        previous.0 = pos.0;
+    }
+    if state.paused {
+        if !state.step {
+            return;
+        }
+        state.step = false;
+    }
+    let (_, dt) = step_size(&rate, &timestep, &state, &slow);
+    let substeps = slow.substeps(*substeps);
+
+    let forces = Forces {
+        g: g.0,
+        softening: softening.0,
+        algorithm: *algorithm,
+        theta: theta.0,
+        speed_of_light: relativity.0,
+        external_field: external.0,
+        drag: *drag,
+        interactions: &groups.interactions,
+        pool: &pool,
+    };
+    state.time += dt;
+    state.last_step = dt;
+    state.steps += 1;
+
+    if *precision == Precision::Single {
+        bodies.clear();
+        for (_, mass, pos, _, vel, .., particle, beta, group) in query.iter_mut() {
+            bodies.push(mass.0, pos.0, vel.0, particle.is_some());
+            if let (true, Some(beta)) = (radiation.0, beta) {
+                bodies.irradiate(beta.0);
+            }
+            if let Some(group) = group {
+                bodies.join(groups.index(group));
+            }
+        }
+        bodies.advance(*integrator, &forces, dt, substeps);
+        for (i, (_, _, mut pos, _, mut vel, mut acc, ..)) in query.iter_mut().enumerate() {
+            pos.0 = bodies.pos[i];
+            vel.0 = bodies.vel[i];
+            acc.0 = bodies.acc[i] + bodies.pn[i];
+        }
+        return;
+    }
+
+    precise_bodies.clear();
+    for (_, mass, pos, _, vel, _, precise, particle, beta, group) in query.iter_mut() {
+        // The precise state is stale if the body was moved by something else
+        let (pos, vel) = match precise {
+            Some(p) if p.pos.as_f32() == pos.0 && p.vel.as_f32() == vel.0 => (p.pos, p.vel),
+            _ => (pos.0.as_f64(), vel.0.as_f64()),
+        };
+        precise_bodies.push(mass.0, pos, vel, particle.is_some());
+        if let (true, Some(beta)) = (radiation.0, beta) {
+            precise_bodies.irradiate(beta.0);
+        }
+        if let Some(group) = group {
+            precise_bodies.join(groups.index(group));
+        }
+    }
+    precise_bodies.advance(*integrator, &forces, dt, substeps);
+    for (i, (entity, _, mut position, _, mut vel, mut acc, precise, ..)) in
+        query.iter_mut().enumerate()
+    {
+        let (pos, v) = (precise_bodies.pos[i], precise_bodies.vel[i]);
+        position.0 = pos.as_f32();
+        vel.0 = v.as_f32();
+        acc.0 = precise_bodies.acc[i] + precise_bodies.pn[i];
+        match precise {
+            Some(mut precise) => {
+                precise.pos = pos;
+                precise.vel = v;
+            }
+            None => {
+                commands.entity(entity).insert(PreciseState { pos, vel: v });
+            }
+        }
+    }
+}
+
+/// Second copy of the bodies, advanced with its own integrator from the same initial
+/// conditions, step by step with the others, e.g. to show how far a cruder integrator
+/// strays. Its bodies are not entities: nothing else sees them, and they neither collide
+/// nor merge. Nothing is advanced while it is empty.
+#[derive(Default)]
+pub struct GhostWorld {
+    pub integrator: Integrator,
+    pub bodies: Bodies<DVec3>,
+    /// Positions before the last step, to draw the bodies between their last two states
+    pub previous: Vec<DVec3>,
+}
+
+/// Advance the [`GhostWorld`] by the step the bodies just took, with the same forces
+#[allow(clippy::too_many_arguments)]
+fn integrate_ghosts(
+    state: Res<SimState>,
+    g: Res<Gravity>,
+    softening: Res<Softening>,
+    algorithm: Res<Algorithm>,
+    theta: Res<Theta>,
+    (relativity, external, drag, groups): (
+        Res<Relativity>,
+        Res<ExternalField>,
+        Res<Drag>,
+        Res<GravityGroups>,
+    ),
+    substeps: Res<Substeps>,
+    slow: Res<SlowMotion>,
+    pool: Res<ComputeTaskPool>,
+    ghosts: Option<ResMut<GhostWorld>>,
+    mut last_steps: Local<u64>,
+) {
+    // The bodies stay put while paused
+    if std::mem::replace(&mut *last_steps, state.steps) == state.steps {
+        return;
+    }
+    let mut ghosts = match ghosts {
+        Some(ghosts) if !ghosts.bodies.mass.is_empty() => ghosts,
+        _ => return,
+    };
+    let forces = Forces {
+        g: g.0,
+        softening: softening.0,
+        algorithm: *algorithm,
+        theta: theta.0,
+        speed_of_light: relativity.0,
+        external_field: external.0,
+        drag: *drag,
+        interactions: &groups.interactions,
+        pool: &pool,
+    };
+    let ghosts = &mut *ghosts;
+    ghosts.previous.clone_from(&ghosts.bodies.pos);
+    let substeps = slow.substeps(*substeps);
+    ghosts
+        .bodies
+        .advance(ghosts.integrator, &forces, state.last_step, substeps);
+}
+
+/// Rotation from the inertial frame of the physics to the rendered one, the identity but
+/// in a [`ReferenceFrame::Rotating`] or [`ReferenceFrame::Corotating`], for what is drawn
+/// from the inertial positions and velocities, e.g. orbits and velocity arrows
+#[derive(Default)]
+pub struct FrameRotation(pub Quat);
+
+/// Simulated time the bodies are drawn at, between the last two physics states
+#[derive(Default)]
+pub struct RenderTime(pub f64);
+
+/// Start of a [`ReferenceFrame::Corotating`]: its rotation at that time, and its angular
+/// velocity, `None` until both bodies are found
+#[derive(Default)]
+struct Corotation(Option<(f64, Quat, Vec3)>);
+
+/// Rotation to the frame with the second body on the X axis from the first one and Z along
+/// their angular momentum, `None` if they do not turn around each other
+fn pair_rotation(pos_a: Vec3, vel_a: Vec3, pos_b: Vec3, vel_b: Vec3) -> Option<Quat> {
+    let x = (pos_b - pos_a).normalize_or_zero();
+    let z = x.cross(vel_b - vel_a).normalize_or_zero();
+    (z != Vec3::ZERO)
+        .then(|| Quat::from_rotation_mat3(&Mat3::from_cols(x, z.cross(x), z)).inverse())
+}
+
+/// Angular velocity of the mean motion `sqrt(G * M / a^3)` of a pair on a bound orbit,
+/// along their angular momentum, the semi-major axis `a` being given by the vis-viva
+/// equation `v^2 = G * M * (2 / r - 1 / a)`
+fn mean_motion(g: f32, mass: f32, pos: Vec3, vel: Vec3) -> Option<Vec3> {
+    let gm = g * mass;
+    let inverse_axis = 2.0 / pos.length() - vel.length_squared() / gm;
+    let axis = pos.cross(vel).normalize_or_zero();
+    (inverse_axis > 0.0 && axis != Vec3::ZERO).then(|| axis * (gm * inverse_axis.powi(3)).sqrt())
+}
+
+/// Draw the bodies between their last two physics states, by the fraction of a step
+/// elapsed since the last one, so that motion looks smooth at any frame rate, relative
+/// to the [`ReferenceFrame`]
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn interpolate(
+    clock: Res<PhysicsClock>,
+    (rate, timestep): (Res<PhysicsRate>, Res<Timestep>),
+    slow: Res<SlowMotion>,
+    pacing: Res<Pacing>,
+    frame: Res<ReferenceFrame>,
+    state: Res<SimState>,
+    g: Res<Gravity>,
+    mut rotation: ResMut<FrameRotation>,
+    mut render_time: ResMut<RenderTime>,
+    mut corotation: ResMut<Corotation>,
+    mut query: Query<(
+        &Mass,
+        &Position,
+        &PreviousPosition,
+        &Velocity,
+        &mut Transform,
+        Option<&Name>,
+        Option<&TestParticle>,
+    )>,
+) {
+    let alpha = match *pacing {
+        Pacing::RealTime => {
+            let (period, _) = step_size(&rate, &timestep, &state, &slow);
+            (clock.accumulator / period).min(1.0) as f32
         }
+        Pacing::EveryFrame => 1.0,
+    };
+    let time = state.time - (1.0 - alpha as f64) * state.last_step;
+    render_time.0 = time;
+    let mut find = |name: &str| {
+        query
+            .iter_mut()
+            .find(|(.., body, _)| body.is_some_and(|body| body.as_str() == name))
+            .map(|(mass, pos, previous, vel, ..)| (mass.0, previous.0.lerp(pos.0, alpha), vel.0))
+    };
+    rotation.0 = Quat::IDENTITY;
+    if frame.is_changed() {
+        corotation.0 = None;
+    }
+    let barycenter = |(mass_a, pos_a, _): (f32, Vec3, Vec3),
+                      (mass_b, pos_b, _): (f32, Vec3, Vec3)| {
+        (pos_a * mass_a + pos_b * mass_b) / (mass_a + mass_b).max(f32::MIN_POSITIVE)
+    };
+    let origin = match &*frame {
+        ReferenceFrame::Origin => Vec3::ZERO,
+        ReferenceFrame::Barycenter => {
+            let (moment, mass) = query
+                .iter_mut()
+                .filter(|(.., particle)| particle.is_none())
+                .fold(
+                    (Vec3::ZERO, 0.0),
+                    |(moment, mass), (m, pos, previous, ..)| {
+                        (moment + previous.0.lerp(pos.0, alpha) * m.0, mass + m.0)
+                    },
+                );
+            if mass > 0.0 {
+                moment / mass
+            } else {
+                Vec3::ZERO
+            }
+        }
+        // Left at the origin if the body is gone, e.g. merged into another one
+        ReferenceFrame::Body(name) => find(name).map_or(Vec3::ZERO, |(_, pos, _)| pos),
+        // Inertial if one of them is gone, or if they do not turn around each other
+        ReferenceFrame::Rotating(a, b) => match (find(a), find(b)) {
+            (Some(a), Some(b)) => {
+                if let Some(pair) = pair_rotation(a.1, a.2, b.1, b.2) {
+                    rotation.0 = pair;
+                }
+                barycenter(a, b)
+            }
+            _ => Vec3::ZERO,
+        },
+        // Started from the rotating frame, at the time of the interpolated positions
+        ReferenceFrame::Corotating(a, b) => match (find(a), find(b)) {
+            (Some(a), Some(b)) => {
+                if corotation.0.is_none() {
+                    corotation.0 = pair_rotation(a.1, a.2, b.1, b.2)
+                        .zip(mean_motion(g.0, a.0 + b.0, b.1 - a.1, b.2 - a.2))
+                        .map(|(start, spin)| (time, start, spin));
+                }
+                if let Some((start_time, start, spin)) = corotation.0 {
+                    let angle = spin.length() * (time - start_time) as f32;
+                    rotation.0 = start * Quat::from_axis_angle(spin.normalize(), -angle);
+                }
+                barycenter(a, b)
+            }
+            _ => Vec3::ZERO,
+        },
+    };
+    for (_, pos, previous, _, mut transform, ..) in query.iter_mut() {
+        transform.translation = rotation.0 * (previous.0.lerp(pos.0, alpha) - origin);
     }
 }
 
-/// Newton's law of universal gravitation
-/// ```
-/// F = G*m1*m2/r^2
-/// ```
-/// where:
-/// - `F` is the gravitational force acting between two objects
-/// - `G` is the gravitational constant
-/// - `m1` and `m2` are the masses of the objects
-/// - `r` is the distance between the centers of their masses
-fn update_acceleration(g: Res<Gravity>, mut query: Query<(&Mass, &Transform, &mut Acceleration)>) {
-    let mut bodies: Vec<(&Mass, &Transform, Mut<Acceleration>)> = Vec::new();
-    for (mass, transform, mut acc) in query.iter_mut() {
-        acc.0 = Vec3::ZERO;
-        for (other_mass, other_pos, other_acc) in bodies.iter_mut() {
-            let diff = other_pos.translation - transform.translation;
-            if let Some(mut force) = diff.try_normalize() {
-                let magnitude = g.0 * mass.0 * other_mass.0 / diff.length_squared();
-                force *= magnitude;
-                acc.0 += force;
-                other_acc.0 -= force;
+/// Body resulting from one or more merges
+struct Merged {
+    mass: f32,
+    pos: Vec3,
+    vel: Vec3,
+    /// Volumes add up, so this is the sum of the cubed radii
+    radius_cubed: f32,
+    /// Sum of the cubed [`TrueRadius`] of the parents, or of their radii without one, if
+    /// any of them had one
+    true_radius_cubed: Option<f32>,
+    /// Index of the heaviest parent, whose material, light, name, group, [`Beta`], [`Spin`],
+    /// color and texture are inherited
+    heaviest: usize,
+}
+
+/// Merge bodies whose spheres overlap: mass, position and linear momentum are conserved,
+/// and the volume of the new sphere is the sum of the volumes, as is its actual volume when
+/// the parents have a [`TrueRadius`].
+/// A body can absorb several others in the same frame.
+#[allow(clippy::type_complexity)]
+fn merge_collisions(
+    mut commands: Commands,
+    collisions: Res<Collisions>,
+    mut count: ResMut<CollisionCount>,
+    mut events: EventWriter<CollisionEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<
+        (
+            Entity,
+            &Mass,
+            &Radius,
+            &Position,
+            &Velocity,
+            Option<&TrueRadius>,
+            &Handle<StandardMaterial>,
+            Option<&Light>,
+            Option<&Name>,
+            Option<&Group>,
+            Option<&Beta>,
+            Option<&Spin>,
+            Option<&BodyColor>,
+            Option<&SurfaceTexture>,
+        ),
+        Without<TestParticle>,
+    >,
+) {
+    if *collisions != Collisions::Merge {
+        return;
+    }
+
+    let bodies: Vec<_> = query.iter().collect();
+    let mut absorbed = vec![false; bodies.len()];
+    for i in 0..bodies.len() {
+        if absorbed[i] {
+            continue;
+        }
+        let (_, mass, radius, pos, vel, true_radius, ..) = bodies[i];
+        let mut current = Merged {
+            mass: mass.0,
+            pos: pos.0,
+            vel: vel.0,
+            radius_cubed: radius.0.powi(3),
+            true_radius_cubed: true_radius.map(|true_radius| true_radius.0.powi(3)),
+            heaviest: i,
+        };
+        let mut changed = false;
+        for j in i + 1..bodies.len() {
+            if absorbed[j] {
+                continue;
+            }
+            let (_, other_mass, other_radius, other_pos, other_vel, other_true_radius, ..) =
+                bodies[j];
+            let distance = current.pos.distance(other_pos.0);
+            if distance >= current.radius_cubed.cbrt() + other_radius.0 {
+                continue;
+            }
+
+            let energy = impact_energy((current.mass, current.vel), (other_mass.0, other_vel.0));
+            let total = current.mass + other_mass.0;
+            current.pos = (current.pos * current.mass + other_pos.0 * other_mass.0) / total;
+            current.vel = (current.vel * current.mass + other_vel.0 * other_mass.0) / total;
+            current.true_radius_cubed = match (current.true_radius_cubed, other_true_radius) {
+                (None, None) => None,
+                (true_radius_cubed, other_true_radius) => Some(
+                    true_radius_cubed.unwrap_or(current.radius_cubed)
+                        + other_true_radius.map_or(other_radius.0, |r| r.0).powi(3),
+                ),
+            };
+            current.radius_cubed += other_radius.0.powi(3);
+            if other_mass.0 > bodies[current.heaviest].1 .0 {
+                current.heaviest = j;
+            }
+            current.mass = total;
+            absorbed[j] = true;
+            changed = true;
+            count.0 += 1;
+            events.send(CollisionEvent {
+                a: bodies[i].0,
+                b: bodies[j].0,
+                merged: true,
+                energy,
+            });
+            commands.entity(bodies[j].0).despawn_recursive();
+        }
+
+        if changed {
+            let body = current;
+            commands.entity(bodies[i].0).despawn_recursive();
+
+            let (.., material, light, name, group, beta, spin, color, texture) =
+                bodies[body.heaviest];
+            let radius = body.radius_cubed.cbrt();
+            let mut entity = commands.spawn_bundle(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Icosphere {
+                    radius,
+                    subdivisions: 5,
+                })),
+                material: material.clone(),
+                ..Default::default()
+            });
+            let mut bundle = BodyBundle::new(body.mass, body.pos, body.vel).with_radius(radius);
+            if let Some(&BodyColor(Some(color))) = color {
+                bundle = bundle.with_color(color);
+            }
+            entity.insert_bundle(bundle);
+            if let Some(light) = light {
+                entity.insert(Light {
+                    color: light.color,
+                    fov: light.fov,
+                    intensity: light.intensity,
+                    range: light.range,
+                    depth: light.depth.clone(),
+                });
+            }
+            if let Some(name) = name {
+                entity.insert(name.clone());
+            }
+            // Test particles never merge, but the body is turned into one again by
+            // `exert_no_gravity` if its group is one of the `ParticleGroups`
+            if let Some(group) = group {
+                entity.insert(group.clone());
+            }
+            if let Some(true_radius_cubed) = body.true_radius_cubed {
+                entity.insert(TrueRadius(true_radius_cubed.cbrt()));
+            }
+            if let Some(&beta) = beta {
+                entity.insert(beta);
+            }
+            if let Some(&spin) = spin {
+                entity.insert(spin);
+            }
+            // Mapped again onto the new sphere once loaded
+            if let Some(texture) = texture {
+                entity.insert(texture.clone());
             }
         }
-        bodies.push((mass, transform, acc));
     }
+}
+
+/// Two bodies that collided, unless [`Collisions::Off`]: when `merged`, `a` and `b` are
+/// despawned by the time it is read, replaced with a body of their mass, and otherwise they
+/// bounced off each other
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub merged: bool,
+    /// Kinetic energy of the motion of the bodies relative to their barycenter at the impact,
+    /// `μ v^2 / 2` with the reduced mass `μ` and their relative speed `v`
+    pub energy: f32,
+}
 
-    // Newton's second law of motion: `F = ma => a = F/m`
-    for (mass, _, acc) in bodies.iter_mut() {
-        acc.0 /= mass.0;
+fn impact_energy((mass_a, vel_a): (f32, Vec3), (mass_b, vel_b): (f32, Vec3)) -> f32 {
+    let total = mass_a + mass_b;
+    if total <= 0.0 {
+        return 0.0;
     }
+    mass_a * mass_b / total * vel_a.distance_squared(vel_b) / 2.0
 }
 
-fn update_velocity(mut query: Query<(&mut Velocity, &Acceleration)>) {
-    for (mut vel, acc) in query.iter_mut() {
-        vel.0 += acc.0 * DT;
+/// Velocities of two bodies after an impact along `normal`, the unit vector from `a` to
+/// `b`, `None` if they are already moving apart. The impulse is along the normal only, the
+/// tangential velocities are unchanged.
+fn bounce(
+    (mass_a, vel_a): (f32, Vec3),
+    (mass_b, vel_b): (f32, Vec3),
+    normal: Vec3,
+    restitution: f32,
+) -> Option<(Vec3, Vec3)> {
+    let approach = (vel_a - vel_b).dot(normal);
+    if approach <= 0.0 {
+        return None;
     }
+    // Reduced mass: the impulse reverses the approach speed, scaled by the restitution
+    let impulse = (1.0 + restitution) * approach * mass_a * mass_b / (mass_a + mass_b);
+    Some((
+        vel_a - normal * impulse / mass_a,
+        vel_b + normal * impulse / mass_b,
+    ))
 }
 
-fn movement(mut query: Query<(&mut Transform, &Velocity)>) {
-    for (mut transform, vel) in query.iter_mut() {
-        transform.translation += vel.0 * DT;
+/// Bounce bodies whose spheres overlap and that are still approaching each other, then
+/// push them apart until they only touch, moving the lighter one more so that the
+/// barycenter stays put. Massless bodies cannot bounce and pass through.
+fn bounce_collisions(
+    collisions: Res<Collisions>,
+    restitution: Res<Restitution>,
+    mut count: ResMut<CollisionCount>,
+    mut events: EventWriter<CollisionEvent>,
+    mut query: Query<(Entity, &Mass, &Radius, &mut Position, &mut Velocity), Without<TestParticle>>,
+) {
+    if *collisions != Collisions::Bounce {
+        return;
+    }
+
+    let mut bodies: Vec<_> = query.iter_mut().collect();
+    for i in 0..bodies.len() {
+        let (head, tail) = bodies.split_at_mut(i + 1);
+        let (entity, mass, radius, pos, vel) = &mut head[i];
+        for (other, other_mass, other_radius, other_pos, other_vel) in tail.iter_mut() {
+            let offset = other_pos.0 - pos.0;
+            let overlap = radius.0 + other_radius.0 - offset.length();
+            if overlap <= 0.0 || mass.0 <= 0.0 || other_mass.0 <= 0.0 {
+                continue;
+            }
+            // Concentric bodies have no line of centers, pick one
+            let normal = offset.try_normalize().unwrap_or(Vec3::X);
+            let energy = impact_energy((mass.0, vel.0), (other_mass.0, other_vel.0));
+            if let Some((v, other_v)) = bounce(
+                (mass.0, vel.0),
+                (other_mass.0, other_vel.0),
+                normal,
+                restitution.0,
+            ) {
+                vel.0 = v;
+                other_vel.0 = other_v;
+                count.0 += 1;
+                events.send(CollisionEvent {
+                    a: *entity,
+                    b: *other,
+                    merged: false,
+                    energy,
+                });
+            }
+            let total = mass.0 + other_mass.0;
+            pos.0 -= normal * overlap * other_mass.0 / total;
+            other_pos.0 += normal * overlap * mass.0 / total;
+        }
     }
 }
@@ -0,0 +1,136 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use std::f64::consts::TAU;
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut, to_local};
+use super::nbody::{FrameRotation, Gravity, Mass, PhysicsSystem, Position, TestParticle, Velocity};
+use crate::kepler::{dominant_attractor, orbital_elements};
+
+/// Draws the osculating orbit of every body around its dominant attractor: the two-body
+/// ellipse, or hyperbola branch, that it would follow if the other bodies disappeared
+pub struct OrbitsPlugin {
+    /// Initial value of [`ShowOrbits`]
+    pub enabled: bool,
+}
+
+impl Plugin for OrbitsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ShowOrbits(self.enabled))
+            .add_system(add_orbits.system())
+            .add_system(remove_orbits.system())
+            .add_system(update_orbits.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+/// Orbits are drawn only while this is set
+pub struct ShowOrbits(pub bool);
+
+/// Points sampled along each orbit
+const SAMPLES: usize = 128;
+/// Hyperbolas are cut before their asymptotes, at this many times the current distance
+const MAX_HYPERBOLA_DISTANCE: f32 = 10.0;
+
+/// Line strip drawing the orbit of a body, as a child entity
+struct OrbitPath {
+    mesh: Handle<Mesh>,
+    line: Entity,
+    color: Color,
+}
+
+/// Test particles are left out, there can be too many of them
+#[allow(clippy::type_complexity)]
+fn add_orbits(
+    mut commands: Commands,
+    show: Res<ShowOrbits>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    query: Query<
+        (Entity, Option<&Handle<StandardMaterial>>),
+        (With<Mass>, Without<OrbitPath>, Without<TestParticle>),
+    >,
+) {
+    if !show.0 {
+        return;
+    }
+    for (entity, material) in query.iter() {
+        let color = material
+            .and_then(|material| materials.get(material))
+            .map_or(Color::WHITE, |material| material.base_color);
+        // One more vertex to close the ellipses
+        let mesh = meshes.add(empty_mesh(PrimitiveTopology::LineStrip, SAMPLES + 1));
+        let line = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+        commands
+            .entity(entity)
+            .push_children(&[line])
+            .insert(OrbitPath { mesh, line, color });
+    }
+}
+
+/// Drop the orbits once they are hidden
+fn remove_orbits(
+    mut commands: Commands,
+    show: Res<ShowOrbits>,
+    query: Query<(Entity, &OrbitPath)>,
+) {
+    if show.0 {
+        return;
+    }
+    for (entity, orbit) in query.iter() {
+        commands.entity(orbit.line).despawn_recursive();
+        commands.entity(entity).remove::<OrbitPath>();
+    }
+}
+
+/// Recompute the elements of every orbit from the current state and sample them, in the
+/// local space of the body. The attractor is the heavier body pulling the hardest, `m / r^2`,
+/// so that moons orbit their planet and the heaviest body has no orbit. The orbits are
+/// inertial, turned like the [`FrameRotation`] at the current time.
+#[allow(clippy::type_complexity)]
+fn update_orbits(
+    gravity: Res<Gravity>,
+    rotation: Res<FrameRotation>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    orbits: Query<(&Mass, &Position, &Velocity, &Transform, &OrbitPath)>,
+    attractors: Query<(Entity, &Mass, &Position, &Velocity, &Transform), Without<TestParticle>>,
+) {
+    for (mass, pos, vel, transform, orbit) in orbits.iter() {
+        let attractor = dominant_attractor(
+            mass.0,
+            pos.0,
+            attractors.iter().map(|body| (body.1 .0, body.2 .0, body)),
+        );
+        let mesh = match meshes.get_mut(&orbit.mesh) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        let [r, g, b, _] = orbit.color.as_rgba_f32();
+        let (_, center_mass, center_pos, center_vel, center_transform) = match attractor {
+            Some(attractor) => attractor,
+            None => {
+                colors_mut(mesh)
+                    .iter_mut()
+                    .for_each(|c| *c = [r, g, b, 0.0]);
+                continue;
+            }
+        };
+
+        let rel = pos.0 - center_pos.0;
+        let mu = gravity.0 * (mass.0 + center_mass.0);
+        let elements = orbital_elements(rel, vel.0 - center_vel.0, mu);
+        // A whole turn, or the part of the hyperbola branch around the periapsis
+        let max_distance = (MAX_HYPERBOLA_DISTANCE * rel.length()) as f64;
+        let (start, span) = match elements.max_true_anomaly(max_distance) {
+            None => (0.0, TAU),
+            Some(max) => (-max, 2.0 * max),
+        };
+
+        let origin = center_transform.translation - transform.translation;
+        for (i, vertex) in positions_mut(mesh).iter_mut().enumerate() {
+            let nu = start + span * i as f64 / SAMPLES as f64;
+            let pos = origin + rotation.0 * elements.position_at(nu);
+            *vertex = to_local(transform, pos).into();
+        }
+        for color in colors_mut(mesh).iter_mut() {
+            *color = [r, g, b, 0.5];
+        }
+    }
+}
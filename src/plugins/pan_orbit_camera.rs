@@ -1,15 +1,208 @@
 use bevy::input::mouse::{MouseMotion, MouseWheel};
 use bevy::prelude::*;
-use bevy::render::camera::PerspectiveProjection;
+use bevy::render::{
+    camera::{Camera, CameraProjection, PerspectiveProjection},
+    RenderSystem,
+};
+use std::str::FromStr;
 
-pub struct PanOrbitCameraPlugin;
+use super::nbody::{FrameTime, Mass, PhysicsSystem, Radius, SpawnIndex};
+use super::restart::reset_resource;
+
+pub struct PanOrbitCameraPlugin {
+    /// Factor of the orbit, pan and zoom of the touch gestures, a finger dragging as far as
+    /// the mouse at 1
+    pub touch_sensitivity: f32,
+    /// Initial distance of the camera from its focus, instead of the one of the startup
+    pub distance: Option<f32>,
+    /// Initial focus of the camera, instead of the one of the startup
+    pub target: Option<CameraFocus>,
+    /// How slowly the camera eases towards where the controls move it, from 0, instantly,
+    /// to 1, see [`CameraSmoothing`]
+    pub smoothing: f32,
+    /// Initial projection, switched with P
+    pub projection: Projection,
+}
+
+impl Default for PanOrbitCameraPlugin {
+    fn default() -> Self {
+        Self {
+            touch_sensitivity: 1.0,
+            distance: None,
+            target: None,
+            smoothing: 0.3,
+            projection: Projection::Perspective,
+        }
+    }
+}
 
 impl Plugin for PanOrbitCameraPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(pan_orbit_camera.system());
+        app.insert_resource(TouchSensitivity(self.touch_sensitivity))
+            .insert_resource(CameraStart {
+                distance: self.distance,
+                target: self.target.clone(),
+            })
+            .insert_resource(CameraSmoothing(self.smoothing.clamp(0.0, 1.0)))
+            .insert_resource(self.projection)
+            .init_resource::<CameraTarget>()
+            .init_resource::<Follow>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<CameraTarget>.system(),
+            )
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<Follow>.system(),
+            )
+            .add_system(restore_goal.system().label(CameraSystem::Restore))
+            .add_system(
+                place_camera
+                    .system()
+                    .label(CameraSystem::Place)
+                    .after(CameraSystem::Restore)
+                    .after(PhysicsSystem::Interpolate),
+            )
+            .add_system(save_home.system().after(CameraSystem::Place))
+            .add_system(
+                reset_camera
+                    .system()
+                    .after(CameraSystem::Restore)
+                    .before(CameraSystem::PanOrbit),
+            )
+            .add_system(
+                view_presets
+                    .system()
+                    .after(CameraSystem::Restore)
+                    .before(CameraSystem::PanOrbit),
+            )
+            .add_system(
+                fit_all
+                    .system()
+                    .after(CameraSystem::Restore)
+                    .before(CameraSystem::PanOrbit)
+                    .after(PhysicsSystem::Interpolate),
+            )
+            .add_system(
+                pan_orbit_camera
+                    .system()
+                    .label(CameraSystem::PanOrbit)
+                    .after(CameraSystem::Restore),
+            )
+            .add_system(select_target.system().before(CameraSystem::Track))
+            .add_system(
+                follow_target
+                    .system()
+                    .label(CameraSystem::Track)
+                    .after(CameraSystem::PanOrbit)
+                    .after(PhysicsSystem::Interpolate),
+            )
+            .add_system(
+                smooth_camera
+                    .system()
+                    .label(CameraSystem::Ease)
+                    .after(CameraSystem::Track)
+                    .after(CameraSystem::Place),
+            )
+            .add_system(toggle_projection.system())
+            .add_system(
+                project_camera
+                    .system()
+                    .label(CameraSystem::Follow)
+                    .after(CameraSystem::Ease),
+            )
+            // Again once Bevy has recomputed the perspective matrix of a resized window
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                project_camera.system().after(RenderSystem::VisibleEntities),
+            );
     }
 }
 
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum CameraSystem {
+    /// Puts the camera back where the controls left it, before it was eased
+    Restore,
+    Place,
+    PanOrbit,
+    /// Moves the focus along with the target
+    Track,
+    /// Eases the camera towards where the controls moved it
+    Ease,
+    /// The camera is where and how it is drawn from for the frame
+    Follow,
+}
+
+struct TouchSensitivity(f32);
+
+/// What the camera looks at when it is spawned
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraFocus {
+    Origin,
+    Barycenter,
+    /// A body, by name
+    Body(String),
+}
+
+impl FromStr for CameraFocus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "origin" => Ok(CameraFocus::Origin),
+            "barycenter" => Ok(CameraFocus::Barycenter),
+            "" => Err("expected a body name, `origin` or `barycenter`".to_string()),
+            name => Ok(CameraFocus::Body(name.to_string())),
+        }
+    }
+}
+
+/// How the camera projects the scene onto the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,
+    /// Along parallel lines, without perspective: distances in the planes facing the camera
+    /// keep their ratios, e.g. between the orbits seen from above
+    Orthographic,
+}
+
+impl FromStr for Projection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "perspective" => Ok(Projection::Perspective),
+            "ortho" | "orthographic" => Ok(Projection::Orthographic),
+            _ => Err(format!("unknown projection `{}`", s)),
+        }
+    }
+}
+
+/// Time constant of the easing of the camera, in seconds, at a [`CameraSmoothing`] of 1
+const MAX_SMOOTHING_TIME: f32 = 0.5;
+
+/// Between 0 and 1, the fraction of `MAX_SMOOTHING_TIME` it takes the camera to cover
+/// two thirds of the way to where the controls move it
+pub struct CameraSmoothing(pub f32);
+
+/// Where the controls move the camera, and what it is drawn from: the systems moving the
+/// camera work on the goal, swapped into its `Transform` in the meantime
+struct CameraEasing {
+    goal: Transform,
+    focus: Vec3,
+    radius: f32,
+    rotation: Quat,
+}
+
+struct CameraStart {
+    distance: Option<f32>,
+    target: Option<CameraFocus>,
+}
+
+/// Body whose position the focus of the camera follows
+#[derive(Default)]
+pub struct CameraTarget(pub Option<Entity>);
+
 /// SOURCE: https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
 /// Tags an entity as capable of panning and orbiting.
 pub struct PanOrbitCamera {
@@ -29,14 +222,300 @@ impl Default for PanOrbitCamera {
     }
 }
 
+/// Initial state of a camera, restored with C
+struct CameraHome {
+    focus: Vec3,
+    radius: f32,
+    transform: Transform,
+}
+
+/// Overrides the radius and focus the startup spawned the camera with. Bodies spawned after
+/// the camera, e.g. in [`StartupStage::PostStartup`], are there by the first frame, and
+/// their transforms are in the rendered frame once interpolated.
+fn place_camera(
+    start: Res<CameraStart>,
+    mut target: ResMut<CameraTarget>,
+    bodies: Query<(Entity, Option<&Name>, &Mass, &Transform), Without<PanOrbitCamera>>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform), Added<PanOrbitCamera>>,
+) {
+    for (mut pan_orbit, mut transform) in cameras.iter_mut() {
+        if let Some(distance) = start.distance {
+            pan_orbit.radius = distance.max(0.05);
+        }
+        match &start.target {
+            None => {}
+            Some(CameraFocus::Origin) => {
+                target.0 = None;
+                pan_orbit.focus = Vec3::ZERO;
+            }
+            Some(CameraFocus::Barycenter) => {
+                target.0 = None;
+                let (moment, mass) = bodies
+                    .iter()
+                    .fold((Vec3::ZERO, 0.0), |(moment, mass), (_, _, m, body)| {
+                        (moment + body.translation * m.0, mass + m.0)
+                    });
+                if mass > 0.0 {
+                    pan_orbit.focus = moment / mass;
+                }
+            }
+            Some(CameraFocus::Body(name)) => {
+                let found = bodies
+                    .iter()
+                    .find(|(_, body, ..)| body.is_some_and(|body| body.as_str() == name));
+                match found {
+                    // Followed from there on
+                    Some((entity, _, _, body)) => {
+                        target.0 = Some(entity);
+                        pan_orbit.focus = body.translation;
+                    }
+                    None => warn!("no body named `{}` for the camera to follow", name),
+                }
+            }
+        }
+        let rot_matrix = Mat3::from_quat(transform.rotation);
+        transform.translation =
+            pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+    }
+}
+
+fn restore_goal(mut cameras: Query<(&mut Transform, &CameraEasing)>) {
+    for (mut transform, easing) in cameras.iter_mut() {
+        *transform = easing.goal;
+    }
+}
+
+/// Ease the focus, the radius and the orientation towards the goal, rather than the
+/// position, so that the camera keeps orbiting around the focus on its way. The radius is
+/// eased logarithmically since it spans orders of magnitude. A followed focus is not
+/// eased, it would lag behind its body, and the switch between targets already glides.
+fn smooth_camera(
+    mut commands: Commands,
+    frame: Res<FrameTime>,
+    smoothing: Res<CameraSmoothing>,
+    target: Res<CameraTarget>,
+    follow: Res<Follow>,
+    mut cameras: Query<(
+        Entity,
+        &PanOrbitCamera,
+        &mut Transform,
+        Option<&mut CameraEasing>,
+    )>,
+) {
+    let time_constant = smoothing.0 * MAX_SMOOTHING_TIME;
+    let t = if time_constant > 0.0 {
+        1.0 - (-frame.0 as f32 / time_constant).exp()
+    } else {
+        1.0
+    };
+    let following = target.0.is_some() || follow.to_barycenter;
+    for (entity, pan_orbit, mut transform, easing) in cameras.iter_mut() {
+        let mut easing = match easing {
+            Some(easing) => easing,
+            None => {
+                commands.entity(entity).insert(CameraEasing {
+                    goal: *transform,
+                    focus: pan_orbit.focus,
+                    radius: pan_orbit.radius,
+                    rotation: transform.rotation,
+                });
+                continue;
+            }
+        };
+        easing.goal = *transform;
+        easing.focus = if following {
+            pan_orbit.focus
+        } else {
+            easing.focus.lerp(pan_orbit.focus, t)
+        };
+        easing.radius =
+            (easing.radius.ln() + (pan_orbit.radius.ln() - easing.radius.ln()) * t).exp();
+        easing.rotation = easing.rotation.slerp(transform.rotation, t);
+
+        transform.rotation = easing.rotation;
+        transform.translation = easing.focus
+            + Mat3::from_quat(easing.rotation).mul_vec3(Vec3::new(0.0, 0.0, easing.radius));
+    }
+}
+
+fn toggle_projection(keys: Res<Input<KeyCode>>, mut projection: ResMut<Projection>) {
+    if keys.just_pressed(KeyCode::P) {
+        *projection = match *projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        };
+    }
+}
+
+/// The camera keeps its [`PerspectiveProjection`] under an orthographic projection, whose
+/// field of view the controls go by, and only the matrix is replaced. The orthographic view
+/// is as high as the perspective one at the focus, so that switching keeps the framing
+/// there, and zooming scales it with the radius, moving the camera no longer changing the
+/// picture. It is as deep as the perspective one, centered on the focus rather than
+/// starting at the camera, for the bodies behind it to be drawn too.
+fn project_camera(
+    projection: Res<Projection>,
+    mut cameras: Query<(
+        &mut Camera,
+        &PanOrbitCamera,
+        &PerspectiveProjection,
+        Option<&CameraEasing>,
+    )>,
+) {
+    for (mut camera, pan_orbit, perspective, easing) in cameras.iter_mut() {
+        camera.projection_matrix = match *projection {
+            Projection::Perspective => perspective.get_projection_matrix(),
+            Projection::Orthographic => {
+                let radius = easing.map_or(pan_orbit.radius, |easing| easing.radius);
+                let half_height = radius * (perspective.fov / 2.0).tan();
+                let half_width = half_height * perspective.aspect_ratio;
+                Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    radius - perspective.far,
+                    radius + perspective.far,
+                )
+            }
+        };
+    }
+}
+
+fn save_home(
+    mut commands: Commands,
+    query: Query<(Entity, &PanOrbitCamera, &Transform), Added<PanOrbitCamera>>,
+) {
+    for (entity, pan_orbit, transform) in query.iter() {
+        commands.entity(entity).insert(CameraHome {
+            focus: pan_orbit.focus,
+            radius: pan_orbit.radius,
+            transform: *transform,
+        });
+    }
+}
+
+fn reset_camera(
+    keys: Res<Input<KeyCode>>,
+    mut target: ResMut<CameraTarget>,
+    mut follow: ResMut<Follow>,
+    mut query: Query<(&mut PanOrbitCamera, &mut Transform, &CameraHome)>,
+) {
+    if !keys.just_pressed(KeyCode::C) {
+        return;
+    }
+    target.0 = None;
+    follow.to_barycenter = false;
+    for (mut pan_orbit, mut transform, home) in query.iter_mut() {
+        pan_orbit.focus = home.focus;
+        pan_orbit.radius = home.radius;
+        *transform = home.transform;
+    }
+}
+
+/// T looks straight down the Z axis onto the XY plane, the ecliptic in the solar system,
+/// and E looks at it edge-on, along the Y axis with Z up. The radius and focus are kept, so
+/// that a followed body stays followed.
+fn view_presets(
+    keys: Res<Input<KeyCode>>,
+    mut query: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    // The camera looks down its local -Z axis, from `radius` along its local +Z
+    let rotation = if keys.just_pressed(KeyCode::T) {
+        Quat::IDENTITY
+    } else if keys.just_pressed(KeyCode::E) {
+        Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)
+    } else {
+        return;
+    };
+    for (mut pan_orbit, mut transform) in query.iter_mut() {
+        pan_orbit.upside_down = false;
+        transform.rotation = rotation;
+        let rot_matrix = Mat3::from_quat(transform.rotation);
+        transform.translation =
+            pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+    }
+}
+
+/// F moves the focus to the center of a sphere bounding all the bodies, and backs the
+/// camera away until the sphere fits in the narrower of the two fields of view. The camera
+/// stops following its target and keeps its orientation.
+#[allow(clippy::type_complexity)]
+fn fit_all(
+    keys: Res<Input<KeyCode>>,
+    mut target: ResMut<CameraTarget>,
+    mut follow: ResMut<Follow>,
+    bodies: Query<(&Transform, Option<&Radius>), (With<Mass>, Without<PanOrbitCamera>)>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform, &PerspectiveProjection)>,
+) {
+    // Extra room around the bounding sphere, as a fraction of its radius
+    const MARGIN: f32 = 0.1;
+
+    if !keys.just_pressed(KeyCode::F) {
+        return;
+    }
+    // Centered on the bounding box, then large enough for the farthest body
+    let (min, max) = bodies.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), (transform, _)| {
+            (
+                min.min(transform.translation),
+                max.max(transform.translation),
+            )
+        },
+    );
+    if min.x > max.x {
+        return;
+    }
+    let center = (min + max) / 2.0;
+    let radius = bodies
+        .iter()
+        .map(|(transform, radius)| {
+            let size = radius.map_or(0.0, |r| r.0) * transform.scale.max_element();
+            transform.translation.distance(center) + size
+        })
+        .fold(0.0, f32::max);
+
+    target.0 = None;
+    follow.to_barycenter = false;
+    for (mut pan_orbit, mut transform, projection) in cameras.iter_mut() {
+        // `fov` is vertical, the horizontal one is wider in a landscape window
+        let half_fov = (projection.fov / 2.0)
+            .min(((projection.fov / 2.0).tan() * projection.aspect_ratio).atan());
+        pan_orbit.focus = center;
+        // Far enough for the sphere to be tangent to the edges of the view
+        pan_orbit.radius = f32::max(radius * (1.0 + MARGIN) / half_fov.sin(), 0.05);
+        let rot_matrix = Mat3::from_quat(transform.rotation);
+        transform.translation =
+            pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+    }
+}
+
 /// Pan the camera with middle mouse click, zoom with scroll wheel, orbit with right mouse click.
+/// Shift and right mouse click also pans, for trackpads without a middle button. Panning
+/// moves the focus, stopping to follow the target if there is one.
+/// The keyboard orbits with WASD and zooms with +/-, going through the same code path.
+/// On touch screens, one finger orbits, and two pan and pinch to zoom.
+#[allow(clippy::too_many_arguments)]
 fn pan_orbit_camera(
     windows: Res<Windows>,
+    frame: Res<FrameTime>,
     mut ev_motion: EventReader<MouseMotion>,
     mut ev_scroll: EventReader<MouseWheel>,
     input_mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    touch_sensitivity: Res<TouchSensitivity>,
+    keys: Res<Input<KeyCode>>,
+    mut target: ResMut<CameraTarget>,
+    mut follow: ResMut<Follow>,
     mut query: Query<(&mut PanOrbitCamera, &mut Transform, &PerspectiveProjection)>,
 ) {
+    // Keyboard speeds, per second: a full turn every 4s, and as if scrolling 10 times
+    const KEY_ORBIT_SPEED: f32 = 0.25;
+    const KEY_ZOOM_SPEED: f32 = 10.0;
+    // Scrolls per pixel of change of the distance between two fingers
+    const PINCH_ZOOM_SPEED: f32 = 0.05;
+
     // change input mapping for orbit and panning here
     let orbit_button = MouseButton::Right;
     let pan_button = MouseButton::Middle;
@@ -46,11 +525,13 @@ fn pan_orbit_camera(
     let mut scroll = 0.0;
     let mut orbit_button_changed = false;
 
-    if input_mouse.pressed(orbit_button) {
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    if input_mouse.pressed(orbit_button) && !shift {
         for ev in ev_motion.iter() {
             rotation_move += ev.delta;
         }
-    } else if input_mouse.pressed(pan_button) {
+    } else if input_mouse.pressed(pan_button) || input_mouse.pressed(orbit_button) {
         // Pan only if we're not rotating at the moment
         for ev in ev_motion.iter() {
             pan += ev.delta;
@@ -59,7 +540,56 @@ fn pan_orbit_camera(
     for ev in ev_scroll.iter() {
         scroll += ev.y;
     }
-    if input_mouse.just_released(orbit_button) || input_mouse.just_pressed(orbit_button) {
+
+    // Fingers move like the mouse, in pixels, and go through the same code path
+    let sensitivity = touch_sensitivity.0;
+    let fingers: Vec<_> = touches.iter().collect();
+    match fingers[..] {
+        [finger] => rotation_move += finger.delta() * sensitivity,
+        [a, b] => {
+            // Panning and zooming are exclusive, the larger of the two motions wins
+            let motion = (a.delta() + b.delta()) / 2.0;
+            let previous = a.previous_position().distance(b.previous_position());
+            let pinch = a.position().distance(b.position()) - previous;
+            if pinch.abs() > motion.length() {
+                scroll += pinch * PINCH_ZOOM_SPEED * sensitivity;
+            } else {
+                pan += motion * sensitivity;
+            }
+        }
+        _ => {}
+    }
+
+    // Orbit speed is expressed in window sizes, like the mouse motion
+    let dt = frame.0 as f32;
+    let mut key_orbit = Vec2::ZERO;
+    let pressed = |codes: &[KeyCode]| codes.iter().any(|code| keys.pressed(*code));
+    if pressed(&[KeyCode::A]) {
+        key_orbit.x -= 1.0;
+    }
+    if pressed(&[KeyCode::D]) {
+        key_orbit.x += 1.0;
+    }
+    if pressed(&[KeyCode::W]) {
+        key_orbit.y -= 1.0;
+    }
+    if pressed(&[KeyCode::S]) {
+        key_orbit.y += 1.0;
+    }
+    if key_orbit != Vec2::ZERO {
+        rotation_move += key_orbit * KEY_ORBIT_SPEED * dt * get_primary_window_size(&windows);
+    }
+    if pressed(&[KeyCode::Equals, KeyCode::Plus, KeyCode::NumpadAdd]) {
+        scroll += KEY_ZOOM_SPEED * dt;
+    }
+    if pressed(&[KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        scroll -= KEY_ZOOM_SPEED * dt;
+    }
+    if input_mouse.just_released(orbit_button)
+        || input_mouse.just_pressed(orbit_button)
+        || touches.iter_just_pressed().next().is_some()
+        || touches.iter_just_released().next().is_some()
+    {
         orbit_button_changed = true;
     }
 
@@ -99,6 +629,8 @@ fn pan_orbit_camera(
             // make panning proportional to distance away from focus point
             let translation = (right + up) * pan_orbit.radius;
             pan_orbit.focus += translation;
+            target.0 = None;
+            follow.to_barycenter = false;
         } else if scroll.abs() > 0.0 {
             any = true;
             pan_orbit.radius -= scroll * pan_orbit.radius * 0.05;
@@ -122,3 +654,105 @@ fn get_primary_window_size(windows: &Res<Windows>) -> Vec2 {
     Vec2::new(window.width(), window.height())
 }
 
+/// Keys 1-9 follow the bodies in spawn order, 0 moves the focus back to the barycenter
+fn select_target(
+    keys: Res<Input<KeyCode>>,
+    mut target: ResMut<CameraTarget>,
+    mut follow: ResMut<Follow>,
+    bodies: Query<(Entity, &SpawnIndex), With<Mass>>,
+) {
+    const KEYS: [KeyCode; 10] = [
+        KeyCode::Key0,
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    let index = match KEYS.iter().position(|key| keys.just_pressed(*key)) {
+        Some(index) => index,
+        None => return,
+    };
+
+    if index == 0 {
+        target.0 = None;
+        follow.to_barycenter = true;
+    } else {
+        let mut entities: Vec<_> = bodies.iter().collect();
+        entities.sort_by_key(|(_, spawned)| **spawned);
+        if let Some((entity, _)) = entities.get(index - 1) {
+            target.0 = Some(*entity);
+            follow.to_barycenter = false;
+        }
+    }
+    follow.switched = true;
+}
+
+/// State of the transition between two targets
+#[derive(Default)]
+pub struct Follow {
+    /// Offset of the focus from the target, decaying to zero after a switch
+    offset: Vec3,
+    switched: bool,
+    to_barycenter: bool,
+}
+
+/// Move the focus along with the target, preserving the orbit radius and angles.
+/// When switching, the focus glides from the previous position instead of jumping.
+fn follow_target(
+    frame: Res<FrameTime>,
+    mut target: ResMut<CameraTarget>,
+    mut follow: ResMut<Follow>,
+    bodies: Query<(Entity, &Mass, &Transform), Without<PanOrbitCamera>>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    // Time constant of the transition, in seconds
+    const SMOOTHING: f32 = 0.25;
+
+    let goal = match target.0 {
+        Some(entity) => match bodies.get(entity) {
+            Ok((_, _, transform)) => transform.translation,
+            Err(_) => {
+                // The body is gone, e.g. merged into another one
+                target.0 = None;
+                return;
+            }
+        },
+        None if follow.to_barycenter => {
+            let (moment, mass) =
+                bodies
+                    .iter()
+                    .fold((Vec3::ZERO, 0.0), |(moment, mass), (_, m, transform)| {
+                        (moment + transform.translation * m.0, mass + m.0)
+                    });
+            if mass > 0.0 {
+                moment / mass
+            } else {
+                Vec3::ZERO
+            }
+        }
+        None => return,
+    };
+
+    for (mut pan_orbit, mut transform) in cameras.iter_mut() {
+        if follow.switched {
+            follow.offset = pan_orbit.focus - goal;
+        }
+        follow.offset *= (-frame.0 as f32 / SMOOTHING).exp();
+        pan_orbit.focus = goal + follow.offset;
+
+        let rot_matrix = Mat3::from_quat(transform.rotation);
+        transform.translation =
+            pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+    }
+    follow.switched = false;
+
+    // The barycenter is only a destination, the focus is free again once there
+    if target.0.is_none() && follow.offset.length_squared() < 1e-6 {
+        follow.to_barycenter = false;
+    }
+}
@@ -0,0 +1,497 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+use copernicus::integrator::Integrator;
+
+use super::approach::{ApproachPair, Approaches};
+use super::clock::{format_duration, Epoch};
+use super::hohmann::Transfer;
+use super::labels::ShowLabels;
+use super::nbody::{
+    Gravity, Mass, Position, ReferenceFrame, SimState, Velocity, MAX_SPEED, MIN_SPEED,
+};
+use super::orbits::ShowOrbits;
+use super::picking::Selected;
+use super::spacecraft::SpacecraftOrbit;
+use super::trails::ShowTrails;
+use super::ui::UiFont;
+use super::vectors::ShowVectors;
+use crate::kepler::orbital_elements;
+
+/// Side panel with live controls of the simulation, hidden at first and toggled with Tab.
+/// Its widgets reflect the resources, so keyboard shortcuts and CLI flags stay in sync.
+pub struct ControlPanelPlugin;
+
+impl Plugin for ControlPanelPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<PanelMaterials>()
+            .add_system(toggle_panel.system())
+            .add_system(click_buttons.system().label(PanelSystem::Input))
+            .add_system(drag_speed.system().label(PanelSystem::Input))
+            .add_system(update_buttons.system().after(PanelSystem::Input))
+            .add_system(update_speed.system().after(PanelSystem::Input))
+            .add_system(update_inspector.system());
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+enum PanelSystem {
+    Input,
+}
+
+const SLIDER_WIDTH: f32 = 220.0;
+const FONT_SIZE: f32 = 14.0;
+
+struct PanelMaterials {
+    background: Handle<ColorMaterial>,
+    button: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+    active: Handle<ColorMaterial>,
+    track: Handle<ColorMaterial>,
+    fill: Handle<ColorMaterial>,
+}
+
+impl FromWorld for PanelMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        Self {
+            background: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            button: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
+            hovered: materials.add(Color::rgb(0.35, 0.35, 0.35).into()),
+            active: materials.add(Color::rgb(0.2, 0.4, 0.7).into()),
+            track: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
+            fill: materials.add(Color::rgb(0.2, 0.4, 0.7).into()),
+        }
+    }
+}
+
+/// Root node of the panel
+struct ControlPanel;
+
+#[derive(Clone, Copy)]
+enum PanelButton {
+    Pause,
+    Integrator(Integrator),
+    Trails,
+    Labels,
+    Orbits,
+    Vectors,
+    Frame(Frame),
+}
+
+/// Kinds of [`ReferenceFrame`], the body frame is the one of the selected body
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    Origin,
+    Barycenter,
+    Body,
+}
+
+impl Frame {
+    /// `None` for the rotating frame, which has no button and is only set from the command
+    /// line
+    fn of(frame: &ReferenceFrame) -> Option<Self> {
+        match frame {
+            ReferenceFrame::Origin => Some(Frame::Origin),
+            ReferenceFrame::Barycenter => Some(Frame::Barycenter),
+            ReferenceFrame::Body(_) => Some(Frame::Body),
+            ReferenceFrame::Rotating(..) | ReferenceFrame::Corotating(..) => None,
+        }
+    }
+}
+
+struct SpeedSlider;
+struct SpeedFill;
+struct SpeedText;
+struct InspectorText;
+
+fn toggle_panel(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    font: Res<UiFont>,
+    materials: Res<PanelMaterials>,
+    query: Query<Entity, With<ControlPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    match query.iter().next() {
+        Some(panel) => commands.entity(panel).despawn_recursive(),
+        None => spawn_panel(&mut commands, &font, &materials),
+    }
+}
+
+fn text(value: &str, font: &UiFont) -> TextBundle {
+    TextBundle {
+        style: Style {
+            margin: Rect::all(Val::Px(4.0)),
+            ..Default::default()
+        },
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.0.clone(),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            ..Default::default()
+        },
+        visible: Visible {
+            is_visible: false,
+            is_transparent: true,
+        },
+        ..Default::default()
+    }
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    font: &UiFont,
+    materials: &PanelMaterials,
+    label: &str,
+    button: PanelButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.button.clone(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn_bundle(text(label, font));
+        });
+}
+
+fn spawn_panel(commands: &mut Commands, font: &UiFont, materials: &PanelMaterials) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                // The y axis points up, reverse it to lay out from the top
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                padding: Rect::all(Val::Px(6.0)),
+                ..Default::default()
+            },
+            material: materials.background.clone(),
+            ..Default::default()
+        })
+        .insert(ControlPanel)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("Speed", font)).insert(SpeedText);
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(SLIDER_WIDTH), Val::Px(12.0)),
+                        margin: Rect::all(Val::Px(4.0)),
+                        ..Default::default()
+                    },
+                    material: materials.track.clone(),
+                    ..Default::default()
+                })
+                .insert(SpeedSlider)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            material: materials.fill.clone(),
+                            ..Default::default()
+                        })
+                        .insert(SpeedFill)
+                        // Let the clicks through to the slider
+                        .insert(FocusPolicy::Pass);
+                });
+            parent.spawn_bundle(row()).with_children(|parent| {
+                spawn_button(parent, font, materials, "Pause", PanelButton::Pause);
+            });
+
+            parent.spawn_bundle(text("Integrator", font));
+            parent.spawn_bundle(row()).with_children(|parent| {
+                for &(label, integrator) in &[
+                    ("Euler", Integrator::Euler),
+                    ("RK4", Integrator::Rk4),
+                    ("Leapfrog", Integrator::Leapfrog),
+                ] {
+                    let button = PanelButton::Integrator(integrator);
+                    spawn_button(parent, font, materials, label, button);
+                }
+            });
+
+            parent.spawn_bundle(text("Overlays", font));
+            parent.spawn_bundle(row()).with_children(|parent| {
+                spawn_button(parent, font, materials, "Trails", PanelButton::Trails);
+                spawn_button(parent, font, materials, "Labels", PanelButton::Labels);
+                spawn_button(parent, font, materials, "Orbits", PanelButton::Orbits);
+                spawn_button(parent, font, materials, "Vectors", PanelButton::Vectors);
+            });
+
+            parent.spawn_bundle(text("Frame", font));
+            parent.spawn_bundle(row()).with_children(|parent| {
+                for &(label, frame) in &[
+                    ("Origin", Frame::Origin),
+                    ("Barycenter", Frame::Barycenter),
+                    ("Selected", Frame::Body),
+                ] {
+                    spawn_button(parent, font, materials, label, PanelButton::Frame(frame));
+                }
+            });
+
+            parent.spawn_bundle(text("Selected body", font));
+            parent.spawn_bundle(text("", font)).insert(InspectorText);
+        })
+        // Clicks on the background of the panel should not deselect the body
+        .insert(Interaction::default());
+}
+
+/// The frame of the selected body is only available for named bodies, since the
+/// [`ReferenceFrame`] refers to them by name
+#[allow(clippy::too_many_arguments)]
+fn click_buttons(
+    mut state: ResMut<SimState>,
+    mut integrator: ResMut<Integrator>,
+    mut trails: ResMut<ShowTrails>,
+    mut labels: ResMut<ShowLabels>,
+    mut orbits: ResMut<ShowOrbits>,
+    mut vectors: ResMut<ShowVectors>,
+    mut frame: ResMut<ReferenceFrame>,
+    selected: Res<Selected>,
+    names: Query<&Name>,
+    query: Query<(&PanelButton, &Interaction), Changed<Interaction>>,
+) {
+    for (button, interaction) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            PanelButton::Pause => state.paused = !state.paused,
+            PanelButton::Integrator(selected) => *integrator = selected,
+            PanelButton::Trails => trails.0 = !trails.0,
+            PanelButton::Labels => labels.0 = !labels.0,
+            PanelButton::Orbits => orbits.0 = !orbits.0,
+            PanelButton::Vectors => vectors.0 = !vectors.0,
+            PanelButton::Frame(Frame::Origin) => *frame = ReferenceFrame::Origin,
+            PanelButton::Frame(Frame::Barycenter) => *frame = ReferenceFrame::Barycenter,
+            PanelButton::Frame(Frame::Body) => {
+                let name = selected.0.and_then(|entity| names.get(entity).ok());
+                if let Some(name) = name {
+                    *frame = ReferenceFrame::Body(name.as_str().to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Highlight the buttons of the active settings
+#[allow(clippy::too_many_arguments)]
+fn update_buttons(
+    materials: Res<PanelMaterials>,
+    state: Res<SimState>,
+    integrator: Res<Integrator>,
+    trails: Res<ShowTrails>,
+    labels: Res<ShowLabels>,
+    orbits: Res<ShowOrbits>,
+    vectors: Res<ShowVectors>,
+    frame: Res<ReferenceFrame>,
+    mut query: Query<(&PanelButton, &Interaction, &mut Handle<ColorMaterial>)>,
+) {
+    for (button, interaction, mut material) in query.iter_mut() {
+        let active = match *button {
+            PanelButton::Pause => state.paused,
+            PanelButton::Integrator(selected) => *integrator == selected,
+            PanelButton::Trails => trails.0,
+            PanelButton::Labels => labels.0,
+            PanelButton::Orbits => orbits.0,
+            PanelButton::Vectors => vectors.0,
+            PanelButton::Frame(selected) => Frame::of(&frame) == Some(selected),
+        };
+        let expected = if active {
+            &materials.active
+        } else if *interaction == Interaction::Hovered {
+            &materials.hovered
+        } else {
+            &materials.button
+        };
+        if *material != *expected {
+            *material = expected.clone();
+        }
+    }
+}
+
+/// Position of `speed` along the slider, between 0 and 1. The slider is logarithmic, it
+/// covers `MIN_SPEED..MAX_SPEED`.
+fn slider_fraction(speed: f32) -> f32 {
+    ((speed / MIN_SPEED).ln() / (MAX_SPEED / MIN_SPEED).ln()).clamp(0.0, 1.0)
+}
+
+/// Set the speed factor from the cursor position while the slider is held
+fn drag_speed(
+    windows: Res<Windows>,
+    mut state: ResMut<SimState>,
+    query: Query<(&Interaction, &Node, &GlobalTransform), With<SpeedSlider>>,
+) {
+    let cursor = match windows.get_primary().and_then(|w| w.cursor_position()) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    for (interaction, node, transform) in query.iter() {
+        if *interaction != Interaction::Clicked || node.size.x <= 0.0 {
+            continue;
+        }
+        let left = transform.translation.x - node.size.x / 2.0;
+        let fraction = ((cursor.x - left) / node.size.x).clamp(0.0, 1.0);
+        state.speed_factor = MIN_SPEED * (MAX_SPEED / MIN_SPEED).powf(fraction);
+    }
+}
+
+fn update_speed(
+    state: Res<SimState>,
+    mut texts: Query<&mut Text, With<SpeedText>>,
+    mut fills: Query<&mut Style, With<SpeedFill>>,
+) {
+    let value = format!("Speed: {:.2}x", state.speed_factor);
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+    let width = Val::Percent(100.0 * slider_fraction(state.speed_factor));
+    for mut style in fills.iter_mut() {
+        if style.size.width != width {
+            style.size.width = width;
+        }
+    }
+}
+
+/// Stats of the selected body. Distances and orbital elements are relative to the heaviest
+/// of the other bodies, e.g. the Sun, with angles in degrees, and the period in days for
+/// the scenes whose time unit is the day, updated every frame. The last closest approach is
+/// shown for the bodies of the [`ApproachPair`], and the orbit of the spacecraft whatever
+/// the selection, like the planned Hohmann transfer.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_inspector(
+    selected: Res<Selected>,
+    g: Res<Gravity>,
+    epoch: Res<Epoch>,
+    pair: Res<ApproachPair>,
+    approaches: Res<Approaches>,
+    spacecraft: Option<Res<SpacecraftOrbit>>,
+    transfer: Option<Res<Transfer>>,
+    mut texts: Query<&mut Text, With<InspectorText>>,
+    bodies: Query<(Entity, Option<&Name>, &Mass, &Position, &Velocity)>,
+) {
+    let body = selected.0.and_then(|entity| bodies.get(entity).ok());
+    let mut value = match body {
+        None => "Click a body to inspect it".to_string(),
+        Some((entity, name, mass, pos, vel)) => {
+            let name = name.map_or_else(|| format!("Body {}", entity.id()), |n| n.to_string());
+            let mut value = format!(
+                "{}\nMass: {:.4}\nSpeed: {:.4}",
+                name,
+                mass.0,
+                vel.0.length()
+            );
+            let primary = bodies
+                .iter()
+                .filter(|(other, ..)| *other != entity)
+                .max_by(|(_, _, a, ..), (_, _, b, ..)| a.0.partial_cmp(&b.0).unwrap());
+            if let Some((other, primary_name, primary_mass, primary_pos, primary_vel)) = primary {
+                let r = pos.0 - primary_pos.0;
+                let primary_name =
+                    primary_name.map_or_else(|| format!("body {}", other.id()), |n| n.to_string());
+                value += &format!("\nDistance to {}: {:.3}", primary_name, r.length());
+                let mu = g.0 * (mass.0 + primary_mass.0);
+                let elements = orbital_elements(r, vel.0 - primary_vel.0, mu);
+                value += &format!(
+                    "\na: {:.3}  e: {:.4}  i: {:.2}\nΩ: {:.2}  ω: {:.2}  ν: {:.2}",
+                    elements.semi_major_axis,
+                    elements.eccentricity,
+                    elements.inclination.to_degrees(),
+                    elements.longitude_of_ascending_node.to_degrees(),
+                    elements.argument_of_periapsis.to_degrees(),
+                    elements.true_anomaly.to_degrees(),
+                );
+                value += &match elements.period(mu as f64) {
+                    Some(period) => format!("\nPeriod: {}", format_duration(period, &epoch)),
+                    None => "\nPeriod: unbound".to_string(),
+                };
+            }
+            let partner = match pair.0 {
+                Some((a, b)) if a == entity => Some(b),
+                Some((a, b)) if b == entity => Some(a),
+                _ => None,
+            };
+            if let Some(partner) = partner {
+                let partner_name = match bodies.get(partner) {
+                    Ok((_, Some(name), ..)) => name.to_string(),
+                    _ => format!("body {}", partner.id()),
+                };
+                value += &match &approaches.last {
+                    Some(approach) => format!(
+                        "\nClosest approach to {}: {:.4} at t = {:.2}",
+                        partner_name, approach.distance, approach.time
+                    ),
+                    None => format!("\nClosest approach to {}: none yet", partner_name),
+                };
+            }
+            value
+        }
+    };
+    if let Some(orbit) = spacecraft {
+        if let (Some(around), Some(elements)) = (&orbit.around, &orbit.elements) {
+            value += &format!(
+                "\n\nSpacecraft around {}\na: {:.3}  e: {:.4}  i: {:.2}",
+                around,
+                elements.semi_major_axis,
+                elements.eccentricity,
+                elements.inclination.to_degrees(),
+            );
+            value += &match orbit.period {
+                Some(period) => format!("\nPeriod: {:.2} time units", period),
+                None => "\nPeriod: unbound".to_string(),
+            };
+            value += &format!("\nDelta-v spent: {:.4}", orbit.delta_v);
+        }
+    }
+    if let Some(transfer) = transfer {
+        value += &format!(
+            "\n\nHohmann transfer {} to {}\nBurns: {:.5} + {:.5}\nDuration: {:.2} time units\n\
+             Phase: {:.1}° needed, {:.1}° at t = {:.2}",
+            transfer.from,
+            transfer.to,
+            transfer.departure_burn,
+            transfer.arrival_burn,
+            transfer.duration,
+            transfer.phase,
+            transfer.current_phase,
+            transfer.start,
+        );
+    }
+
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+
+use super::nbody::Radius;
+use super::pan_orbit_camera::PanOrbitCamera;
+
+/// Select bodies by left-clicking them
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Selected>()
+            .add_system(pick_body.system())
+            .add_system(forget_despawned.system());
+    }
+}
+
+/// Body picked with the mouse
+#[derive(Default)]
+pub struct Selected(pub Option<Entity>);
+
+/// Bodies far away are a few pixels wide at most: they can be picked within this angle
+/// (in radians) from the cursor, seen from the camera, even though the ray misses their
+/// sphere
+const PICK_ANGLE: f32 = 0.01;
+
+/// Ray through the cursor from the near plane, `(origin, direction)`: from the eye of the
+/// camera under a perspective projection, along its view under an orthographic one
+pub fn cursor_ray(camera: &Camera, transform: &Transform, window: &Window) -> Option<(Vec3, Vec3)> {
+    let cursor = window.cursor_position()?;
+    let size = Vec2::new(window.width(), window.height());
+    let ndc = cursor / size * 2.0 - Vec2::ONE;
+    let ndc_to_world = transform.compute_matrix() * camera.projection_matrix.inverse();
+    let near = ndc_to_world.project_point3(ndc.extend(0.0));
+    let far = ndc_to_world.project_point3(ndc.extend(1.0));
+    Some((near, (far - near).normalize()))
+}
+
+/// Distance along the ray to the closest intersection with a sphere
+fn ray_sphere(origin: Vec3, dir: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+    let to_center = center - origin;
+    let along = to_center.dot(dir);
+    let d2 = to_center.length_squared() - along * along;
+    let r2 = radius * radius;
+    if d2 > r2 {
+        return None;
+    }
+    let t = along - (r2 - d2).sqrt();
+    // The eye may be inside the sphere
+    let t = if t > 0.0 { t } else { along + (r2 - d2).sqrt() };
+    if t > 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Nearest body under the cursor, none if it misses them all or there is no camera
+pub fn body_under_cursor(
+    windows: &Windows,
+    cameras: &Query<(&Camera, &Transform), With<PanOrbitCamera>>,
+    bodies: &Query<(Entity, &Transform, &Radius)>,
+) -> Option<Entity> {
+    let (camera, camera_transform) = cameras.iter().next()?;
+    let window = windows.get(camera.window)?;
+    let (origin, dir) = cursor_ray(camera, camera_transform, window)?;
+    bodies
+        .iter()
+        .filter_map(|(entity, transform, radius)| {
+            let center = transform.translation;
+            let radius = radius
+                .0
+                .max(PICK_ANGLE * center.distance(camera_transform.translation));
+            ray_sphere(origin, dir, center, radius).map(|t| (entity, t))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(entity, _)| entity)
+}
+
+/// On a left click, select the nearest body under the cursor, or none if it misses them all.
+/// Clicks on the UI are ignored.
+fn pick_body(
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut selected: ResMut<Selected>,
+    cameras: Query<(&Camera, &Transform), With<PanOrbitCamera>>,
+    bodies: Query<(Entity, &Transform, &Radius)>,
+    ui: Query<&Interaction>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || ui.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    selected.0 = body_under_cursor(&windows, &cameras, &bodies);
+}
+
+/// Deselect bodies that no longer exist, e.g. after a merge
+fn forget_despawned(mut selected: ResMut<Selected>, bodies: Query<(), With<Radius>>) {
+    if let Some(entity) = selected.0 {
+        if bodies.get(entity).is_err() {
+            selected.0 = None;
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use std::str::FromStr;
+
+use super::nbody::{BodyBundle, Gravity, Mass, Position, Velocity};
+use crate::kepler::{state_from_elements, OrbitalElements};
+use crate::scene::{spawn_body, Appearance};
+use crate::AU_TO_UNIT_SCALE;
+
+/// Adds hypothetical planets described by their orbital elements around the heaviest body
+/// of the scene, once the startup systems have spawned it
+pub struct PlanetsPlugin {
+    pub planets: Vec<PlanetDescription>,
+}
+
+impl Plugin for PlanetsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Planets(self.planets.clone()))
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_planets.system());
+    }
+}
+
+struct Planets(Vec<PlanetDescription>);
+
+/// Planet given on the command line as comma-separated `key=value` pairs, e.g.
+/// `a=2.5,e=0.1,i=5,mass=6`. Keys are `a` (semi-major axis in AU), `e`, `i`, `node`
+/// (longitude of the ascending node), `periapsis` (argument of periapsis), `anomaly`
+/// (true anomaly), with angles in degrees, plus `mass` (10^24 kg), `radius` (scene units)
+/// and `name`.
+#[derive(Clone)]
+pub struct PlanetDescription {
+    pub name: Option<String>,
+    pub mass: f32,
+    pub radius: f32,
+    /// With the semi-major axis in AU
+    pub elements: OrbitalElements,
+}
+
+impl FromStr for PlanetDescription {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut planet = PlanetDescription {
+            name: None,
+            mass: 1.0,
+            radius: 0.5,
+            elements: OrbitalElements {
+                semi_major_axis: 0.0,
+                eccentricity: 0.0,
+                inclination: 0.0,
+                longitude_of_ascending_node: 0.0,
+                argument_of_periapsis: 0.0,
+                true_anomaly: 0.0,
+            },
+        };
+        for pair in s.split(',') {
+            let (key, value) = match pair.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("expected `key=value`, got `{}`", pair)),
+            };
+            if key == "name" {
+                planet.name = Some(value.to_string());
+                continue;
+            }
+            let value: f64 = value
+                .parse()
+                .map_err(|_| format!("invalid number `{}` for `{}`", value, key))?;
+            let elements = &mut planet.elements;
+            match key {
+                "a" => elements.semi_major_axis = value,
+                "e" => elements.eccentricity = value,
+                "i" => elements.inclination = value.to_radians(),
+                "node" => elements.longitude_of_ascending_node = value.to_radians(),
+                "periapsis" => elements.argument_of_periapsis = value.to_radians(),
+                "anomaly" => elements.true_anomaly = value.to_radians(),
+                "mass" => planet.mass = value as f32,
+                "radius" => planet.radius = value as f32,
+                _ => return Err(format!("unknown key `{}`", key)),
+            }
+        }
+        if planet.elements.semi_major_axis == 0.0 {
+            return Err("missing semi-major axis `a`".to_string());
+        }
+        if planet.elements.eccentricity == 1.0 {
+            return Err("parabolic orbits are not supported".to_string());
+        }
+        Ok(planet)
+    }
+}
+
+fn spawn_planets(
+    mut commands: Commands,
+    planets: Res<Planets>,
+    g: Res<Gravity>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(&Mass, &Position, &Velocity)>,
+) {
+    let (sun_mass, sun_pos, sun_vel) = match bodies
+        .iter()
+        .max_by(|(a, ..), (b, ..)| a.0.partial_cmp(&b.0).unwrap())
+    {
+        Some((mass, pos, vel)) => (mass.0, pos.0, vel.0),
+        None => {
+            warn!("no body for the planets to orbit");
+            return;
+        }
+    };
+
+    for (i, planet) in planets.0.iter().enumerate() {
+        let mut elements = planet.elements;
+        elements.semi_major_axis *= AU_TO_UNIT_SCALE as f64;
+        let (pos, vel) = state_from_elements(&elements, g.0 * (sun_mass + planet.mass));
+        let appearance = Appearance {
+            radius: planet.radius,
+            color: Color::rgb(0.6, 0.8, 0.7),
+            emissive: Color::BLACK,
+            light: None,
+            rings: None,
+        };
+        let entity = spawn_body(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            BodyBundle::new(planet.mass, sun_pos + pos, sun_vel + vel),
+            &appearance,
+        );
+        let name = planet
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Planet {}", i + 1));
+        commands.entity(entity).insert(Name::new(name));
+    }
+}
@@ -0,0 +1,352 @@
+use bevy::{
+    diagnostic::{Diagnostic, Diagnostics},
+    ecs::component::Component,
+    prelude::*,
+};
+use std::collections::VecDeque;
+
+use super::diagnostics::NBodyDiagnosticsPlugin;
+use super::nbody::{Position, SimState, Velocity};
+use super::picking::Selected;
+use super::ui::UiFont;
+
+/// Plots the drift of the total energy over the simulated time in an inset in the
+/// bottom-left corner, above the HUD, showing how far each integrator strays from the
+/// conserved energy: the leapfrog oscillates around it, Euler and RK4 drift away. The energy
+/// is the one measured by the [`NBodyDiagnosticsPlugin`], which must be added too. Like the
+/// minimap, the plots are drawn with UI nodes, one per point.
+pub struct PlotPlugin {
+    /// Also plot the Poincaré section of the selected body next to the energy
+    pub section: bool,
+}
+
+impl Plugin for PlotPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(EnergyPlot::default())
+            .add_startup_system(spawn_energy_plot.system())
+            .add_system(record_energy.system())
+            .add_system(update_energy_plot.system());
+        if self.section {
+            app.insert_resource(Section::default())
+                .add_startup_system(spawn_section.system())
+                .add_system(record_section.system())
+                .add_system(update_section.system());
+        }
+    }
+}
+
+/// Size of the insets, in pixels
+const WIDTH: f32 = 240.0;
+const HEIGHT: f32 = 120.0;
+const MARGIN: f32 = 10.0;
+/// Bottom of the insets, clear of the HUD
+const BOTTOM: f32 = 90.0;
+const DOT_SIZE: f32 = 2.0;
+const FONT_SIZE: f32 = 12.0;
+/// Points kept in each plot. Past it, the energy plot drops every other sample and samples
+/// half as often, so that it still covers the whole run, and the section drops its oldest
+/// points.
+const HISTORY: usize = 256;
+const ENERGY_COLOR: Color = Color::rgb(0.4, 0.8, 1.0);
+const SECTION_COLOR: Color = Color::rgb(1.0, 0.7, 0.3);
+
+/// Total energy sampled every `every` steps, with the simulated time of each sample
+struct EnergyPlot {
+    samples: VecDeque<(f64, f64)>,
+    every: u64,
+    /// Step of the last sample
+    last: Option<u64>,
+}
+
+impl Default for EnergyPlot {
+    fn default() -> Self {
+        EnergyPlot {
+            samples: VecDeque::with_capacity(HISTORY + 1),
+            every: 1,
+            last: None,
+        }
+    }
+}
+
+/// Crossings of the plane `y = 0` upwards by the selected body, at position `x` and
+/// velocity `vx`
+#[derive(Default)]
+struct Section {
+    body: Option<Entity>,
+    /// Position and velocity of the body at the last frame
+    previous: Option<(Vec3, Vec3)>,
+    points: VecDeque<(f32, f32)>,
+}
+
+/// Point of a plot, by index in its history
+struct EnergyDot(usize);
+
+struct SectionDot(usize);
+
+struct EnergyText;
+
+struct SectionText;
+
+/// Inset at `left`, holding `HISTORY` hidden dots marked with `marker` and a caption
+fn spawn_inset<T: Component>(
+    commands: &mut Commands,
+    materials: &mut Assets<ColorMaterial>,
+    font: &UiFont,
+    left: f32,
+    color: Color,
+    marker: impl Fn(usize) -> T,
+    caption: impl Component,
+) {
+    let dot = materials.add(color.into());
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(left),
+                    bottom: Val::Px(BOTTOM),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(WIDTH), Val::Px(HEIGHT)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for i in 0..HISTORY {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            size: Size::new(Val::Px(DOT_SIZE), Val::Px(DOT_SIZE)),
+                            ..Default::default()
+                        },
+                        material: dot.clone(),
+                        visible: Visible {
+                            is_visible: false,
+                            is_transparent: false,
+                        },
+                        ..Default::default()
+                    })
+                    .insert(marker(i));
+            }
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            left: Val::Px(4.0),
+                            top: Val::Px(2.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: FONT_SIZE,
+                            color: Color::WHITE,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(caption);
+        });
+}
+
+fn spawn_energy_plot(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    font: Res<UiFont>,
+) {
+    spawn_inset(
+        &mut commands,
+        &mut materials,
+        &font,
+        MARGIN,
+        ENERGY_COLOR,
+        EnergyDot,
+        EnergyText,
+    );
+}
+
+fn spawn_section(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    font: Res<UiFont>,
+) {
+    spawn_inset(
+        &mut commands,
+        &mut materials,
+        &font,
+        2.0 * MARGIN + WIDTH,
+        SECTION_COLOR,
+        SectionDot,
+        SectionText,
+    );
+}
+
+/// Sample the energy once per `every` steps, none while paused
+fn record_energy(
+    state: Res<SimState>,
+    diagnostics: Res<Diagnostics>,
+    mut plot: ResMut<EnergyPlot>,
+) {
+    if matches!(plot.last, Some(last) if state.steps < last + plot.every) {
+        return;
+    }
+    let energy = match diagnostics
+        .get(NBodyDiagnosticsPlugin::TOTAL_ENERGY)
+        .and_then(Diagnostic::value)
+    {
+        Some(energy) => energy,
+        None => return,
+    };
+    plot.samples.push_back((state.time, energy));
+    plot.last = Some(state.steps);
+    if plot.samples.len() > HISTORY {
+        // Keep the first sample, the reference of the drift
+        let mut i = 0;
+        plot.samples.retain(|_| {
+            i += 1;
+            i % 2 == 1
+        });
+        plot.every *= 2;
+    }
+}
+
+/// Hide the dots past the points of a plot, and put the others at `(x, y)` in `[0, 1]`
+fn place_dots<'a>(
+    dots: impl Iterator<Item = (usize, Mut<'a, Style>, Mut<'a, Visible>)>,
+    point: impl Fn(usize) -> Option<(f32, f32)>,
+) {
+    for (i, mut style, mut visible) in dots {
+        match point(i) {
+            Some((x, y)) => {
+                visible.is_visible = true;
+                style.position.left = Val::Px(x * (WIDTH - DOT_SIZE));
+                style.position.bottom = Val::Px(y * (HEIGHT - DOT_SIZE));
+            }
+            None => visible.is_visible = false,
+        }
+    }
+}
+
+/// Relative drift `(E - E0) / |E0|` against the time, on a vertical scale fitting the
+/// largest drift so far, centered on zero
+fn update_energy_plot(
+    plot: Res<EnergyPlot>,
+    mut dots: Query<(&EnergyDot, &mut Style, &mut Visible)>,
+    mut text: Query<&mut Text, With<EnergyText>>,
+) {
+    if !plot.is_changed() {
+        return;
+    }
+    let (&(t0, e0), &(t1, _)) = match (plot.samples.front(), plot.samples.back()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return,
+    };
+    let drift = |e: f64| {
+        if e0 == 0.0 {
+            e - e0
+        } else {
+            (e - e0) / e0.abs()
+        }
+    };
+    let scale = plot
+        .samples
+        .iter()
+        .map(|&(_, e)| drift(e).abs())
+        .fold(f64::MIN_POSITIVE, f64::max);
+    let span = (t1 - t0).max(f64::MIN_POSITIVE);
+    place_dots(
+        dots.iter_mut()
+            .map(|(dot, style, visible)| (dot.0, style, visible)),
+        |i| {
+            plot.samples.get(i).map(|&(t, e)| {
+                (
+                    ((t - t0) / span) as f32,
+                    (0.5 + 0.5 * drift(e) / scale) as f32,
+                )
+            })
+        },
+    );
+    if let Ok(mut text) = text.single_mut() {
+        text.sections[0].value = format!("Energy drift ±{:.1e}", scale);
+    }
+}
+
+/// Follow the selected body, starting over when the selection changes. Crossings are
+/// interpolated between the states of two frames.
+fn record_section(
+    selected: Res<Selected>,
+    bodies: Query<(&Position, &Velocity)>,
+    mut section: ResMut<Section>,
+) {
+    if section.body != selected.0 {
+        *section = Section {
+            body: selected.0,
+            ..Default::default()
+        };
+    }
+    let (pos, vel) = match section.body.and_then(|body| bodies.get(body).ok()) {
+        Some((pos, vel)) => (pos.0, vel.0),
+        None => return,
+    };
+    if let Some((p0, v0)) = section.previous {
+        if p0.y < 0.0 && pos.y >= 0.0 {
+            let f = -p0.y / (pos.y - p0.y);
+            let x = p0.x + f * (pos.x - p0.x);
+            let vx = v0.x + f * (vel.x - v0.x);
+            section.points.push_back((x, vx));
+            if section.points.len() > HISTORY {
+                section.points.pop_front();
+            }
+        }
+    }
+    if section.previous != Some((pos, vel)) {
+        section.previous = Some((pos, vel));
+    }
+}
+
+/// Crossings as `(x, vx)`, on scales fitting all of them
+fn update_section(
+    section: Res<Section>,
+    mut dots: Query<(&SectionDot, &mut Style, &mut Visible)>,
+    mut text: Query<&mut Text, With<SectionText>>,
+) {
+    if !section.is_changed() {
+        return;
+    }
+    let bounds = |coordinate: fn(&(f32, f32)) -> f32| {
+        let (min, max) = section
+            .points
+            .iter()
+            .map(coordinate)
+            .fold((f32::MAX, f32::MIN), |(min, max), c| {
+                (min.min(c), max.max(c))
+            });
+        (min, (max - min).max(f32::MIN_POSITIVE))
+    };
+    let (x0, width) = bounds(|p| p.0);
+    let (v0, height) = bounds(|p| p.1);
+    place_dots(
+        dots.iter_mut()
+            .map(|(dot, style, visible)| (dot.0, style, visible)),
+        |i| {
+            section
+                .points
+                .get(i)
+                .map(|&(x, vx)| ((x - x0) / width, (vx - v0) / height))
+        },
+    );
+    if let Ok(mut text) = text.single_mut() {
+        text.sections[0].value = match section.body {
+            Some(_) => format!("Poincaré x, vx at y = 0 ({})", section.points.len()),
+            None => "Select a body for its Poincaré section".to_string(),
+        };
+    }
+}
@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Shared random number generator of the randomized scenarios, so that a run can be
+/// reproduced from its seed alone
+pub struct RandomPlugin {
+    pub seed: u64,
+}
+
+impl Plugin for RandomPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        info!("random seed: {}", self.seed);
+        app.insert_resource(Seed(self.seed))
+            .insert_resource(SimRng(StdRng::seed_from_u64(self.seed)))
+            .add_startup_system_to_stage(StartupStage::PreStartup, reseed.system());
+    }
+}
+
+/// Each run draws the same values, even when the startup systems run again
+fn reseed(seed: Res<Seed>, mut rng: ResMut<SimRng>) {
+    rng.0 = StdRng::seed_from_u64(seed.0);
+}
+
+/// Seed of the [`SimRng`]
+pub struct Seed(pub u64);
+
+/// Generator every randomized spawn draws from. The values depend on the order of the
+/// draws, so the systems using it must be ordered with one another.
+pub struct SimRng(pub StdRng);
@@ -0,0 +1,338 @@
+use bevy::{
+    app::Events,
+    input::{
+        keyboard::KeyboardInput,
+        mouse::{MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel},
+        ElementState, InputSystem,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{LineWriter, Write},
+    path::Path,
+};
+
+use super::nbody::{FrameTime, SimState};
+use super::pan_orbit_camera::PanOrbitCamera;
+
+/// Writes the inputs of each frame to a [`Recorder`], with the time the frame counts for
+/// and where the camera ended up, for the [`ReplayPlugin`] to play them back. Touches are
+/// not recorded.
+pub struct RecordPlugin;
+
+impl Plugin for RecordPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            record_inputs.system().before(InputSystem),
+        )
+        .add_system_to_stage(CoreStage::Last, record.system());
+    }
+}
+
+/// Plays back the inputs of a [`Recording`] over a fresh simulation, in place of those of
+/// the keyboard and the mouse, and with the frame times of the recording. With the seed of
+/// the recording and the same options, the simulation, the camera and the bodies spawned
+/// with the mouse then go exactly as they did, whatever the frame rate of the replay: the
+/// first frame that does not is logged. Once the recording is over, the controls are live
+/// again.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            replay_inputs.system().before(InputSystem),
+        )
+        .add_system_to_stage(CoreStage::Last, check_replay.system());
+    }
+}
+
+/// First line of a recording, before one line per frame
+#[derive(Serialize, Deserialize)]
+struct Header {
+    /// Seed of the [`SimRng`](super::random::SimRng)
+    seed: u64,
+}
+
+/// Inputs of a frame, in the order they came in. Those of no input can be left out,
+/// e.g. in a recording written by hand.
+#[derive(Serialize, Deserialize)]
+struct Frame {
+    /// Seconds since the start of the app
+    time: f64,
+    /// See [`FrameTime`]
+    delta: f64,
+    #[serde(default)]
+    keys: Vec<Key>,
+    #[serde(default)]
+    buttons: Vec<(MouseButton, bool)>,
+    #[serde(default)]
+    motion: Vec<Vec2>,
+    #[serde(default)]
+    wheel: Vec<Wheel>,
+    #[serde(default)]
+    cursor: Option<Vec2>,
+    /// Size of the window, none when headless
+    #[serde(default)]
+    window: Option<Vec2>,
+    /// Physics steps at the end of the frame, and where the camera was
+    steps: u64,
+    #[serde(default)]
+    camera: Option<CameraState>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Key {
+    scan_code: u32,
+    key_code: Option<KeyCode>,
+    pressed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Wheel {
+    /// In lines rather than pixels
+    lines: bool,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct CameraState {
+    focus: Vec3,
+    radius: f32,
+    translation: Vec3,
+    rotation: Quat,
+}
+
+fn element(pressed: bool) -> ElementState {
+    if pressed {
+        ElementState::Pressed
+    } else {
+        ElementState::Released
+    }
+}
+
+fn camera_state(cameras: &Query<(&PanOrbitCamera, &Transform)>) -> Option<CameraState> {
+    cameras
+        .iter()
+        .next()
+        .map(|(pan_orbit, transform)| CameraState {
+            focus: pan_orbit.focus,
+            radius: pan_orbit.radius,
+            translation: transform.translation,
+            rotation: transform.rotation,
+        })
+}
+
+/// File of `--record`, written a line per frame so that it is complete whenever the app
+/// exits
+pub struct Recorder {
+    writer: LineWriter<File>,
+    /// Inputs of the current frame
+    frame: Option<Frame>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>, seed: u64) -> Result<Self, String> {
+        let path = path.as_ref();
+        let error = |e: std::io::Error| format!("cannot write `{}`: {}", path.display(), e);
+        let mut writer = LineWriter::new(File::create(path).map_err(error)?);
+        let header = ron::ser::to_string(&Header { seed }).map_err(|e| e.to_string())?;
+        writeln!(writer, "{}", header).map_err(error)?;
+        Ok(Recorder {
+            writer,
+            frame: None,
+        })
+    }
+}
+
+/// Frames of `--replay`, and the next one to play back
+pub struct Recording {
+    pub seed: u64,
+    frames: Vec<Frame>,
+    next: usize,
+    diverged: bool,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read `{}`: {}", path.display(), e))?;
+        let error = |line: usize, e: ron::Error| {
+            format!(
+                "invalid recording `{}`, line {}: {}",
+                path.display(),
+                line + 1,
+                e
+            )
+        };
+        let mut lines = content.lines();
+        let header: Header =
+            ron::from_str(lines.next().unwrap_or_default()).map_err(|e| error(0, e))?;
+        let frames = lines
+            .enumerate()
+            .map(|(i, line)| ron::from_str(line).map_err(|e| error(i + 1, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Recording {
+            seed: header.seed,
+            frames,
+            next: 0,
+            diverged: false,
+        })
+    }
+}
+
+/// Events are read as soon as they come in, before any system can consume them, e.g. the
+/// scrolls of the [`SandboxPlugin`](super::sandbox::SandboxPlugin)
+#[allow(clippy::too_many_arguments)]
+fn record_inputs(
+    time: Res<Time>,
+    frame_time: Res<FrameTime>,
+    windows: Option<Res<Windows>>,
+    mut recorder: ResMut<Recorder>,
+    mut keys: EventReader<KeyboardInput>,
+    mut buttons: EventReader<MouseButtonInput>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+) {
+    let window = windows.as_ref().and_then(|windows| windows.get_primary());
+    recorder.frame = Some(Frame {
+        time: time.seconds_since_startup(),
+        delta: frame_time.0,
+        keys: keys
+            .iter()
+            .map(|key| Key {
+                scan_code: key.scan_code,
+                key_code: key.key_code,
+                pressed: key.state.is_pressed(),
+            })
+            .collect(),
+        buttons: buttons
+            .iter()
+            .map(|button| (button.button, button.state.is_pressed()))
+            .collect(),
+        motion: motion.iter().map(|motion| motion.delta).collect(),
+        wheel: wheel
+            .iter()
+            .map(|wheel| Wheel {
+                lines: matches!(wheel.unit, MouseScrollUnit::Line),
+                x: wheel.x,
+                y: wheel.y,
+            })
+            .collect(),
+        cursor: window.and_then(Window::cursor_position),
+        window: window.map(|window| Vec2::new(window.width(), window.height())),
+        steps: 0,
+        camera: None,
+    });
+}
+
+/// Complete the frame with where the simulation and the camera ended up
+fn record(
+    state: Res<SimState>,
+    mut recorder: ResMut<Recorder>,
+    cameras: Query<(&PanOrbitCamera, &Transform)>,
+) {
+    let mut frame = match recorder.frame.take() {
+        Some(frame) => frame,
+        None => return,
+    };
+    frame.steps = state.steps;
+    frame.camera = camera_state(&cameras);
+    let written = ron::ser::to_string(&frame)
+        .map_err(|e| e.to_string())
+        .and_then(|line| writeln!(recorder.writer, "{}", line).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        error!("cannot record the frame: {}", e);
+    }
+}
+
+/// The events of the devices are replaced with those of the recording before Bevy updates
+/// the inputs from them, and the window is resized as it was
+fn replay_inputs(
+    recording: Res<Recording>,
+    mut frame_time: ResMut<FrameTime>,
+    mut windows: Option<ResMut<Windows>>,
+    mut keys: ResMut<Events<KeyboardInput>>,
+    mut buttons: ResMut<Events<MouseButtonInput>>,
+    mut motion: ResMut<Events<MouseMotion>>,
+    mut wheel: ResMut<Events<MouseWheel>>,
+) {
+    let frame = match recording.frames.get(recording.next) {
+        Some(frame) => frame,
+        None => return,
+    };
+    frame_time.0 = frame.delta;
+    keys.clear();
+    buttons.clear();
+    motion.clear();
+    wheel.clear();
+    for key in &frame.keys {
+        keys.send(KeyboardInput {
+            scan_code: key.scan_code,
+            key_code: key.key_code,
+            state: element(key.pressed),
+        });
+    }
+    for &(button, pressed) in &frame.buttons {
+        buttons.send(MouseButtonInput {
+            button,
+            state: element(pressed),
+        });
+    }
+    for &delta in &frame.motion {
+        motion.send(MouseMotion { delta });
+    }
+    for scroll in &frame.wheel {
+        wheel.send(MouseWheel {
+            unit: if scroll.lines {
+                MouseScrollUnit::Line
+            } else {
+                MouseScrollUnit::Pixel
+            },
+            x: scroll.x,
+            y: scroll.y,
+        });
+    }
+    if let Some(window) = windows
+        .as_mut()
+        .and_then(|windows| windows.get_primary_mut())
+    {
+        window.update_cursor_position_from_backend(frame.cursor);
+        if let Some(size) = frame.window {
+            if Vec2::new(window.width(), window.height()) != size {
+                window.set_resolution(size.x, size.y);
+            }
+        }
+    }
+}
+
+/// Compare the end of the frame with the recording, and move on to the next frame
+fn check_replay(
+    state: Res<SimState>,
+    mut recording: ResMut<Recording>,
+    cameras: Query<(&PanOrbitCamera, &Transform)>,
+) {
+    let index = recording.next;
+    let frame = match recording.frames.get(index) {
+        Some(frame) => frame,
+        None => return,
+    };
+    let diverged = frame.steps != state.steps || frame.camera != camera_state(&cameras);
+    if diverged && !recording.diverged {
+        warn!(
+            "the replay diverged from the recording at frame {} ({:.2}s): {} steps instead \
+             of {}, or the camera moved elsewhere",
+            index, frame.time, state.steps, frame.steps
+        );
+        recording.diverged = true;
+    }
+    recording.next += 1;
+    if recording.next == recording.frames.len() {
+        info!("end of the replay after {} frames", recording.next);
+    }
+}
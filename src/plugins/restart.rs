@@ -0,0 +1,66 @@
+use bevy::{
+    ecs::{component::Component, schedule::ShouldRun},
+    prelude::*,
+};
+
+/// Restarts the simulation with R, as if launched again with the same options and seed:
+/// every entity is despawned, the camera included, and the startup systems run again. The
+/// settings changed while running, e.g. the speed or the integrator, are kept.
+///
+/// Startup systems thus run more than once: the plugins whose resources hold the state of
+/// a run reset them at startup, e.g. with [`reset_resource`] in
+/// [`StartupStage::PreStartup`].
+pub struct RestartPlugin;
+
+impl Plugin for RestartPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Restart>()
+            .stage(CoreStage::Startup, |schedule: &mut Schedule| {
+                schedule.set_run_criteria(run_startup.system())
+            })
+            .add_system_to_stage(CoreStage::First, despawn_all.exclusive_system())
+            .add_system(request_restart.system());
+    }
+}
+
+#[derive(Default)]
+struct Restart {
+    started: bool,
+    requested: bool,
+}
+
+/// Resets a resource to its default value, for a new run
+pub fn reset_resource<T: Component + Default>(mut resource: ResMut<T>) {
+    *resource = T::default();
+}
+
+fn request_restart(keys: Res<Input<KeyCode>>, mut restart: ResMut<Restart>) {
+    if keys.just_pressed(KeyCode::R) {
+        restart.requested = true;
+    }
+}
+
+/// Before the startup stage, which runs in the same frame
+fn despawn_all(world: &mut World) {
+    if !world
+        .get_resource::<Restart>()
+        .is_some_and(|restart| restart.requested)
+    {
+        return;
+    }
+    let entities: Vec<Entity> = world.query::<Entity>().iter(world).collect();
+    for entity in entities {
+        world.despawn(entity);
+    }
+    info!("restarting the simulation");
+}
+
+/// Replaces the criterion of the startup stage, which otherwise runs once
+fn run_startup(mut restart: ResMut<Restart>) -> ShouldRun {
+    if restart.started && !restart.requested {
+        return ShouldRun::No;
+    }
+    restart.started = true;
+    restart.requested = false;
+    ShouldRun::Yes
+}
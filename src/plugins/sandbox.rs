@@ -0,0 +1,413 @@
+use bevy::{
+    app::Events,
+    input::{mouse::MouseWheel, InputSystem},
+    prelude::*,
+    render::{camera::Camera, pipeline::PrimitiveTopology},
+};
+use std::f64::consts::TAU;
+
+use super::escapes::EjectionEvent;
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut};
+use super::nbody::{
+    BodyBundle, FrameRotation, Gravity, Mass, PhysicsSystem, Position, Radius, SimState,
+    TestParticle, Velocity,
+};
+use super::pan_orbit_camera::PanOrbitCamera;
+use super::picking::{body_under_cursor, cursor_ray};
+use super::restart::reset_resource;
+use crate::kepler::{dominant_attractor, orbital_elements};
+
+/// Spawns bodies with the mouse: a left click and drag on the ecliptic, the XY plane, throws
+/// a body from where the button was pressed, with a velocity along the drag, previewed as
+/// the two-body orbit it would follow around its attractor. Dragging as far as the
+/// attractor is gives the speed of a circular orbit, whatever the units of the scene. A
+/// click that barely moves only selects, as without `--sandbox`.
+///
+/// The mass of the body is set by the [`Brush`], while the button is held: scrolling makes
+/// it heavier or lighter, 1 to 9 pick from a hundred-millionth of the heaviest body to as
+/// heavy as it, and 0 a test particle. The camera neither zooms nor follows meanwhile.
+///
+/// Alt and a left click delete the body under the cursor instead, with its overlays, sending
+/// an [`EjectionEvent`] as it leaves the system. It needs an
+/// [`EscapesPlugin`](super::escapes::EscapesPlugin) for the event.
+pub struct SandboxPlugin;
+
+impl Plugin for SandboxPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Brush>()
+            .init_resource::<Drag>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, reset_resource::<Drag>.system())
+            .add_startup_system(spawn_preview.system())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                adjust_brush.system().after(InputSystem),
+            )
+            .add_system(delete_body.system().before(SandboxSystem::Drag))
+            .add_system(drag_body.system().label(SandboxSystem::Drag))
+            .add_system(
+                preview_orbit
+                    .system()
+                    .after(SandboxSystem::Drag)
+                    .after(PhysicsSystem::Interpolate),
+            );
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+enum SandboxSystem {
+    Drag,
+}
+
+/// Mass of the spawned bodies, `10^exponent` times that of the heaviest body, with its
+/// density, or a massless test particle
+pub struct Brush {
+    pub exponent: f32,
+    pub test_particle: bool,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Brush {
+            exponent: -3.0,
+            test_particle: false,
+        }
+    }
+}
+
+/// Change of the exponent of the [`Brush`] per line scrolled
+const SCROLL_STEP: f32 = 0.25;
+/// Lightest brush, that of key 1, giving the radius of the test particles
+const MIN_EXPONENT: f32 = -8.0;
+/// Pixels the cursor must move between the press and the release to throw a body
+const DEAD_ZONE: f32 = 4.0;
+/// Points sampled along the previewed orbit
+const SAMPLES: usize = 128;
+/// Hyperbolas are cut before their asymptotes, at this many times the distance of the throw
+const MAX_HYPERBOLA_DISTANCE: f32 = 10.0;
+const PREVIEW_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.6];
+/// Colors of the spawned bodies, in turn
+const COLORS: [Color; 6] = [
+    Color::CYAN,
+    Color::ORANGE,
+    Color::LIME_GREEN,
+    Color::PINK,
+    Color::GOLD,
+    Color::VIOLET,
+];
+
+/// Where the drag started, on the screen and in the plane, and where it is now
+#[derive(Default)]
+struct Drag {
+    start: Option<(Vec2, Vec3)>,
+    end: Option<Vec3>,
+    spawned: usize,
+}
+
+/// Line strips of the orbit and of the drag, hidden but while dragging
+struct Preview {
+    orbit: Handle<Mesh>,
+    drag: Handle<Mesh>,
+}
+
+/// Bodies are spawned from the state of the others where they are drawn
+struct Body {
+    mass: f32,
+    radius: f32,
+    translation: Vec3,
+    pos: Vec3,
+    vel: Vec3,
+}
+
+/// Body thrown from `start` along `drag` in the plane, and its attractor if any
+struct Throw {
+    mass: f32,
+    radius: f32,
+    pos: Vec3,
+    vel: Vec3,
+    attractor: Option<usize>,
+}
+
+/// Keys 1-9 of the brush, 0 being the test particles
+const KEYS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// The scrolls and keys are taken away from the camera once used, as soon as Bevy has read
+/// them
+fn adjust_brush(
+    drag: Res<Drag>,
+    mouse: Res<Input<MouseButton>>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut wheel: ResMut<Events<MouseWheel>>,
+    mut brush: ResMut<Brush>,
+) {
+    if drag.start.is_none() || !mouse.pressed(MouseButton::Left) {
+        return;
+    }
+    let scroll: f32 = wheel.drain().map(|scroll| scroll.y).sum();
+    let key = KEYS.iter().position(|key| keys.just_pressed(*key));
+    if scroll != 0.0 {
+        brush.exponent = (brush.exponent + scroll * SCROLL_STEP).clamp(MIN_EXPONENT, 0.0);
+        brush.test_particle = false;
+    }
+    if let Some(index) = key {
+        keys.reset(KEYS[index]);
+        brush.test_particle = index == 0;
+        if index > 0 {
+            brush.exponent = MIN_EXPONENT + (index - 1) as f32;
+        }
+    }
+    if scroll == 0.0 && key.is_none() {
+        return;
+    }
+    if brush.test_particle {
+        info!("sandbox brush: test particle");
+    } else {
+        info!(
+            "sandbox brush: 10^{:.2} of the heaviest body",
+            brush.exponent
+        );
+    }
+}
+
+fn spawn_preview(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let orbit = meshes.add(empty_mesh(PrimitiveTopology::LineStrip, SAMPLES + 1));
+    let drag = meshes.add(empty_mesh(PrimitiveTopology::LineStrip, 2));
+    commands.spawn_bundle(line_bundle(orbit.clone()));
+    commands.spawn_bundle(line_bundle(drag.clone()));
+    commands.insert_resource(Preview { orbit, drag });
+}
+
+/// Point of the XY plane under the cursor, none if the ray runs along it or away from it
+fn plane_point(
+    windows: &Windows,
+    cameras: &Query<(&Camera, &Transform), With<PanOrbitCamera>>,
+) -> Option<(Vec2, Vec3)> {
+    let (camera, transform) = cameras.iter().next()?;
+    let window = windows.get(camera.window)?;
+    let (origin, dir) = cursor_ray(camera, transform, window)?;
+    let t = -origin.z / dir.z;
+    if !t.is_finite() || t <= 0.0 {
+        return None;
+    }
+    Some((window.cursor_position()?, origin + dir * t))
+}
+
+/// The attractor is the heavier body pulling the hardest, `m / r^2`, as for the orbits. The
+/// drawn positions are those of the physics turned by the [`FrameRotation`] and moved, so
+/// that the position and the drag are brought back to the physics through a body.
+fn throw(brush: &Brush, g: f32, rotation: Quat, bodies: &[Body], start: Vec3, end: Vec3) -> Throw {
+    let heaviest =
+        (0..bodies.len()).max_by(|&a, &b| bodies[a].mass.partial_cmp(&bodies[b].mass).unwrap());
+    let (heaviest_mass, heaviest_radius) =
+        heaviest.map_or((1.0, 1.0), |i| (bodies[i].mass, bodies[i].radius));
+    let ratio = 10f32.powf(brush.exponent);
+    let (mass, radius) = if brush.test_particle {
+        (0.0, heaviest_radius * 10f32.powf(MIN_EXPONENT).cbrt())
+    } else {
+        (heaviest_mass * ratio, heaviest_radius * ratio.cbrt())
+    };
+    let attractor = dominant_attractor(
+        mass,
+        start,
+        bodies
+            .iter()
+            .enumerate()
+            .map(|(i, body)| (body.mass, body.translation, i)),
+    );
+
+    let inverse = rotation.inverse();
+    let drag = inverse * (end - start);
+    let (pos, vel) = match attractor.or(heaviest) {
+        Some(i) => {
+            let reference = &bodies[i];
+            let pos = reference.pos + inverse * (start - reference.translation);
+            match attractor {
+                // Circular speed per unit of the distance to the attractor
+                Some(_) => {
+                    let r = pos.distance(reference.pos).max(f32::MIN_POSITIVE);
+                    let speed = (g * (reference.mass + mass) / r).sqrt();
+                    (pos, reference.vel + drag * speed / r)
+                }
+                None => (pos, reference.vel + drag),
+            }
+        }
+        None => (inverse * start, drag),
+    };
+    Throw {
+        mass,
+        radius,
+        pos,
+        vel,
+        attractor,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn bodies_of(
+    query: &Query<(&Mass, &Radius, &Transform, &Position, &Velocity), Without<TestParticle>>,
+) -> Vec<Body> {
+    query
+        .iter()
+        .map(|(mass, radius, transform, pos, vel)| Body {
+            mass: mass.0,
+            radius: radius.0,
+            translation: transform.translation,
+            pos: pos.0,
+            vel: vel.0,
+        })
+        .collect()
+}
+
+fn alt_pressed(keys: &Input<KeyCode>) -> bool {
+    keys.pressed(KeyCode::LAlt) || keys.pressed(KeyCode::RAlt)
+}
+
+/// The overlays of the body are its children, or drop themselves once it is gone, e.g. its
+/// label. Following systems see the event before the body is despawned.
+#[allow(clippy::too_many_arguments)]
+fn delete_body(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    state: Res<SimState>,
+    mut events: EventWriter<EjectionEvent>,
+    cameras: Query<(&Camera, &Transform), With<PanOrbitCamera>>,
+    bodies: Query<(Entity, &Transform, &Radius)>,
+    names: Query<&Name>,
+    ui: Query<&Interaction>,
+) {
+    if !mouse.just_pressed(MouseButton::Left)
+        || !alt_pressed(&keys)
+        || ui.iter().any(|i| *i != Interaction::None)
+    {
+        return;
+    }
+    let body = match body_under_cursor(&windows, &cameras, &bodies) {
+        Some(body) => body,
+        None => return,
+    };
+    info!(
+        "{} deleted at t = {:.2}",
+        names
+            .get(body)
+            .map_or_else(|_| format!("body {}", body.id()), |name| name.to_string()),
+        state.time
+    );
+    events.send(EjectionEvent { body });
+    commands.entity(body).despawn_recursive();
+}
+
+/// Presses on the UI are left to it, and those deleting a body
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn drag_body(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    (brush, g, rotation): (Res<Brush>, Res<Gravity>, Res<FrameRotation>),
+    mut drag: ResMut<Drag>,
+    cameras: Query<(&Camera, &Transform), With<PanOrbitCamera>>,
+    bodies: Query<(&Mass, &Radius, &Transform, &Position, &Velocity), Without<TestParticle>>,
+    ui: Query<&Interaction>,
+) {
+    if mouse.just_pressed(MouseButton::Left)
+        && !alt_pressed(&keys)
+        && ui.iter().all(|i| *i == Interaction::None)
+    {
+        drag.start = plane_point(&windows, &cameras);
+        drag.end = drag.start.map(|(_, point)| point);
+    }
+    let (cursor, start) = match drag.start {
+        Some(start) => start,
+        None => return,
+    };
+    let current = plane_point(&windows, &cameras);
+    if let Some((_, point)) = current {
+        drag.end = Some(point);
+    }
+    if !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    let end = drag.end.unwrap_or(start);
+    drag.start = None;
+    drag.end = None;
+    let moved = current.map_or(0.0, |(released, _)| released.distance(cursor));
+    if moved < DEAD_ZONE {
+        return;
+    }
+
+    let body = throw(&brush, g.0, rotation.0, &bodies_of(&bodies), start, end);
+    let color = COLORS[drag.spawned % COLORS.len()];
+    drag.spawned += 1;
+    let mut entity = commands.spawn_bundle(
+        BodyBundle::new(body.mass, body.pos, body.vel)
+            .with_radius(body.radius)
+            .with_color(color),
+    );
+    entity.insert(Name::new(format!("Sandbox {}", drag.spawned)));
+    if brush.test_particle {
+        entity.insert(TestParticle);
+    }
+}
+
+/// The orbit of the throw around its attractor, while dragging, and the drag itself
+#[allow(clippy::type_complexity)]
+fn preview_orbit(
+    (brush, g, rotation): (Res<Brush>, Res<Gravity>, Res<FrameRotation>),
+    drag: Res<Drag>,
+    preview: Res<Preview>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Query<(&Mass, &Radius, &Transform, &Position, &Velocity), Without<TestParticle>>,
+) {
+    let dragging = match (drag.start, drag.end) {
+        (Some((_, start)), Some(end)) => Some((start, end)),
+        _ => None,
+    };
+    if let Some(mesh) = meshes.get_mut(&preview.drag) {
+        let color = if dragging.is_some() {
+            PREVIEW_COLOR
+        } else {
+            [0.0; 4]
+        };
+        let (start, end) = dragging.unwrap_or_default();
+        *positions_mut(mesh) = vec![start.into(), end.into()];
+        colors_mut(mesh).iter_mut().for_each(|c| *c = color);
+    }
+    let mesh = match meshes.get_mut(&preview.orbit) {
+        Some(mesh) => mesh,
+        None => return,
+    };
+    let bodies = bodies_of(&bodies);
+    let throw = dragging.map(|(start, end)| throw(&brush, g.0, rotation.0, &bodies, start, end));
+    let (throw, attractor) = match throw.and_then(|t| t.attractor.map(|i| (t, &bodies[i]))) {
+        Some(orbit) => orbit,
+        None => {
+            colors_mut(mesh).iter_mut().for_each(|c| *c = [0.0; 4]);
+            return;
+        }
+    };
+    let rel = throw.pos - attractor.pos;
+    let mu = g.0 * (throw.mass + attractor.mass);
+    let elements = orbital_elements(rel, throw.vel - attractor.vel, mu);
+    let max_distance = (MAX_HYPERBOLA_DISTANCE * rel.length()) as f64;
+    let (start, span) = match elements.max_true_anomaly(max_distance) {
+        None => (0.0, TAU),
+        Some(max) => (-max, 2.0 * max),
+    };
+    for (i, vertex) in positions_mut(mesh).iter_mut().enumerate() {
+        let nu = start + span * i as f64 / SAMPLES as f64;
+        *vertex = (attractor.translation + rotation.0 * elements.position_at(nu)).into();
+    }
+    colors_mut(mesh).iter_mut().for_each(|c| *c = PREVIEW_COLOR);
+}
@@ -0,0 +1,334 @@
+use bevy::{
+    prelude::*,
+    render::{
+        pass::{
+            LoadOp, Operations, PassDescriptor, RenderPassDepthStencilAttachmentDescriptor,
+            TextureAttachment,
+        },
+        render_graph::{
+            base::{camera, node, MainPass},
+            Node, PassNode, RenderGraph, ResourceSlotInfo, ResourceSlots, WindowTextureNode,
+        },
+        renderer::{
+            BufferId, BufferInfo, BufferMapMode, BufferUsage, RenderContext, RenderResourceContext,
+        },
+        texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
+    },
+    window::WindowId,
+};
+use std::{
+    cell::RefCell,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Saves PNG screenshots of the 3D scene, without the UI overlays, with F12 and optionally
+/// every `every` frames. Files are named `screenshot-<start of the run>-<index>.png` in the
+/// working directory, so that they sort in order of capture and never overwrite each other.
+///
+/// The swap chain cannot be read back, so the scene is drawn a second time, in a copyable
+/// texture, on the frames where a screenshot is taken.
+#[derive(Default)]
+pub struct ScreenshotPlugin {
+    pub every: Option<u32>,
+}
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let samples = app
+            .world()
+            .get_resource::<Msaa>()
+            .map_or(1, |msaa| msaa.samples);
+        app.insert_resource(Screenshots {
+            every: self.every,
+            started,
+            count: 0,
+            frame: 0,
+            pending: Vec::new(),
+        })
+        .add_system(request_screenshots.system());
+
+        let world = app.world_mut();
+        let mut graph = world.get_resource_mut::<RenderGraph>().unwrap();
+        add_screenshot_graph(&mut graph, samples);
+    }
+}
+
+/// Frame counter and screenshots waiting for the render graph
+pub struct Screenshots {
+    every: Option<u32>,
+    /// Seconds since the epoch at the start of the run, prefix of the file names
+    started: u64,
+    count: u32,
+    frame: u32,
+    pending: Vec<PathBuf>,
+}
+
+impl Screenshots {
+    fn request(&mut self) {
+        self.count += 1;
+        let name = format!("screenshot-{}-{:05}.png", self.started, self.count);
+        self.pending.push(name.into());
+    }
+}
+
+fn request_screenshots(keys: Res<Input<KeyCode>>, mut screenshots: ResMut<Screenshots>) {
+    screenshots.frame += 1;
+    if keys.just_pressed(KeyCode::F12) {
+        screenshots.request();
+    }
+    if let Some(every) = screenshots.every {
+        // Not `is_multiple_of`, which is too recent for the toolchain of the web build
+        if screenshots.frame.checked_rem(every) == Some(0) {
+            screenshots.request();
+        }
+    }
+}
+
+const SCREENSHOT_PASS: &str = "screenshot_pass";
+const SCREENSHOT_COLOR_ATTACHMENT: &str = "screenshot_color_attachment";
+const SCREENSHOT_SAMPLED_COLOR_ATTACHMENT: &str = "screenshot_sampled_color_attachment";
+const SCREENSHOT_DEPTH_TEXTURE: &str = "screenshot_depth_texture";
+
+fn window_texture(samples: u32, format: TextureFormat, usage: TextureUsage) -> WindowTextureNode {
+    WindowTextureNode::new(
+        WindowId::primary(),
+        TextureDescriptor {
+            size: Extent3d {
+                depth: 1,
+                width: 1,
+                height: 1,
+            },
+            mip_level_count: 1,
+            sample_count: samples,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+        },
+    )
+}
+
+/// Same as the main pass of the base render graph, except for the final color attachment
+/// which can be copied
+fn add_screenshot_graph(graph: &mut RenderGraph, samples: u32) {
+    let msaa = Msaa { samples };
+    let mut pass = PassNode::<&MainPass>::new(PassDescriptor {
+        color_attachments: vec![msaa.color_attachment_descriptor(
+            TextureAttachment::Input("color_attachment".to_string()),
+            TextureAttachment::Input("color_resolve_target".to_string()),
+            Operations {
+                load: LoadOp::Clear(Color::BLACK),
+                store: true,
+            },
+        )],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+            attachment: TextureAttachment::Input("depth".to_string()),
+            depth_ops: Some(Operations {
+                load: LoadOp::Clear(1.0),
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+        sample_count: samples,
+    });
+    pass.use_default_clear_color(0);
+    pass.add_camera(camera::CAMERA_3D);
+    graph.add_node(SCREENSHOT_PASS, ScreenshotNode::new(pass));
+    for upstream in &[node::TEXTURE_COPY, node::SHARED_BUFFERS, node::CAMERA_3D] {
+        graph.add_node_edge(*upstream, SCREENSHOT_PASS).unwrap();
+    }
+
+    graph.add_node(
+        SCREENSHOT_COLOR_ATTACHMENT,
+        window_texture(
+            1,
+            TextureFormat::default(),
+            TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::COPY_SRC,
+        ),
+    );
+    graph.add_node(
+        SCREENSHOT_DEPTH_TEXTURE,
+        window_texture(
+            samples,
+            TextureFormat::Depth32Float,
+            TextureUsage::OUTPUT_ATTACHMENT,
+        ),
+    );
+    let out = WindowTextureNode::OUT_TEXTURE;
+    graph
+        .add_slot_edge(
+            SCREENSHOT_COLOR_ATTACHMENT,
+            out,
+            SCREENSHOT_PASS,
+            ScreenshotNode::target_slot(samples),
+        )
+        .unwrap();
+    graph
+        .add_slot_edge(SCREENSHOT_DEPTH_TEXTURE, out, SCREENSHOT_PASS, "depth")
+        .unwrap();
+    if samples > 1 {
+        graph.add_node(
+            SCREENSHOT_SAMPLED_COLOR_ATTACHMENT,
+            window_texture(
+                samples,
+                TextureFormat::default(),
+                TextureUsage::OUTPUT_ATTACHMENT,
+            ),
+        );
+        graph
+            .add_slot_edge(
+                SCREENSHOT_SAMPLED_COLOR_ATTACHMENT,
+                out,
+                SCREENSHOT_PASS,
+                "color_attachment",
+            )
+            .unwrap();
+    }
+}
+
+/// Buffer the last screenshot was copied to, read on the next frame once the GPU is done
+struct Readback {
+    buffer: BufferId,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    path: PathBuf,
+}
+
+/// Runs the wrapped pass only when a screenshot is pending, then copies its result
+struct ScreenshotNode {
+    pass: PassNode<&'static MainPass>,
+    capture: Option<PathBuf>,
+    readback: Option<Readback>,
+}
+
+impl ScreenshotNode {
+    fn new(pass: PassNode<&'static MainPass>) -> Self {
+        Self {
+            pass,
+            capture: None,
+            readback: None,
+        }
+    }
+
+    /// Input slot of the texture that ends up with the final image
+    fn target_slot(samples: u32) -> &'static str {
+        if samples > 1 {
+            "color_resolve_target"
+        } else {
+            "color_attachment"
+        }
+    }
+}
+
+impl Node for ScreenshotNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        self.pass.input()
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        if let Some(readback) = self.readback.take() {
+            let context = world
+                .get_resource::<Box<dyn RenderResourceContext>>()
+                .unwrap();
+            save(&**context, readback);
+        }
+
+        let mut screenshots = world.get_resource_mut::<Screenshots>().unwrap();
+        if screenshots.pending.is_empty() {
+            return;
+        }
+        // Screenshots requested on the same frame would be identical
+        self.capture = screenshots.pending.drain(..).next_back();
+        self.pass.prepare(world);
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        let path = match self.capture.take() {
+            Some(path) => path,
+            None => return,
+        };
+        let window = match world.get_resource::<Windows>().unwrap().get_primary() {
+            Some(window) => window,
+            None => return,
+        };
+        let samples = world.get_resource::<Msaa>().map_or(1, |msaa| msaa.samples);
+        let texture = match input.get(Self::target_slot(samples)) {
+            Some(texture) => texture.get_texture().unwrap(),
+            None => return,
+        };
+        self.pass.update(world, render_context, input, output);
+
+        let (width, height) = (window.physical_width(), window.physical_height());
+        let bytes_per_row = render_context
+            .resources()
+            .get_aligned_texture_size(4 * width as usize);
+        let buffer = render_context.resources().create_buffer(BufferInfo {
+            size: bytes_per_row * height as usize,
+            buffer_usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let size = Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        render_context.copy_texture_to_buffer(
+            texture,
+            [0, 0, 0],
+            0,
+            buffer,
+            0,
+            bytes_per_row as u32,
+            size,
+        );
+        self.readback = Some(Readback {
+            buffer,
+            width,
+            height,
+            bytes_per_row: bytes_per_row as u32,
+            path,
+        });
+    }
+}
+
+/// Read the pixels back and write them to a PNG, in the background since encoding is slow
+fn save(context: &dyn RenderResourceContext, readback: Readback) {
+    let Readback {
+        buffer,
+        width,
+        height,
+        bytes_per_row,
+        path,
+    } = readback;
+    let pixels = RefCell::new(Vec::with_capacity((4 * width * height) as usize));
+    context.map_buffer(buffer, BufferMapMode::Read);
+    context.read_mapped_buffer(buffer, 0..(bytes_per_row * height) as u64, &|data, _| {
+        let mut pixels = pixels.borrow_mut();
+        for row in data.chunks(bytes_per_row as usize) {
+            // BGRA to RGBA, dropping the padding at the end of the row
+            for bgra in row[..4 * width as usize].chunks(4) {
+                pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], 255]);
+            }
+        }
+    });
+    context.unmap_buffer(buffer);
+    context.remove_buffer(buffer);
+
+    let pixels = pixels.into_inner();
+    std::thread::spawn(move || {
+        match image::save_buffer(&path, &pixels, width, height, image::ColorType::Rgba8) {
+            Ok(()) => info!("screenshot saved to `{}`", path.display()),
+            Err(e) => error!("cannot save screenshot `{}`: {}", path.display(), e),
+        }
+    });
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use std::str::FromStr;
+
+use super::nbody::{FrameTime, Radius, TrueRadius};
+
+/// Switches the bodies between exaggerated sizes, visible from afar, and their true sizes,
+/// with Z. Only the meshes are scaled: collisions and picking keep using [`Radius`].
+pub struct SizesPlugin {
+    /// Initial value of the [`SizeScale`]
+    pub scale: SizeScale,
+}
+
+impl Plugin for SizesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(self.scale)
+            .add_system(toggle_sizes.system())
+            .add_system(scale_bodies.system());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeScale {
+    /// Each body is drawn with its [`TrueRadius`], most are then too small to be seen
+    Realistic,
+    /// Each body is drawn with its [`Radius`]
+    #[default]
+    Exaggerated,
+}
+
+impl FromStr for SizeScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "realistic" => Ok(SizeScale::Realistic),
+            "exaggerated" => Ok(SizeScale::Exaggerated),
+            _ => Err(format!("unknown size scale `{}`", s)),
+        }
+    }
+}
+
+fn toggle_sizes(keys: Res<Input<KeyCode>>, mut scale: ResMut<SizeScale>) {
+    if keys.just_pressed(KeyCode::Z) {
+        *scale = match *scale {
+            SizeScale::Realistic => SizeScale::Exaggerated,
+            SizeScale::Exaggerated => SizeScale::Realistic,
+        };
+    }
+}
+
+/// Ease the scale of the meshes towards the selected sizes. The scale is interpolated
+/// logarithmically since it spans orders of magnitude.
+fn scale_bodies(
+    frame: Res<FrameTime>,
+    scale: Res<SizeScale>,
+    mut query: Query<(&Radius, &TrueRadius, &mut Transform)>,
+) {
+    // Time constant of the transition, in seconds
+    const SMOOTHING: f32 = 0.25;
+
+    let t = 1.0 - (-frame.0 as f32 / SMOOTHING).exp();
+    for (radius, true_radius, mut transform) in query.iter_mut() {
+        let goal = match *scale {
+            SizeScale::Realistic => true_radius.0 / radius.0,
+            SizeScale::Exaggerated => 1.0,
+        };
+        let current = transform.scale.x;
+        let next = if (current.ln() - goal.ln()).abs() < 1e-3 {
+            goal
+        } else {
+            (current.ln() + (goal.ln() - current.ln()) * t).exp()
+        };
+        transform.scale = Vec3::splat(next);
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use super::nbody::{BodyBundle, Gravity, PhysicsSystem, Position, TestParticle, Velocity};
+use super::pan_orbit_camera::CameraTarget;
+use super::restart::reset_resource;
+use super::spacecraft::Spacecraft;
+use crate::scene::{spawn_body, Appearance};
+use crate::spawn_z_camera;
+
+/// Gravity assist: a spacecraft crosses the orbit of a planet just behind it, is pulled
+/// along by the planet and leaves faster around the star than it came. The speeds relative
+/// to the star before and after the flyby are printed, with its closest approach.
+pub struct SlingshotPlugin;
+
+impl Plugin for SlingshotPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Flyby>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, reset_resource::<Flyby>.system())
+            .add_startup_system(slingshot.system())
+            .add_system(track_flyby.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+const G: f32 = 1.0;
+const STAR_MASS: f32 = 1000.0;
+const PLANET_MASS: f32 = 1.0;
+/// Radius of the circular orbit of the planet around the star
+const PLANET_DISTANCE: f32 = 50.0;
+/// The spacecraft starts this far from the planet, towards the star, and behind the planet
+/// by the impact parameter, moving away from the star at the excess speed relative to the
+/// planet. The planet pulling the spacecraft forward, passing behind it gains speed, and
+/// passing in front of it (a negative impact parameter) loses some.
+const START_DISTANCE: f32 = 6.0;
+const IMPACT_PARAMETER: f32 = 0.8;
+const EXCESS_SPEED: f32 = 1.5;
+
+struct Star;
+struct Planet;
+
+/// Speeds of the spacecraft relative to the star, and its distance to the planet
+#[derive(Default)]
+struct Flyby {
+    before: Option<f32>,
+    closest: Option<f32>,
+    done: bool,
+}
+
+pub fn slingshot(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    g.0 = G;
+
+    // Circular orbit of the planet, the star recoiling so that the barycenter stays at rest
+    // at the origin
+    let total_mass = STAR_MASS + PLANET_MASS;
+    let speed = (G * total_mass / PLANET_DISTANCE).sqrt();
+    let (star_share, planet_share) = (PLANET_MASS / total_mass, STAR_MASS / total_mass);
+    let planet_pos = Vec3::X * PLANET_DISTANCE * planet_share;
+    let planet_vel = Vec3::Y * speed * planet_share;
+
+    let star = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(
+            STAR_MASS,
+            Vec3::X * -PLANET_DISTANCE * star_share,
+            Vec3::Y * -speed * star_share,
+        ),
+        &Appearance {
+            radius: 2.0,
+            color: Color::rgb(1.0, 0.85, 0.4),
+            emissive: Color::rgb(1.0, 0.85, 0.4),
+            light: Some(50_000.0),
+            rings: None,
+        },
+    );
+    commands.entity(star).insert(Star).insert(Name::new("Star"));
+
+    let planet = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(PLANET_MASS, planet_pos, planet_vel),
+        &Appearance {
+            radius: 0.3,
+            color: Color::rgb(0.8, 0.5, 0.3),
+            emissive: Color::BLACK,
+            light: None,
+            rings: None,
+        },
+    );
+    commands
+        .entity(planet)
+        .insert(Planet)
+        .insert(Name::new("Planet"));
+    // The camera follows the planet, the flyby being small next to its orbit
+    commands.insert_resource(CameraTarget(Some(planet)));
+
+    let spacecraft = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(
+            0.0,
+            planet_pos - Vec3::new(START_DISTANCE, IMPACT_PARAMETER, 0.0),
+            planet_vel + Vec3::X * EXCESS_SPEED,
+        ),
+        &Appearance {
+            radius: 0.1,
+            color: Color::WHITE,
+            emissive: Color::WHITE,
+            light: None,
+            rings: None,
+        },
+    );
+    commands
+        .entity(spacecraft)
+        .insert(TestParticle)
+        .insert(Spacecraft)
+        .insert(Name::new("Spacecraft"));
+
+    spawn_z_camera(&mut commands, 20.0);
+}
+
+/// The flyby is over once the spacecraft is back as far from the planet as it started,
+/// after its closest approach
+#[allow(clippy::type_complexity)]
+fn track_flyby(
+    mut flyby: ResMut<Flyby>,
+    spacecraft: Query<(&Position, &Velocity), With<Spacecraft>>,
+    star: Query<(&Position, &Velocity), With<Star>>,
+    planet: Query<&Position, With<Planet>>,
+) {
+    if flyby.done {
+        return;
+    }
+    let ((pos, vel), (star_pos, star_vel), planet_pos) =
+        match (spacecraft.single(), star.single(), planet.single()) {
+            (Ok(spacecraft), Ok(star), Ok(planet)) => (spacecraft, star, planet),
+            // Merged, e.g. with `--collisions merge`
+            _ => return,
+        };
+    let speed = (vel.0 - star_vel.0).length();
+    let distance = pos.0.distance(planet_pos.0);
+    let before = *flyby.before.get_or_insert(speed);
+    match flyby.closest {
+        Some(closest) if distance >= closest => {}
+        _ => {
+            flyby.closest = Some(distance);
+            return;
+        }
+    }
+
+    if distance >= START_DISTANCE {
+        flyby.done = true;
+        println!(
+            "gravity assist: {:.3} -> {:.3} relative to the star ({:+.1}%), {:.3} from \
+             the star, closest approach to the planet {:.3}",
+            before,
+            speed,
+            (speed / before - 1.0) * 100.0,
+            pos.0.distance(star_pos.0),
+            flyby.closest.unwrap_or(distance)
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::{
+    audio::{play_queued_audio_system, AudioOutput, Decodable},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use rodio::{buffer::SamplesBuffer, source::Amplify, Source};
+use std::{convert::TryInto, fs, sync::Arc};
+
+use super::nbody::{CollisionEvent, PhysicsSystem};
+
+/// Plays the sound of a WAV file on collisions, louder the more energy the impact has.
+/// The audio of Bevy 0.5 plays sounds at full volume only, so each impact is an
+/// [`ImpactSound`] carrying its volume, played by an audio output of its own. The sound is
+/// left out, with a warning, without an audio device or if the file cannot be read.
+pub struct SoundPlugin {
+    /// Path of a 16-bit PCM WAV file
+    pub path: String,
+}
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let clip = match read_wav(&self.path) {
+            Ok(clip) => clip,
+            Err(e) => {
+                warn!("no impact sound: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = rodio::OutputStream::try_default() {
+            warn!("no impact sound: {}", e);
+            return;
+        }
+        app.insert_resource(clip)
+            .init_resource::<Impacts>()
+            .init_non_send_resource::<AudioOutput<ImpactSound>>()
+            .add_asset::<ImpactSound>()
+            .init_resource::<Audio<ImpactSound>>()
+            .add_system(play_impacts.system().after(PhysicsSystem::Collisions))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<ImpactSound>.exclusive_system(),
+            );
+    }
+}
+
+/// Seconds between two sounds at least: the impacts in the meantime, e.g. many collisions
+/// in the same frame, are heard as the hardest of them
+const MIN_INTERVAL: f64 = 0.05;
+
+/// Orders of magnitude of the energies below the loudest impact so far that can be heard,
+/// the volume being logarithmic in the energy
+const DYNAMIC_RANGE: f32 = 4.0;
+
+/// Decoded samples of the sound file
+#[derive(Clone)]
+struct ImpactClip {
+    channels: u16,
+    sample_rate: u32,
+    samples: Arc<[i16]>,
+}
+
+/// The sound of an impact, at its volume
+#[derive(Clone, TypeUuid)]
+#[uuid = "10f3e345-c141-477c-8dc2-26f40b455225"]
+pub struct ImpactSound {
+    clip: ImpactClip,
+    /// Between 0 and 1
+    volume: f32,
+}
+
+impl Decodable for ImpactSound {
+    type Decoder = Amplify<SamplesBuffer<i16>>;
+
+    fn decoder(&self) -> Self::Decoder {
+        let clip = &self.clip;
+        SamplesBuffer::new(clip.channels, clip.sample_rate, clip.samples.to_vec())
+            .amplify(self.volume)
+    }
+}
+
+#[derive(Default)]
+struct Impacts {
+    /// Energy of the hardest impact since the last sound
+    pending: f32,
+    /// Energy of the hardest impact so far, played at full volume
+    loudest: f32,
+    /// When the last sound was played, in seconds since startup
+    last: f64,
+}
+
+/// Samples of the `data` chunk of a PCM WAV file, 16 bits per sample
+fn read_wav(path: &str) -> Result<ImpactClip, String> {
+    let bytes = fs::read(path).map_err(|e| format!("cannot read `{}`: {}", path, e))?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(format!("`{}` is not a WAV file", path));
+    }
+    let mut format = None;
+    let mut offset = 12;
+    // Chunks are an id, a little-endian size and the data, padded to an even size
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let data = &bytes[offset + 8..(offset + 8 + size).min(bytes.len())];
+        match id {
+            b"fmt " if data.len() >= 16 => {
+                let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+                let (encoding, channels, bits) = (u16_at(0), u16_at(2), u16_at(14));
+                if encoding != 1 || bits != 16 || channels == 0 {
+                    return Err(format!("`{}` is not 16-bit PCM", path));
+                }
+                let sample_rate = u32::from_le_bytes(data[4..8].try_into().unwrap());
+                format = Some((channels, sample_rate));
+            }
+            b"data" => {
+                let (channels, sample_rate) =
+                    format.ok_or_else(|| format!("`{}` has no format before its data", path))?;
+                let samples = data
+                    .chunks_exact(2)
+                    .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+                    .collect();
+                return Ok(ImpactClip {
+                    channels,
+                    sample_rate,
+                    samples,
+                });
+            }
+            _ => {}
+        }
+        offset += 8 + size + size % 2;
+    }
+    Err(format!("`{}` has no data", path))
+}
+
+fn play_impacts(
+    time: Res<Time>,
+    clip: Res<ImpactClip>,
+    audio: Res<Audio<ImpactSound>>,
+    mut sounds: ResMut<Assets<ImpactSound>>,
+    mut impacts: ResMut<Impacts>,
+    mut events: EventReader<CollisionEvent>,
+) {
+    for event in events.iter() {
+        impacts.pending = impacts.pending.max(event.energy);
+        impacts.loudest = impacts.loudest.max(event.energy);
+    }
+    let now = time.seconds_since_startup();
+    if impacts.pending <= 0.0 || now - impacts.last < MIN_INTERVAL {
+        return;
+    }
+    let volume = 1.0 + (impacts.pending / impacts.loudest).log10() / DYNAMIC_RANGE;
+    impacts.pending = 0.0;
+    if volume <= 0.0 {
+        return;
+    }
+    impacts.last = now;
+    // Freed once played, with the last handle
+    audio.play(sounds.add(ImpactSound {
+        clip: clip.clone(),
+        volume,
+    }));
+}
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+
+use super::nbody::{
+    BodyBundle, Gravity, Mass, PhysicsSystem, Position, Radius, SimState, TestParticle, TrueRadius,
+    Velocity,
+};
+use super::restart::reset_resource;
+use crate::kepler::{dominant_attractor, orbital_elements, OrbitalElements};
+
+/// Launches a spacecraft, a [`TestParticle`] on a circular orbit around a named body, which
+/// the arrow keys steer with small impulsive burns: up and down along its velocity
+/// (prograde and retrograde), left and right along the normal of its orbit
+pub struct SpacecraftPlugin {
+    /// Name of the body the spacecraft starts around
+    pub around: String,
+}
+
+impl Plugin for SpacecraftPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(LaunchSite(self.around.clone()))
+            .init_resource::<SpacecraftOrbit>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<SpacecraftOrbit>.system(),
+            )
+            .add_startup_system_to_stage(StartupStage::PostStartup, launch.system())
+            .add_system(burn.system().before(PhysicsSystem::Integrate))
+            .add_system(track_orbit.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+struct LaunchSite(String);
+
+pub struct Spacecraft;
+
+/// Orbit of the spacecraft around the body pulling it the hardest, for the control panel
+#[derive(Default)]
+pub struct SpacecraftOrbit {
+    /// Name of the body it orbits
+    pub around: Option<String>,
+    pub elements: Option<OrbitalElements>,
+    /// `None` if unbound
+    pub period: Option<f64>,
+    /// Sum of the magnitudes of all the burns
+    pub delta_v: f32,
+}
+
+/// Starting distance from the center of the body, in radii of the body. The true radius is
+/// used when the drawn one is exaggerated, which could put the spacecraft out of reach of
+/// the body, so that it is only seen outside of the body with realistic sizes.
+const ALTITUDE: f32 = 3.0;
+const RADIUS: f32 = 0.1;
+/// Velocity change of each burn, as a fraction of the speed relative to the attractor
+const BURN: f32 = 0.01;
+
+#[allow(clippy::type_complexity)]
+fn launch(
+    mut commands: Commands,
+    site: Res<LaunchSite>,
+    g: Res<Gravity>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    bodies: Query<(
+        &Name,
+        &Mass,
+        &Radius,
+        Option<&TrueRadius>,
+        &Position,
+        &Velocity,
+    )>,
+) {
+    let (mass, radius, pos, vel) = match bodies.iter().find(|(name, ..)| name.as_str() == site.0) {
+        Some((_, mass, radius, true_radius, pos, vel)) => {
+            (mass.0, true_radius.map_or(radius.0, |r| r.0), pos.0, vel.0)
+        }
+        None => {
+            warn!("no body named `{}` to launch the spacecraft around", site.0);
+            return;
+        }
+    };
+
+    // Circular orbit in the XY plane, counterclockwise like the planets
+    let distance = ALTITUDE * radius;
+    let speed = (g.0 * mass / distance).sqrt();
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: RADIUS,
+                subdivisions: 2,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                emissive: Color::WHITE,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .insert_bundle(
+            BodyBundle::new(0.0, pos + Vec3::X * distance, vel + Vec3::Y * speed)
+                .with_radius(RADIUS),
+        )
+        .insert(TrueRadius(RADIUS.min(distance / 10.0)))
+        .insert(TestParticle)
+        .insert(Spacecraft)
+        .insert(Name::new("Spacecraft"));
+}
+
+/// Attractor of a body at `pos`: the heavier body pulling the hardest, `m / r^2`, like the
+/// drawn orbits
+fn attractor<'a>(
+    pos: Vec3,
+    bodies: impl Iterator<Item = (&'a Mass, &'a Position, &'a Velocity, Option<&'a Name>)>,
+) -> Option<(&'a Mass, &'a Position, &'a Velocity, Option<&'a Name>)> {
+    dominant_attractor(0.0, pos, bodies.map(|body| (body.0 .0, body.1 .0, body)))
+}
+
+/// Arrow keys change the velocity relative to the attractor. Burns are ignored while paused,
+/// when the right arrow steps the simulation instead.
+#[allow(clippy::type_complexity)]
+fn burn(
+    keys: Res<Input<KeyCode>>,
+    state: Res<SimState>,
+    mut orbit: ResMut<SpacecraftOrbit>,
+    mut spacecraft: Query<(&Position, &mut Velocity), With<Spacecraft>>,
+    bodies: Query<(&Mass, &Position, &Velocity, Option<&Name>), Without<Spacecraft>>,
+) {
+    if state.paused {
+        return;
+    }
+    let pressed = |key| keys.just_pressed(key);
+    let (prograde, normal) = (
+        pressed(KeyCode::Up) as i32 - pressed(KeyCode::Down) as i32,
+        pressed(KeyCode::Right) as i32 - pressed(KeyCode::Left) as i32,
+    );
+    if prograde == 0 && normal == 0 {
+        return;
+    }
+
+    for (pos, mut vel) in spacecraft.iter_mut() {
+        let (_, center_pos, center_vel, _) = match attractor(pos.0, bodies.iter()) {
+            Some(attractor) => attractor,
+            None => continue,
+        };
+        let rel_vel = vel.0 - center_vel.0;
+        let direction = rel_vel.normalize_or_zero();
+        let up = (pos.0 - center_pos.0).cross(rel_vel).normalize_or_zero();
+        let delta_v = (direction * prograde as f32 + up * normal as f32) * BURN * rel_vel.length();
+        vel.0 += delta_v;
+        orbit.delta_v += delta_v.length();
+    }
+}
+
+fn track_orbit(
+    g: Res<Gravity>,
+    mut orbit: ResMut<SpacecraftOrbit>,
+    spacecraft: Query<(&Position, &Velocity), With<Spacecraft>>,
+    bodies: Query<(&Mass, &Position, &Velocity, Option<&Name>), Without<Spacecraft>>,
+) {
+    let (pos, vel) = match spacecraft.iter().next() {
+        Some(spacecraft) => spacecraft,
+        None => {
+            orbit.around = None;
+            orbit.elements = None;
+            return;
+        }
+    };
+    let (mass, center_pos, center_vel, name) = match attractor(pos.0, bodies.iter()) {
+        Some(attractor) => attractor,
+        None => return,
+    };
+    let mu = g.0 * mass.0;
+    let elements = orbital_elements(pos.0 - center_pos.0, vel.0 - center_vel.0, mu);
+    orbit.around = Some(name.map_or_else(|| "a body".to_string(), |n| n.to_string()));
+    orbit.period = elements.period(mu as f64);
+    orbit.elements = Some(elements);
+}
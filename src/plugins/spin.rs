@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use std::f64::consts::TAU;
+
+pub use super::nbody::Spin;
+use super::nbody::{FrameRotation, PhysicsSystem, RenderTime};
+
+/// Turns the bodies with a [`Spin`] on themselves, at the pace of the simulated time so
+/// that a day of rotation lasts as long as a day of orbit whatever the speed. Only the
+/// meshes turn, with their rings: gravity does not depend on the spin.
+pub struct SpinPlugin;
+
+impl Plugin for SpinPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(spin_bodies.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+fn spin_bodies(
+    time: Res<RenderTime>,
+    rotation: Res<FrameRotation>,
+    mut query: Query<(&Spin, &mut Transform)>,
+) {
+    for (spin, mut transform) in query.iter_mut() {
+        // Reduced to a turn in double precision, single precision running out of digits
+        // for the angle after a few years of spin
+        let angle = (spin.rate as f64 * time.0) % TAU;
+        transform.rotation = rotation.0 * Quat::from_axis_angle(spin.axis, angle as f32);
+    }
+}
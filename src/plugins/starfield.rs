@@ -0,0 +1,76 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut};
+use super::pan_orbit_camera::{CameraSystem, PanOrbitCamera};
+
+/// Background of distant stars. They are centered on the camera, so that they only appear
+/// to move when it rotates, as if infinitely far away.
+pub struct StarfieldPlugin {
+    pub count: usize,
+}
+
+impl Default for StarfieldPlugin {
+    fn default() -> Self {
+        Self { count: 4000 }
+    }
+}
+
+impl Plugin for StarfieldPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ClearColor(Color::BLACK))
+            .insert_resource(StarCount(self.count))
+            .add_startup_system(spawn_stars.system())
+            .add_system(follow_camera.system().after(CameraSystem::Follow));
+    }
+}
+
+struct StarCount(usize);
+
+struct Starfield;
+
+/// Distance of the stars from the camera, within the far plane of the default projection
+const DISTANCE: f32 = 900.0;
+
+fn spawn_stars(mut commands: Commands, count: Res<StarCount>, mut meshes: ResMut<Assets<Mesh>>) {
+    // The same sky at every run
+    let mut rng = StdRng::seed_from_u64(0x57a2_f1e1d);
+    let mut mesh = empty_mesh(PrimitiveTopology::PointList, count.0);
+    for pos in positions_mut(&mut mesh).iter_mut() {
+        // Normal coordinates are uniformly distributed in direction
+        let dir = Vec3::new(
+            rng.sample(StandardNormal),
+            rng.sample(StandardNormal),
+            rng.sample(StandardNormal),
+        );
+        *pos = (DISTANCE * dir.normalize()).into();
+    }
+    for color in colors_mut(&mut mesh).iter_mut() {
+        // Mostly faint stars, slightly tinted towards red or blue
+        let brightness = rng.gen::<f32>().powi(3) * 0.8 + 0.2;
+        let tint = rng.gen_range(-0.15..0.15);
+        *color = [
+            brightness * (1.0 + tint),
+            brightness,
+            brightness * (1.0 - tint),
+            1.0,
+        ];
+    }
+
+    commands
+        .spawn_bundle(line_bundle(meshes.add(mesh)))
+        .insert(Starfield);
+}
+
+#[allow(clippy::type_complexity)]
+fn follow_camera(
+    cameras: Query<&Transform, (With<PanOrbitCamera>, Without<Starfield>)>,
+    mut stars: Query<&mut Transform, With<Starfield>>,
+) {
+    if let Some(camera) = cameras.iter().next() {
+        for mut transform in stars.iter_mut() {
+            transform.translation = camera.translation;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use bevy::{app::AppExit, prelude::*};
+
+use super::nbody::{PhysicsSystem, SimState};
+
+/// Exits once the simulation has run for a given simulated time or number of steps,
+/// whichever comes first, e.g. for a one-shot `--headless --export-csv` run
+pub struct StopPlugin {
+    /// Simulated time, in days for the solar scenes
+    pub until: Option<f64>,
+    pub max_steps: Option<u64>,
+}
+
+impl Plugin for StopPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(StopCondition {
+            until: self.until,
+            max_steps: self.max_steps,
+        })
+        // The frame is still run to its end, e.g. exporting its last step
+        .add_system(stop.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+struct StopCondition {
+    until: Option<f64>,
+    max_steps: Option<u64>,
+}
+
+/// Relative tolerance on `until`, which the sum of the time steps can miss by a rounding
+/// error, running one more step
+const TIME_TOLERANCE: f64 = 1e-9;
+
+fn stop(condition: Res<StopCondition>, state: Res<SimState>, mut exit: EventWriter<AppExit>) {
+    let time_out = condition
+        .until
+        .is_some_and(|until| state.time >= until - TIME_TOLERANCE * until.abs().max(1.0));
+    let steps_out = condition
+        .max_steps
+        .is_some_and(|max_steps| state.steps >= max_steps);
+    if time_out || steps_out {
+        info!(
+            "stopping at t = {:.3} after {} steps",
+            state.time, state.steps
+        );
+        exit.send(AppExit);
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::{app::AppExit, prelude::*};
+use copernicus::forces::potential_energy;
+use std::sync::{Arc, Mutex};
+
+use super::escapes::{Ejected, Unbound};
+use super::nbody::{
+    CollisionCount, Gravity, Mass, PhysicsSystem, Position, SimState, Softening, TestParticle,
+    Velocity,
+};
+
+/// Outcome of a run, written when the app exits to `summary`, which outlives the app
+pub struct SummaryPlugin {
+    pub summary: Arc<Mutex<Option<Summary>>>,
+}
+
+impl Plugin for SummaryPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(SummaryState {
+            initial_energy: None,
+            summary: self.summary.clone(),
+        })
+        .add_system(initial_energy.system().before(PhysicsSystem::Integrate))
+        // After the exit is sent during the update
+        .add_system_to_stage(CoreStage::PostUpdate, summarize.system());
+    }
+}
+
+pub struct Summary {
+    pub bodies: usize,
+    pub steps: u64,
+    pub time: f64,
+    /// Change of the total energy relative to its initial value
+    pub energy_drift: f64,
+    pub collisions: u64,
+    /// Bodies escaping at the end, or culled before
+    pub ejections: u64,
+}
+
+struct SummaryState {
+    initial_energy: Option<f64>,
+    summary: Arc<Mutex<Option<Summary>>>,
+}
+
+/// Kinetic and potential energy of the massive bodies, as in the diagnostics
+fn total_energy<'a>(
+    g: f32,
+    softening: f32,
+    bodies: impl Iterator<Item = (&'a Mass, &'a Position, &'a Velocity)>,
+) -> f64 {
+    let (mut mass, mut pos) = (Vec::new(), Vec::new());
+    let mut kinetic = 0.0;
+    for (m, p, v) in bodies {
+        kinetic += 0.5 * (m.0 * v.0.length_squared()) as f64;
+        mass.push(m.0);
+        pos.push(p.0);
+    }
+    kinetic + potential_energy(g, softening, &mass, &pos)
+}
+
+/// Before the first step, once the startup systems have spawned the bodies
+fn initial_energy(
+    mut state: ResMut<SummaryState>,
+    g: Res<Gravity>,
+    softening: Res<Softening>,
+    bodies: Query<(&Mass, &Position, &Velocity), Without<TestParticle>>,
+) {
+    if state.initial_energy.is_none() {
+        state.initial_energy = Some(total_energy(g.0, softening.0, bodies.iter()));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn summarize(
+    state: Res<SummaryState>,
+    sim: Res<SimState>,
+    g: Res<Gravity>,
+    softening: Res<Softening>,
+    collisions: Res<CollisionCount>,
+    ejected: Res<Ejected>,
+    bodies: Query<(&Mass, &Position, &Velocity), Without<TestParticle>>,
+    all: Query<(), With<Mass>>,
+    unbound: Query<(), With<Unbound>>,
+    mut exit: EventReader<AppExit>,
+) {
+    if exit.iter().next().is_none() {
+        return;
+    }
+    let initial = state.initial_energy.unwrap_or(0.0);
+    let energy = total_energy(g.0, softening.0, bodies.iter());
+    *state.summary.lock().unwrap() = Some(Summary {
+        bodies: all.iter().count(),
+        steps: sim.steps,
+        time: sim.time,
+        energy_drift: (energy - initial) / initial.abs().max(f64::MIN_POSITIVE),
+        collisions: collisions.0,
+        ejections: ejected.0 + unbound.iter().count() as u64,
+    });
+}
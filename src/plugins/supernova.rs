@@ -0,0 +1,371 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::PI;
+
+use super::nbody::{
+    BodyBundle, Gravity, Mass, PhysicsSystem, Position, Radius, SimState, Velocity,
+};
+use super::picking::Selected;
+use super::random::SimRng;
+use super::restart::reset_resource;
+use crate::kepler::{state_from_elements, OrbitalElements};
+use crate::scene::{spawn_body, Appearance};
+use crate::spawn_z_camera;
+
+/// Blows off most of the mass of a star at a [`SupernovaEvent`]: the star keeps a
+/// [`REMNANT`] of it, the rest flying away in a shell of [`Ejecta`], massive bodies which
+/// conserve the mass, center of mass and linear momentum of the star. The bodies around it
+/// keep their velocities as it suddenly weakens, and those which were too fast for what is
+/// left of it escape: the fractions of them bound to the star before and after are kept in
+/// the [`Supernova`] resource, for the HUD.
+///
+/// N explodes the selected body, or the heaviest one if none is.
+pub struct SupernovaPlugin;
+
+impl Plugin for SupernovaPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<SupernovaEvent>()
+            .init_resource::<Supernova>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<Supernova>.system(),
+            )
+            .add_system(explode_selected.system().before(SupernovaSystem::Explode))
+            .add_system(
+                explode
+                    .system()
+                    .label(SupernovaSystem::Explode)
+                    .after(PhysicsSystem::Collisions),
+            )
+            .add_system(measure_bound.system().after(SupernovaSystem::Explode));
+    }
+}
+
+/// A star with planets on eccentric orbits, the heaviest body exploding with a
+/// [`SupernovaEvent`] after a few orbits of the innermost planet. As the mass it blows off
+/// is most of it, a planet escapes unless it is far enough out on its orbit, where it is
+/// slower. Needs a [`SupernovaPlugin`].
+pub struct NovaPlugin;
+
+impl Plugin for NovaPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Countdown>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                reset_resource::<Countdown>.system(),
+            )
+            .add_startup_system(nova.system())
+            .add_system(countdown.system().before(SupernovaSystem::Explode));
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum SupernovaSystem {
+    Explode,
+}
+
+/// Fraction of its mass the star keeps, below half: a body on a circular orbit then
+/// escapes
+pub const REMNANT: f32 = 0.4;
+/// Bodies of the shell, spread evenly over the sphere
+pub const SHELL: usize = 100;
+/// Speed of the ejecta, in escape speeds from the surface of the star
+const EJECTA_SPEED: f32 = 3.0;
+/// Radius of the ejecta, and the distance they start at from the center of the star,
+/// relative to its radius
+const EJECTA_RADIUS: f32 = 0.1;
+const SHELL_RADIUS: f32 = 1.5;
+const EJECTA_COLOR: Color = Color::rgb(1.0, 0.45, 0.2);
+
+/// Star to explode
+#[derive(Debug, Clone, Copy)]
+pub struct SupernovaEvent {
+    pub star: Entity,
+}
+
+/// Body of the shell blown off by a supernova
+pub struct Ejecta;
+
+/// The last star to explode, and the fractions of the bodies around it bound to it just
+/// before and now, none without any body around it. Bodies are bound when their energy in
+/// the two-body problem with the star is negative.
+#[derive(Default)]
+pub struct Supernova {
+    pub star: Option<Entity>,
+    pub before: Option<f32>,
+    pub now: Option<f32>,
+}
+
+/// Whether the body has gone off yet
+#[derive(Default)]
+struct Countdown {
+    done: bool,
+}
+
+/// Simulated time of the supernova of [`NovaPlugin`], a bit less than two orbits of the
+/// innermost planet
+const NOVA_TIME: f64 = 20.0;
+
+/// Fraction of `bodies`, as `(mass, position, velocity)` triplets, bound to a star, with
+/// `v² / 2 < G (M + m) / r`
+fn bound_fraction(g: f32, star: (f32, Vec3, Vec3), bodies: &[(f32, Vec3, Vec3)]) -> Option<f32> {
+    if bodies.is_empty() {
+        return None;
+    }
+    let (star_mass, star_pos, star_vel) = star;
+    let bound = bodies
+        .iter()
+        .filter(|(mass, pos, vel)| {
+            let r = pos.distance(star_pos);
+            r > 0.0 && 0.5 * vel.distance_squared(star_vel) < g * (star_mass + mass) / r
+        })
+        .count();
+    Some(bound as f32 / bodies.len() as f32)
+}
+
+fn heaviest(bodies: &Query<(Entity, &Mass), Without<Ejecta>>) -> Option<Entity> {
+    bodies
+        .iter()
+        .max_by(|(_, a), (_, b)| a.0.total_cmp(&b.0))
+        .map(|(entity, _)| entity)
+}
+
+fn explode_selected(
+    keys: Res<Input<KeyCode>>,
+    selected: Option<Res<Selected>>,
+    bodies: Query<(Entity, &Mass), Without<Ejecta>>,
+    mut events: EventWriter<SupernovaEvent>,
+) {
+    if !keys.just_pressed(KeyCode::N) {
+        return;
+    }
+    let star = selected
+        .and_then(|selected| selected.0)
+        .filter(|&body| bodies.get(body).is_ok())
+        .or_else(|| heaviest(&bodies));
+    if let Some(star) = star {
+        events.send(SupernovaEvent { star });
+    }
+}
+
+fn countdown(
+    state: Res<SimState>,
+    mut countdown: ResMut<Countdown>,
+    bodies: Query<(Entity, &Mass), Without<Ejecta>>,
+    mut events: EventWriter<SupernovaEvent>,
+) {
+    if countdown.done || state.time < NOVA_TIME {
+        return;
+    }
+    countdown.done = true;
+    if let Some(star) = heaviest(&bodies) {
+        events.send(SupernovaEvent { star });
+    }
+}
+
+/// Test particles and ejecta do not explode. The ejecta leave from a sphere around the
+/// star, all at the same speed, offsets and velocities averaging to zero.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn explode(
+    mut commands: Commands,
+    g: Res<Gravity>,
+    state: Res<SimState>,
+    mut events: EventReader<SupernovaEvent>,
+    mut supernova: ResMut<Supernova>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut bodies: Query<
+        (
+            Entity,
+            &mut Mass,
+            &Position,
+            &Velocity,
+            &Radius,
+            Option<&Name>,
+        ),
+        Without<Ejecta>,
+    >,
+) {
+    for &SupernovaEvent { star } in events.iter() {
+        let (mass, pos, vel, radius) = match bodies.get_mut(star) {
+            Ok((_, mass, pos, vel, radius, _)) if mass.0 > 0.0 => (mass.0, pos.0, vel.0, radius.0),
+            _ => continue,
+        };
+        let others: Vec<(f32, Vec3, Vec3)> = bodies
+            .iter_mut()
+            .filter(|(entity, ..)| *entity != star)
+            .map(|(_, mass, pos, vel, ..)| (mass.0, pos.0, vel.0))
+            .collect();
+        let before = bound_fraction(g.0, (mass, pos, vel), &others);
+        if let Ok((_, mut star_mass, ..)) = bodies.get_mut(star) {
+            star_mass.0 = mass * REMNANT;
+        }
+
+        // A Fibonacci lattice: points along a spiral from pole to pole, a golden angle apart
+        let golden_angle = PI * (3.0 - 5f32.sqrt());
+        let directions: Vec<Vec3> = (0..SHELL)
+            .map(|i| {
+                let z = 1.0 - 2.0 * (i as f32 + 0.5) / SHELL as f32;
+                let (sin, cos) = (i as f32 * golden_angle).sin_cos();
+                let ring = (1.0 - z * z).sqrt();
+                Vec3::new(ring * cos, ring * sin, z)
+            })
+            .collect();
+        let mean = directions.iter().sum::<Vec3>() / SHELL as f32;
+        let speed = EJECTA_SPEED * (2.0 * g.0 * mass / radius).sqrt();
+        let ejecta_mass = mass * (1.0 - REMNANT) / SHELL as f32;
+        let mesh = meshes.add(Mesh::from(shape::Icosphere {
+            radius: radius * EJECTA_RADIUS,
+            subdivisions: 2,
+        }));
+        let material = materials.add(StandardMaterial {
+            base_color: EJECTA_COLOR,
+            emissive: EJECTA_COLOR,
+            ..Default::default()
+        });
+        for direction in directions {
+            let direction = direction - mean;
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    ..Default::default()
+                })
+                .insert_bundle(
+                    BodyBundle::new(
+                        ejecta_mass,
+                        pos + direction * radius * SHELL_RADIUS,
+                        vel + direction * speed,
+                    )
+                    .with_radius(radius * EJECTA_RADIUS),
+                )
+                .insert(Ejecta);
+        }
+
+        let name = bodies
+            .get_mut(star)
+            .ok()
+            .and_then(|(.., name)| name.map(|name| name.to_string()))
+            .unwrap_or_else(|| format!("body {}", star.id()));
+        info!(
+            "{} went supernova at t = {:.2}, blowing off {:.0}% of its mass{}",
+            name,
+            state.time,
+            100.0 * (1.0 - REMNANT),
+            before.map_or_else(String::new, |before| format!(
+                ", {:.0}% of the bodies around it being bound to it",
+                100.0 * before
+            ))
+        );
+        *supernova = Supernova {
+            star: Some(star),
+            before,
+            now: before,
+        };
+    }
+}
+
+fn measure_bound(
+    g: Res<Gravity>,
+    mut supernova: ResMut<Supernova>,
+    bodies: Query<(Entity, &Mass, &Position, &Velocity), Without<Ejecta>>,
+) {
+    let star = match supernova.star.and_then(|star| bodies.get(star).ok()) {
+        Some((_, mass, pos, vel)) => (mass.0, pos.0, vel.0),
+        None => return,
+    };
+    let others: Vec<(f32, Vec3, Vec3)> = bodies
+        .iter()
+        .filter(|(entity, ..)| Some(*entity) != supernova.star)
+        .map(|(_, mass, pos, vel)| (mass.0, pos.0, vel.0))
+        .collect();
+    supernova.now = bound_fraction(g.0, star, &others);
+}
+
+/// Semi-major axes of the planets, evenly spaced
+const PLANETS: usize = 8;
+const INNER: f32 = 15.0;
+const OUTER: f32 = 50.0;
+/// Range of the eccentricities of the planets
+const ECCENTRICITY: (f32, f32) = (0.2, 0.6);
+
+/// The orientations of the orbits and where the planets are on them are drawn from the
+/// [`SimRng`], all in the XY plane
+fn nova(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    const G: f32 = 1.0;
+    const STAR_MASS: f32 = 1000.0;
+    const STAR_RADIUS: f32 = 2.0;
+    const PLANET_MASS: f32 = 0.01;
+    const PLANET_RADIUS: f32 = 0.5;
+    const COLORS: [Color; 4] = [
+        Color::rgb(0.8, 0.6, 0.4),
+        Color::rgb(0.4, 0.6, 0.9),
+        Color::rgb(0.7, 0.4, 0.3),
+        Color::rgb(0.6, 0.8, 0.7),
+    ];
+    g.0 = G;
+
+    let planets: Vec<(Vec3, Vec3)> = (0..PLANETS)
+        .map(|i| {
+            let elements = OrbitalElements {
+                semi_major_axis: (INNER + (OUTER - INNER) * i as f32 / (PLANETS - 1) as f32) as f64,
+                eccentricity: rng.0.gen_range(ECCENTRICITY.0..ECCENTRICITY.1) as f64,
+                inclination: 0.0,
+                longitude_of_ascending_node: 0.0,
+                argument_of_periapsis: rng.0.gen_range(0.0..std::f64::consts::TAU),
+                true_anomaly: rng.0.gen_range(0.0..std::f64::consts::TAU),
+            };
+            state_from_elements(&elements, G * (STAR_MASS + PLANET_MASS))
+        })
+        .collect();
+
+    // The star recoils so that the barycenter stays at the origin
+    let total = STAR_MASS + PLANET_MASS * PLANETS as f32;
+    let (moment, momentum) = planets.iter().fold(
+        (Vec3::ZERO, Vec3::ZERO),
+        |(moment, momentum), &(pos, vel)| {
+            (moment + pos * PLANET_MASS, momentum + vel * PLANET_MASS)
+        },
+    );
+    let (center, drift) = (moment / total, momentum / total);
+    let star = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(STAR_MASS, -center, -drift),
+        &Appearance {
+            radius: STAR_RADIUS,
+            color: Color::rgb(0.7, 0.8, 1.0),
+            emissive: Color::rgb(0.7, 0.8, 1.0),
+            light: Some(20_000.0),
+            rings: None,
+        },
+    );
+    commands.entity(star).insert(Name::new("Star"));
+    for (i, (pos, vel)) in planets.into_iter().enumerate() {
+        let planet = spawn_body(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            BodyBundle::new(PLANET_MASS, pos - center, vel - drift),
+            &Appearance {
+                radius: PLANET_RADIUS,
+                color: COLORS[i % COLORS.len()],
+                emissive: Color::BLACK,
+                light: None,
+                rings: None,
+            },
+        );
+        commands
+            .entity(planet)
+            .insert(Name::new(format!("Planet {}", i + 1)));
+    }
+
+    spawn_z_camera(&mut commands, 2.5 * OUTER);
+}
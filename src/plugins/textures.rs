@@ -0,0 +1,122 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+};
+use std::f32::consts::{PI, TAU};
+
+use super::nbody::Radius;
+pub use super::nbody::SurfaceTexture;
+
+/// Maps the image of their [`SurfaceTexture`] onto the bodies that have one, once it is
+/// loaded. Bodies whose image is missing keep their flat color, so the textures are
+/// optional: images are not shipped, see `assets/textures/README.md`.
+pub struct TexturesPlugin;
+
+impl Plugin for TexturesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(load_textures.system())
+            .add_system(apply_textures.system());
+    }
+}
+
+/// Image of a [`SurfaceTexture`] being loaded
+struct Loading(Handle<Texture>);
+
+/// Segments of the sphere along the longitudes and the latitudes
+const SECTORS: usize = 64;
+const STACKS: usize = 32;
+
+/// Sphere of `radius` with its poles along Z, the axis of [`Spin`](super::spin::Spin), and
+/// texture coordinates of an equirectangular map. Vertices are repeated along the seam
+/// where the longitude wraps around, which the icosphere of Bevy does not do.
+pub fn uv_sphere(radius: f32, sectors: usize, stacks: usize) -> Mesh {
+    let vertices = (sectors + 1) * (stacks + 1);
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(vertices);
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(vertices);
+    let mut uvs = Vec::with_capacity(vertices);
+    for i in 0..=stacks {
+        let v = i as f32 / stacks as f32;
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        for j in 0..=sectors {
+            let u = j as f32 / sectors as f32;
+            let (sin_phi, cos_phi) = (u * TAU).sin_cos();
+            let normal = Vec3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta);
+            positions.push((normal * radius).into());
+            normals.push(normal.into());
+            uvs.push([u, v]);
+        }
+    }
+
+    // Counterclockwise seen from outside
+    let row = sectors as u32 + 1;
+    let mut indices = Vec::with_capacity(sectors * stacks * 6);
+    for i in 0..stacks as u32 {
+        for j in 0..sectors as u32 {
+            let (top, bottom) = (i * row + j, (i + 1) * row + j);
+            indices.extend_from_slice(&[top, bottom, bottom + 1, top, bottom + 1, top + 1]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
+
+fn load_textures(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &SurfaceTexture), Added<SurfaceTexture>>,
+) {
+    for (entity, texture) in query.iter() {
+        commands
+            .entity(entity)
+            .insert(Loading(asset_server.load(texture.0.as_str())));
+    }
+}
+
+/// The mesh is replaced in place rather than swapped for another one, so that the
+/// [`LodPlugin`](super::lod::LodPlugin) keeps it as the finest level
+#[allow(clippy::type_complexity)]
+fn apply_textures(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query: Query<(
+        Entity,
+        &SurfaceTexture,
+        &Loading,
+        &Radius,
+        &Handle<Mesh>,
+        &Handle<StandardMaterial>,
+    )>,
+) {
+    for (entity, texture, loading, radius, mesh, material) in query.iter() {
+        match asset_server.get_load_state(&loading.0) {
+            LoadState::Loaded => {}
+            LoadState::Failed => {
+                warn!("no texture `{}`, keeping the color of the body", texture.0);
+                commands.entity(entity).remove::<Loading>();
+                continue;
+            }
+            _ => continue,
+        }
+        commands.entity(entity).remove::<Loading>();
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            *mesh = uv_sphere(radius.0, SECTORS, STACKS);
+        }
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = Color::WHITE;
+            material.base_color_texture = Some(loading.0.clone());
+            // Stars glow with the colors of their surface
+            if material.emissive != Color::BLACK {
+                material.emissive = Color::WHITE;
+                material.emissive_texture = Some(loading.0.clone());
+            }
+        }
+    }
+}
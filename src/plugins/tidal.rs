@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use rand_distr::{Distribution, UnitSphere};
+use std::f32::consts::PI;
+
+use super::nbody::{
+    BodyBundle, Mass, PhysicsSystem, Position, Radius, SimState, TestParticle, TrueRadius, Velocity,
+};
+use super::random::SimRng;
+
+/// Tears apart the bodies passing within the Roche limit of a heavier one, as Jupiter did
+/// with comet Shoemaker-Levy 9: the body is replaced with fragments of its density, strung
+/// along the line to the heavier body, which conserve its mass, center of mass and linear
+/// momentum. The fragments drift apart along their orbits, helped by a slight random
+/// dispersion of their velocities drawn from the [`SimRng`], and are not torn apart again.
+pub struct TidalPlugin {
+    /// Number of fragments each body breaks into, at least 2
+    pub fragments: usize,
+}
+
+impl Plugin for TidalPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(FragmentCount(self.fragments.max(2)))
+            .add_system(break_up.system().after(PhysicsSystem::Collisions));
+    }
+}
+
+/// Coefficient of the Roche limit of a fluid body, held together by its gravity only, e.g.
+/// a comet or rubble pile
+const ROCHE_COEFFICIENT: f32 = 2.44;
+/// Random velocities of the fragments, as a fraction of the speed of the body relative to
+/// the heavier one
+const DISPERSION: f32 = 0.002;
+
+struct FragmentCount(usize);
+
+/// Body born from the breakup of another one
+pub struct Fragment;
+
+/// Distance to a body of mass `big_mass` and radius `big_radius` within which one of
+/// `small_mass` and `small_radius`, held together by its own gravity, is torn apart by the
+/// difference of the attraction on its near and far sides:
+/// ```text
+/// d = 2.44 * R * (ρ_M / ρ_m)^(1/3)
+/// ```
+/// with the densities `ρ_M` and `ρ_m` of the bodies
+fn roche_limit((big_mass, big_radius): (f32, f32), (small_mass, small_radius): (f32, f32)) -> f32 {
+    let density = |mass: f32, radius: f32| mass / (4.0 / 3.0 * PI * radius.powi(3));
+    let ratio = density(big_mass, big_radius) / density(small_mass, small_radius);
+    ROCHE_COEFFICIENT * big_radius * ratio.cbrt()
+}
+
+fn body_name(entity: Entity, name: Option<&Name>) -> String {
+    name.map_or_else(|| format!("body {}", entity.id()), |n| n.to_string())
+}
+
+/// Densities are those of the true sizes of the bodies, whatever the sizes they are drawn
+/// with. Test particles have no density, and the bodies touching the heavier one are left
+/// to the collisions.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn break_up(
+    mut commands: Commands,
+    state: Res<SimState>,
+    fragments: Res<FragmentCount>,
+    mut rng: ResMut<SimRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bodies: Query<
+        (
+            Entity,
+            &Mass,
+            &Position,
+            &Velocity,
+            &Radius,
+            Option<&TrueRadius>,
+            Option<&Name>,
+            Option<&Fragment>,
+        ),
+        Without<TestParticle>,
+    >,
+    materials: Query<&Handle<StandardMaterial>>,
+) {
+    let true_radius = |radius: &Radius, true_radius: Option<&TrueRadius>| {
+        true_radius.map_or(radius.0, |true_radius| true_radius.0)
+    };
+    for (entity, mass, pos, vel, radius, true_radius_of, name, fragment) in bodies.iter() {
+        if fragment.is_some() || mass.0 <= 0.0 {
+            continue;
+        }
+        let size = true_radius(radius, true_radius_of);
+        let tearing = bodies
+            .iter()
+            .filter(|(other, other_mass, ..)| *other != entity && other_mass.0 > mass.0)
+            .find(
+                |&(_, other_mass, other_pos, _, other_radius, other_true_radius, ..)| {
+                    let other_size = true_radius(other_radius, other_true_radius);
+                    let distance = pos.0.distance(other_pos.0);
+                    distance > other_size + size
+                        && distance < roche_limit((other_mass.0, other_size), (mass.0, size))
+                },
+            );
+        let (other, _, other_pos, other_vel, _, _, other_name, _) = match tearing {
+            Some(tearing) => tearing,
+            None => continue,
+        };
+
+        let n = fragments.0;
+        // Same density, the volume being shared
+        let shrink = (n as f32).cbrt().recip();
+        let axis = (pos.0 - other_pos.0).normalize();
+        let dispersion = DISPERSION * vel.0.distance(other_vel.0);
+        let kicks: Vec<Vec3> = (0..n)
+            .map(|_| Vec3::from(UnitSphere.sample(&mut rng.0)) * dispersion)
+            .collect();
+        let mean_kick = kicks.iter().sum::<Vec3>() / n as f32;
+        let mesh = meshes.add(Mesh::from(shape::Icosphere {
+            radius: radius.0 * shrink,
+            subdivisions: 3,
+        }));
+        let material = materials.get(entity).ok().cloned();
+        let name = body_name(entity, name);
+
+        for (i, kick) in kicks.iter().enumerate() {
+            // Touching each other in a line centered on the body, like pearls on a string
+            let offset = (i as f32 - (n - 1) as f32 / 2.0) * 2.0 * size * shrink;
+            let mut fragment = commands.spawn();
+            if let Some(material) = material.clone() {
+                fragment.insert_bundle(PbrBundle {
+                    mesh: mesh.clone(),
+                    material,
+                    ..Default::default()
+                });
+            }
+            fragment
+                .insert_bundle(
+                    BodyBundle::new(
+                        mass.0 / n as f32,
+                        pos.0 + axis * offset,
+                        vel.0 + *kick - mean_kick,
+                    )
+                    .with_radius(radius.0 * shrink),
+                )
+                .insert(Fragment)
+                // Named from A, as the fragments of Shoemaker-Levy 9 were
+                .insert(Name::new(format!(
+                    "{} {}",
+                    name,
+                    char::from(b'A' + (i % 26) as u8)
+                )));
+            if let Some(true_radius) = true_radius_of {
+                fragment.insert(TrueRadius(true_radius.0 * shrink));
+            }
+        }
+        commands.entity(entity).despawn_recursive();
+        info!(
+            "{} torn apart by {} into {} fragments at t = {:.2}",
+            name,
+            body_name(other, other_name),
+            n,
+            state.time
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut, to_local};
+use super::nbody::{Mass, PhysicsSystem, ReferenceFrame};
+
+/// Draws a fading trail behind every body
+pub struct TrailsPlugin {
+    /// Number of recorded positions per trail
+    pub length: usize,
+    /// Initial value of [`ShowTrails`]
+    pub enabled: bool,
+}
+
+impl Default for TrailsPlugin {
+    fn default() -> Self {
+        Self {
+            length: 500,
+            enabled: true,
+        }
+    }
+}
+
+impl Plugin for TrailsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(TrailLength(self.length))
+            .insert_resource(ShowTrails(self.enabled))
+            .add_system(add_trails.system())
+            .add_system(remove_trails.system())
+            .add_system(update_trails.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+pub struct TrailLength(pub usize);
+
+/// Trails are recorded and drawn only while this is set
+pub struct ShowTrails(pub bool);
+
+/// Recent rendered positions of a body, in a fixed-capacity ring buffer.
+/// They are drawn by a child line strip, so that the trail is despawned with the body.
+pub struct Trail {
+    positions: Vec<Vec3>,
+    length: usize,
+    /// Index of the next position to overwrite once the buffer is full
+    head: usize,
+    mesh: Handle<Mesh>,
+    /// Child entity drawing the trail
+    line: Entity,
+    color: Color,
+}
+
+impl Trail {
+    fn push(&mut self, pos: Vec3) {
+        if self.positions.len() < self.length {
+            self.positions.push(pos);
+        } else {
+            self.positions[self.head] = pos;
+            self.head = (self.head + 1) % self.positions.len();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.positions.clear();
+        self.head = 0;
+    }
+
+    fn last(&self) -> Option<Vec3> {
+        match self.head {
+            0 => self.positions.last().copied(),
+            head => Some(self.positions[head - 1]),
+        }
+    }
+
+    /// Positions from the most recent to the oldest
+    fn iter(&self) -> impl Iterator<Item = &Vec3> {
+        let (recent, old) = self.positions.split_at(self.head);
+        old.iter().chain(recent).rev()
+    }
+}
+
+/// Give a trail to new bodies, including the ones resulting from a merge
+#[allow(clippy::type_complexity)]
+fn add_trails(
+    mut commands: Commands,
+    show: Res<ShowTrails>,
+    length: Res<TrailLength>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    query: Query<(Entity, Option<&Handle<StandardMaterial>>), (With<Mass>, Without<Trail>)>,
+) {
+    if !show.0 {
+        return;
+    }
+    for (entity, material) in query.iter() {
+        let color = material
+            .and_then(|material| materials.get(material))
+            .map_or(Color::WHITE, |material| material.base_color);
+        let mesh = meshes.add(empty_mesh(PrimitiveTopology::LineStrip, length.0));
+        let line = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+        commands
+            .entity(entity)
+            .push_children(&[line])
+            .insert(Trail {
+                positions: Vec::with_capacity(length.0),
+                length: length.0,
+                head: 0,
+                mesh,
+                line,
+                color,
+            });
+    }
+}
+
+/// Drop the trails once they are hidden, they start anew when shown again
+fn remove_trails(mut commands: Commands, show: Res<ShowTrails>, query: Query<(Entity, &Trail)>) {
+    if show.0 {
+        return;
+    }
+    for (entity, trail) in query.iter() {
+        commands.entity(trail.line).despawn_recursive();
+        commands.entity(entity).remove::<Trail>();
+    }
+}
+
+/// Record the current positions and rebuild the trail meshes in place, in the local space
+/// of their body. Alpha fades out linearly from the body to the end of the trail.
+/// The recorded positions are relative to the [`ReferenceFrame`], they are dropped when
+/// it changes.
+fn update_trails(
+    frame: Res<ReferenceFrame>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&Transform, &mut Trail)>,
+) {
+    for (transform, mut trail) in query.iter_mut() {
+        if frame.is_changed() {
+            trail.clear();
+        }
+        if trail.last() == Some(transform.translation) {
+            continue;
+        }
+        trail.push(transform.translation);
+
+        let mesh = match meshes.get_mut(&trail.mesh) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        let mut recorded = trail
+            .iter()
+            .map(|pos| to_local(transform, *pos - transform.translation));
+        let positions = positions_mut(mesh);
+        // Unused vertices collapse on the end of the trail
+        let mut end = Vec3::ZERO;
+        for vertex in positions.iter_mut() {
+            end = recorded.next().unwrap_or(end);
+            *vertex = end.into();
+        }
+
+        let n = positions.len() as f32;
+        let [r, g, b, _] = trail.color.as_rgba_f32();
+        for (i, color) in colors_mut(mesh).iter_mut().enumerate() {
+            let alpha = if i < trail.positions.len() {
+                1.0 - i as f32 / n
+            } else {
+                0.0
+            };
+            *color = [r, g, b, alpha];
+        }
+    }
+}
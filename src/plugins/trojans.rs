@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::nbody::{BodyBundle, Gravity, TestParticle, TrueRadius, G};
+use super::random::SimRng;
+use crate::lagrange::lagrange_points;
+use crate::scene::{spawn_body, Appearance};
+use crate::{km_to_units, spawn_z_camera, units, AU_TO_UNIT_SCALE};
+
+/// The Sun and Jupiter on a circular orbit, with test particles scattered around their L4
+/// and L5 points: the Trojan asteroids, which librate around them instead of drifting
+/// away. The particles are drawn from the [`SimRng`]. Best watched in the co-rotating
+/// frame `--frame rotating:Sun,Jupiter` or `corotating:Sun:Jupiter`, where they trace
+/// tadpoles around the points.
+pub struct TrojansPlugin {
+    /// Asteroids, split between the two points
+    pub count: usize,
+}
+
+impl Plugin for TrojansPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(TrojanCount(self.count))
+            .add_startup_system(trojans.system());
+    }
+}
+
+struct TrojanCount(usize);
+
+/// Semi-major axis of Jupiter, in AU
+const JUPITER_DISTANCE: f32 = 5.2026;
+/// In 10^24 kg
+const JUPITER_MASS: f32 = 1898.187;
+/// Largest offsets of the asteroids from their point, along the distance to the barycenter
+/// as a fraction of it, and along the orbit in degrees. Larger ones librate further, up to
+/// horseshoes around L3 and L4 and L5 together, and then escapes.
+const RADIAL_SPREAD: f32 = 0.01;
+const ANGULAR_SPREAD: f32 = 5.0;
+const RADIUS: f32 = 0.1;
+
+fn trojans(
+    mut commands: Commands,
+    count: Res<TrojanCount>,
+    mut rng: ResMut<SimRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+) {
+    g.0 = G * units::solar_g_scale(AU_TO_UNIT_SCALE);
+    let sun_mass = units::kg_to_mass_units(units::SOLAR_MASS) as f32;
+
+    // Both on circular orbits around their barycenter at rest at the origin, turning at
+    // the mean motion `sqrt(G * M / a^3)` of their relative orbit
+    let distance = AU_TO_UNIT_SCALE * JUPITER_DISTANCE;
+    let total_mass = sun_mass + JUPITER_MASS;
+    let mean_motion = (g.0 * total_mass / distance.powi(3)).sqrt();
+    let velocity = |pos: Vec3| Vec3::Z.cross(pos) * mean_motion;
+    let sun_pos = Vec3::X * -distance * JUPITER_MASS / total_mass;
+    let jupiter_pos = Vec3::X * distance * sun_mass / total_mass;
+
+    let sun = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(sun_mass, sun_pos, velocity(sun_pos)),
+        &Appearance {
+            radius: 2.8,
+            color: Color::YELLOW,
+            emissive: Color::YELLOW,
+            light: Some(50_000.0),
+            rings: None,
+        },
+    );
+    commands
+        .entity(sun)
+        .insert(TrueRadius(km_to_units(695_700.0)))
+        .insert(Name::new("Sun"));
+    let jupiter = spawn_body(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        BodyBundle::new(JUPITER_MASS, jupiter_pos, velocity(jupiter_pos)),
+        &Appearance {
+            radius: 6.991,
+            color: Color::rgb(1.0, 0.89, 0.77),
+            emissive: Color::BLACK,
+            light: None,
+            rings: None,
+        },
+    );
+    commands
+        .entity(jupiter)
+        .insert(TrueRadius(km_to_units(69_911.0)))
+        .insert(Name::new("Jupiter"));
+
+    // Shared by all the asteroids
+    let mesh = meshes.add(Mesh::from(shape::Icosphere {
+        radius: RADIUS,
+        subdivisions: 1,
+    }));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::GRAY,
+        roughness: 0.9,
+        reflectance: 0.1,
+        ..Default::default()
+    });
+    let points = lagrange_points(sun_mass, sun_pos, JUPITER_MASS, jupiter_pos, Vec3::Z);
+    let rng = &mut rng.0;
+    let split = [
+        ("L4", points[3], count.0.div_ceil(2)),
+        ("L5", points[4], count.0 / 2),
+    ];
+    for (name, point, count) in split {
+        for i in 0..count {
+            let scale = 1.0 + rng.gen_range(-RADIAL_SPREAD..RADIAL_SPREAD);
+            let angle = rng.gen_range(-ANGULAR_SPREAD..ANGULAR_SPREAD).to_radians();
+            let pos = Quat::from_rotation_z(angle) * point * scale;
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    ..Default::default()
+                })
+                .insert_bundle(BodyBundle::new(0.0, pos, velocity(pos)).with_radius(RADIUS))
+                .insert(TestParticle)
+                .insert(Name::new(format!("{} asteroid {}", name, i + 1)));
+        }
+    }
+
+    spawn_z_camera(&mut commands, 150.0);
+}
@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+
+/// Camera and font shared by the text overlays drawn on top of the scene
+pub struct UiCameraPlugin;
+
+impl Plugin for UiCameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let font = app
+            .world()
+            .get_resource::<AssetServer>()
+            .expect("UiCameraPlugin requires the AssetPlugin")
+            .load("fonts/DejaVuSans.ttf");
+        app.insert_resource(UiFont(font))
+            .add_startup_system(spawn_ui_camera.system());
+    }
+}
+
+pub struct UiFont(pub Handle<Font>);
+
+fn spawn_ui_camera(mut commands: Commands) {
+    commands.spawn_bundle(UiCameraBundle::default());
+}
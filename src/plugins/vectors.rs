@@ -0,0 +1,124 @@
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+
+use super::lines::{colors_mut, empty_mesh, line_bundle, positions_mut, to_local};
+use super::nbody::{Acceleration, FrameRotation, Mass, PhysicsSystem, Velocity};
+
+/// Draws arrows from the center of every body along its velocity and its acceleration
+pub struct VectorsPlugin {
+    /// Initial value of [`ShowVectors`]
+    pub enabled: bool,
+}
+
+impl Plugin for VectorsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(ShowVectors(self.enabled))
+            .add_system(add_arrows.system())
+            .add_system(remove_arrows.system())
+            .add_system(update_arrows.system().after(PhysicsSystem::Interpolate));
+    }
+}
+
+/// Arrows are drawn only while this is set
+pub struct ShowVectors(pub bool);
+
+const VELOCITY_COLOR: Color = Color::rgb(0.2, 0.9, 0.3);
+const ACCELERATION_COLOR: Color = Color::rgb(1.0, 0.3, 0.2);
+
+/// Magnitudes below which arrows shrink linearly to nothing, above which they grow
+/// logarithmically, so that the Sun and a comet both get a readable arrow
+const VELOCITY_REFERENCE: f32 = 1e-3;
+const ACCELERATION_REFERENCE: f32 = 1e-5;
+/// Length of an arrow per e-fold of its magnitude over the reference, in scene units
+const ARROW_SCALE: f32 = 1.0;
+/// Length of the two strokes of an arrowhead, as a fraction of the arrow
+const HEAD_SIZE: f32 = 0.2;
+
+/// Vertices of one arrow in the line list: the shaft and two strokes for the head
+const ARROW_VERTICES: usize = 6;
+
+/// Line list drawing the arrows of a body, as a child entity
+struct Arrows {
+    mesh: Handle<Mesh>,
+    line: Entity,
+}
+
+fn add_arrows(
+    mut commands: Commands,
+    show: Res<ShowVectors>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<Entity, (With<Mass>, Without<Arrows>)>,
+) {
+    if !show.0 {
+        return;
+    }
+    for entity in query.iter() {
+        let mut mesh = empty_mesh(PrimitiveTopology::LineList, 2 * ARROW_VERTICES);
+        for (i, color) in colors_mut(&mut mesh).iter_mut().enumerate() {
+            *color = if i < ARROW_VERTICES {
+                VELOCITY_COLOR.as_rgba_f32()
+            } else {
+                ACCELERATION_COLOR.as_rgba_f32()
+            };
+        }
+        let mesh = meshes.add(mesh);
+        let line = commands.spawn_bundle(line_bundle(mesh.clone())).id();
+        commands
+            .entity(entity)
+            .push_children(&[line])
+            .insert(Arrows { mesh, line });
+    }
+}
+
+fn remove_arrows(mut commands: Commands, show: Res<ShowVectors>, query: Query<(Entity, &Arrows)>) {
+    if show.0 {
+        return;
+    }
+    for (entity, arrows) in query.iter() {
+        commands.entity(arrows.line).despawn_recursive();
+        commands.entity(entity).remove::<Arrows>();
+    }
+}
+
+/// Vertices of an arrow from the origin along `v`, `ln(1 + |v| / reference)` long
+fn arrow(v: Vec3, reference: f32) -> [Vec3; ARROW_VERTICES] {
+    let magnitude = v.length();
+    if magnitude == 0.0 {
+        return [Vec3::ZERO; ARROW_VERTICES];
+    }
+    let dir = v / magnitude;
+    let tip = dir * ARROW_SCALE * (1.0 + magnitude / reference).ln();
+    // Strokes of the head in the plane of the arrow and the Z axis, the normal of
+    // the orbits of most scenes
+    let side = dir.cross(Vec3::Z);
+    let side = if side.length_squared() > 1e-6 {
+        side.normalize()
+    } else {
+        Vec3::X
+    };
+    let back = tip * (1.0 - HEAD_SIZE);
+    let spread = side * tip.length() * HEAD_SIZE / 2.0;
+    [Vec3::ZERO, tip, tip, back + spread, tip, back - spread]
+}
+
+/// Rebuild the arrows from the velocity and the latest acceleration computed by the
+/// integrator, in the local space of their body and turned like the [`FrameRotation`]
+fn update_arrows(
+    rotation: Res<FrameRotation>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<(&Transform, &Velocity, &Acceleration, &Arrows)>,
+) {
+    for (transform, vel, acc, arrows) in query.iter() {
+        let mesh = match meshes.get_mut(&arrows.mesh) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        let vertices = arrow(rotation.0 * vel.0, VELOCITY_REFERENCE)
+            .iter()
+            .chain(&arrow(rotation.0 * acc.0, ACCELERATION_REFERENCE))
+            .map(|&pos| to_local(transform, pos))
+            .collect::<Vec<_>>();
+        for (vertex, pos) in positions_mut(mesh).iter_mut().zip(vertices) {
+            *vertex = pos.into();
+        }
+    }
+}
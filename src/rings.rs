@@ -0,0 +1,83 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::lines::line_bundle;
+
+/// Planetary rings, drawn as a flat annulus in the equatorial plane of the body.
+/// Distances are in scene units, from the center of the body.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Rings {
+    pub inner: f32,
+    pub outer: f32,
+    /// Angle between the equatorial plane and the XY plane (the ecliptic in the solar
+    /// system), in degrees
+    #[serde(default)]
+    pub tilt: f32,
+}
+
+const SEGMENTS: usize = 128;
+const BANDS: usize = 48;
+
+/// Opacity at `t`, between 0 at the inner edge and 1 at the outer one.
+/// A few sines of unrelated frequencies give irregular bands.
+fn banding(t: f32) -> f32 {
+    let bands = 0.55 + 0.2 * (23.0 * t).sin() + 0.15 * (57.0 * t + 1.0).sin();
+    let edges = (t * 10.0).min((1.0 - t) * 10.0).min(1.0);
+    (bands * edges).clamp(0.0, 1.0)
+}
+
+/// Triangle mesh of the annulus in the XY plane, with per-vertex colors for the line pipeline
+fn ring_mesh(rings: &Rings, color: Color) -> Mesh {
+    let [r, g, b, _] = color.as_rgba_f32();
+    let mut positions = Vec::with_capacity(SEGMENTS * (BANDS + 1));
+    let mut colors = Vec::with_capacity(SEGMENTS * (BANDS + 1));
+    for i in 0..SEGMENTS {
+        let angle = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+        let (sin, cos) = angle.sin_cos();
+        for j in 0..=BANDS {
+            let t = j as f32 / BANDS as f32;
+            let radius = rings.inner + t * (rings.outer - rings.inner);
+            positions.push([radius * cos, radius * sin, 0.0]);
+            colors.push([r, g, b, 0.8 * banding(t)]);
+        }
+    }
+
+    let mut indices = Vec::with_capacity(SEGMENTS * BANDS * 6);
+    let vertex = |i: usize, j: usize| ((i % SEGMENTS) * (BANDS + 1) + j) as u32;
+    for i in 0..SEGMENTS {
+        for j in 0..BANDS {
+            let (a, b) = (vertex(i, j), vertex(i, j + 1));
+            let (c, d) = (vertex(i + 1, j), vertex(i + 1, j + 1));
+            indices.extend_from_slice(&[a, c, b, b, c, d]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Give rings to `body`, drawn by a child entity so that they move with it.
+/// Their color is a paler shade of the color of the body.
+pub fn spawn_rings(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    body: Entity,
+    rings: Rings,
+    color: Color,
+) {
+    let [r, g, b, _] = color.as_rgba_f32();
+    let color = Color::rgb((1.0 + r) / 2.0, (1.0 + g) / 2.0, (1.0 + b) / 2.0);
+    let ring = commands
+        .spawn_bundle(MeshBundle {
+            transform: Transform::from_rotation(Quat::from_rotation_x(rings.tilt.to_radians())),
+            ..line_bundle(meshes.add(ring_mesh(&rings, color)))
+        })
+        .id();
+    commands.entity(body).insert(rings).push_children(&[ring]);
+}
@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use crate::kepler::{state_from_elements, OrbitalElements};
+use crate::plugins::nbody::{
+    Beta, BodyBundle, Gravity, GravityGroups, Group, TestParticle, TrueRadius, G,
+};
+use crate::plugins::textures::SurfaceTexture;
+use crate::rings::{spawn_rings, Rings};
+use crate::spawn_z_camera;
+use crate::units::radius_from_mass_density;
+
+/// Initial conditions of a simulation, loaded from a RON file:
+/// ```ron
+/// (
+///     g_scale: 2229.7247,
+///     unit_scale: 10.0,
+///     bodies: [
+///         (name: "Sun", mass: 1988500.0, position: (0.0, 0.0, 0.0), velocity: (0.0, 0.0, 0.0),
+///          radius: 2.8, color: (1.0, 1.0, 0.0), light: Some(50000.0)),
+///         (name: "Earth", mass: 5.972, radius: 0.64, color: (0.0, 0.0, 1.0),
+///          orbit: Some((around: "Sun", semi_major_axis: 1.0, eccentricity: 0.0167))),
+///     ],
+/// )
+/// ```
+/// A body can be given a `density` instead of its `radius`, in masses of the file per cubic
+/// scene unit, the radius then being that of a uniform sphere, see
+/// [`radius_from_mass_density`]. Merges conserving the volume, and the actual volume of the
+/// [`TrueRadius`] with a `radius` too, two bodies of the same density merge into one of that
+/// density too.
+/// Bodies can be put in groups that do not attract each other, e.g. asteroids in
+/// `group: Some("asteroids")` with `no_gravity: [("asteroids", "asteroids")]`.
+#[derive(Deserialize)]
+pub struct SceneDescription {
+    /// Factor applied to the SI gravitational constant to account for the units of the file
+    pub g_scale: f32,
+    /// Scene units per unit of distance of the file, applied to positions and velocities
+    pub unit_scale: f32,
+    pub bodies: Vec<BodyDescription>,
+    /// Pairs of groups where the bodies of the first do not attract those of the second, see
+    /// [`GravityGroups`]
+    #[serde(default)]
+    pub no_gravity: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+pub struct BodyDescription {
+    pub name: String,
+    pub mass: f32,
+    /// Ignored if the body is described by its `orbit`
+    #[serde(default)]
+    pub position: (f32, f32, f32),
+    #[serde(default)]
+    pub velocity: (f32, f32, f32),
+    #[serde(default)]
+    pub orbit: Option<OrbitDescription>,
+    /// Radius in scene units, it is not affected by `unit_scale`. Without one, the radius of
+    /// the `density`.
+    #[serde(default)]
+    pub radius: f32,
+    /// In masses of the file per cubic scene unit, giving the actual radius of the body: its
+    /// [`TrueRadius`] when it is drawn with an exaggerated `radius`
+    #[serde(default)]
+    pub density: Option<f32>,
+    /// Actual radius in scene units when `radius` is exaggerated, see [`TrueRadius`]
+    #[serde(default)]
+    pub true_radius: Option<f32>,
+    /// RGB, between 0 and 1
+    pub color: (f32, f32, f32),
+    /// Intensity of a point light emitted by the body, for stars
+    #[serde(default)]
+    pub light: Option<f32>,
+    /// e.g. `rings: Some((inner: 7.5, outer: 13.7, tilt: 26.7))`
+    #[serde(default)]
+    pub rings: Option<Rings>,
+    /// Feels gravity but exerts none, see [`TestParticle`]
+    #[serde(default)]
+    pub test_particle: bool,
+    /// Pushed away from the Sun with `--radiation-pressure`, see [`Beta`]
+    #[serde(default)]
+    pub beta: Option<f32>,
+    /// Image in the assets folder drawn on the body with `--textures`, see
+    /// [`SurfaceTexture`], e.g. `Some("textures/earth.png")`
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// See [`Group`], e.g. `Some("asteroids")`
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// Two-body orbit around a body described earlier in the file, in the units of the file
+/// for the semi-major axis and in degrees for the angles
+#[derive(Deserialize)]
+pub struct OrbitDescription {
+    pub around: String,
+    pub semi_major_axis: f32,
+    #[serde(default)]
+    pub eccentricity: f32,
+    #[serde(default)]
+    pub inclination: f32,
+    #[serde(default)]
+    pub longitude_of_ascending_node: f32,
+    #[serde(default)]
+    pub argument_of_periapsis: f32,
+    #[serde(default)]
+    pub true_anomaly: f32,
+}
+
+impl OrbitDescription {
+    fn elements(&self, unit_scale: f32) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis: (unit_scale * self.semi_major_axis) as f64,
+            eccentricity: self.eccentricity as f64,
+            inclination: self.inclination.to_radians() as f64,
+            longitude_of_ascending_node: self.longitude_of_ascending_node.to_radians() as f64,
+            argument_of_periapsis: self.argument_of_periapsis.to_radians() as f64,
+            true_anomaly: self.true_anomaly.to_radians() as f64,
+        }
+    }
+}
+
+pub enum SceneError {
+    Io(String, std::io::Error),
+    Parse(String, ron::Error),
+    /// Path and description of the error, of a CSV file of state vectors
+    Csv(String, String),
+    /// Path of the scene, and name of a body whose density is not positive
+    Density(String, String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(path, e) => write!(f, "cannot read scene `{}`: {}", path, e),
+            SceneError::Parse(path, e) => write!(f, "invalid scene `{}`: {}", path, e),
+            SceneError::Csv(path, e) => write!(f, "invalid CSV `{}`: {}", path, e),
+            SceneError::Density(path, body) => write!(
+                f,
+                "invalid scene `{}`: the density of `{}` is not positive",
+                path, body
+            ),
+        }
+    }
+}
+
+impl SceneDescription {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let content = fs::read_to_string(path).map_err(|e| SceneError::Io(name.clone(), e))?;
+        let scene: Self =
+            ron::from_str(&content).map_err(|e| SceneError::Parse(name.clone(), e))?;
+        // Zero, negative or NaN densities would give infinite or NaN radii
+        let invalid = scene.bodies.iter().find(|body| {
+            body.density
+                .is_some_and(|density| !density.is_finite() || density <= 0.0)
+        });
+        match invalid {
+            Some(body) => Err(SceneError::Density(name, body.name.clone())),
+            None => Ok(scene),
+        }
+    }
+}
+
+fn vec3((x, y, z): (f32, f32, f32)) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+/// How a body is rendered
+pub struct Appearance {
+    pub radius: f32,
+    pub color: Color,
+    pub emissive: Color,
+    /// Intensity of a point light emitted by the body
+    pub light: Option<f32>,
+    pub rings: Option<Rings>,
+}
+
+/// Spawn a body with a sphere mesh
+pub fn spawn_body(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    body: BodyBundle,
+    appearance: &Appearance,
+) -> Entity {
+    let mut entity = commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Icosphere {
+            radius: appearance.radius,
+            subdivisions: 5,
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: appearance.color,
+            roughness: 0.6,
+            reflectance: 0.1,
+            emissive: appearance.emissive,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    entity.insert_bundle(body.with_radius(appearance.radius));
+    if let Some(intensity) = appearance.light {
+        entity.insert(Light {
+            color: Color::WHITE,
+            intensity,
+            range: 2000.0,
+            ..Default::default()
+        });
+    }
+    let entity = entity.id();
+    if let Some(rings) = appearance.rings {
+        spawn_rings(commands, meshes, entity, rings, appearance.color);
+    }
+    entity
+}
+
+/// Spawn the bodies of the [`SceneDescription`] resource
+pub fn spawn_scene(
+    mut commands: Commands,
+    scene: Res<SceneDescription>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+    mut groups: ResMut<GravityGroups>,
+) {
+    g.0 = G * scene.g_scale;
+    *groups = GravityGroups::without_gravity(&scene.no_gravity);
+
+    // Mass, position and velocity of the bodies spawned so far, for the orbits around them
+    let mut spawned = HashMap::new();
+    let mut extent: f32 = 0.0;
+    for body in scene.bodies.iter() {
+        let mut pos = scene.unit_scale * vec3(body.position);
+        let mut vel = scene.unit_scale * vec3(body.velocity);
+        if let Some(orbit) = &body.orbit {
+            match spawned.get(&orbit.around) {
+                Some(&(mass, center, center_vel)) => {
+                    let mu = g.0 * (mass + body.mass);
+                    let (r, v) = state_from_elements(&orbit.elements(scene.unit_scale), mu);
+                    pos = center + r;
+                    vel = center_vel + v;
+                }
+                None => warn!(
+                    "`{}` orbits `{}`, which is not described before it",
+                    body.name, orbit.around
+                ),
+            }
+        }
+        spawned.insert(body.name.clone(), (body.mass, pos, vel));
+        extent = extent.max(pos.length());
+
+        let density_radius = body
+            .density
+            .map(|density| radius_from_mass_density(body.mass as f64, density as f64) as f32);
+        let (radius, true_radius) = match density_radius {
+            Some(actual) if body.radius > 0.0 => (body.radius, body.true_radius.or(Some(actual))),
+            Some(actual) => (actual, body.true_radius),
+            None => (body.radius, body.true_radius),
+        };
+        if radius <= 0.0 && body.density.is_none() {
+            warn!("`{}` has neither a radius nor a density", body.name);
+        }
+
+        let color = Color::rgb(body.color.0, body.color.1, body.color.2);
+        let appearance = Appearance {
+            radius,
+            color,
+            emissive: if body.light.is_some() {
+                color
+            } else {
+                Color::BLACK
+            },
+            light: body.light,
+            rings: body.rings,
+        };
+        let entity = spawn_body(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            BodyBundle::new(body.mass, pos, vel),
+            &appearance,
+        );
+        commands.entity(entity).insert(Name::new(body.name.clone()));
+        if let Some(radius) = true_radius {
+            commands.entity(entity).insert(TrueRadius(radius));
+        }
+        if body.test_particle {
+            commands.entity(entity).insert(TestParticle);
+        }
+        if let Some(beta) = body.beta {
+            commands.entity(entity).insert(Beta(beta));
+        }
+        if let Some(texture) = &body.texture {
+            commands
+                .entity(entity)
+                .insert(SurfaceTexture(texture.clone()));
+        }
+        if let Some(group) = &body.group {
+            commands.entity(entity).insert(Group(group.clone()));
+        }
+    }
+
+    spawn_z_camera(&mut commands, (2.5 * extent).max(10.0));
+}
@@ -0,0 +1,298 @@
+use bevy::{app::AppExit, math::DVec3, prelude::*};
+use copernicus::integrator::{Integrator, Substeps};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path::Path, path::PathBuf};
+
+use crate::plugins::nbody::{
+    Beta, BodyBundle, Gravity, GravityGroups, Group, Mass, PhysicsRate, Position, PreciseState,
+    Precision, Radius, SimState, TestParticle, Timestep, TrueRadius, Velocity,
+};
+use crate::rings::Rings;
+use crate::scene::{spawn_body, Appearance};
+use crate::spawn_z_camera;
+
+/// Full state of a simulation, in scene units. Floats are written with their shortest
+/// exact representation so that restoring a snapshot reproduces the same trajectory.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub time: f64,
+    pub speed_factor: f32,
+    pub g: f32,
+    pub bodies: Vec<BodySnapshot>,
+    /// See [`SceneDescription::no_gravity`](crate::scene::SceneDescription::no_gravity)
+    #[serde(default)]
+    pub no_gravity: Vec<(String, String)>,
+    /// Missing from the states saved before they were, which then keep those of the flags
+    #[serde(default)]
+    pub settings: Option<Settings>,
+}
+
+/// How the bodies were being advanced, restored over the flags for the trajectory to go on
+/// as it would have without the snapshot
+#[derive(Serialize, Deserialize)]
+pub struct Settings {
+    pub integrator: Integrator,
+    pub substeps: u32,
+    pub adaptive_substeps: bool,
+    /// See [`Timestep`]
+    pub dt: Option<f64>,
+    /// See [`PhysicsRate`]
+    pub physics_hz: f64,
+    pub precision: Precision,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BodySnapshot {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub mass: f32,
+    pub position: (f32, f32, f32),
+    pub velocity: (f32, f32, f32),
+    /// See [`PreciseState`], of which `position` and `velocity` are then rounded copies
+    #[serde(default)]
+    pub precise_position: Option<(f64, f64, f64)>,
+    #[serde(default)]
+    pub precise_velocity: Option<(f64, f64, f64)>,
+    pub radius: f32,
+    #[serde(default)]
+    pub true_radius: Option<f32>,
+    /// RGBA, between 0 and 1
+    pub color: (f32, f32, f32, f32),
+    pub emissive: (f32, f32, f32, f32),
+    #[serde(default)]
+    pub light: Option<f32>,
+    #[serde(default)]
+    pub rings: Option<Rings>,
+    #[serde(default)]
+    pub test_particle: bool,
+    #[serde(default)]
+    pub beta: Option<f32>,
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+pub enum SnapshotError {
+    Io(String, std::io::Error),
+    Parse(String, ron::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(path, e) => write!(f, "cannot access state `{}`: {}", path, e),
+            SnapshotError::Parse(path, e) => write!(f, "invalid state `{}`: {}", path, e),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let content = fs::read_to_string(path).map_err(|e| SnapshotError::Io(name.clone(), e))?;
+        ron::from_str(&content).map_err(|e| SnapshotError::Parse(name, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let content = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| SnapshotError::Parse(name.clone(), e))?;
+        fs::write(path, content).map_err(|e| SnapshotError::Io(name, e))
+    }
+}
+
+/// Where F5 saves the state of the simulation, as does exiting with `on_exit`, e.g. at the
+/// end of a headless run
+pub struct SavePath {
+    pub path: PathBuf,
+    pub on_exit: bool,
+}
+
+fn tuple(v: Vec3) -> (f32, f32, f32) {
+    (v.x, v.y, v.z)
+}
+
+fn precise_tuple(v: DVec3) -> (f64, f64, f64) {
+    (v.x, v.y, v.z)
+}
+
+fn rgba(color: Color) -> (f32, f32, f32, f32) {
+    let [r, g, b, a] = color.as_rgba_f32();
+    (r, g, b, a)
+}
+
+/// After the last step of the frame, which may be the last of the run
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn save_snapshot(
+    keys: Res<Input<KeyCode>>,
+    mut exit: EventReader<AppExit>,
+    path: Res<SavePath>,
+    state: Res<SimState>,
+    g: Res<Gravity>,
+    groups: Res<GravityGroups>,
+    (integrator, substeps, precision): (Res<Integrator>, Res<Substeps>, Res<Precision>),
+    (rate, timestep): (Res<PhysicsRate>, Res<Timestep>),
+    materials: Res<Assets<StandardMaterial>>,
+    query: Query<(
+        Option<&Name>,
+        &Mass,
+        &Radius,
+        Option<&TrueRadius>,
+        &Position,
+        &Velocity,
+        Option<&PreciseState>,
+        Option<&Handle<StandardMaterial>>,
+        Option<&Light>,
+        Option<&Rings>,
+        Option<&TestParticle>,
+        Option<&Beta>,
+        Option<&Group>,
+    )>,
+) {
+    let exiting = exit.iter().next().is_some();
+    if !(keys.just_pressed(KeyCode::F5) || path.on_exit && exiting) {
+        return;
+    }
+
+    let bodies = query
+        .iter()
+        .map(
+            |(
+                name,
+                mass,
+                radius,
+                true_radius,
+                pos,
+                vel,
+                precise,
+                material,
+                light,
+                rings,
+                particle,
+                beta,
+                group,
+            )| {
+                let material = material.and_then(|material| materials.get(material));
+                BodySnapshot {
+                    name: name.map(|name| name.as_str().to_string()),
+                    mass: mass.0,
+                    position: tuple(pos.0),
+                    velocity: tuple(vel.0),
+                    precise_position: precise.map(|precise| precise_tuple(precise.pos)),
+                    precise_velocity: precise.map(|precise| precise_tuple(precise.vel)),
+                    radius: radius.0,
+                    true_radius: true_radius.map(|radius| radius.0),
+                    color: rgba(material.map_or(Color::WHITE, |m| m.base_color)),
+                    emissive: rgba(material.map_or(Color::BLACK, |m| m.emissive)),
+                    light: light.map(|light| light.intensity),
+                    rings: rings.copied(),
+                    test_particle: particle.is_some(),
+                    beta: beta.map(|beta| beta.0),
+                    group: group.map(|group| group.0.clone()),
+                }
+            },
+        )
+        .collect();
+    let snapshot = Snapshot {
+        time: state.time,
+        speed_factor: state.speed_factor,
+        g: g.0,
+        bodies,
+        no_gravity: groups.pairs_without_gravity(),
+        settings: Some(Settings {
+            integrator: *integrator,
+            substeps: substeps.count,
+            adaptive_substeps: substeps.adaptive,
+            dt: timestep.0,
+            physics_hz: rate.0,
+            precision: *precision,
+        }),
+    };
+    match snapshot.save(&path.path) {
+        Ok(()) => info!("state saved to `{}`", path.path.display()),
+        Err(e) => error!("{}", e),
+    }
+}
+
+/// Spawn the bodies of the [`Snapshot`] resource, and restore its [`Settings`]
+#[allow(clippy::too_many_arguments)]
+pub fn restore_snapshot(
+    mut commands: Commands,
+    snapshot: Res<Snapshot>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut g: ResMut<Gravity>,
+    mut groups: ResMut<GravityGroups>,
+    mut state: ResMut<SimState>,
+    (mut integrator, mut substeps, mut precision): (
+        ResMut<Integrator>,
+        ResMut<Substeps>,
+        ResMut<Precision>,
+    ),
+    (mut rate, mut timestep): (ResMut<PhysicsRate>, ResMut<Timestep>),
+) {
+    g.0 = snapshot.g;
+    *groups = GravityGroups::without_gravity(&snapshot.no_gravity);
+    state.time = snapshot.time;
+    if let Some(settings) = &snapshot.settings {
+        *integrator = settings.integrator;
+        *substeps = Substeps {
+            count: settings.substeps,
+            adaptive: settings.adaptive_substeps,
+        };
+        *precision = settings.precision;
+        rate.0 = settings.physics_hz;
+        timestep.0 = settings.dt;
+    }
+
+    let mut extent: f32 = 0.0;
+    for body in snapshot.bodies.iter() {
+        let (x, y, z) = body.position;
+        let pos = Vec3::new(x, y, z);
+        let (vx, vy, vz) = body.velocity;
+        extent = extent.max(pos.length());
+
+        let (r, g, b, a) = body.color;
+        let (er, eg, eb, ea) = body.emissive;
+        let appearance = Appearance {
+            radius: body.radius,
+            color: Color::rgba(r, g, b, a),
+            emissive: Color::rgba(er, eg, eb, ea),
+            light: body.light,
+            rings: body.rings,
+        };
+        let entity = spawn_body(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            BodyBundle::new(body.mass, pos, Vec3::new(vx, vy, vz)),
+            &appearance,
+        );
+        if let (Some((x, y, z)), Some((vx, vy, vz))) =
+            (body.precise_position, body.precise_velocity)
+        {
+            commands.entity(entity).insert(PreciseState {
+                pos: DVec3::new(x, y, z),
+                vel: DVec3::new(vx, vy, vz),
+            });
+        }
+        if let Some(name) = &body.name {
+            commands.entity(entity).insert(Name::new(name.clone()));
+        }
+        if let Some(radius) = body.true_radius {
+            commands.entity(entity).insert(TrueRadius(radius));
+        }
+        if body.test_particle {
+            commands.entity(entity).insert(TestParticle);
+        }
+        if let Some(beta) = body.beta {
+            commands.entity(entity).insert(Beta(beta));
+        }
+        if let Some(group) = &body.group {
+            commands.entity(entity).insert(Group(group.clone()));
+        }
+    }
+
+    spawn_z_camera(&mut commands, (2.5 * extent).max(10.0));
+}
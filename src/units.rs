@@ -0,0 +1,53 @@
+//! Physical constants in SI units, and conversions to the units of the Solar System scenes:
+//! masses in 10^24 kg, distances in AU and times in days. Positions and velocities are then
+//! rendered at `AU_TO_UNIT_SCALE` scene units per AU by the `nbody` binary.
+
+/// Gravitational constant, in m^3 / kg / s^2
+pub const G: f64 = 6.674_30e-11;
+/// Astronomical unit, in m
+pub const AU: f64 = 149_597_870_700.0;
+pub const KM: f64 = 1_000.0;
+pub const SECONDS_PER_DAY: f64 = 86_400.0;
+/// In kg
+pub const SOLAR_MASS: f64 = 1.988_5e30;
+/// Unit of mass of the scenes, in kg
+pub const MASS_UNIT: f64 = 1e24;
+/// In m / s
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+pub fn kg_to_mass_units(kg: f64) -> f64 {
+    kg / MASS_UNIT
+}
+
+pub fn meters_to_au(meters: f64) -> f64 {
+    meters / AU
+}
+
+pub fn meters_per_second_to_au_per_day(speed: f64) -> f64 {
+    speed * SECONDS_PER_DAY / AU
+}
+
+/// Factor of [`G`] in the units where distances are `length` m, times `time` s and masses
+/// `mass` kg:
+/// ```text
+/// G = m^3 / kg / s^2
+/// G' = G * mass * time^2 / length^3
+/// ```
+pub fn g_scale(length: f64, time: f64, mass: f64) -> f64 {
+    mass * time * time / length.powi(3)
+}
+
+/// Radius of a uniform sphere of `mass` and `density`, in any consistent units, e.g. in m
+/// for kg and kg / m^3:
+/// ```text
+/// r = (3 m / (4 π ρ))^(1/3)
+/// ```
+pub fn radius_from_mass_density(mass: f64, density: f64) -> f64 {
+    (3.0 * mass / (4.0 * std::f64::consts::PI * density)).cbrt()
+}
+
+/// [`g_scale`] of the Solar System scenes, whose positions are in AU times `unit_scale`,
+/// e.g. 0.1 AU when it is 10, their `g_scale`
+pub fn solar_g_scale(unit_scale: f32) -> f32 {
+    g_scale(AU / unit_scale as f64, SECONDS_PER_DAY, MASS_UNIT) as f32
+}
@@ -0,0 +1,39 @@
+//! Barnes-Hut against the exact sum on a random cloud of 200 bodies: at `θ = 0.3`, the
+//! acceleration of each body stays within a few percent of the exact one.
+use bevy::tasks::TaskPool;
+use copernicus::forces::{Algorithm, Forces};
+
+mod common;
+
+const SEED: u64 = 42;
+const BODIES: usize = 200;
+const THETA: f32 = 0.3;
+/// Largest error of an acceleration, relative to the exact one
+const TOLERANCE: f32 = 0.03;
+
+#[test]
+fn barnes_hut_matches_brute_force() {
+    let pool = TaskPool::new();
+    let brute = Forces {
+        softening: 0.01,
+        ..Forces::new(1.0, &pool)
+    };
+    let tree = Forces {
+        algorithm: Algorithm::BarnesHut,
+        theta: THETA,
+        ..brute
+    };
+    let bodies = common::cloud(BODIES, SEED);
+    let exact = brute.compute(&bodies);
+    let approximate = tree.compute(&bodies);
+    for (i, (exact, approximate)) in exact.iter().zip(&approximate).enumerate() {
+        let error = exact.distance(*approximate) / exact.length();
+        assert!(
+            error < TOLERANCE,
+            "acceleration of body {} {} off at θ = {}",
+            i,
+            error,
+            THETA
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Runs a head-on impact of two bodies headless, with `--collisions bounce` and a
+//! coefficient of restitution of 0.5, and checks that they bounced off each other, their
+//! momentum conserved and their normal relative speed after the impact half of that before,
+//! their common tangential velocity left as it was.
+use std::{env, fs, process::Command};
+
+const STEPS: u64 = 300;
+const RESTITUTION: f64 = 0.5;
+/// Without gravity, so that the velocities before the impact are the initial ones. Both
+/// move along Y at the same speed, so that the line of their centers stays along X.
+const SCENE: &str = r#"(
+    g_scale: 0.0,
+    unit_scale: 1.0,
+    bodies: [
+        (name: "A", mass: 3.0, position: (-5.0, 0.0, 0.0), velocity: (1.0, 0.2, 0.0),
+         radius: 1.0, color: (1.0, 0.0, 0.0)),
+        (name: "B", mass: 1.0, position: (5.0, 0.0, 0.0), velocity: (-2.0, 0.2, 0.0),
+         radius: 1.0, color: (0.0, 0.0, 1.0)),
+    ],
+)"#;
+/// Of the positions and velocities, exported in AU while the scene has a unit scale of 1
+const AU_PER_UNIT: f64 = 0.1;
+const TOLERANCE: f64 = 1e-5;
+
+struct Body {
+    mass: f64,
+    vel: [f64; 3],
+}
+
+#[test]
+fn bounce_conserves_momentum_and_restitutes() {
+    let dir = env::temp_dir();
+    let scene = dir.join(format!("nbody-bounce-{}.ron", std::process::id()));
+    let csv = dir.join(format!("nbody-bounce-{}.csv", std::process::id()));
+    fs::write(&scene, SCENE).expect("cannot write the scene");
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--collisions", "bounce"])
+        .args(["--restitution", &RESTITUTION.to_string()])
+        .arg("--scene")
+        .arg(&scene)
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    let _ = fs::remove_file(&scene);
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`: the bodies at each export, in order of
+    // time and of the scene
+    let mut exports: Vec<(String, Vec<Body>)> = Vec::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let number = |i: usize| -> f64 { fields[i].parse().expect("invalid number") };
+        let body = Body {
+            mass: number(2),
+            vel: [7, 8, 9].map(|i| number(i) / AU_PER_UNIT),
+        };
+        match exports.last_mut() {
+            Some((time, bodies)) if time == fields[0] => bodies.push(body),
+            _ => exports.push((fields[0].to_string(), vec![body])),
+        }
+    }
+    let (before, after) = match exports.as_slice() {
+        [(_, first), (_, last)] => match (first.as_slice(), last.as_slice()) {
+            ([a, b], [c, d]) => ((a, b), (c, d)),
+            _ => panic!("the bodies merged or vanished"),
+        },
+        _ => panic!("{} exports instead of 2", exports.len()),
+    };
+
+    let momentum =
+        |(a, b): (&Body, &Body)| [0, 1, 2].map(|k| a.mass * a.vel[k] + b.mass * b.vel[k]);
+    let (p_before, p_after) = (momentum(before), momentum(after));
+    for k in 0..3 {
+        assert!(
+            (p_after[k] - p_before[k]).abs() < TOLERANCE,
+            "momentum of {:?} after the impact, {:?} before",
+            p_after,
+            p_before
+        );
+    }
+    // Along the line of centers, X, from A to B
+    let approach = before.0.vel[0] - before.1.vel[0];
+    let separation = after.1.vel[0] - after.0.vel[0];
+    assert!(approach > 0.0, "the bodies were not approaching");
+    assert!(
+        (separation - RESTITUTION * approach).abs() < TOLERANCE,
+        "separating at {} after approaching at {}",
+        separation,
+        approach
+    );
+    for (before, after) in [(before.0, after.0), (before.1, after.1)] {
+        assert!(
+            (after.vel[1] - before.vel[1]).abs() < TOLERANCE && after.vel[2] == before.vel[2],
+            "tangential velocity of {:?} after the impact, {:?} before",
+            after.vel,
+            before.vel
+        );
+    }
+}
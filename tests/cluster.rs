@@ -0,0 +1,87 @@
+//! Runs `--startup cluster` headless for a step and checks that the stars drawn from the
+//! Plummer sphere are in virial equilibrium, twice their kinetic energy balancing their
+//! potential energy, `2K / |U| = 1` up to the noise of sampling them, and at rest.
+use std::{env, fs, process::Command};
+
+const STARS: usize = 1000;
+/// Of the cluster startup
+const G: f64 = 1.0;
+/// Units of the export, in AU, per unit of the scene
+const AU_PER_UNIT: f64 = 0.1;
+/// Of the virial ratio, off by about `1 / sqrt(N)` from the sampling of the stars
+const TOLERANCE: f64 = 0.05;
+
+struct Star {
+    mass: f64,
+    pos: [f64; 3],
+    vel: [f64; 3],
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter()
+        .zip(&b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+#[test]
+fn plummer_cluster_is_virialized() {
+    let csv = env::temp_dir().join(format!("nbody-cluster-{}.csv", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--startup", "cluster", "--seed", "1"])
+        .args(["--bodies", &STARS.to_string()])
+        .args(["--max-steps", "1", "--export-every", "1"])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`, in AU and AU per unit of time
+    let stars: Vec<Star> = content
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let number = |i: usize| -> f64 { fields[i].parse().expect("invalid number") };
+            Star {
+                mass: number(2),
+                pos: [4, 5, 6].map(|i| number(i) / AU_PER_UNIT),
+                vel: [7, 8, 9].map(|i| number(i) / AU_PER_UNIT),
+            }
+        })
+        .collect();
+    assert_eq!(stars.len(), STARS, "not a single export of {} stars", STARS);
+
+    let kinetic: f64 = stars
+        .iter()
+        .map(|star| 0.5 * star.mass * distance(star.vel, [0.0; 3]).powi(2))
+        .sum();
+    let potential: f64 = stars
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| stars[i + 1..].iter().map(move |b| (a, b)))
+        .map(|(a, b)| -G * a.mass * b.mass / distance(a.pos, b.pos))
+        .sum();
+    let ratio = 2.0 * kinetic / potential.abs();
+    assert!((ratio - 1.0).abs() < TOLERANCE, "virial ratio of {}", ratio);
+
+    let mass: f64 = stars.iter().map(|star| star.mass).sum();
+    let momentum = (0..3)
+        .map(|k| {
+            stars
+                .iter()
+                .map(|star| star.mass * star.vel[k])
+                .sum::<f64>()
+        })
+        .fold(0.0, |sum: f64, p| sum.max(p.abs()));
+    let typical_speed = (2.0 * kinetic / mass).sqrt();
+    assert!(
+        momentum < 1e-3 * mass * typical_speed,
+        "the cluster drifts with a momentum of {}",
+        momentum
+    );
+}
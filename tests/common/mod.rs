@@ -0,0 +1,34 @@
+//! Fixtures shared by the tests, each of which uses some of them
+#![allow(dead_code)]
+use bevy::math::{DVec3, Vec3};
+use copernicus::{forces::BodyState, integrator::Bodies};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Bodies of random masses uniformly spread in a cube
+pub fn cloud(bodies: usize, seed: u64) -> Vec<BodyState> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..bodies)
+        .map(|_| BodyState {
+            pos: Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            ),
+            mass: rng.gen_range(0.5..2.0),
+        })
+        .collect()
+}
+
+/// Sun of unit mass at rest at the origin, and a test particle at the periapsis of an
+/// orbit of eccentricity 0.44 at unit distance, of semi-major axis `1 / 0.56`, with `G = 1`
+pub fn eccentric_orbit() -> Bodies<DVec3> {
+    let mut bodies = Bodies::default();
+    bodies.push(1.0, DVec3::ZERO, DVec3::ZERO, false);
+    bodies.push(0.0, DVec3::X, DVec3::Y * 1.2, true);
+    bodies
+}
+
+/// Specific orbital energy of the particle of the [`eccentric_orbit`]
+pub fn orbital_energy(bodies: &Bodies<DVec3>) -> f64 {
+    0.5 * bodies.vel[1].length_squared() - 1.0 / bodies.pos[1].length()
+}
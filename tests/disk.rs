@@ -0,0 +1,60 @@
+//! Runs `--startup disk` headless for about an orbit of its middle, and checks that the
+//! planetesimals have started to merge into clumps, the mass of the disk and the star being
+//! conserved, while the dust, made of test particles, is all still there.
+use std::{collections::BTreeMap, env, fs, process::Command};
+
+const BODIES: usize = 100;
+/// One body in five is dust
+const DUST: usize = BODIES / 5;
+const STEPS: u64 = 1000;
+/// Mass of the star and of the planetesimals
+const TOTAL_MASS: f64 = 1050.0;
+
+#[test]
+fn planetesimals_merge_into_clumps() {
+    let csv = env::temp_dir().join(format!("nbody-disk-{}.csv", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--startup", "disk", "--seed", "7"])
+        .args(["--bodies", &BODIES.to_string()])
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,...`: the masses of the bodies at each export, in order of time
+    let mut exports: BTreeMap<u64, Vec<f64>> = BTreeMap::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let time: f64 = fields[0].parse().expect("invalid time");
+        let mass: f64 = fields[2].parse().expect("invalid mass");
+        exports.entry(time.to_bits()).or_default().push(mass);
+    }
+    let (first, last) = match (exports.values().next(), exports.values().last()) {
+        (Some(first), Some(last)) if exports.len() == 2 => (first, last),
+        _ => panic!("{} exports instead of 2", exports.len()),
+    };
+
+    assert_eq!(first.len(), BODIES + 1, "not a star and {} bodies", BODIES);
+    assert!(
+        last.len() < first.len() * 9 / 10,
+        "only {} of {} bodies merged",
+        first.len() - last.len(),
+        first.len()
+    );
+    for masses in [first, last] {
+        let total: f64 = masses.iter().sum();
+        assert!(
+            (total - TOTAL_MASS).abs() < 1e-4 * TOTAL_MASS,
+            "total mass of {} instead of {}",
+            total,
+            TOTAL_MASS
+        );
+        let dust = masses.iter().filter(|&&mass| mass == 0.0).count();
+        assert_eq!(dust, DUST, "the dust merged");
+    }
+}
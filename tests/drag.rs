@@ -0,0 +1,69 @@
+//! A test particle on a circular orbit through a medium with linear drag loses energy on
+//! every orbit: its semi-major axis decreases at each check, and about as `a0 e^(-2 k t)`,
+//! the orbit shrinking while staying nearly circular.
+use bevy::{math::DVec3, tasks::TaskPool};
+use copernicus::{
+    forces::{Drag, Forces},
+    integrator::{Bodies, Integrator, Substeps},
+};
+
+const K: f32 = 0.002;
+const DT: f64 = 0.01;
+/// About 16 orbits, of period `2π`
+const STEPS: usize = 10_000;
+/// Steps between two checks of the semi-major axis
+const CHECK_EVERY: usize = 100;
+/// Largest error on the final semi-major axis, relative to the expected one
+const TOLERANCE: f64 = 0.01;
+
+/// Semi-major axis from the vis-viva equation `v^2 = mu (2 / r - 1 / a)`, around a unit
+/// mass with `G = 1`
+fn semi_major_axis(pos: DVec3, vel: DVec3) -> f64 {
+    1.0 / (2.0 / pos.length() - vel.length_squared())
+}
+
+#[test]
+fn drag_shrinks_orbit() {
+    let pool = TaskPool::new();
+    let forces = Forces {
+        drag: Drag {
+            k: K,
+            particles_only: true,
+        },
+        ..Forces::new(1.0, &pool)
+    };
+    let substeps = Substeps {
+        count: 1,
+        adaptive: false,
+    };
+    let mut bodies = Bodies::default();
+    bodies.push(1.0, DVec3::ZERO, DVec3::ZERO, false);
+    bodies.push(0.0, DVec3::X, DVec3::Y, true);
+
+    let initial = semi_major_axis(bodies.pos[1], bodies.vel[1]);
+    let mut previous = initial;
+    for step in 1..=STEPS {
+        bodies.advance(Integrator::Leapfrog, &forces, DT, substeps);
+        if step % CHECK_EVERY != 0 {
+            continue;
+        }
+        let a = semi_major_axis(bodies.pos[1], bodies.vel[1]);
+        assert!(
+            a < previous,
+            "semi-major axis grew from {} to {} at step {}",
+            previous,
+            a,
+            step
+        );
+        previous = a;
+    }
+
+    assert_eq!(bodies.pos[0], DVec3::ZERO, "the central mass felt the drag");
+    let expected = initial * (-2.0 * K as f64 * STEPS as f64 * DT).exp();
+    assert!(
+        (previous - expected).abs() < TOLERANCE * expected,
+        "semi-major axis of {} instead of {}",
+        previous,
+        expected
+    );
+}
@@ -0,0 +1,59 @@
+//! A single body in a uniform external field, with no other body to pull it, falls along
+//! the parabola `x = x0 + v0 t + a t^2 / 2`. The leapfrog and RK4 being exact for a
+//! constant acceleration, they only stray from it by rounding errors.
+use bevy::{
+    math::{DVec3, Vec3},
+    tasks::TaskPool,
+};
+use copernicus::{
+    forces::Forces,
+    integrator::{Bodies, Integrator, Substeps},
+};
+
+const FIELD: [f32; 3] = [0.3, 0.0, -1.0];
+const STEPS: usize = 1000;
+const DT: f64 = 0.01;
+/// Largest distance to the parabola, relative to the distance covered
+const TOLERANCE: f64 = 1e-9;
+
+#[test]
+fn body_in_uniform_field_follows_parabola() {
+    let pool = TaskPool::new();
+    let forces = Forces {
+        external_field: Vec3::from(FIELD),
+        ..Forces::new(1.0, &pool)
+    };
+    let substeps = Substeps {
+        count: 1,
+        adaptive: false,
+    };
+    let (pos, vel) = (DVec3::new(1.0, 2.0, 3.0), DVec3::new(2.0, 0.5, 4.0));
+    let acc = Vec3::from(FIELD).as_f64();
+
+    for &integrator in &[Integrator::Leapfrog, Integrator::Rk4] {
+        let mut bodies = Bodies::default();
+        bodies.push(1.0, pos, vel, false);
+        for _ in 0..STEPS {
+            bodies.advance(integrator, &forces, DT, substeps);
+        }
+        let t = STEPS as f64 * DT;
+        let expected = pos + vel * t + acc * t * t / 2.0;
+        let error = (bodies.pos[0] - expected).length() / (expected - pos).length();
+        assert!(
+            error < TOLERANCE,
+            "{:?} ended at {:?} instead of {:?}, {:e} off",
+            integrator,
+            bodies.pos[0],
+            expected,
+            error
+        );
+        let error = (bodies.vel[0] - (vel + acc * t)).length() / (acc * t).length();
+        assert!(
+            error < TOLERANCE,
+            "{:?} ended at speed {:?} instead of {:?}",
+            integrator,
+            bodies.vel[0],
+            vel + acc * t
+        );
+    }
+}
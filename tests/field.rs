@@ -0,0 +1,60 @@
+//! The field of a cluster at a few points is exactly the acceleration test particles there
+//! feel, with the brute-force sum as with Barnes-Hut, whose tree is traversed the same way.
+use bevy::{math::Vec3, tasks::TaskPool};
+use copernicus::{
+    barnes_hut::Octree,
+    forces::{Algorithm, BodyState, Forces},
+};
+
+const BODIES: usize = 50;
+const POINTS: usize = 20;
+
+/// Deterministic spread of points in the unit cube, scaled by `size`
+fn spread(count: usize, offset: usize, size: f32) -> Vec<Vec3> {
+    (0..count)
+        .map(|i| {
+            let k = (i + offset) as f32;
+            Vec3::new((k * 0.37).sin(), (k * 0.73).cos(), (k * 1.13).sin()) * size
+        })
+        .collect()
+}
+
+#[test]
+fn field_is_felt_by_test_particles() {
+    let pool = TaskPool::new();
+    let pos = spread(BODIES, 0, 1.0);
+    let mass: Vec<f32> = (0..BODIES).map(|i| 1.0 + (i % 3) as f32).collect();
+    let points = spread(POINTS, BODIES, 3.0);
+    let massive: Vec<usize> = (0..BODIES).collect();
+
+    for &algorithm in &[Algorithm::BruteForce, Algorithm::BarnesHut] {
+        let forces = Forces {
+            softening: 0.01,
+            algorithm,
+            ..Forces::new(1.0, &pool)
+        };
+        let mut field = vec![Vec3::ZERO; POINTS];
+        forces.field(
+            &mut Octree::default(),
+            &mass,
+            &pos,
+            &massive,
+            &points,
+            &mut field,
+        );
+
+        let bodies: Vec<BodyState> = pos
+            .iter()
+            .zip(&mass)
+            .map(|(&pos, &mass)| BodyState { pos, mass })
+            .chain(points.iter().map(|&pos| BodyState { pos, mass: 0.0 }))
+            .collect();
+        let felt = forces.compute(&bodies);
+        assert_eq!(
+            field,
+            felt[BODIES..],
+            "the field is not what test particles feel with {:?}",
+            algorithm
+        );
+    }
+}
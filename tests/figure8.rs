@@ -0,0 +1,93 @@
+//! Runs `--startup figure8` headless for exactly one period, with the leapfrog integrator
+//! and a fixed time step, and checks that the three bodies come back to their initial
+//! conditions. Being unstable to small errors, the figure-8 covers the integrator, the
+//! initial conditions and their scaling to the units of the scene together.
+use std::{env, fs, process::Command};
+
+/// Period of the canonical solution, with `G = 1` and unit masses
+const PERIOD: f64 = 6.325_913_98;
+/// Positions and speeds of `figure8`, scaled from the canonical solution
+const L: f64 = 10.0;
+const V: f64 = 1.0;
+/// Scene units per AU, positions and velocities being exported in AU
+const AU_TO_UNIT_SCALE: f64 = 10.0;
+/// Steps in a period, each of `1 / --physics-hz` of simulated time with `--headless`
+const STEPS: u64 = 4000;
+/// Largest error on positions and velocities after a period, relative to the size of the
+/// orbit and the speed of the third body
+const TOLERANCE: f64 = 1e-3;
+
+/// Positions and velocities of the canonical solution (Chenciner & Montgomery, 2000)
+fn initial_conditions() -> [[f64; 4]; 3] {
+    let (x1, y1) = (0.970_004_4, -0.243_087_5);
+    let (vx3, vy3) = (-0.932_407_4, -0.864_731_5);
+    [
+        [x1, y1, -vx3 / 2.0, -vy3 / 2.0],
+        [-x1, -y1, -vx3 / 2.0, -vy3 / 2.0],
+        [0.0, 0.0, vx3, vy3],
+    ]
+}
+
+#[test]
+fn figure8_returns_after_one_period() {
+    let period = PERIOD * L / V;
+    let csv = env::temp_dir().join(format!("nbody-figure8-{}.csv", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args([
+            "--headless",
+            "--startup",
+            "figure8",
+            "--integrator",
+            "leapfrog",
+        ])
+        .args(["--physics-hz", &(STEPS as f64 / period).to_string()])
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`, the last rows being those of the period:
+    // the time, and the position and velocity in scene units
+    let rows: Vec<[f64; 5]> = content
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let field = |i: usize| fields[i].parse::<f64>().expect("invalid number");
+            let scaled = |i: usize| field(i) * AU_TO_UNIT_SCALE;
+            [field(0), scaled(4), scaled(5), scaled(7), scaled(8)]
+        })
+        .collect();
+    let last = &rows[rows.len() - 3..];
+
+    let speed = V * 0.932_407_4_f64.hypot(0.864_731_5);
+    for (row, initial) in last.iter().zip(initial_conditions().iter()) {
+        assert!(
+            (row[0] - period).abs() < 1e-6 * period,
+            "stopped at t = {} instead of {}",
+            row[0],
+            period
+        );
+        let position = [L * initial[0], L * initial[1]];
+        let velocity = [V * initial[2], V * initial[3]];
+        for axis in 0..2 {
+            assert!(
+                (row[1 + axis] - position[axis]).abs() < TOLERANCE * L,
+                "position {:?} instead of {:?}",
+                &row[1..3],
+                position
+            );
+            assert!(
+                (row[3 + axis] - velocity[axis]).abs() < TOLERANCE * speed,
+                "velocity {:?} instead of {:?}",
+                &row[3..5],
+                velocity
+            );
+        }
+    }
+}
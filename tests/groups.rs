@@ -0,0 +1,68 @@
+//! Asteroids around a Sun, in a group whose gravity is turned off between its own bodies,
+//! feel the Sun and nothing else: each accelerates exactly as if it were alone with the Sun,
+//! with all the force algorithms, serial or parallel, while they would perturb each other
+//! with their gravity on.
+use bevy::{math::Vec3, tasks::TaskPool};
+use copernicus::{
+    forces::{attraction, Algorithm, Forces, Interactions},
+    integrator::{Bodies, Integrator},
+};
+
+/// Group of the asteroids, the Sun being in group 0
+const ASTEROIDS: usize = 1;
+/// Under and over the number of bodies the forces are summed in parallel from
+const COUNTS: [usize; 2] = [10, 200];
+const MASS: f32 = 1e-3;
+
+/// Accelerations of a Sun of unit mass at the origin and of `n` asteroids on a ring around
+/// it, after a step with `interactions`
+fn accelerations(algorithm: Algorithm, n: usize, interactions: &Interactions) -> Bodies<Vec3> {
+    let pool = TaskPool::new();
+    let forces = Forces {
+        algorithm,
+        interactions,
+        ..Forces::new(1.0, &pool)
+    };
+    let mut bodies = Bodies::default();
+    bodies.push(1.0, Vec3::ZERO, Vec3::ZERO, false);
+    for i in 0..n {
+        let angle = i as f32 / n as f32 * std::f32::consts::TAU;
+        let radius = 1.0 + i as f32 / n as f32;
+        let pos = Vec3::new(angle.cos(), angle.sin(), 0.0) * radius;
+        bodies.push(MASS, pos, Vec3::ZERO, false);
+        bodies.join(ASTEROIDS);
+    }
+    let before = bodies.pos.clone();
+    bodies.step(Integrator::Euler, &forces, 0.01);
+    bodies.pos = before;
+    bodies
+}
+
+#[test]
+fn asteroids_without_self_gravity_do_not_perturb_each_other() {
+    let mut interactions = Interactions::default();
+    interactions.set(ASTEROIDS, ASTEROIDS, false);
+    for &algorithm in &[Algorithm::BruteForce, Algorithm::BarnesHut] {
+        for &n in &COUNTS {
+            let bodies = accelerations(algorithm, n, &interactions);
+            for i in 1..=n {
+                let alone = attraction(1.0, 0.0, bodies.pos[0] - bodies.pos[i]);
+                assert_eq!(
+                    bodies.acc[i], alone,
+                    "asteroid {} of {} was perturbed by the others with {:?}",
+                    i, n, algorithm
+                );
+            }
+            assert_ne!(
+                bodies.acc[0],
+                Vec3::ZERO,
+                "the asteroids did not attract the Sun"
+            );
+
+            let bodies = accelerations(algorithm, n, &Interactions::default());
+            let perturbed = (1..=n)
+                .any(|i| bodies.acc[i] != attraction(1.0, 0.0, bodies.pos[0] - bodies.pos[i]));
+            assert!(perturbed, "the asteroids never attract each other");
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! A test particle orbiting a unit mass, with `G = 1`, integrated with a coarse step. On a
+//! circular orbit, RK4 comes back much closer to where it started after a period than Euler.
+//! On an eccentric one, over many periods, the energy error of the leapfrog stays bounded,
+//! as does that of Euler, semi-implicit and so symplectic too though ten times larger, while
+//! that of RK4, small at first, keeps growing.
+//! The Sun and the Earth, at the step of the app, over a year: the energy of the leapfrog
+//! stays within 0.1% of its initial value, which plain, explicit Euler drifts out of.
+use bevy::{
+    math::{DVec3, Vec3},
+    tasks::TaskPool,
+};
+use copernicus::{
+    forces::{BodyState, Forces},
+    integrator::{Bodies, Integrator},
+    units::{solar_g_scale, G},
+};
+use std::f64::consts::TAU;
+
+mod common;
+
+/// Steps per period
+const STEPS: usize = 100;
+
+/// Sun at rest at the origin, and a particle on a circular orbit of unit radius, of period
+/// `2π`
+fn circular_orbit() -> Bodies<DVec3> {
+    let mut bodies = Bodies::default();
+    bodies.push(1.0, DVec3::ZERO, DVec3::ZERO, false);
+    bodies.push(0.0, DVec3::X, DVec3::Y, true);
+    bodies
+}
+
+/// Distance from the start after `periods` periods
+fn return_error(integrator: Integrator, periods: usize) -> f64 {
+    let pool = TaskPool::new();
+    let forces = Forces::new(1.0, &pool);
+    let mut bodies = circular_orbit();
+    for _ in 0..periods * STEPS {
+        bodies.step(integrator, &forces, TAU / STEPS as f64);
+    }
+    bodies.pos[1].distance(DVec3::X)
+}
+
+#[test]
+fn rk4_returns_closer_than_euler() {
+    let euler = return_error(Integrator::Euler, 1);
+    let rk4 = return_error(Integrator::Rk4, 1);
+    assert!(
+        rk4 < euler / 100.0,
+        "RK4 ended {} from the start, Euler {}",
+        rk4,
+        euler
+    );
+}
+
+/// Periods the energy is followed for
+const PERIODS: usize = 100;
+/// Largest energy error of the leapfrog on the eccentric orbit, relative to the energy
+const ENERGY_TOLERANCE: f64 = 0.01;
+
+/// Largest relative energy error over each period of an orbit of eccentricity 0.44, from
+/// its periapsis at unit distance
+fn energy_errors(integrator: Integrator) -> Vec<f64> {
+    let pool = TaskPool::new();
+    let forces = Forces::new(1.0, &pool);
+    let mut bodies = common::eccentric_orbit();
+    let initial = common::orbital_energy(&bodies);
+    // Kepler's third law, with the semi-major axis of the vis-viva equation
+    let semi_major_axis = 1.0 / (2.0 - bodies.vel[1].length_squared());
+    let period = TAU * semi_major_axis.powf(1.5);
+    (0..PERIODS)
+        .map(|_| {
+            (0..STEPS)
+                .map(|_| {
+                    bodies.step(integrator, &forces, period / STEPS as f64);
+                    ((common::orbital_energy(&bodies) - initial) / initial).abs()
+                })
+                .fold(0.0, f64::max)
+        })
+        .collect()
+}
+
+#[test]
+fn leapfrog_energy_error_stays_bounded() {
+    let leapfrog = energy_errors(Integrator::Leapfrog);
+    let (first, last) = (leapfrog[0], leapfrog[PERIODS - 1]);
+    let worst = leapfrog.iter().copied().fold(0.0, f64::max);
+    assert!(
+        worst < ENERGY_TOLERANCE,
+        "energy error of the leapfrog up to {}",
+        worst
+    );
+    assert!(
+        last < 1.1 * first,
+        "energy error of the leapfrog grew from {} to {}",
+        first,
+        last
+    );
+
+    let rk4 = energy_errors(Integrator::Rk4);
+    assert!(
+        rk4[PERIODS - 1] > 10.0 * rk4[0],
+        "energy error of RK4 only grew from {} to {}",
+        rk4[0],
+        rk4[PERIODS - 1]
+    );
+    let euler = energy_errors(Integrator::Euler);
+    assert!(
+        euler[PERIODS - 1] < 1.1 * euler[0] && euler[0] > 10.0 * first,
+        "energy error of Euler from {} to {}, instead of bounded and larger than {}",
+        euler[0],
+        euler[PERIODS - 1],
+        first
+    );
+}
+
+/// Step of the app at its default speed, of a 60th of a day
+const DT: f32 = 1.0 / 60.0;
+const DAYS: usize = 365;
+/// Largest drift of the energy of the Sun and the Earth over a year, relative to it
+const DRIFT_TOLERANCE: f64 = 1e-3;
+const SUN_MASS: f32 = 1_988_500.0;
+const EARTH_MASS: f32 = 5.972;
+
+/// Sun at rest at the origin, and the Earth at its position and velocity of the solar
+/// startup, in the units of the scenes: 10 units per AU, days and 10^24 kg
+fn sun_and_earth() -> Bodies<Vec3> {
+    let mut bodies = Bodies::default();
+    bodies.push(SUN_MASS, Vec3::ZERO, Vec3::ZERO, false);
+    bodies.push(
+        EARTH_MASS,
+        Vec3::new(-8.8737, -4.6974, 0.0002),
+        Vec3::new(0.07769, -0.15270, 0.00001),
+        false,
+    );
+    bodies
+}
+
+fn total_energy(g: f32, bodies: &Bodies<Vec3>) -> f64 {
+    let kinetic: f64 = (0..2)
+        .map(|i| 0.5 * bodies.mass[i] as f64 * bodies.vel[i].as_f64().length_squared())
+        .sum();
+    let distance = bodies.pos[0].as_f64().distance(bodies.pos[1].as_f64());
+    kinetic - g as f64 * SUN_MASS as f64 * EARTH_MASS as f64 / distance
+}
+
+/// Largest drift of the energy over a year, relative to it, of the bodies advanced by `step`
+fn yearly_drift(step: impl Fn(&Forces, &mut Bodies<Vec3>)) -> f64 {
+    let pool = TaskPool::new();
+    let g = G as f32 * solar_g_scale(10.0);
+    let forces = Forces::new(g, &pool);
+    let mut bodies = sun_and_earth();
+    let initial = total_energy(g, &bodies);
+    (0..DAYS * (1.0 / DT) as usize)
+        .map(|_| {
+            step(&forces, &mut bodies);
+            ((total_energy(g, &bodies) - initial) / initial).abs()
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Positions advanced with the velocities, and velocities with the accelerations, from the
+/// start of the step, unlike the semi-implicit [`Integrator::Euler`]
+fn explicit_euler(forces: &Forces, bodies: &mut Bodies<Vec3>) {
+    let states: Vec<BodyState> = (0..2)
+        .map(|i| BodyState {
+            pos: bodies.pos[i],
+            mass: bodies.mass[i],
+        })
+        .collect();
+    for (i, acc) in forces.compute(&states).into_iter().enumerate() {
+        bodies.pos[i] += bodies.vel[i] * DT;
+        bodies.vel[i] += acc * DT;
+    }
+}
+
+#[test]
+fn leapfrog_conserves_the_energy_of_the_earth_over_a_year() {
+    let leapfrog =
+        yearly_drift(|forces, bodies| bodies.step(Integrator::Leapfrog, forces, DT as f64));
+    assert!(
+        leapfrog < DRIFT_TOLERANCE,
+        "energy of the leapfrog drifted by {}",
+        leapfrog
+    );
+    let euler = yearly_drift(explicit_euler);
+    assert!(
+        euler > DRIFT_TOLERANCE,
+        "energy of explicit Euler only drifted by {}",
+        euler
+    );
+}
@@ -0,0 +1,210 @@
+//! Orbital elements of a body from its position and velocity, against those of a known
+//! orbit whose state is worked out by hand from the perifocal frame, and back to the state
+//! from the elements. On equatorial orbits, the ascending node is undefined and measured
+//! along X, and on circular ones so is the periapsis, on the node: only the sums of the
+//! angles left come back as they were. The attractor of a moon is its planet, that of the
+//! planet the Sun.
+use bevy::math::{DVec3, Vec3};
+use copernicus::kepler::{
+    dominant_attractor, orbital_elements, state_from_elements, OrbitalElements,
+};
+use std::f64::consts::TAU;
+
+const MU: f64 = 1.5;
+/// Of the elements, computed from a state in single precision
+const TOLERANCE: f64 = 1e-5;
+
+/// Inclined eccentric orbit, none of whose angles is zero
+const ORBIT: OrbitalElements = OrbitalElements {
+    semi_major_axis: 2.0,
+    eccentricity: 0.3,
+    inclination: 0.5,
+    longitude_of_ascending_node: 1.0,
+    argument_of_periapsis: 2.0,
+    true_anomaly: 0.7,
+};
+
+/// Position and velocity on `orbit`, from the directions `P` of the periapsis and `Q` of
+/// the body a quarter of a turn further
+fn state(orbit: &OrbitalElements) -> (Vec3, Vec3) {
+    let (e, nu) = (orbit.eccentricity, orbit.true_anomaly);
+    let (sin_i, cos_i) = orbit.inclination.sin_cos();
+    let (sin_node, cos_node) = orbit.longitude_of_ascending_node.sin_cos();
+    let (sin_w, cos_w) = orbit.argument_of_periapsis.sin_cos();
+    let p_dir = DVec3::new(
+        cos_node * cos_w - sin_node * sin_w * cos_i,
+        sin_node * cos_w + cos_node * sin_w * cos_i,
+        sin_w * sin_i,
+    );
+    let q_dir = DVec3::new(
+        -cos_node * sin_w - sin_node * cos_w * cos_i,
+        -sin_node * sin_w + cos_node * cos_w * cos_i,
+        cos_w * sin_i,
+    );
+    let p = orbit.semi_major_axis * (1.0 - e * e);
+    let r = p / (1.0 + e * nu.cos());
+    let speed = (MU / p).sqrt();
+    let pos = (p_dir * nu.cos() + q_dir * nu.sin()) * r;
+    let vel = (-p_dir * nu.sin() + q_dir * (e + nu.cos())) * speed;
+    (pos.as_f32(), vel.as_f32())
+}
+
+#[test]
+fn elements_of_a_known_orbit() {
+    let (pos, vel) = state(&ORBIT);
+    let elements = orbital_elements(pos, vel, MU as f32);
+    let pairs = [
+        ("a", elements.semi_major_axis, ORBIT.semi_major_axis),
+        ("e", elements.eccentricity, ORBIT.eccentricity),
+        ("i", elements.inclination, ORBIT.inclination),
+        (
+            "Ω",
+            elements.longitude_of_ascending_node,
+            ORBIT.longitude_of_ascending_node,
+        ),
+        (
+            "ω",
+            elements.argument_of_periapsis,
+            ORBIT.argument_of_periapsis,
+        ),
+        ("ν", elements.true_anomaly, ORBIT.true_anomaly),
+    ];
+    for (name, value, expected) in pairs {
+        assert!(
+            (value - expected).abs() < TOLERANCE,
+            "{} of {} instead of {}",
+            name,
+            value,
+            expected
+        );
+    }
+    assert!(elements.is_bound(), "the orbit is not bound");
+}
+
+/// Difference of two angles, in `[-π, π)`
+fn angle_between(a: f64, b: f64) -> f64 {
+    (a - b + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0
+}
+
+#[test]
+fn state_round_trip() {
+    let orbits = [
+        ORBIT,
+        OrbitalElements {
+            inclination: 0.0,
+            ..ORBIT
+        },
+        OrbitalElements {
+            eccentricity: 0.0,
+            ..ORBIT
+        },
+        OrbitalElements {
+            eccentricity: 0.0,
+            inclination: 0.0,
+            ..ORBIT
+        },
+    ];
+    for orbit in orbits {
+        let (pos, vel) = state_from_elements(&orbit, MU as f32);
+        let (expected_pos, expected_vel) = state(&orbit);
+        assert!(
+            pos.distance(expected_pos) < TOLERANCE as f32
+                && vel.distance(expected_vel) < TOLERANCE as f32,
+            "state of {:?}, {:?} instead of {:?}, {:?} on {:?}",
+            pos,
+            vel,
+            expected_pos,
+            expected_vel,
+            orbit
+        );
+
+        let elements = orbital_elements(pos, vel, MU as f32);
+        let (back_pos, back_vel) = state_from_elements(&elements, MU as f32);
+        assert!(
+            back_pos.distance(pos) < TOLERANCE as f32 && back_vel.distance(vel) < TOLERANCE as f32,
+            "back to {:?}, {:?} from {:?}, {:?} through {:?}",
+            back_pos,
+            back_vel,
+            pos,
+            vel,
+            elements
+        );
+
+        let sizes = [
+            ("a", elements.semi_major_axis, orbit.semi_major_axis),
+            ("e", elements.eccentricity, orbit.eccentricity),
+        ];
+        for (name, value, expected) in sizes {
+            assert!(
+                (value - expected).abs() < TOLERANCE,
+                "{} of {} instead of {} on {:?}",
+                name,
+                value,
+                expected,
+                orbit
+            );
+        }
+        let (node, periapsis, nu) = (
+            elements.longitude_of_ascending_node,
+            elements.argument_of_periapsis,
+            elements.true_anomaly,
+        );
+        let (expected_node, expected_periapsis, expected_nu) = (
+            orbit.longitude_of_ascending_node,
+            orbit.argument_of_periapsis,
+            orbit.true_anomaly,
+        );
+        let mut angles = vec![("i", elements.inclination, orbit.inclination)];
+        match (orbit.inclination == 0.0, orbit.eccentricity == 0.0) {
+            (false, false) => angles.extend([
+                ("Ω", node, expected_node),
+                ("ω", periapsis, expected_periapsis),
+                ("ν", nu, expected_nu),
+            ]),
+            (true, false) => angles.extend([
+                ("Ω", node, 0.0),
+                (
+                    "Ω + ω",
+                    node + periapsis,
+                    expected_node + expected_periapsis,
+                ),
+                ("ν", nu, expected_nu),
+            ]),
+            (false, true) => angles.extend([
+                ("Ω", node, expected_node),
+                ("ω + ν", periapsis + nu, expected_periapsis + expected_nu),
+            ]),
+            (true, true) => angles.extend([
+                ("Ω", node, 0.0),
+                (
+                    "Ω + ω + ν",
+                    node + periapsis + nu,
+                    expected_node + expected_periapsis + expected_nu,
+                ),
+            ]),
+        }
+        for (name, value, expected) in angles {
+            assert!(
+                angle_between(value, expected).abs() < TOLERANCE,
+                "{} of {} instead of {} on {:?}",
+                name,
+                value,
+                expected,
+                orbit
+            );
+        }
+    }
+}
+
+#[test]
+fn moons_orbit_their_planet() {
+    let bodies = [
+        (1000.0, Vec3::ZERO, "Sun"),
+        (1.0, Vec3::X * 10.0, "Planet"),
+        (1e-3, Vec3::X * 10.1, "Moon"),
+    ];
+    let attractor = |(mass, pos, _): (f32, Vec3, &str)| dominant_attractor(mass, pos, bodies);
+    assert_eq!(attractor(bodies[2]), Some("Planet"));
+    assert_eq!(attractor(bodies[1]), Some("Sun"));
+    assert_eq!(attractor(bodies[0]), None);
+}
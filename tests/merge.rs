@@ -0,0 +1,147 @@
+//! Runs scenes of two bodies on course to collide headless, with `--collisions merge`, and
+//! checks that they merged into a single body of their total mass and linear momentum,
+//! which moves on in a straight line with the velocity of their barycenter, and which stays
+//! in their group.
+use std::{env, fs, process::Command};
+
+const STEPS: u64 = 300;
+/// Without gravity, so that the momentum before the impact is the initial one
+const SCENE: &str = r#"(
+    g_scale: 0.0,
+    unit_scale: 1.0,
+    bodies: [
+        (name: "A", mass: 3.0, position: (-5.0, 0.0, 0.0), velocity: (1.0, 0.5, 0.0),
+         radius: 1.0, color: (1.0, 0.0, 0.0)),
+        (name: "B", mass: 1.0, position: (5.0, 0.0, 0.0), velocity: (-2.0, 0.0, 0.3),
+         radius: 1.0, color: (0.0, 0.0, 1.0)),
+    ],
+)"#;
+/// Of the positions and velocities, exported in AU while the scene has a unit scale of 1
+const AU_PER_UNIT: f64 = 0.1;
+const TOLERANCE: f64 = 1e-5;
+
+struct Body {
+    mass: f64,
+    momentum: [f64; 3],
+}
+
+/// CSV export of `scene` at the first step and after [`STEPS`]
+fn run(test: &str, scene: &str) -> String {
+    let dir = env::temp_dir();
+    let path = |extension: &str| {
+        dir.join(format!(
+            "nbody-merge-{}-{}.{}",
+            test,
+            std::process::id(),
+            extension
+        ))
+    };
+    let (scene_path, csv) = (path("ron"), path("csv"));
+    fs::write(&scene_path, scene).expect("cannot write the scene");
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--collisions", "merge"])
+        .arg("--scene")
+        .arg(&scene_path)
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    let _ = fs::remove_file(&scene_path);
+    assert!(status.success(), "nbody exited with {}", status);
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    content
+}
+
+#[test]
+fn merge_conserves_mass_and_momentum() {
+    let content = run("conservation", SCENE);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`: the mass and momentum of the bodies at
+    // each export, in order of time
+    let mut exports: Vec<(String, Vec<Body>)> = Vec::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let number = |i: usize| -> f64 { fields[i].parse().expect("invalid number") };
+        let mass = number(2);
+        let body = Body {
+            mass,
+            momentum: [7, 8, 9].map(|i| mass * number(i) / AU_PER_UNIT),
+        };
+        match exports.last_mut() {
+            Some((time, bodies)) if time == fields[0] => bodies.push(body),
+            _ => exports.push((fields[0].to_string(), vec![body])),
+        }
+    }
+    let (first, last) = match exports.as_slice() {
+        [(_, first), (_, last)] => (first, last),
+        _ => panic!("{} exports instead of 2", exports.len()),
+    };
+
+    assert_eq!(first.len(), 2, "the bodies merged before the first export");
+    assert_eq!(last.len(), 1, "the bodies did not merge");
+    let total = |bodies: &[Body]| {
+        bodies.iter().fold((0.0, [0.0; 3]), |(mass, p), body| {
+            let q = body.momentum;
+            (mass + body.mass, [p[0] + q[0], p[1] + q[1], p[2] + q[2]])
+        })
+    };
+    let (mass, momentum) = total(last);
+    assert!(
+        (mass - 4.0).abs() < TOLERANCE,
+        "merged into a mass of {}",
+        mass
+    );
+    let expected = [1.0, 1.5, 0.3];
+    for (p, expected) in momentum.iter().zip(&expected) {
+        assert!(
+            (p - expected).abs() < TOLERANCE,
+            "momentum of {:?} instead of {:?}",
+            momentum,
+            expected
+        );
+    }
+    assert_eq!(total(first).0, mass, "mass changed before the merge");
+}
+
+/// Two rocks colliding, which do not attract a distant probe, the lightest body, at rest
+const GROUPS: &str = r#"(
+    g_scale: 1e10,
+    unit_scale: 1.0,
+    bodies: [
+        (name: "A", mass: 3.0, position: (-5.0, 0.0, 0.0), velocity: (1.0, 0.0, 0.0),
+         radius: 1.0, color: (1.0, 0.0, 0.0), group: Some("rocks")),
+        (name: "B", mass: 1.0, position: (5.0, 0.0, 0.0), velocity: (-2.0, 0.0, 0.0),
+         radius: 1.0, color: (0.0, 0.0, 1.0), group: Some("rocks")),
+        (name: "Probe", mass: 1e-3, position: (0.0, 20.0, 0.0),
+         radius: 0.1, color: (1.0, 1.0, 1.0), group: Some("probes")),
+    ],
+    no_gravity: [("rocks", "probes")],
+)"#;
+
+#[test]
+fn merge_keeps_the_group() {
+    let content = run("groups", GROUPS);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`: the number of bodies at each export, and
+    // the last velocity of the probe
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let mut probe = Vec::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields[1] == "Probe" {
+            probe = fields[7..10].to_vec();
+        }
+        match counts.last_mut() {
+            Some((time, count)) if time == fields[0] => *count += 1,
+            _ => counts.push((fields[0].to_string(), 1)),
+        }
+    }
+    let counts: Vec<usize> = counts.into_iter().map(|(_, count)| count).collect();
+    assert_eq!(counts, [3, 2], "the rocks did not merge");
+    assert!(
+        probe.iter().all(|v| v.parse::<f64>() == Ok(0.0)),
+        "the merged rocks pulled on the probe, moving at {:?}",
+        probe
+    );
+}
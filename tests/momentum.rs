@@ -0,0 +1,87 @@
+//! Total linear momentum over random configurations of a few bodies, integrated with the
+//! leapfrog. The pairwise forces being opposite, by Newton's third law, it only changes by
+//! rounding errors: a drift points at an asymmetry in the accumulation of the forces.
+use bevy::{math::DVec3, tasks::TaskPool};
+use copernicus::{
+    forces::Forces,
+    integrator::{Bodies, Integrator, Substeps},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Random configurations checked, each from its own seed
+const CASES: u64 = 100;
+const STEPS: usize = 200;
+const DT: f64 = 0.01;
+/// Largest change of the total momentum, relative to the sum of the momenta of the bodies.
+/// The accelerations are in single precision, so that close encounters still cost 10^-7.
+const TOLERANCE: f64 = 1e-6;
+
+/// Between 2 and 8 bodies in a cube, moving in random directions
+fn configuration(rng: &mut StdRng) -> Bodies<DVec3> {
+    let mut bodies = Bodies::default();
+    let random_vector = |rng: &mut StdRng, size: f64| {
+        DVec3::new(
+            rng.gen_range(-size..size),
+            rng.gen_range(-size..size),
+            rng.gen_range(-size..size),
+        )
+    };
+    for _ in 0..rng.gen_range(2..=8) {
+        let mass = rng.gen_range(0.1..10.0);
+        let pos = random_vector(rng, 10.0);
+        let vel = random_vector(rng, 1.0);
+        bodies.push(mass, pos, vel, false);
+    }
+    bodies
+}
+
+fn momentum(bodies: &Bodies<DVec3>) -> DVec3 {
+    bodies
+        .mass
+        .iter()
+        .zip(&bodies.vel)
+        .map(|(&mass, &vel)| vel * mass as f64)
+        .fold(DVec3::ZERO, |total, p| total + p)
+}
+
+#[test]
+fn momentum_is_conserved() {
+    let pool = TaskPool::new();
+    // Softened so that a close encounter of random bodies stays finite, which keeps the
+    // forces symmetric
+    let forces = Forces {
+        softening: 0.1,
+        ..Forces::new(1.0, &pool)
+    };
+    let substeps = Substeps {
+        count: 1,
+        adaptive: false,
+    };
+
+    for seed in 0..CASES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut bodies = configuration(&mut rng);
+        let initial = momentum(&bodies);
+        let scale: f64 = bodies
+            .mass
+            .iter()
+            .zip(&bodies.vel)
+            .map(|(&mass, vel)| mass as f64 * vel.length())
+            .sum();
+
+        for _ in 0..STEPS {
+            bodies.advance(Integrator::Leapfrog, &forces, DT, substeps);
+        }
+        let drift = (momentum(&bodies) - initial).length() / scale;
+        assert!(
+            drift < TOLERANCE,
+            "momentum changed by {:e} of its scale with seed {}, from {:?} to {:?} for \
+             masses {:?}",
+            drift,
+            seed,
+            initial,
+            momentum(&bodies),
+            bodies.mass
+        );
+    }
+}
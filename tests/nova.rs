@@ -0,0 +1,129 @@
+//! Runs `--startup nova` headless until just after its star has gone supernova, and checks
+//! that the star blew off most of its mass in a shell of ejecta, the mass of the system
+//! being conserved, and that it lost its hold on some of the planets, all bound to it
+//! before.
+use std::{env, fs, process::Command};
+
+const PLANETS: usize = 8;
+/// The supernova goes off at `t = 20`, 1200 steps of `1 / 60`
+const STEPS: u64 = 1300;
+const SHELL: usize = 100;
+const STAR_MASS: f64 = 1000.0;
+const REMNANT: f64 = 0.4;
+/// Of the star and the planets
+const TOTAL_MASS: f64 = 1000.08;
+const G: f64 = 1.0;
+/// Units of the export, in AU, per unit of the scene
+const AU_PER_UNIT: f64 = 0.1;
+
+struct Body {
+    name: String,
+    mass: f64,
+    pos: [f64; 3],
+    vel: [f64; 3],
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter()
+        .zip(&b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Planets bound to the star, with a negative energy in the two-body problem with it
+fn bound_planets(bodies: &[Body]) -> usize {
+    let star = bodies
+        .iter()
+        .find(|body| body.name == "Star")
+        .expect("no star");
+    bodies
+        .iter()
+        .filter(|body| body.name.starts_with("Planet"))
+        .filter(|planet| {
+            let r = distance(planet.pos, star.pos);
+            let v = distance(planet.vel, star.vel);
+            0.5 * v * v < G * (star.mass + planet.mass) / r
+        })
+        .count()
+}
+
+#[test]
+fn supernova_unbinds_planets() {
+    let csv = env::temp_dir().join(format!("nbody-nova-{}.csv", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--startup", "nova", "--seed", "3"])
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`, in AU and AU per unit of time, at each
+    // export in order of time
+    let mut exports: Vec<(String, Vec<Body>)> = Vec::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let number = |i: usize| -> f64 { fields[i].parse().expect("invalid number") };
+        let body = Body {
+            name: fields[1].to_string(),
+            mass: number(2),
+            pos: [4, 5, 6].map(|i| number(i) / AU_PER_UNIT),
+            vel: [7, 8, 9].map(|i| number(i) / AU_PER_UNIT),
+        };
+        match exports.last_mut() {
+            Some((time, bodies)) if time == fields[0] => bodies.push(body),
+            _ => exports.push((fields[0].to_string(), vec![body])),
+        }
+    }
+    let (first, last) = match exports.as_slice() {
+        [(_, first), (_, last)] => (first, last),
+        _ => panic!("{} exports instead of 2", exports.len()),
+    };
+
+    assert_eq!(
+        first.len(),
+        1 + PLANETS,
+        "not a star and {} planets",
+        PLANETS
+    );
+    assert_eq!(
+        last.len(),
+        1 + PLANETS + SHELL,
+        "no shell of {} ejecta",
+        SHELL
+    );
+    for bodies in [first, last] {
+        let total: f64 = bodies.iter().map(|body| body.mass).sum();
+        assert!(
+            (total - TOTAL_MASS).abs() < 1e-4 * TOTAL_MASS,
+            "total mass of {} instead of {}",
+            total,
+            TOTAL_MASS
+        );
+    }
+    let star = last
+        .iter()
+        .find(|body| body.name == "Star")
+        .expect("no star");
+    assert!(
+        (star.mass - REMNANT * STAR_MASS).abs() < 1e-4 * STAR_MASS,
+        "the star kept a mass of {}",
+        star.mass
+    );
+
+    assert_eq!(
+        bound_planets(first),
+        PLANETS,
+        "planets escaped before the supernova"
+    );
+    let bound = bound_planets(last);
+    assert!(
+        bound < PLANETS,
+        "all the planets are still bound to the star"
+    );
+}
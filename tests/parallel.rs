@@ -0,0 +1,46 @@
+//! The brute-force sum runs on a single thread below 128 bodies, and is spread over the
+//! threads of the pool above. Either way, the acceleration of each body is the sum of the
+//! attractions of the others in the order of the bodies, so that both match the sum of the
+//! accelerations of each pair of bodies on its own to the last bit.
+use bevy::{math::Vec3, tasks::TaskPool};
+use copernicus::forces::{BodyState, Forces};
+
+mod common;
+
+const SEED: u64 = 7;
+
+/// Accelerations of the bodies, each the sum of those of the bodies taken two by two
+fn pairwise(forces: &Forces, bodies: &[BodyState]) -> Vec<Vec3> {
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(i, body)| {
+            bodies
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(Vec3::ZERO, |acc, (_, other)| {
+                    acc + forces.compute(&[*body, *other])[0]
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn parallel_sum_matches_serial_one() {
+    let pool = TaskPool::new();
+    let forces = Forces {
+        softening: 0.01,
+        ..Forces::new(1.0, &pool)
+    };
+    // Below and above the threshold of the parallel sum
+    for &count in &[100, 300] {
+        let bodies = common::cloud(count, SEED);
+        assert_eq!(
+            forces.compute(&bodies),
+            pairwise(&forces, &bodies),
+            "accelerations of {} bodies differ from the pairwise ones",
+            count
+        );
+    }
+}
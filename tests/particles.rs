@@ -0,0 +1,110 @@
+//! Test particles feel the gravity of the massive bodies but exert none: a Sun and a planet
+//! accelerate exactly as they would alone, however heavy and many the particles around
+//! them, with all the force algorithms, serial or parallel. A ring of heavy test particles
+//! on circular orbits around a single massive body stays on them for a period, while the
+//! body stays where it is.
+use bevy::{math::Vec3, tasks::TaskPool};
+use copernicus::{
+    forces::{Algorithm, Forces},
+    integrator::{Bodies, Integrator},
+};
+
+/// Under and over the number of bodies the forces are summed in parallel from
+const COUNTS: [usize; 2] = [10, 200];
+/// Of each particle, as heavy as the Sun, for any pull of theirs to show
+const MASS: f32 = 1.0;
+
+/// Accelerations of a Sun of unit mass at the origin, a planet and `n` test particles on
+/// a ring around them
+fn accelerations(algorithm: Algorithm, n: usize) -> Vec<Vec3> {
+    let pool = TaskPool::new();
+    let forces = Forces {
+        algorithm,
+        ..Forces::new(1.0, &pool)
+    };
+    let mut bodies = Bodies::default();
+    bodies.push(1.0, Vec3::ZERO, Vec3::ZERO, false);
+    bodies.push(1e-3, Vec3::X, Vec3::ZERO, false);
+    for i in 0..n {
+        let angle = i as f32 / n as f32 * std::f32::consts::TAU;
+        let pos = Vec3::new(angle.cos(), angle.sin(), 0.1) * 2.0;
+        bodies.push(MASS, pos, Vec3::ZERO, true);
+    }
+    bodies.step(Integrator::Euler, &forces, 0.01);
+    bodies.acc
+}
+
+#[test]
+fn test_particles_do_not_attract_massive_bodies() {
+    for &algorithm in &[Algorithm::BruteForce, Algorithm::BarnesHut] {
+        let alone = accelerations(algorithm, 0);
+        for &n in &COUNTS {
+            let acc = accelerations(algorithm, n);
+            assert_eq!(
+                acc[..2],
+                alone[..],
+                "{} test particles pulled on the Sun and the planet with {:?}",
+                n,
+                algorithm
+            );
+            assert!(
+                acc[2..].iter().all(|acc| *acc != Vec3::ZERO),
+                "the test particles felt no gravity with {:?}",
+                algorithm
+            );
+        }
+    }
+}
+
+/// Of the ring, with `G = 1` and a unit central mass: its period is `2π * RING^1.5`
+const RING: f32 = 2.0;
+const STEPS: usize = 1000;
+/// Largest drift of the distance of a particle to the central body, relative to the ring
+const RADIUS_TOLERANCE: f32 = 1e-3;
+
+#[test]
+fn ring_of_test_particles_stays_on_its_orbit() {
+    let pool = TaskPool::new();
+    let forces = Forces::new(1.0, &pool);
+    let mut bodies = Bodies::default();
+    bodies.push(1.0, Vec3::ZERO, Vec3::ZERO, false);
+    let n = COUNTS[1];
+    let speed = (1.0 / RING).sqrt();
+    for i in 0..n {
+        let angle = i as f32 / n as f32 * std::f32::consts::TAU;
+        let (sin, cos) = angle.sin_cos();
+        let pos = Vec3::new(cos, sin, 0.0) * RING;
+        let vel = Vec3::new(-sin, cos, 0.0) * speed;
+        bodies.push(MASS, pos, vel, true);
+    }
+    let period = std::f32::consts::TAU * RING.powf(1.5);
+    for _ in 0..STEPS {
+        bodies.step(
+            Integrator::Leapfrog,
+            &forces,
+            (period / STEPS as f32) as f64,
+        );
+    }
+
+    // Pulled by nothing
+    assert_eq!(
+        bodies.pos[0],
+        Vec3::ZERO,
+        "the particles moved the central body"
+    );
+    assert_eq!(
+        bodies.vel[0],
+        Vec3::ZERO,
+        "the particles moved the central body"
+    );
+    for (i, pos) in bodies.pos.iter().enumerate().skip(1) {
+        let drift = (pos.length() - RING).abs() / RING;
+        assert!(
+            drift < RADIUS_TOLERANCE,
+            "particle {} drifted {} off its orbit, to {:?}",
+            i,
+            drift,
+            pos
+        );
+    }
+}
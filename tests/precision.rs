@@ -0,0 +1,98 @@
+//! Neptune around the Sun for ten orbits of daily steps, in the units of the scenes, with
+//! the leapfrog in single and in double precision. At 300 units from the Sun, single
+//! precision rounds the position of Neptune off by up to a thousandth of the distance it
+//! moves in a step, which makes its energy and its position drift far more than in double
+//! precision.
+use bevy::{
+    math::{DVec3, Vec3},
+    tasks::TaskPool,
+};
+use copernicus::{
+    forces::Forces,
+    integrator::{Bodies, Integrator, Vector},
+    units::{solar_g_scale, G},
+};
+use std::f64::consts::TAU;
+
+const SUN_MASS: f64 = 1_988_500.0;
+const NEPTUNE_MASS: f64 = 102.4;
+/// 30.07 AU
+const DISTANCE: f64 = 300.7;
+const ORBITS: usize = 10;
+/// In days
+const DT: f64 = 1.0;
+/// Least ratio of the drifts in single precision to those in double precision
+const GAIN: f64 = 10.0;
+
+struct Drift {
+    /// Relative to the initial energy
+    energy: f64,
+    /// From the initial position relative to the Sun, after the last orbit
+    position: f64,
+}
+
+fn total_energy<V: Vector>(g: f64, bodies: &Bodies<V>) -> f64 {
+    let [sun, neptune] = [0, 1].map(|i| {
+        let (pos, vel) = (bodies.pos[i].to_vec3(), bodies.vel[i].to_vec3());
+        (pos.as_f64(), vel.as_f64())
+    });
+    0.5 * SUN_MASS * sun.1.length_squared() + 0.5 * NEPTUNE_MASS * neptune.1.length_squared()
+        - g * SUN_MASS * NEPTUNE_MASS / sun.0.distance(neptune.0)
+}
+
+/// Neptune on a circular orbit around the Sun, their momentum cancelling out
+fn neptune_drift<V: Vector>() -> Drift
+where
+    Bodies<V>: Default,
+{
+    let pool = TaskPool::new();
+    let g = G as f32 * solar_g_scale(10.0);
+    let forces = Forces::new(g, &pool);
+    let mu = g as f64 * (SUN_MASS + NEPTUNE_MASS);
+    let speed = (mu / DISTANCE).sqrt();
+    let total = SUN_MASS + NEPTUNE_MASS;
+    let mut bodies: Bodies<V> = Bodies::default();
+    bodies.push(
+        SUN_MASS as f32,
+        V::from_vec3(Vec3::ZERO),
+        V::from_vec3((-DVec3::Y * speed * NEPTUNE_MASS / total).as_f32()),
+        false,
+    );
+    bodies.push(
+        NEPTUNE_MASS as f32,
+        V::from_vec3((DVec3::X * DISTANCE).as_f32()),
+        V::from_vec3((DVec3::Y * speed * SUN_MASS / total).as_f32()),
+        false,
+    );
+    let initial = total_energy(g as f64, &bodies);
+
+    // A whole number of steps per orbit, for Neptune to end where it started
+    let period = TAU * (DISTANCE.powi(3) / mu).sqrt();
+    let steps = (period / DT).round() as usize;
+    for _ in 0..ORBITS * steps {
+        bodies.step(Integrator::Leapfrog, &forces, period / steps as f64);
+    }
+    let relative = bodies.pos[1].to_vec3().as_f64() - bodies.pos[0].to_vec3().as_f64();
+    Drift {
+        energy: ((total_energy(g as f64, &bodies) - initial) / initial).abs(),
+        position: relative.distance(DVec3::X * DISTANCE),
+    }
+}
+
+#[test]
+fn double_precision_drifts_less_on_neptune() {
+    let single = neptune_drift::<Vec3>();
+    let double = neptune_drift::<DVec3>();
+    assert!(
+        double.energy * GAIN < single.energy,
+        "energy drift of {} in double precision, {} in single",
+        double.energy,
+        single.energy
+    );
+    assert!(
+        double.position * GAIN < single.position,
+        "Neptune ended {} from its start in double precision, {} in single",
+        double.position,
+        single.position
+    );
+}
@@ -0,0 +1,82 @@
+//! Replays headless a recording of a random cluster, written by hand, in which the speed is
+//! raised with `.` after a few steps, and checks that two replays end in the same state,
+//! drawn from the seed of the recording, and that it is not the state of the same cluster
+//! left at its initial speed: the key was played back.
+use std::{env, fs, process::Command};
+
+/// Seed of the recording
+const SEED: u64 = 42;
+const STEPS: u64 = 50;
+/// Frame where `.` is pressed, released on the next one
+const PRESS: u64 = 10;
+/// Scan code of `.` on a US keyboard, which Bevy ignores
+const SCAN_CODE: u32 = 52;
+
+/// One line per frame, each running one step with `--headless`
+fn recording() -> String {
+    let mut lines = vec![format!("(seed: {})", SEED)];
+    for frame in 0..STEPS {
+        let keys = match frame {
+            PRESS => format!(
+                "(scan_code: {}, key_code: Some(Period), pressed: true)",
+                SCAN_CODE
+            ),
+            _ if frame == PRESS + 1 => {
+                format!(
+                    "(scan_code: {}, key_code: Some(Period), pressed: false)",
+                    SCAN_CODE
+                )
+            }
+            _ => String::new(),
+        };
+        lines.push(format!(
+            "(time: {}, delta: 0.016, keys: [{}], steps: {})",
+            frame as f64 * 0.016,
+            keys,
+            frame + 1
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Rows of the first and last steps of a cluster of 20 stars, run with `args`
+fn states(name: &str, args: &[&str]) -> (String, String) {
+    let csv = env::temp_dir().join(format!("nbody-replay-{}-{}.csv", std::process::id(), name));
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--startup", "cluster", "--bodies", "20"])
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .args(args)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    let rows: Vec<&str> = content.lines().skip(1).collect();
+    let (first, last) = rows.split_at(rows.len() / 2);
+    (first.join("\n"), last.join("\n"))
+}
+
+#[test]
+fn replay_reproduces_the_run() {
+    let path = env::temp_dir().join(format!("nbody-replay-{}.ron", std::process::id()));
+    fs::write(&path, recording()).expect("cannot write the recording");
+    let path = path.to_str().expect("invalid temporary path");
+
+    let first = states("first", &["--replay", path]);
+    let second = states("second", &["--replay", path]);
+    let _ = fs::remove_file(path);
+    assert_eq!(first, second, "two replays went apart");
+
+    let live = states("live", &["--seed", &SEED.to_string()]);
+    assert_eq!(
+        first.0, live.0,
+        "the replay did not draw the cluster from the seed of the recording"
+    );
+    assert_ne!(
+        first.1, live.1,
+        "the key of the recording was not played back"
+    );
+}
@@ -0,0 +1,34 @@
+//! A scene with a body of zero or negative density, which would be drawn with an infinite or
+//! NaN radius, is rejected when it is loaded, naming the body.
+use std::{env, fs, process::Command};
+
+#[test]
+fn scene_rejects_invalid_densities() {
+    for density in ["0.0", "-1.0"] {
+        let scene = env::temp_dir().join(format!("nbody-density-{}.ron", std::process::id()));
+        let content = format!(
+            r#"(
+    g_scale: 1.0,
+    unit_scale: 1.0,
+    bodies: [
+        (name: "Rock", mass: 1.0, density: Some({}), color: (1.0, 1.0, 1.0)),
+    ],
+)"#,
+            density
+        );
+        fs::write(&scene, content).expect("cannot write the scene");
+        let output = Command::new(env!("CARGO_BIN_EXE_nbody"))
+            .args(["--headless", "--max-steps", "1", "--scene"])
+            .arg(&scene)
+            .output()
+            .expect("cannot run nbody");
+        let _ = fs::remove_file(&scene);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            !output.status.success() && stderr.contains("density of `Rock`"),
+            "a density of {} was accepted: {}",
+            density,
+            stderr
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Saves headless the state of a random cluster halfway through a run, in double precision
+//! with the leapfrog, adaptive substeps and a fixed step, restores it without any of these
+//! flags, and checks that the restored run ends in the state of the uninterrupted one, to
+//! the last bit of every position and velocity.
+use std::{env, fs, path::PathBuf, process::Command};
+
+const STEPS: u64 = 100;
+/// Flags the snapshot records, and which its restored run is not given
+const SETTINGS: [&str; 8] = [
+    "--integrator",
+    "leapfrog",
+    "--double-precision",
+    "--adaptive-substeps",
+    "--substeps",
+    "2",
+    "--dt",
+    "0.01",
+];
+
+fn state_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "nbody-snapshot-{}-{}.ron",
+        std::process::id(),
+        name
+    ))
+}
+
+/// State saved after `steps` steps with `args`
+fn run(name: &str, steps: u64, args: &[&str]) -> String {
+    let path = state_path(name);
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--max-steps", &steps.to_string()])
+        .arg("--save-state")
+        .arg(&path)
+        .args(args)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+    let state = fs::read_to_string(&path).expect("no state saved");
+    let _ = fs::remove_file(&path);
+    state
+}
+
+#[test]
+fn restored_state_goes_on_as_the_saved_one() {
+    let cluster = ["--startup", "cluster", "--bodies", "20", "--seed", "3"];
+    let full = run("full", 2 * STEPS, &[&cluster[..], &SETTINGS[..]].concat());
+    let half = run("half", STEPS, &[&cluster[..], &SETTINGS[..]].concat());
+    let path = state_path("restored");
+    fs::write(&path, &half).expect("cannot write the state");
+    let restored = run(
+        "resumed",
+        STEPS,
+        &["--load-state", path.to_str().expect("non UTF-8 path")],
+    );
+    let _ = fs::remove_file(&path);
+
+    assert!(
+        half.contains("precise_position: Some("),
+        "the double-precision state was not saved"
+    );
+    let difference = restored.lines().zip(full.lines()).find(|(a, b)| a != b);
+    assert_eq!(
+        difference, None,
+        "the restored run ended in another state than the uninterrupted one"
+    );
+    assert_eq!(restored.len(), full.len(), "the restored run lost bodies");
+}
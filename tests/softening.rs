@@ -0,0 +1,67 @@
+//! Softened gravity stays finite however close two bodies get, even on top of each other,
+//! its largest acceleration being `2 / (3√3) G m / ε²`, and it is Newton's law again for
+//! bodies much further apart than the softening length `ε`.
+use bevy::{math::Vec3, tasks::TaskPool};
+use copernicus::forces::{BodyState, Forces};
+
+const SOFTENING: f32 = 0.1;
+const MASS: f32 = 2.0;
+/// Of the accelerations far apart, relative to Newton's, off by about `3 ε² / (2 r²)`
+const TOLERANCE: f32 = 1e-5;
+
+/// Accelerations of two bodies of [`MASS`] at `separation` along X
+fn accelerations(forces: &Forces, separation: f32) -> Vec<Vec3> {
+    forces.compute(&[
+        BodyState {
+            pos: Vec3::ZERO,
+            mass: MASS,
+        },
+        BodyState {
+            pos: Vec3::X * separation,
+            mass: MASS,
+        },
+    ])
+}
+
+#[test]
+fn softening_keeps_close_encounters_finite() {
+    let pool = TaskPool::new();
+    let forces = Forces {
+        softening: SOFTENING,
+        ..Forces::new(1.0, &pool)
+    };
+    let largest = 2.0 / (3.0 * 3f32.sqrt()) * MASS / (SOFTENING * SOFTENING);
+    for &separation in &[0.0, 1e-6, 1e-3, SOFTENING / 2f32.sqrt(), SOFTENING] {
+        for acc in accelerations(&forces, separation) {
+            assert!(
+                acc.is_finite() && acc.length() <= largest * (1.0 + TOLERANCE),
+                "acceleration of {:?} {} apart",
+                acc,
+                separation
+            );
+        }
+    }
+    assert_eq!(
+        accelerations(&forces, 0.0),
+        vec![Vec3::ZERO; 2],
+        "bodies on top of each other pulled in some direction"
+    );
+
+    let newton = Forces::new(1.0, &pool);
+    let separation = 1000.0 * SOFTENING;
+    let softened = accelerations(&forces, separation);
+    let exact = accelerations(&newton, separation);
+    assert!(
+        (exact[0].x - MASS / (separation * separation)).abs() < TOLERANCE * exact[0].x,
+        "unsoftened acceleration of {:?}",
+        exact[0]
+    );
+    for (softened, exact) in softened.iter().zip(&exact) {
+        assert!(
+            softened.distance(*exact) < TOLERANCE * exact.length(),
+            "softened acceleration of {:?} instead of {:?} far apart",
+            softened,
+            exact
+        );
+    }
+}
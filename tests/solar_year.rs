@@ -0,0 +1,61 @@
+//! Runs `--startup solar` headless for a sidereal year and checks that the Earth comes back
+//! to where it started around the Sun. The year of the rounded vectors of the planets, 357
+//! days, would carry it 0.14 AU past its start; the pulls of the Moon and of the other
+//! planets only move it by less than a hundredth of an AU.
+use std::{env, fs, process::Command};
+
+/// In days
+const SIDEREAL_YEAR: f64 = 365.256;
+/// Exports are at the first step and at every multiple of [`STEPS`], a year apart
+const STEPS: u64 = 3000;
+/// In AU, about a day of the motion of the Earth
+const TOLERANCE: f64 = 0.02;
+
+/// Position of the Earth relative to the Sun at each export, in AU
+fn earth_positions(content: &str) -> Vec<[f64; 3]> {
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`, in AU, the Sun first
+    let mut sun = [0.0; 3];
+    let mut earth = Vec::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let number = |i: usize| -> f64 { fields[i].parse().expect("invalid number") };
+        let pos = [4, 5, 6].map(number);
+        match fields[1] {
+            "Sun" => sun = pos,
+            "Earth" => earth.push([0, 1, 2].map(|k| pos[k] - sun[k])),
+            _ => {}
+        }
+    }
+    earth
+}
+
+#[test]
+fn earth_returns_after_a_year() {
+    let csv = env::temp_dir().join(format!("nbody-solar-year-{}.csv", std::process::id()));
+    let dt = SIDEREAL_YEAR / (STEPS - 1) as f64;
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--startup", "solar", "--dt", &dt.to_string()])
+        .args(["--max-steps", &STEPS.to_string()])
+        .args(["--export-every", &STEPS.to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    let (start, end) = match earth_positions(&content).as_slice() {
+        [start, end] => (*start, *end),
+        positions => panic!("{} exports of the Earth instead of 2", positions.len()),
+    };
+    let distance = (0..3)
+        .map(|k| (end[k] - start[k]).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    assert!(
+        distance < TOLERANCE,
+        "the Earth ended {} AU from its start after a year",
+        distance
+    );
+}
@@ -0,0 +1,83 @@
+//! Substeps of a test particle on an eccentric orbit around a unit mass, with `G = 1`: a
+//! step of `dt` in `N` substeps is `N` steps of `dt / N`, and so cuts the energy error of
+//! a coarse step down.
+use bevy::tasks::TaskPool;
+use copernicus::{
+    forces::Forces,
+    integrator::{Integrator, Substeps},
+};
+use std::f64::consts::TAU;
+
+mod common;
+
+/// Steps per period, coarse enough for the energy error to show
+const STEPS: usize = 50;
+const SUBSTEPS: u32 = 10;
+
+/// Largest relative energy error over a period of steps of `count` substeps
+fn energy_error(count: u32) -> f64 {
+    let pool = TaskPool::new();
+    let forces = Forces::new(1.0, &pool);
+    let mut bodies = common::eccentric_orbit();
+    let initial = common::orbital_energy(&bodies);
+    let period = TAU * (1.0 / 0.56f64).powf(1.5);
+    let substeps = Substeps {
+        count,
+        adaptive: false,
+    };
+    (0..STEPS)
+        .map(|_| {
+            bodies.advance(Integrator::Euler, &forces, period / STEPS as f64, substeps);
+            ((common::orbital_energy(&bodies) - initial) / initial).abs()
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Including the leapfrog, whose substeps reuse the accelerations of the previous one
+#[test]
+fn substeps_are_shorter_steps() {
+    let pool = TaskPool::new();
+    let forces = Forces::new(1.0, &pool);
+    let dt = 0.1;
+    for &integrator in &[Integrator::Rk4, Integrator::Leapfrog] {
+        let mut substepped = common::eccentric_orbit();
+        let mut stepped = common::eccentric_orbit();
+        for _ in 0..STEPS {
+            substepped.advance(
+                integrator,
+                &forces,
+                dt,
+                Substeps {
+                    count: SUBSTEPS,
+                    adaptive: false,
+                },
+            );
+            for _ in 0..SUBSTEPS {
+                stepped.step(integrator, &forces, dt / SUBSTEPS as f64);
+            }
+        }
+        assert_eq!(
+            substepped.pos, stepped.pos,
+            "positions differ with {:?}",
+            integrator
+        );
+        assert_eq!(
+            substepped.vel, stepped.vel,
+            "velocities differ with {:?}",
+            integrator
+        );
+    }
+}
+
+#[test]
+fn substeps_reduce_the_energy_error() {
+    let coarse = energy_error(1);
+    let fine = energy_error(SUBSTEPS);
+    assert!(
+        fine < coarse / 5.0,
+        "energy error of {} in {} substeps, {} without",
+        fine,
+        SUBSTEPS,
+        coarse
+    );
+}
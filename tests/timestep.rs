@@ -0,0 +1,90 @@
+//! Runs Earth on a circular orbit around the Sun headless for a year with a fixed `--dt`,
+//! and checks that the orbit keeps its size with steps of a tenth of a day, but strays from
+//! it with steps of five days: the accuracy depends on the size of the steps, whatever the
+//! speed they are run at.
+use std::{env, fs, process::Command};
+
+/// Sun and Earth in the units of `scenes/solar.ron`, Earth at 1 AU
+const SCENE: &str = r#"(
+    g_scale: 2229.7247,
+    unit_scale: 10.0,
+    bodies: [
+        (name: "Sun", mass: 1988500.0, radius: 2.8, color: (1.0, 1.0, 0.0)),
+        (name: "Earth", mass: 5.972, radius: 0.637, color: (0.0, 0.0, 1.0),
+         orbit: Some((around: "Sun", semi_major_axis: 1.0))),
+    ],
+)"#;
+/// In days
+const YEAR: f64 = 365.25;
+/// Rows exported over the year
+const EXPORTS: u64 = 73;
+
+/// Largest relative change of the distance from Earth to the Sun over a year of steps of
+/// `dt` days, and `speed` days per second
+fn radius_error(dt: f64, speed: f64) -> f64 {
+    let dir = env::temp_dir();
+    let name = format!("nbody-timestep-{}-{}", std::process::id(), dt);
+    let (scene, csv) = (
+        dir.join(format!("{}.ron", name)),
+        dir.join(format!("{}.csv", name)),
+    );
+    fs::write(&scene, SCENE).expect("cannot write the scene");
+    let steps = (YEAR / dt).round() as u64;
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args(["--headless", "--integrator", "euler"])
+        .arg("--scene")
+        .arg(&scene)
+        .args(["--dt", &dt.to_string()])
+        .args(["--speed", &speed.to_string()])
+        .args(["--max-steps", &steps.to_string()])
+        .args(["--export-every", &(steps / EXPORTS).max(1).to_string()])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&scene);
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`, positions in AU, the Sun first
+    let rows: Vec<[f64; 3]> = content
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<f64> = line
+                .split(',')
+                .skip(4)
+                .take(3)
+                .map(|field| field.parse().expect("invalid position"))
+                .collect();
+            [fields[0], fields[1], fields[2]]
+        })
+        .collect();
+    let times: usize = content.lines().skip(1).count() / 2;
+    assert!(times as u64 >= EXPORTS, "only {} exports", times);
+    rows.chunks(2)
+        .map(|pair| {
+            let (sun, earth) = (pair[0], pair[1]);
+            let r = (0..3)
+                .map(|i| (earth[i] - sun[i]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            (r - 1.0).abs()
+        })
+        .fold(0.0, f64::max)
+}
+
+#[test]
+fn orbit_accuracy_depends_on_timestep() {
+    let fine = radius_error(0.1, 10.0);
+    let coarse = radius_error(5.0, 10.0);
+    assert!(fine < 2e-3, "orbit strayed by {} with dt = 0.1", fine);
+    assert!(
+        coarse > 2e-2,
+        "orbit only strayed by {} with dt = 5",
+        coarse
+    );
+    // The speed sets how often the steps run, not their size
+    assert_eq!(radius_error(5.0, 40.0), coarse);
+}
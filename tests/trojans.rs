@@ -0,0 +1,96 @@
+//! Runs `--startup trojans` headless for ten orbits of Jupiter, and checks that the asteroids
+//! stay in tadpoles around L4 and L5: ahead of Jupiter around the Sun, or behind it, by
+//! roughly 60°, and near its distance to the Sun. Asteroids drifting away would mean wrong
+//! Lagrange points, initial velocities, or an integrator losing the resonance.
+use std::{collections::HashMap, env, fs, process::Command};
+
+/// Orbital period of Jupiter, in days
+const PERIOD: f64 = 4332.6;
+/// Days per step, `--speed` over `--physics-hz`
+const STEP: f64 = 4.0;
+const ORBITS: f64 = 10.0;
+/// Bounds of the angle of the L4 asteroids ahead of Jupiter, in degrees, those of L5 being
+/// behind it by as much. The asteroids start within 5° of the points, and librate between
+/// about 35° and 98°.
+const LEADING: (f64, f64) = (20.0, 140.0);
+/// Bounds of the distance of the asteroids to the Sun, relative to that of Jupiter
+const DISTANCE: (f64, f64) = (0.9, 1.1);
+
+#[test]
+fn trojans_librate_around_l4_and_l5() {
+    let steps = (ORBITS * PERIOD / STEP).ceil() as u64;
+    let csv = env::temp_dir().join(format!("nbody-trojans-{}.csv", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_nbody"))
+        .args([
+            "--headless",
+            "--startup",
+            "trojans",
+            "--bodies",
+            "4",
+            "--seed",
+            "1",
+            "--integrator",
+            "leapfrog",
+            "--physics-hz",
+            "1",
+        ])
+        .args(["--speed", &STEP.to_string()])
+        .args(["--max-steps", &steps.to_string()])
+        .args(["--export-every", "100"])
+        .arg("--export-csv")
+        .arg(&csv)
+        .status()
+        .expect("cannot run nbody");
+    assert!(status.success(), "nbody exited with {}", status);
+
+    let content = fs::read_to_string(&csv).expect("no CSV export");
+    let _ = fs::remove_file(&csv);
+    // `time,name,mass,radius,x,y,z,vx,vy,vz,color`: positions in the plane of the orbit, by
+    // time and name
+    let mut frames: HashMap<&str, HashMap<&str, (f64, f64)>> = HashMap::new();
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let field = |i: usize| fields[i].parse::<f64>().expect("invalid number");
+        frames
+            .entry(fields[0])
+            .or_default()
+            .insert(fields[1], (field(4), field(5)));
+    }
+    assert!(frames.len() > 100, "only {} exports", frames.len());
+
+    let mut asteroids = 0;
+    for (time, bodies) in &frames {
+        let (sun, jupiter) = (bodies["Sun"], bodies["Jupiter"]);
+        let polar = |(x, y): (f64, f64)| {
+            let (dx, dy) = (x - sun.0, y - sun.1);
+            (dx.hypot(dy), dy.atan2(dx).to_degrees())
+        };
+        let (jupiter_distance, jupiter_angle) = polar(jupiter);
+        for (name, &pos) in bodies.iter().filter(|(name, _)| name.contains("asteroid")) {
+            asteroids += 1;
+            let (distance, angle) = polar(pos);
+            let ahead = (angle - jupiter_angle + 540.0) % 360.0 - 180.0;
+            let ahead = if name.starts_with("L5") {
+                -ahead
+            } else {
+                ahead
+            };
+            assert!(
+                ahead > LEADING.0 && ahead < LEADING.1,
+                "{} at {:.1}° from Jupiter at t = {}",
+                name,
+                ahead,
+                time
+            );
+            let distance = distance / jupiter_distance;
+            assert!(
+                distance > DISTANCE.0 && distance < DISTANCE.1,
+                "{} at {:.3} times the distance of Jupiter at t = {}",
+                name,
+                distance,
+                time
+            );
+        }
+    }
+    assert_eq!(asteroids, 4 * frames.len(), "asteroids missing");
+}
@@ -0,0 +1,50 @@
+//! The radius of a uniform sphere from its mass and density, for the Earth: 5.972e24 kg at a
+//! mean density of 5,514 kg/m^3 make a sphere of its mean radius, 6,371 km. The scaled
+//! gravitational constant of the Solar System scenes, with which a body at 1 AU from the Sun
+//! goes around it in a sidereal year, 365.256 days.
+use copernicus::units::{
+    g_scale, radius_from_mass_density, solar_g_scale, AU, G, KM, MASS_UNIT, SECONDS_PER_DAY,
+    SOLAR_MASS,
+};
+use std::f64::consts::TAU;
+
+const EARTH_MASS: f64 = 5.972e24;
+const EARTH_DENSITY: f64 = 5514.0;
+const EARTH_RADIUS: f64 = 6371.0 * KM;
+
+#[test]
+fn radius_of_the_earth_from_its_density() {
+    let radius = radius_from_mass_density(EARTH_MASS, EARTH_DENSITY);
+    assert!(
+        (radius - EARTH_RADIUS).abs() < 1e-3 * EARTH_RADIUS,
+        "radius of {} km instead of {} km",
+        radius / KM,
+        EARTH_RADIUS / KM
+    );
+}
+
+/// In days, of a body of negligible mass at 1 AU from the Sun
+const SIDEREAL_YEAR: f64 = 365.256;
+
+#[test]
+fn year_from_the_scaled_gravitational_constant() {
+    // In AU, days and solar masses, `G` is the square of the Gaussian gravitational constant
+    let mu = G * g_scale(AU, SECONDS_PER_DAY, SOLAR_MASS);
+    assert!(
+        (mu.sqrt() - 0.017_202_098_95).abs() < 1e-6,
+        "Gaussian gravitational constant of {}",
+        mu.sqrt()
+    );
+    let year = TAU / mu.sqrt();
+    assert!((year - SIDEREAL_YEAR).abs() < 0.01, "year of {} days", year);
+
+    // In the scenes, 10 units per AU and masses in 10^24 kg
+    let unit_scale = 10.0;
+    let mu = G * solar_g_scale(unit_scale) as f64 * SOLAR_MASS / MASS_UNIT;
+    let year = TAU * ((unit_scale as f64).powi(3) / mu).sqrt();
+    assert!(
+        (year - SIDEREAL_YEAR).abs() < 0.01,
+        "year of {} days in the scenes",
+        year
+    );
+}